@@ -56,6 +56,8 @@ pub struct CommonArgs {
     pub initial_cwnd_packets: u64,
     pub send_fec: bool,
     pub receive_fec: bool,
+    pub fec_scheduler: String,
+    pub fec_rng_seed: Option<u64>,
 }
 
 /// Creates a new `CommonArgs` structure using the provided [`Docopt`].
@@ -82,6 +84,8 @@ pub struct CommonArgs {
 /// --qpack-max-table-capacity BYTES  Max capacity of dynamic QPACK decoding.
 /// --qpack-blocked-streams STREAMS  Limit of blocked streams while decoding.
 /// --initial-cwnd-packets      Size of initial congestion window, in packets.
+/// --fec-scheduler NAME        Set a FEC redundancy scheduler.
+/// --fec-rng-seed SEED         Seed of the FEC randomness.
 ///
 /// [`Docopt`]: https://docs.rs/docopt/1.1.0/docopt/
 impl Args for CommonArgs {
@@ -190,6 +194,24 @@ impl Args for CommonArgs {
         let send_fec = args.get_bool("--send-fec");
         let receive_fec = args.get_bool("--receive-fec");
 
+        let fec_scheduler = args.get_str("--fec-scheduler");
+
+        if fec_scheduler == "help" {
+            let names: Vec<String> = quiche::FECSchedulerAlgorithm::all()
+                .iter()
+                .map(|alg| alg.to_string())
+                .collect();
+
+            println!("Available FEC schedulers: {}", names.join(", "));
+            std::process::exit(0);
+        }
+
+        let fec_rng_seed = if !args.get_str("--fec-rng-seed").is_empty() {
+            Some(args.get_str("--fec-rng-seed").parse::<u64>().unwrap())
+        } else {
+            None
+        };
+
         let initial_cwnd_packets = args
             .get_str("--initial-cwnd-packets")
             .parse::<u64>()
@@ -220,6 +242,8 @@ impl Args for CommonArgs {
             initial_cwnd_packets,
             send_fec,
             receive_fec,
+            fec_scheduler: fec_scheduler.to_string(),
+            fec_rng_seed,
         }
     }
 }
@@ -251,6 +275,8 @@ impl Default for CommonArgs {
             initial_cwnd_packets: 10,
             send_fec: false,
             receive_fec: false,
+            fec_scheduler: "noredundancy".to_string(),
+            fec_rng_seed: None,
         }
     }
 }
@@ -288,6 +314,8 @@ Options:
   --max-active-cids NUM    The maximum number of active Connection IDs we can support [default: 2].
   --enable-active-migration   Enable active connection migration.
   --perform-migration      Perform connection migration on another source port.
+  --race-addr ADDRESS      Race the path to another server address, e.g. of the other IP family.
+  --race-keep-loser        Keep the path that lost the race as a FEC-only path.
   -H --header HEADER ...   Add a request header.
   -n --requests REQUESTS   Send the given number of identical requests [default: 1].
   --send-priority-update   Send HTTP/3 priority updates if the query string params 'u' or 'i' are present in URLs
@@ -297,8 +325,11 @@ Options:
   --session-file PATH      File used to cache a TLS session for resumption.
   --source-port PORT       Source port to use when connecting to the server [default: 0].
   --initial-cwnd-packets PACKETS   The initial congestion window size in terms of packet count [default: 10].
+  --disable-gso            Disable GSO and GRO (linux only).
   --send-fec               Sends FEC to protect the STREAM and DATAGRAM frames
   --receive-fec            Processes FEC data to protect the received STREAM and DATAGRAM frames
+  --fec-scheduler NAME     FEC redundancy scheduler, `help` lists them [default: noredundancy].
+  --fec-rng-seed SEED      Seed of the FEC randomness, to reproduce a run. Default is random.
   -h --help                Show this screen.
 ";
 
@@ -318,7 +349,10 @@ pub struct ClientArgs {
     pub session_file: Option<String>,
     pub source_port: u16,
     pub perform_migration: bool,
+    pub race_addr: Option<String>,
+    pub race_keep_loser: bool,
     pub send_priority_update: bool,
+    pub disable_gso: bool,
 }
 
 impl Args for ClientArgs {
@@ -394,8 +428,18 @@ impl Args for ClientArgs {
 
         let perform_migration = args.get_bool("--perform-migration");
 
+        let race_addr = if args.get_bool("--race-addr") {
+            Some(args.get_str("--race-addr").to_string())
+        } else {
+            None
+        };
+
+        let race_keep_loser = args.get_bool("--race-keep-loser");
+
         let send_priority_update = args.get_bool("--send-priority-update");
 
+        let disable_gso = args.get_bool("--disable-gso");
+
         ClientArgs {
             version,
             dump_response_path,
@@ -411,7 +455,10 @@ impl Args for ClientArgs {
             session_file,
             source_port,
             perform_migration,
+            race_addr,
+            race_keep_loser,
             send_priority_update,
+            disable_gso,
         }
     }
 }
@@ -433,7 +480,10 @@ impl Default for ClientArgs {
             session_file: None,
             source_port: 0,
             perform_migration: false,
+            race_addr: None,
+            race_keep_loser: false,
             send_priority_update: false,
+            disable_gso: false,
         }
     }
 }
@@ -471,11 +521,13 @@ Options:
   --max-field-section-size BYTES    Max size of uncompressed HTTP/3 field section. Default is unlimited.
   --qpack-max-table-capacity BYTES  Max capacity of QPACK dynamic table decoding. Any value other that 0 is currently unsupported.
   --qpack-blocked-streams STREAMS   Limit of streams that can be blocked while decoding. Any value other that 0 is currently unsupported.
-  --disable-gso               Disable GSO (linux only).
+  --disable-gso               Disable GSO and GRO (linux only).
   --disable-pacing            Disable pacing (linux only).
   --initial-cwnd-packets PACKETS      The initial congestion window size in terms of packet count [default: 10].
   --send-fec               Sends FEC to protect the STREAM and DATAGRAM frames
   --receive-fec            Processes FEC data to protect the received STREAM and DATAGRAM frames
+  --fec-scheduler NAME     FEC redundancy scheduler, `help` lists them [default: noredundancy].
+  --fec-rng-seed SEED      Seed of the FEC randomness, to reproduce a run. Default is random.
   -h --help                   Show this screen.
 ";
 
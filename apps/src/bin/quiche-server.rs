@@ -47,6 +47,9 @@ use quiche_apps::args::*;
 
 use quiche_apps::common::*;
 
+use quiche_apps::pcap::Capture;
+
+use quiche_apps::recvfrom::*;
 use quiche_apps::sendto::*;
 
 const MAX_BUF_SIZE: usize = 65507;
@@ -100,6 +103,16 @@ fn main() {
 
     trace!("GSO detected: {}", enable_gso);
 
+    let enable_gro = if args.disable_gso {
+        false
+    } else {
+        detect_gro(&socket)
+    };
+
+    trace!("GRO detected: {}", enable_gro);
+
+    let mut gro = GroBuffer::default();
+
     // Create the configuration for the QUIC connections.
     let mut config = quiche::Config::new(quiche::PROTOCOL_VERSION).unwrap();
 
@@ -130,6 +143,13 @@ fn main() {
     config.enable_pacing(pacing);
     config.send_fec(conn_args.send_fec);
     config.receive_fec(conn_args.receive_fec);
+    config
+        .set_fec_scheduler_algorithm_name(&conn_args.fec_scheduler)
+        .unwrap();
+
+    if let Some(seed) = conn_args.fec_rng_seed {
+        config.set_fec_rng_seed(seed);
+    }
 
     let mut keylog = None;
 
@@ -145,6 +165,14 @@ fn main() {
         config.log_keys();
     }
 
+    let mut capture = None;
+
+    if let Some(capture_path) = std::env::var_os("PCAPNGFILE") {
+        capture = Some(Capture::create(capture_path).unwrap());
+
+        config.log_keys();
+    }
+
     if conn_args.early_data {
         config.enable_early_data();
     }
@@ -213,20 +241,21 @@ fn main() {
                 break 'read;
             }
 
-            let (len, from) = match socket.recv_from(&mut buf) {
-                Ok(v) => v,
+            let (len, from) =
+                match recv_from(&socket, &mut buf, &mut gro, enable_gro) {
+                    Ok(v) => v,
 
-                Err(e) => {
-                    // There are no more UDP packets to read, so end the read
-                    // loop.
-                    if e.kind() == std::io::ErrorKind::WouldBlock {
-                        trace!("recv() would block");
-                        break 'read;
-                    }
+                    Err(e) => {
+                        // There are no more UDP packets to read, so end the read
+                        // loop.
+                        if e.kind() == std::io::ErrorKind::WouldBlock {
+                            trace!("recv() would block");
+                            break 'read;
+                        }
 
-                    panic!("recv() failed: {:?}", e);
-                },
-            };
+                        panic!("recv() failed: {:?}", e);
+                    },
+                };
 
             trace!("got {} bytes", len);
 
@@ -369,7 +398,13 @@ fn main() {
                 )
                 .unwrap();
 
-                if let Some(keylog) = &mut keylog {
+                info!("{} FEC RNG seed {}", conn.trace_id(), conn.fec_rng_seed());
+
+                if let Some(capture) = &capture {
+                    let keylog = keylog.as_ref().and_then(|k| k.try_clone().ok());
+                    conn.set_keylog(Box::new(capture.keylog(keylog)));
+                    conn.set_fec_capture_notes(true);
+                } else if let Some(keylog) = &mut keylog {
                     if let Ok(keylog) = keylog.try_clone() {
                         conn.set_keylog(Box::new(keylog));
                     }
@@ -425,8 +460,22 @@ fn main() {
                 from,
             };
 
+            // The datagram is decrypted in place, so keep a copy to capture.
+            let captured = capture.as_ref().map(|_| pkt_buf.to_vec());
+
             // Process potentially coalesced packets.
-            let read = match client.conn.recv(pkt_buf, recv_info) {
+            let read = client.conn.recv(pkt_buf, recv_info);
+
+            if let (Some(capture), Some(captured)) = (&capture, captured) {
+                capture.datagram(
+                    from,
+                    local_addr,
+                    &captured,
+                    &client.conn.take_fec_capture_notes(),
+                );
+            }
+
+            let read = match read {
                 Ok(v) => v,
 
                 Err(e) => {
@@ -558,16 +607,34 @@ fn main() {
                     client.max_datagram_size;
             let mut total_write = 0;
             let mut dst_info = None;
+            let mut segment_size: Option<usize> = None;
 
             while total_write < max_send_burst {
+                // With GSO, all the packets of a burst but the last one must
+                // have the same size. Packets carrying FEC source symbols are
+                // shorter than the maximum datagram size, so the segment size
+                // is set by the first packet of the burst.
+                let max_write = match segment_size {
+                    Some(v) => (total_write + v).min(max_send_burst),
+
+                    None => max_send_burst,
+                };
+
                 let (write, send_info) = match client
                     .conn
-                    .send(&mut out[total_write..max_send_burst])
+                    .send(&mut out[total_write..max_write])
                 {
                     Ok(v) => v,
 
                     Err(quiche::Error::Done) => {
                         trace!("{} done writing", client.conn.trace_id());
+
+                        // The next packet may not fit in the segment size,
+                        // try again in a new burst.
+                        if segment_size.is_some() {
+                            continue_write = true;
+                        }
+
                         break;
                     },
 
@@ -579,12 +646,21 @@ fn main() {
                     },
                 };
 
+                if let Some(capture) = &capture {
+                    capture.datagram(
+                        send_info.from,
+                        send_info.to,
+                        &out[total_write..total_write + write],
+                        &client.conn.take_fec_capture_notes(),
+                    );
+                }
+
                 total_write += write;
 
                 // Use the first packet time to send, not the last.
                 let _ = dst_info.get_or_insert(send_info);
 
-                if write < client.max_datagram_size {
+                if write < *segment_size.get_or_insert(write) {
                     continue_write = true;
                     break;
                 }
@@ -598,7 +674,7 @@ fn main() {
                 &socket,
                 &out[..total_write],
                 &dst_info.unwrap(),
-                client.max_datagram_size,
+                segment_size.unwrap(),
                 pacing,
                 enable_gso,
             ) {
@@ -761,6 +837,15 @@ fn handle_path_events(client: &mut Client) {
                     peer_addr
                 );
             },
+
+            quiche::PathEvent::RedundancyPathDown(local_addr, peer_addr) => {
+                info!(
+                    "{} Redundancy path ({}, {}) is down",
+                    client.conn.trace_id(),
+                    local_addr,
+                    peer_addr
+                );
+            },
         }
     }
 }
@@ -26,6 +26,9 @@
 
 use crate::args::*;
 use crate::common::*;
+use crate::pcap::Capture;
+use crate::recvfrom::*;
+use crate::sendto::*;
 
 use std::net::ToSocketAddrs;
 
@@ -39,6 +42,13 @@ use ring::rand::*;
 
 const MAX_DATAGRAM_SIZE: usize = 1350;
 
+// Maximum number of packets sent at once with GSO.
+const MAX_GSO_SEGMENTS: usize = 16;
+
+// How long the paths to the server addresses are raced once the connection is
+// established, at most.
+const PATH_RACE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
 #[derive(Debug)]
 pub enum ClientError {
     HandshakeFail,
@@ -51,7 +61,7 @@ pub fn connect(
     output_sink: impl FnMut(String) + 'static,
 ) -> Result<(), ClientError> {
     let mut buf = [0; 65535];
-    let mut out = [0; MAX_DATAGRAM_SIZE];
+    let mut out = [0; MAX_DATAGRAM_SIZE * MAX_GSO_SEGMENTS];
 
     let output_sink =
         Rc::new(RefCell::new(output_sink)) as Rc<RefCell<dyn FnMut(_)>>;
@@ -70,13 +80,20 @@ pub fn connect(
         connect_url.to_socket_addrs().unwrap().next().unwrap()
     };
 
+    // The other server address the path is raced to, if any.
+    let race_addr: Option<std::net::SocketAddr> =
+        args.race_addr.as_ref().map(|addr| {
+            addr.parse().expect("--race-addr is expected to be a string containing an IPv4 or IPv6 address with a port. E.g. [2001:db8::1]:443")
+        });
+
     // Bind to INADDR_ANY or IN6ADDR_ANY depending on the IP family of the
     // server address. This is needed on macOS and BSD variants that don't
     // support binding to IN6ADDR_ANY for both v4 and v6.
-    let bind_addr = match peer_addr {
+    let bind_addr_for = |addr: std::net::SocketAddr| match addr {
         std::net::SocketAddr::V4(_) => format!("0.0.0.0:{}", args.source_port),
         std::net::SocketAddr::V6(_) => format!("[::]:{}", args.source_port),
     };
+    let bind_addr = bind_addr_for(peer_addr);
 
     // Create the UDP socket backing the QUIC connection, and register it with
     // the event loop.
@@ -86,7 +103,15 @@ pub fn connect(
         .register(&mut socket, mio::Token(0), mio::Interest::READABLE)
         .unwrap();
 
-    let migrate_socket = if args.perform_migration {
+    // The second socket is used either to migrate, or to race the path to the
+    // other server address.
+    let migrate_socket = if args.perform_migration || race_addr.is_some() {
+        let bind_addr = match race_addr {
+            Some(addr) => bind_addr_for(addr),
+
+            None => bind_addr.clone(),
+        };
+
         let mut socket =
             mio::net::UdpSocket::bind(bind_addr.parse().unwrap()).unwrap();
         poll.registry()
@@ -98,6 +123,29 @@ pub fn connect(
         None
     };
 
+    let enable_gso = !args.disable_gso &&
+        detect_gso(&socket, MAX_DATAGRAM_SIZE) &&
+        match &migrate_socket {
+            Some(s) => detect_gso(s, MAX_DATAGRAM_SIZE),
+
+            None => true,
+        };
+
+    trace!("GSO detected: {}", enable_gso);
+
+    let enable_gro = !args.disable_gso &&
+        detect_gro(&socket) &&
+        match &migrate_socket {
+            Some(s) => detect_gro(s),
+
+            None => true,
+        };
+
+    trace!("GRO detected: {}", enable_gro);
+
+    // One buffer per socket, indexed by the socket token.
+    let mut gro_bufs = [GroBuffer::default(), GroBuffer::default()];
+
     // Create the configuration for the QUIC connection.
     let mut config = quiche::Config::new(args.version).unwrap();
 
@@ -133,6 +181,13 @@ pub fn connect(
 
     config.send_fec(conn_args.send_fec);
     config.receive_fec(conn_args.receive_fec);
+    config
+        .set_fec_scheduler_algorithm_name(&conn_args.fec_scheduler)
+        .unwrap();
+
+    if let Some(seed) = conn_args.fec_rng_seed {
+        config.set_fec_rng_seed(seed);
+    }
 
     let mut keylog = None;
 
@@ -148,6 +203,14 @@ pub fn connect(
         config.log_keys();
     }
 
+    let mut capture = None;
+
+    if let Some(capture_path) = std::env::var_os("PCAPNGFILE") {
+        capture = Some(Capture::create(capture_path).unwrap());
+
+        config.log_keys();
+    }
+
     if conn_args.no_grease {
         config.grease(false);
     }
@@ -199,7 +262,13 @@ pub fn connect(
     )
     .unwrap();
 
-    if let Some(keylog) = &mut keylog {
+    info!("FEC RNG seed {}", conn.fec_rng_seed());
+
+    if let Some(capture) = &capture {
+        let keylog = keylog.as_ref().and_then(|k| k.try_clone().ok());
+        conn.set_keylog(Box::new(capture.keylog(keylog)));
+        conn.set_fec_capture_notes(true);
+    } else if let Some(keylog) = &mut keylog {
         if let Ok(keylog) = keylog.try_clone() {
             conn.set_keylog(Box::new(keylog));
         }
@@ -233,7 +302,18 @@ pub fn connect(
         scid,
     );
 
-    let (write, send_info) = conn.send(&mut out).expect("initial send failed");
+    let (write, send_info) = conn
+        .send(&mut out[..MAX_DATAGRAM_SIZE])
+        .expect("initial send failed");
+
+    if let Some(capture) = &capture {
+        capture.datagram(
+            send_info.from,
+            send_info.to,
+            &out[..write],
+            &conn.take_fec_capture_notes(),
+        );
+    }
 
     while let Err(e) = socket.send_to(&out[..write], send_info.to) {
         if e.kind() == std::io::ErrorKind::WouldBlock {
@@ -252,6 +332,21 @@ pub fn connect(
 
     let app_data_start = std::time::Instant::now();
 
+    let mut path_race = race_addr.map(|addr| {
+        let candidates = [
+            (socket.local_addr().unwrap(), peer_addr),
+            (migrate_socket.as_ref().unwrap().local_addr().unwrap(), addr),
+        ];
+
+        let loser = if args.race_keep_loser {
+            quiche::PathRaceLoser::FECOnly
+        } else {
+            quiche::PathRaceLoser::Unused
+        };
+
+        quiche::PathRace::new(&candidates, loser, PATH_RACE_TIMEOUT)
+    });
+
     let mut pkt_count = 0;
 
     let mut scid_sent = false;
@@ -260,7 +355,20 @@ pub fn connect(
 
     loop {
         if !conn.is_in_early_data() || app_proto_selected {
-            poll.poll(&mut events, conn.timeout()).unwrap();
+            let race_timeout = path_race
+                .as_ref()
+                .and_then(|r| r.timeout())
+                .map(|t| {
+                    t.saturating_duration_since(std::time::Instant::now())
+                });
+
+            let timeout = match (conn.timeout(), race_timeout) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+
+                (a, b) => a.or(b),
+            };
+
+            poll.poll(&mut events, timeout).unwrap();
         }
 
         // If the event loop reported no events, it means that the timeout
@@ -283,24 +391,28 @@ pub fn connect(
                 _ => unreachable!(),
             };
 
+            let gro = &mut gro_bufs[event.token().0];
+
             let local_addr = socket.local_addr().unwrap();
             'read: loop {
-                let (len, from) = match socket.recv_from(&mut buf) {
-                    Ok(v) => v,
-
-                    Err(e) => {
-                        // There are no more UDP packets to read on this socket.
-                        // Process subsequent events.
-                        if e.kind() == std::io::ErrorKind::WouldBlock {
-                            trace!("{}: recv() would block", local_addr);
-                            break 'read;
-                        }
+                let (len, from) =
+                    match recv_from(socket, &mut buf, gro, enable_gro) {
+                        Ok(v) => v,
 
-                        return Err(ClientError::Other(format!(
-                            "{local_addr}: recv() failed: {e:?}"
-                        )));
-                    },
-                };
+                        Err(e) => {
+                            // There are no more UDP packets to read on this
+                            // socket.
+                            // Process subsequent events.
+                            if e.kind() == std::io::ErrorKind::WouldBlock {
+                                trace!("{}: recv() would block", local_addr);
+                                break 'read;
+                            }
+
+                            return Err(ClientError::Other(format!(
+                                "{local_addr}: recv() failed: {e:?}"
+                            )));
+                        },
+                    };
 
                 trace!("{}: got {} bytes", local_addr, len);
 
@@ -320,8 +432,23 @@ pub fn connect(
                     from,
                 };
 
+                // The datagram is decrypted in place, so keep a copy to
+                // capture.
+                let captured = capture.as_ref().map(|_| buf[..len].to_vec());
+
                 // Process potentially coalesced packets.
-                let read = match conn.recv(&mut buf[..len], recv_info) {
+                let read = conn.recv(&mut buf[..len], recv_info);
+
+                if let (Some(capture), Some(captured)) = (&capture, captured) {
+                    capture.datagram(
+                        from,
+                        local_addr,
+                        &captured,
+                        &conn.take_fec_capture_notes(),
+                    );
+                }
+
+                let read = match read {
                     Ok(v) => v,
 
                     Err(e) => {
@@ -439,8 +566,12 @@ pub fn connect(
                         "Path ({}, {}) is now validated",
                         local_addr, peer_addr
                     );
-                    conn.migrate(local_addr, peer_addr).unwrap();
-                    migrated = true;
+
+                    // When racing, the race decides which path is used.
+                    if args.perform_migration {
+                        conn.migrate(local_addr, peer_addr).unwrap();
+                        migrated = true;
+                    }
                 },
 
                 quiche::PathEvent::FailedValidation(local_addr, peer_addr) => {
@@ -469,6 +600,13 @@ pub fn connect(
                 },
 
                 quiche::PathEvent::PeerMigrated(..) => unreachable!(),
+
+                quiche::PathEvent::RedundancyPathDown(local_addr, peer_addr) => {
+                    info!(
+                        "Redundancy path ({}, {}) is down",
+                        local_addr, peer_addr
+                    );
+                },
             }
         }
 
@@ -488,6 +626,19 @@ pub fn connect(
             scid_sent = true;
         }
 
+        if let Some(path_race) = path_race.as_mut() {
+            match path_race.on_progress(&mut conn, std::time::Instant::now()) {
+                Ok(Some((local_addr, peer_addr))) => info!(
+                    "Path ({}, {}) won the race",
+                    local_addr, peer_addr
+                ),
+
+                Ok(None) => (),
+
+                Err(e) => error!("path race failed: {:?}", e),
+            }
+        }
+
         if args.perform_migration &&
             !new_path_probed &&
             scid_sent &&
@@ -511,35 +662,96 @@ pub fn connect(
             let local_addr = socket.local_addr().unwrap();
 
             for peer_addr in conn.paths_iter(local_addr) {
-                loop {
-                    let (write, send_info) = match conn.send_on_path(
-                        &mut out,
-                        Some(local_addr),
-                        Some(peer_addr),
-                    ) {
-                        Ok(v) => v,
+                let mut done = false;
 
-                        Err(quiche::Error::Done) => {
-                            trace!(
-                                "{} -> {}: done writing",
-                                local_addr,
-                                peer_addr
-                            );
+                while !done {
+                    let mut total_write = 0;
+                    let mut dst_info = None;
+                    let mut segment_size: Option<usize> = None;
+
+                    // With GSO, all the packets of a burst but the last one
+                    // must have the same size, which is set by the first
+                    // packet of the burst as packets carrying FEC source
+                    // symbols are shorter than the maximum datagram size.
+                    loop {
+                        let max_write = match segment_size {
+                            Some(v) => total_write + v,
+
+                            None => total_write + MAX_DATAGRAM_SIZE,
+                        };
+
+                        if max_write > out.len() {
                             break;
-                        },
+                        }
 
-                        Err(e) => {
-                            error!(
-                                "{} -> {}: send failed: {:?}",
-                                local_addr, peer_addr, e
+                        let (write, send_info) = match conn.send_on_path(
+                            &mut out[total_write..max_write],
+                            Some(local_addr),
+                            Some(peer_addr),
+                        ) {
+                            Ok(v) => v,
+
+                            Err(quiche::Error::Done) => {
+                                trace!(
+                                    "{} -> {}: done writing",
+                                    local_addr,
+                                    peer_addr
+                                );
+
+                                // The next packet may not fit in the segment
+                                // size, so only stop when the burst is empty.
+                                done = total_write == 0;
+                                break;
+                            },
+
+                            Err(e) => {
+                                error!(
+                                    "{} -> {}: send failed: {:?}",
+                                    local_addr, peer_addr, e
+                                );
+
+                                conn.close(false, 0x1, b"fail").ok();
+                                done = true;
+                                break;
+                            },
+                        };
+
+                        if let Some(capture) = &capture {
+                            capture.datagram(
+                                send_info.from,
+                                send_info.to,
+                                &out[total_write..total_write + write],
+                                &conn.take_fec_capture_notes(),
                             );
+                        }
+
+                        total_write += write;
+
+                        let _ = dst_info.get_or_insert(send_info);
 
-                            conn.close(false, 0x1, b"fail").ok();
+                        if write < *segment_size.get_or_insert(write) ||
+                            !enable_gso
+                        {
                             break;
-                        },
+                        }
+                    }
+
+                    let send_info = match dst_info {
+                        Some(v) => v,
+
+                        None => break,
                     };
 
-                    if let Err(e) = socket.send_to(&out[..write], send_info.to) {
+                    let write = total_write;
+
+                    if let Err(e) = send_to(
+                        socket,
+                        &out[..write],
+                        &send_info,
+                        segment_size.unwrap(),
+                        false,
+                        enable_gso,
+                    ) {
                         if e.kind() == std::io::ErrorKind::WouldBlock {
                             trace!(
                                 "{} -> {}: send() would block",
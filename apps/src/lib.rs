@@ -30,4 +30,6 @@ extern crate log;
 pub mod args;
 pub mod client;
 pub mod common;
+pub mod pcap;
+pub mod recvfrom;
 pub mod sendto;
@@ -0,0 +1,303 @@
+// Copyright (C) 2024, Cloudflare, Inc.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are
+// met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS
+// IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO,
+// THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR
+// PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Packet capture of the datagrams sent and received by the apps.
+//!
+//! The datagrams are written in the pcapng format, wrapped in synthetic
+//! IP/UDP headers. The TLS secrets of the connections are embedded in the
+//! capture as Decryption Secrets Blocks, and the packets carrying FEC frames
+//! are annotated with a comment describing their frames, so the capture can
+//! be analyzed in Wireshark without any additional file.
+
+use std::io::Write;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+const SECTION_HEADER_BLOCK: u32 = 0x0a0d_0d0a;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 0x0000_0001;
+const ENHANCED_PACKET_BLOCK: u32 = 0x0000_0006;
+const DECRYPTION_SECRETS_BLOCK: u32 = 0x0000_000a;
+
+const BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+
+// Raw IP packets, the version is given by the first nibble.
+const LINKTYPE_RAW: u16 = 101;
+
+const TLS_KEY_LOG_SECRETS: u32 = 0x544c_534b;
+
+const OPT_ENDOFOPT: u16 = 0;
+const OPT_COMMENT: u16 = 1;
+
+const UDP_PROTOCOL: u8 = 17;
+
+/// A pcapng capture, shared by all the connections of an app.
+#[derive(Clone)]
+pub struct Capture {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl Capture {
+    /// Creates the capture file at `path`.
+    pub fn create<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {
+        let file = std::fs::File::create(path)?;
+
+        Self::new(Box::new(std::io::BufWriter::new(file)))
+    }
+
+    /// Creates a capture writing to `writer`.
+    pub fn new(mut writer: Box<dyn Write + Send>) -> std::io::Result<Self> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        // Unknown section length.
+        body.extend_from_slice(&(-1i64).to_le_bytes());
+        write_block(&mut writer, SECTION_HEADER_BLOCK, &body)?;
+
+        let mut body = Vec::new();
+        body.extend_from_slice(&LINKTYPE_RAW.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        // No snapshot length limit.
+        body.extend_from_slice(&0u32.to_le_bytes());
+        write_block(&mut writer, INTERFACE_DESCRIPTION_BLOCK, &body)?;
+
+        Ok(Capture {
+            writer: Arc::new(Mutex::new(writer)),
+        })
+    }
+
+    /// Writes the UDP datagram `payload` sent from `from` to `to`, annotated
+    /// with `notes` if any.
+    pub fn datagram(
+        &self, from: SocketAddr, to: SocketAddr, payload: &[u8], notes: &[String],
+    ) {
+        let packet = match ip_udp_packet(from, to, payload) {
+            Some(v) => v,
+
+            None => {
+                warn!("cannot capture datagram from {} to {}", from, to);
+                return;
+            },
+        };
+
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_micros() as u64)
+            .unwrap_or(0);
+
+        let mut body = Vec::new();
+        // Interface ID.
+        body.extend_from_slice(&0u32.to_le_bytes());
+        body.extend_from_slice(&((timestamp >> 32) as u32).to_le_bytes());
+        body.extend_from_slice(&(timestamp as u32).to_le_bytes());
+        body.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        body.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        body.extend_from_slice(&packet);
+        pad(&mut body);
+
+        if !notes.is_empty() {
+            let comment = notes.join("\n");
+
+            body.extend_from_slice(&OPT_COMMENT.to_le_bytes());
+            body.extend_from_slice(&(comment.len() as u16).to_le_bytes());
+            body.extend_from_slice(comment.as_bytes());
+            pad(&mut body);
+
+            body.extend_from_slice(&OPT_ENDOFOPT.to_le_bytes());
+            body.extend_from_slice(&0u16.to_le_bytes());
+        }
+
+        self.write(ENHANCED_PACKET_BLOCK, &body);
+    }
+
+    /// Returns a keylog writer embedding the TLS secrets in the capture, and
+    /// also appending them to `keylog` if any.
+    pub fn keylog(&self, keylog: Option<std::fs::File>) -> CaptureKeylog {
+        CaptureKeylog {
+            capture: self.clone(),
+            keylog,
+        }
+    }
+
+    fn write(&self, block_type: u32, body: &[u8]) {
+        let mut writer = self.writer.lock().unwrap();
+
+        if let Err(e) = write_block(&mut *writer, block_type, body) {
+            error!("failed to write capture: {:?}", e);
+        }
+    }
+}
+
+/// Writer of the TLS secrets of a connection into a capture.
+pub struct CaptureKeylog {
+    capture: Capture,
+    keylog: Option<std::fs::File>,
+}
+
+impl Write for CaptureKeylog {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&TLS_KEY_LOG_SECRETS.to_le_bytes());
+        body.extend_from_slice(&(buf.len() as u32).to_le_bytes());
+        body.extend_from_slice(buf);
+        pad(&mut body);
+
+        self.capture.write(DECRYPTION_SECRETS_BLOCK, &body);
+
+        if let Some(keylog) = &mut self.keylog {
+            keylog.write_all(buf)?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.capture.writer.lock().unwrap().flush()?;
+
+        if let Some(keylog) = &mut self.keylog {
+            keylog.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_block(
+    writer: &mut dyn Write, block_type: u32, body: &[u8],
+) -> std::io::Result<()> {
+    // The block type and the total length are both 32 bits long, and the
+    // total length is repeated at the end of the block.
+    let len = (body.len() + 12) as u32;
+
+    writer.write_all(&block_type.to_le_bytes())?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(body)?;
+    writer.write_all(&len.to_le_bytes())?;
+
+    writer.flush()
+}
+
+// Pads `buf` to a 32-bit boundary.
+fn pad(buf: &mut Vec<u8>) {
+    let len = (buf.len() + 3) & !3;
+
+    buf.resize(len, 0);
+}
+
+fn ip_udp_packet(
+    from: SocketAddr, to: SocketAddr, payload: &[u8],
+) -> Option<Vec<u8>> {
+    let udp_len = 8 + payload.len();
+
+    let mut packet = Vec::with_capacity(40 + udp_len);
+
+    // The pseudo-header of the UDP checksum.
+    let mut pseudo_header = Vec::with_capacity(36);
+
+    match (from.ip(), to.ip()) {
+        (IpAddr::V4(src), IpAddr::V4(dst)) => {
+            let mut header = [0u8; 20];
+            header[0] = 0x45;
+            header[2..4].copy_from_slice(&((20 + udp_len) as u16).to_be_bytes());
+            // Don't fragment.
+            header[6] = 0x40;
+            header[8] = 64;
+            header[9] = UDP_PROTOCOL;
+            header[12..16].copy_from_slice(&src.octets());
+            header[16..20].copy_from_slice(&dst.octets());
+
+            let checksum = checksum(&[&header]);
+            header[10..12].copy_from_slice(&checksum.to_be_bytes());
+
+            packet.extend_from_slice(&header);
+
+            pseudo_header.extend_from_slice(&src.octets());
+            pseudo_header.extend_from_slice(&dst.octets());
+            pseudo_header.extend_from_slice(&[0, UDP_PROTOCOL]);
+            pseudo_header.extend_from_slice(&(udp_len as u16).to_be_bytes());
+        },
+
+        (IpAddr::V6(src), IpAddr::V6(dst)) => {
+            packet.extend_from_slice(&[0x60, 0, 0, 0]);
+            packet.extend_from_slice(&(udp_len as u16).to_be_bytes());
+            packet.extend_from_slice(&[UDP_PROTOCOL, 64]);
+            packet.extend_from_slice(&src.octets());
+            packet.extend_from_slice(&dst.octets());
+
+            pseudo_header.extend_from_slice(&src.octets());
+            pseudo_header.extend_from_slice(&dst.octets());
+            pseudo_header.extend_from_slice(&(udp_len as u32).to_be_bytes());
+            pseudo_header.extend_from_slice(&[0, 0, 0, UDP_PROTOCOL]);
+        },
+
+        _ => return None,
+    }
+
+    let mut header = [0u8; 8];
+    header[0..2].copy_from_slice(&from.port().to_be_bytes());
+    header[2..4].copy_from_slice(&to.port().to_be_bytes());
+    header[4..6].copy_from_slice(&(udp_len as u16).to_be_bytes());
+
+    let checksum = match checksum(&[&pseudo_header, &header, payload]) {
+        // A computed checksum of zero is transmitted as all ones.
+        0 => 0xffff,
+
+        v => v,
+    };
+    header[6..8].copy_from_slice(&checksum.to_be_bytes());
+
+    packet.extend_from_slice(&header);
+    packet.extend_from_slice(payload);
+
+    Some(packet)
+}
+
+// Internet checksum of the concatenation of `data`, as per RFC 1071.
+fn checksum(data: &[&[u8]]) -> u16 {
+    let mut sum = 0u32;
+    let mut odd = None;
+
+    for byte in data.iter().flat_map(|d| d.iter()) {
+        match odd.take() {
+            Some(high) => sum += u32::from(u16::from_be_bytes([high, *byte])),
+
+            None => odd = Some(*byte),
+        }
+    }
+
+    if let Some(high) = odd {
+        sum += u32::from(u16::from_be_bytes([high, 0]));
+    }
+
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+
+    !(sum as u16)
+}
@@ -0,0 +1,177 @@
+// Copyright (C) 2024, Cloudflare, Inc.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are
+// met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS
+// IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO,
+// THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR
+// PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::io;
+use std::net::SocketAddr;
+
+/// Size of the buffer receiving coalesced UDP datagrams.
+const MAX_GRO_BUF_SIZE: usize = 65535;
+
+/// For Linux, try to enable GRO on the socket.
+#[cfg(target_os = "linux")]
+pub fn detect_gro(socket: &mio::net::UdpSocket) -> bool {
+    use nix::sys::socket::setsockopt;
+    use nix::sys::socket::sockopt::UdpGroSegment;
+    use std::os::unix::io::AsRawFd;
+
+    // mio::net::UdpSocket doesn't implement AsFd (yet?).
+    let fd = unsafe { std::os::fd::BorrowedFd::borrow_raw(socket.as_raw_fd()) };
+
+    setsockopt(&fd, UdpGroSegment, &true).is_ok()
+}
+
+/// For non-Linux, there is no GRO support.
+#[cfg(not(target_os = "linux"))]
+pub fn detect_gro(_socket: &mio::net::UdpSocket) -> bool {
+    false
+}
+
+/// Datagrams coalesced by GRO that have not been handed to the application
+/// yet.
+pub struct GroBuffer {
+    buf: Vec<u8>,
+    len: usize,
+    off: usize,
+    segment_size: usize,
+    from: Option<SocketAddr>,
+}
+
+impl Default for GroBuffer {
+    fn default() -> Self {
+        GroBuffer {
+            buf: vec![0; MAX_GRO_BUF_SIZE],
+            len: 0,
+            off: 0,
+            segment_size: 0,
+            from: None,
+        }
+    }
+}
+
+impl GroBuffer {
+    /// Copies the next pending datagram into `buf`.
+    fn next_segment(&mut self, buf: &mut [u8]) -> Option<(usize, SocketAddr)> {
+        let from = self.from?;
+
+        if self.off >= self.len {
+            self.from = None;
+            return None;
+        }
+
+        let seg_len = (self.len - self.off).min(self.segment_size);
+        buf[..seg_len].copy_from_slice(&self.buf[self.off..self.off + seg_len]);
+
+        self.off += seg_len;
+
+        Some((seg_len, from))
+    }
+}
+
+/// Receive coalesced datagrams using recvmsg() with GRO.
+#[cfg(target_os = "linux")]
+fn recv_from_gro(
+    socket: &mio::net::UdpSocket, gro: &mut GroBuffer,
+) -> io::Result<()> {
+    use nix::sys::socket::recvmsg;
+    use nix::sys::socket::ControlMessageOwned;
+    use nix::sys::socket::MsgFlags;
+    use nix::sys::socket::SockaddrStorage;
+    use std::io::IoSliceMut;
+    use std::os::unix::io::AsRawFd;
+
+    let mut cmsg_buf = nix::cmsg_space!(u16);
+    let mut iov = [IoSliceMut::new(&mut gro.buf)];
+
+    let msg = recvmsg::<SockaddrStorage>(
+        socket.as_raw_fd(),
+        &mut iov,
+        Some(&mut cmsg_buf),
+        MsgFlags::empty(),
+    )
+    .map_err(io::Error::from)?;
+
+    let from = msg
+        .address
+        .and_then(|addr| {
+            if let Some(v4) = addr.as_sockaddr_in() {
+                return Some(SocketAddr::from(std::net::SocketAddrV4::from(*v4)));
+            }
+
+            addr.as_sockaddr_in6()
+                .map(|v6| SocketAddr::from(std::net::SocketAddrV6::from(*v6)))
+        })
+        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))?;
+
+    let mut segment_size = msg.bytes;
+
+    for cmsg in msg.cmsgs() {
+        if let ControlMessageOwned::UdpGroSegments(v) = cmsg {
+            segment_size = v as usize;
+        }
+    }
+
+    gro.len = msg.bytes;
+    gro.off = 0;
+    gro.segment_size = segment_size.max(1);
+    gro.from = Some(from);
+
+    Ok(())
+}
+
+/// For non-Linux platforms.
+#[cfg(not(target_os = "linux"))]
+fn recv_from_gro(
+    _socket: &mio::net::UdpSocket, _gro: &mut GroBuffer,
+) -> io::Result<()> {
+    panic!("recv_from_gro() should not be called on non-linux platforms");
+}
+
+/// A wrapper function of recv_from().
+///
+/// When GRO is enabled, the datagrams coalesced by the kernel are received at
+/// once into `gro`, and then returned one by one into `buf`, so that callers
+/// process them exactly as datagrams received with socket.recv_from().
+pub fn recv_from(
+    socket: &mio::net::UdpSocket, buf: &mut [u8], gro: &mut GroBuffer,
+    enable_gro: bool,
+) -> io::Result<(usize, SocketAddr)> {
+    if !enable_gro {
+        return socket.recv_from(buf);
+    }
+
+    if let Some(v) = gro.next_segment(buf) {
+        return Ok(v);
+    }
+
+    recv_from_gro(socket, gro)?;
+
+    if gro.len == 0 {
+        return Ok((0, gro.from.take().unwrap()));
+    }
+
+    gro.next_segment(buf)
+        .ok_or_else(|| io::Error::from(io::ErrorKind::WouldBlock))
+}
@@ -47,11 +47,11 @@ pub fn detect_gso(_socket: &mio::net::UdpSocket, _segment_size: usize) -> bool {
     false
 }
 
-/// Send packets using sendmsg() with GSO.
+/// Send packets using sendmsg() with GSO, and SO_TXTIME when pacing.
 #[cfg(target_os = "linux")]
 fn send_to_gso_pacing(
     socket: &mio::net::UdpSocket, buf: &[u8], send_info: &quiche::SendInfo,
-    segment_size: usize,
+    segment_size: usize, pacing: bool,
 ) -> io::Result<usize> {
     use nix::sys::socket::sendmsg;
     use nix::sys::socket::ControlMessage;
@@ -68,17 +68,20 @@ fn send_to_gso_pacing(
     // GSO option.
     let cmsg_gso = ControlMessage::UdpGsoSegments(&segment_size);
 
-    // Pacing option.
-    let send_time = std_time_to_u64(&send_info.at);
-    let cmsg_txtime = ControlMessage::TxTime(&send_time);
-
-    match sendmsg(
-        sockfd,
-        &iov,
-        &[cmsg_gso, cmsg_txtime],
-        MsgFlags::empty(),
-        Some(&dst),
-    ) {
+    // Pacing option, only when the packets carry a release time.
+    let send_time = send_info
+        .release_time
+        .as_ref()
+        .filter(|_| pacing)
+        .map(std_time_to_u64);
+
+    let mut cmsgs = vec![cmsg_gso];
+
+    if let Some(send_time) = &send_time {
+        cmsgs.push(ControlMessage::TxTime(send_time));
+    }
+
+    match sendmsg(sockfd, &iov, &cmsgs, MsgFlags::empty(), Some(&dst)) {
         Ok(v) => Ok(v),
         Err(e) => Err(e.into()),
     }
@@ -88,21 +91,25 @@ fn send_to_gso_pacing(
 #[cfg(not(target_os = "linux"))]
 fn send_to_gso_pacing(
     _socket: &mio::net::UdpSocket, _buf: &[u8], _send_info: &quiche::SendInfo,
-    _segment_size: usize,
+    _segment_size: usize, _pacing: bool,
 ) -> io::Result<usize> {
     panic!("send_to_gso() should not be called on non-linux platforms");
 }
 
 /// A wrapper function of send_to().
 ///
-/// When GSO and SO_TXTIME are enabled, send packets using send_to_gso().
-/// Otherwise, send packets using socket.send_to().
+/// When GSO is enabled, send packets using send_to_gso_pacing(), which also
+/// sets their release time when SO_TXTIME is enabled. Otherwise, send packets
+/// using socket.send_to().
+///
+/// All the packets in `buf` must be `segment_size` long, except the last one
+/// which can be shorter.
 pub fn send_to(
     socket: &mio::net::UdpSocket, buf: &[u8], send_info: &quiche::SendInfo,
     segment_size: usize, pacing: bool, enable_gso: bool,
 ) -> io::Result<usize> {
-    if pacing && enable_gso {
-        match send_to_gso_pacing(socket, buf, send_info, segment_size) {
+    if enable_gso {
+        match send_to_gso_pacing(socket, buf, send_info, segment_size, pacing) {
             Ok(v) => {
                 return Ok(v);
             },
@@ -275,6 +275,8 @@ impl From<EventType> for EventImportance {
             EventType::RecoveryEventType(
                 RecoveryEventType::MarkedForRetransmit,
             ) => EventImportance::Extra,
+            EventType::RecoveryEventType(RecoveryEventType::FecStatsUpdated) =>
+                EventImportance::Extra,
 
             EventType::Http3EventType(Http3EventType::ParametersSet) =>
                 EventImportance::Base,
@@ -456,6 +458,8 @@ impl From<&EventData> for EventType {
                 EventType::RecoveryEventType(
                     RecoveryEventType::MarkedForRetransmit,
                 ),
+            EventData::FecStatsUpdated { .. } =>
+                EventType::RecoveryEventType(RecoveryEventType::FecStatsUpdated),
 
             EventData::H3ParametersSet { .. } =>
                 EventType::Http3EventType(Http3EventType::ParametersSet),
@@ -619,6 +623,9 @@ pub enum EventData {
     #[serde(rename = "recovery:marked_for_retransmit")]
     MarkedForRetransmit(quic::MarkedForRetransmit),
 
+    #[serde(rename = "recovery:fec_stats_updated")]
+    FecStatsUpdated(quic::FecStatsUpdated),
+
     // HTTP/3
     #[serde(rename = "http:parameters_set")]
     H3ParametersSet(h3::H3ParametersSet),
@@ -308,6 +308,7 @@ pub enum RecoveryEventType {
     LossTimerUpdated,
     PacketLost,
     MarkedForRetransmit,
+    FecStatsUpdated,
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
@@ -372,6 +373,15 @@ pub enum QuicFrameTypeName {
     ApplicationClose,
     HandshakeDone,
     Datagram,
+    Repair,
+    SourceSymbolHeader,
+    SourceSymbolAck,
+    WindowSync,
+    FecRequest,
+    FecHint,
+    RateHint,
+    FecStats,
+    StreamUnreliable,
     Unknown,
 }
 
@@ -506,6 +516,67 @@ pub enum QuicFrame {
         raw: Option<Bytes>,
     },
 
+    /// A repair symbol, carrying redundancy for the source symbols in
+    /// `first_protected_symbol..first_protected_symbol + protected_symbols`.
+    Repair {
+        length: u64,
+        first_protected_symbol: Option<u64>,
+        protected_symbols: Option<u64>,
+
+        raw: Option<Bytes>,
+    },
+
+    /// The header of a source symbol: the frames following it in the packet
+    /// are protected by FEC.
+    SourceSymbolHeader {
+        symbol_id: u64,
+        recovered: Option<bool>,
+
+        raw: Option<Bytes>,
+    },
+
+    SourceSymbolAck {
+        recovered_ranges: Option<AckedRanges>,
+    },
+
+    WindowSync {
+        first_symbol: u64,
+    },
+
+    /// A request for the peer to enable or disable FEC in the opposite
+    /// direction.
+    FecRequest {
+        enable: bool,
+        scheduler: Option<u64>,
+    },
+
+    /// Hints about the upcoming traffic of the sender.
+    FecHint {
+        burst_size: Option<u64>,
+    },
+
+    /// The residual loss after FEC observed by the receiver, in parts per
+    /// million.
+    RateHint {
+        residual_loss: u64,
+    },
+
+    /// The statistics of the FEC decoder of the receiver.
+    FecStats {
+        source_symbols_received: u64,
+        repair_received: u64,
+        repair_useless: u64,
+        repair_rank_deficient: u64,
+        recovered: u64,
+    },
+
+    /// Whether the receiver should skip the gaps of a stream instead of
+    /// waiting for the lost data.
+    StreamUnreliable {
+        stream_id: u64,
+        unreliable: bool,
+    },
+
     Unknown {
         raw_frame_type: u64,
         frame_type_value: Option<u64>,
@@ -800,6 +871,26 @@ pub struct MarkedForRetransmit {
     pub frames: Vec<QuicFrame>,
 }
 
+/// The FEC statistics of the endpoint.
+///
+/// The decoder statistics are logged by the receiver, and the encoder ones,
+/// including the `peer_repair_useless` count reported by the receiver, by the
+/// sender.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug, Default)]
+pub struct FecStatsUpdated {
+    pub source_symbols_received: Option<u64>,
+    pub repair_received: Option<u64>,
+    pub repair_useless: Option<u64>,
+    pub repair_rank_deficient: Option<u64>,
+    pub recovered: Option<u64>,
+
+    pub repair_sent: Option<u64>,
+    pub repair_wasted: Option<u64>,
+    pub repair_useful: Option<u64>,
+    pub peer_repair_useless: Option<u64>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -229,6 +229,8 @@ enum quiche_cc_algorithm {
     QUICHE_CC_CUBIC = 1,
     QUICHE_CC_BBR = 2,
     QUICHE_CC_BBR2 = 3,
+    QUICHE_CC_DISABLED = 4,
+    QUICHE_CC_EXTERNAL = 5,
 };
 
 // Sets the congestion control algorithm used.
@@ -387,6 +389,14 @@ ssize_t quiche_conn_stream_recv(quiche_conn *conn, uint64_t stream_id,
                                 uint8_t *out, size_t buf_len, bool *fin,
                                 uint64_t *out_error_code);
 
+// Reads data from a stream, skipping over data that was not received yet.
+// off is set to the stream offset of the data read.
+ssize_t quiche_conn_stream_recv_out_of_order(quiche_conn *conn,
+                                             uint64_t stream_id, uint8_t *out,
+                                             size_t buf_len, uint64_t *off,
+                                             bool *fin,
+                                             uint64_t *out_error_code);
+
 // Writes data to a stream.
 // out_error_code is only set when STREAM_STOPPED or STREAM_RESET are returned.
 // Set to the reported error code associated with STOP_SENDING or STREAM_RESET. 
@@ -680,6 +690,13 @@ typedef struct {
 
     // The most recent data delivery rate estimate in bytes/s.
     uint64_t delivery_rate;
+
+    // The most recent data delivery rate estimate in bytes/s, excluding the
+    // packets carrying repair symbols.
+    uint64_t data_delivery_rate;
+
+    // The maximum delivery rate sampled over the last ten minimum RTTs.
+    uint64_t max_bandwidth;
 } quiche_path_stats;
 
 
@@ -782,6 +799,7 @@ enum quiche_path_event_type {
     QUICHE_PATH_EVENT_CLOSED,
     QUICHE_PATH_EVENT_REUSED_SOURCE_CONNECTION_ID,
     QUICHE_PATH_EVENT_PEER_MIGRATED,
+    QUICHE_PATH_EVENT_REDUNDANCY_PATH_DOWN,
 };
 
 typedef struct quiche_path_event quiche_path_event;
@@ -820,6 +838,11 @@ void quiche_path_event_peer_migrated(quiche_path_event *ev,
                            struct sockaddr_storage *local, socklen_t *local_len,
                            struct sockaddr_storage *peer, socklen_t *peer_len);
 
+// Should be called if the quiche_path_event_type(...) returns QUICHE_PATH_EVENT_REDUNDANCY_PATH_DOWN.
+void quiche_path_event_redundancy_path_down(quiche_path_event *ev,
+                           struct sockaddr_storage *local, socklen_t *local_len,
+                           struct sockaddr_storage *peer, socklen_t *peer_len);
+
 // Frees the path event object.
 void quiche_path_event_free(quiche_path_event *ev);
 
@@ -0,0 +1,297 @@
+// Copyright (C) 2024, Cloudflare, Inc.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are
+// met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS
+// IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO,
+// THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR
+// PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+use std::time::Instant;
+
+use networkcoding::source_symbol_metadata_to_u64;
+
+use crate::fec::context::EncoderWindow;
+use crate::path::Path;
+use crate::stream::Stream;
+use crate::Connection;
+
+/// A snapshot of the state of a connection, for debugging purposes.
+///
+/// The snapshot is taken with the [`debug_dump()`] method. When the `serde`
+/// feature is enabled, it can be serialized so that bug reports can include
+/// the state of the connection in a machine-readable form. Its layout has no
+/// stability guarantees across versions.
+///
+/// [`debug_dump()`]: struct.Connection.html#method.debug_dump
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConnectionDump {
+    /// The trace ID of the connection.
+    pub trace_id: String,
+
+    /// Whether the connection is on the server side.
+    pub is_server: bool,
+
+    /// Whether the handshake is completed.
+    pub established: bool,
+
+    /// Whether the connection is closing or closed.
+    pub closed: bool,
+
+    /// The number of stream bytes sent.
+    pub tx_data: u64,
+
+    /// The connection-level flow control limit set by the peer.
+    pub max_tx_data: u64,
+
+    /// The number of stream bytes received.
+    pub rx_data: u64,
+
+    /// The connection-level flow control limit set by the local endpoint.
+    pub max_rx_data: u64,
+
+    /// The known paths.
+    pub paths: Vec<PathDump>,
+
+    /// The open streams, sorted by stream ID.
+    pub streams: Vec<StreamDump>,
+
+    /// The FEC state.
+    pub fec: FECDump,
+}
+
+/// The state of a path in a [`ConnectionDump`].
+///
+/// [`ConnectionDump`]: struct.ConnectionDump.html
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PathDump {
+    /// The local address of the path.
+    pub local_addr: SocketAddr,
+
+    /// The peer address of the path.
+    pub peer_addr: SocketAddr,
+
+    /// Whether the path is marked as active.
+    pub active: bool,
+
+    /// The size of the congestion window in bytes.
+    pub cwnd: usize,
+
+    /// The number of bytes in flight.
+    pub bytes_in_flight: usize,
+
+    /// The slow start threshold, if it was set.
+    pub ssthresh: Option<usize>,
+
+    /// Whether the congestion controller is in slow start.
+    pub in_slow_start: bool,
+
+    /// Whether the path is application-limited.
+    pub app_limited: bool,
+
+    /// The pacing rate in bytes per second.
+    pub pacing_rate: u64,
+
+    /// The time the pacer takes to send a full-sized packet.
+    pub pacing_interval: Option<Duration>,
+
+    /// The estimated round-trip time.
+    pub rtt: Duration,
+
+    /// The minimum round-trip time observed.
+    pub min_rtt: Option<Duration>,
+
+    /// The number of repair symbols sent on the path.
+    pub repair_symbols_sent: usize,
+}
+
+/// The state of a stream in a [`ConnectionDump`].
+///
+/// [`ConnectionDump`]: struct.ConnectionDump.html
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StreamDump {
+    /// The stream ID.
+    pub id: u64,
+
+    /// The urgency of the stream.
+    pub urgency: u8,
+
+    /// The offset up to which the application wrote data.
+    pub send_off_back: u64,
+
+    /// The offset up to which data was sent.
+    pub send_off_front: u64,
+
+    /// The offset up to which data was acknowledged.
+    pub send_ack_off: u64,
+
+    /// The flow control limit set by the peer.
+    pub send_max_off: u64,
+
+    /// Whether the final size of the send side is known.
+    pub send_fin: bool,
+
+    /// The offset up to which the application read data.
+    pub recv_off_front: u64,
+
+    /// The largest offset received.
+    pub recv_max_off: u64,
+
+    /// The flow control limit advertised to the peer.
+    pub recv_max_data: u64,
+
+    /// Whether the final size of the receive side is known.
+    pub recv_fin: bool,
+}
+
+/// The FEC state of a [`ConnectionDump`].
+///
+/// [`ConnectionDump`]: struct.ConnectionDump.html
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FECDump {
+    /// Whether repair symbols are sent.
+    pub emit_fec: bool,
+
+    /// Whether repair symbols are received.
+    pub receive_fec: bool,
+
+    /// The name of the redundancy scheduler, if any.
+    pub scheduler: Option<&'static str>,
+
+    /// The number of repair symbols in flight, as counted by the scheduler.
+    pub scheduler_repair_in_flight: u64,
+
+    /// The time until the scheduler wants to wake up, if any.
+    pub scheduler_timeout: Option<Duration>,
+
+    /// The ID of the first source symbol of the encoding window.
+    pub encoder_first_symbol: Option<u64>,
+
+    /// The ID of the last source symbol of the encoding window.
+    pub encoder_last_symbol: Option<u64>,
+
+    /// The number of source symbols in the encoding window.
+    pub encoder_symbols: usize,
+
+    /// The IDs of the first and last source symbols of the decoding window.
+    pub decoder_window: Option<(u64, u64)>,
+}
+
+impl ConnectionDump {
+    pub(crate) fn new(conn: &Connection, now: Instant) -> ConnectionDump {
+        let mut streams: Vec<StreamDump> = conn
+            .streams
+            .iter()
+            .map(|(id, stream)| StreamDump::new(*id, stream))
+            .collect();
+        streams.sort_by_key(|s| s.id);
+
+        ConnectionDump {
+            trace_id: conn.trace_id.clone(),
+            is_server: conn.is_server,
+            established: conn.is_established(),
+            closed: conn.is_closed() || conn.is_draining(),
+            tx_data: conn.tx_data,
+            max_tx_data: conn.max_tx_data,
+            rx_data: conn.rx_data,
+            max_rx_data: conn.flow_control.max_data(),
+            paths: conn.paths.iter().map(|(_, p)| PathDump::new(p)).collect(),
+            streams,
+            fec: FECDump::new(conn, now),
+        }
+    }
+}
+
+impl PathDump {
+    fn new(path: &Path) -> PathDump {
+        let ssthresh = path.recovery.ssthresh();
+
+        PathDump {
+            local_addr: path.local_addr(),
+            peer_addr: path.peer_addr(),
+            active: path.active(),
+            cwnd: path.recovery.cwnd(),
+            bytes_in_flight: path.recovery.bif(),
+            ssthresh: if ssthresh == usize::MAX {
+                None
+            } else {
+                Some(ssthresh)
+            },
+            in_slow_start: path.recovery.in_slow_start(),
+            app_limited: path.recovery.app_limited(),
+            pacing_rate: path.recovery.pacing_rate(),
+            pacing_interval: path.recovery.pacing_interval(),
+            rtt: path.recovery.rtt(),
+            min_rtt: path.recovery.min_rtt(),
+            repair_symbols_sent: path.repair_symbols_sent,
+        }
+    }
+}
+
+impl StreamDump {
+    fn new(id: u64, stream: &Stream) -> StreamDump {
+        StreamDump {
+            id,
+            urgency: stream.urgency,
+            send_off_back: stream.send.off_back(),
+            send_off_front: stream.send.off_front(),
+            send_ack_off: stream.send.ack_off(),
+            send_max_off: stream.send.max_off(),
+            send_fin: stream.send.is_fin(),
+            recv_off_front: stream.recv.off_front(),
+            recv_max_off: stream.recv.max_off(),
+            recv_max_data: stream.recv.max_data(),
+            recv_fin: stream.recv.is_fin(),
+        }
+    }
+}
+
+impl FECDump {
+    fn new(conn: &Connection, now: Instant) -> FECDump {
+        let scheduler = conn.fec_scheduler.as_ref();
+        let window = EncoderWindow::new(conn.fec_encoder.get());
+
+        FECDump {
+            emit_fec: conn.emit_fec,
+            receive_fec: conn.receive_fec,
+            scheduler: scheduler.map(|s| s.name()),
+            scheduler_repair_in_flight: scheduler
+                .map_or(0, |s| s.n_repair_in_flight()),
+            scheduler_timeout: scheduler
+                .and_then(|s| s.timeout())
+                .map(|t| t.saturating_duration_since(now)),
+            encoder_first_symbol: window.first,
+            encoder_last_symbol: window.last,
+            encoder_symbols: window.n_symbols,
+            decoder_window: conn.fec_decoder.bounds().map(|(first, last)| {
+                (
+                    source_symbol_metadata_to_u64(first),
+                    source_symbol_metadata_to_u64(last),
+                )
+            }),
+        }
+    }
+}
@@ -28,11 +28,26 @@ use crate::Error;
 use crate::Result;
 
 use std::collections::VecDeque;
+use std::time::Instant;
+
+/// The urgency of the DATAGRAMs queued without an explicit one.
+pub const DEFAULT_URGENCY: u8 = 127;
+
+/// A queued DATAGRAM.
+struct Datagram {
+    data: Vec<u8>,
+
+    /// The urgency of the DATAGRAM (lower is better).
+    urgency: u8,
+
+    /// The time after which the DATAGRAM is no longer worth sending.
+    expiry: Option<Instant>,
+}
 
 /// Keeps track of DATAGRAM frames.
 #[derive(Default)]
 pub struct DatagramQueue {
-    queue: Option<VecDeque<Vec<u8>>>,
+    queue: Option<VecDeque<Datagram>>,
     queue_max_len: usize,
     queue_bytes_size: usize,
 }
@@ -47,31 +62,55 @@ impl DatagramQueue {
     }
 
     pub fn push(&mut self, data: Vec<u8>) -> Result<()> {
+        self.push_with_priority(data, DEFAULT_URGENCY, None)
+    }
+
+    /// Queues `data` behind the DATAGRAMs of the same or a lower urgency.
+    pub fn push_with_priority(
+        &mut self, data: Vec<u8>, urgency: u8, expiry: Option<Instant>,
+    ) -> Result<()> {
         if self.is_full() {
             return Err(Error::Done);
         }
 
         self.queue_bytes_size += data.len();
-        self.queue
-            .get_or_insert_with(Default::default)
-            .push_back(data);
+
+        let queue = self.queue.get_or_insert_with(Default::default);
+        let pos = queue
+            .iter()
+            .rposition(|d| d.urgency <= urgency)
+            .map_or(0, |pos| pos + 1);
+
+        queue.insert(pos, Datagram {
+            data,
+            urgency,
+            expiry,
+        });
 
         Ok(())
     }
 
     pub fn peek_front_len(&self) -> Option<usize> {
-        self.queue.as_ref().and_then(|q| q.front().map(|d| d.len()))
+        self.queue
+            .as_ref()
+            .and_then(|q| q.front().map(|d| d.data.len()))
+    }
+
+    pub fn peek_front_expiry(&self) -> Option<Instant> {
+        self.queue
+            .as_ref()
+            .and_then(|q| q.front().and_then(|d| d.expiry))
     }
 
     pub fn peek_front_bytes(&self, buf: &mut [u8], len: usize) -> Result<usize> {
         match self.queue.as_ref().and_then(|q| q.front()) {
             Some(d) => {
-                let len = std::cmp::min(len, d.len());
+                let len = std::cmp::min(len, d.data.len());
                 if buf.len() < len {
                     return Err(Error::BufferTooShort);
                 }
 
-                buf[..len].copy_from_slice(&d[..len]);
+                buf[..len].copy_from_slice(&d.data[..len]);
                 Ok(len)
             },
 
@@ -81,21 +120,37 @@ impl DatagramQueue {
 
     pub fn pop(&mut self) -> Option<Vec<u8>> {
         if let Some(d) = self.queue.as_mut().and_then(|q| q.pop_front()) {
-            self.queue_bytes_size = self.queue_bytes_size.saturating_sub(d.len());
-            return Some(d);
+            self.queue_bytes_size =
+                self.queue_bytes_size.saturating_sub(d.data.len());
+            return Some(d.data);
         }
 
         None
     }
 
+    /// Drops the DATAGRAMs that expired at `now`, and returns how many were
+    /// dropped.
+    pub fn purge_expired(&mut self, now: Instant) -> usize {
+        let len = self.len();
+
+        if let Some(q) = self.queue.as_mut() {
+            q.retain(|d| d.expiry.map_or(true, |expiry| expiry > now));
+            self.queue_bytes_size =
+                q.iter().fold(0, |total, d| total + d.data.len());
+        }
+
+        len - self.len()
+    }
+
     pub fn has_pending(&self) -> bool {
         !self.queue.as_ref().map(|q| q.is_empty()).unwrap_or(true)
     }
 
     pub fn purge<F: Fn(&[u8]) -> bool>(&mut self, f: F) {
         if let Some(q) = self.queue.as_mut() {
-            q.retain(|d| !f(d));
-            self.queue_bytes_size = q.iter().fold(0, |total, d| total + d.len());
+            q.retain(|d| !f(&d.data));
+            self.queue_bytes_size =
+                q.iter().fold(0, |total, d| total + d.data.len());
         }
     }
 
@@ -103,6 +158,11 @@ impl DatagramQueue {
         self.len() == self.queue_max_len
     }
 
+    /// Returns whether `n` more DATAGRAMs can be queued.
+    pub fn has_room(&self, n: usize) -> bool {
+        self.len() + n <= self.queue_max_len
+    }
+
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
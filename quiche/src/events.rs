@@ -0,0 +1,151 @@
+// Copyright (C) 2024, Cloudflare, Inc.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are
+// met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS
+// IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO,
+// THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR
+// PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+
+use crate::Connection;
+use crate::FECEvent;
+use crate::PathEvent;
+
+/// An event of a connection, as yielded by [`Connection::events()`].
+///
+/// [`Connection::events()`]: struct.Connection.html#method.events
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    /// The stream with the given ID has data to read.
+    StreamReadable(u64),
+
+    /// New DATAGRAMs can be read with [`dgram_recv()`].
+    ///
+    /// [`dgram_recv()`]: struct.Connection.html#method.dgram_recv
+    DatagramsReceived,
+
+    /// The given number of source symbols were recovered with FEC.
+    SymbolsRecovered(usize),
+
+    /// A [`PathEvent`].
+    ///
+    /// [`PathEvent`]: enum.PathEvent.html
+    Path(PathEvent),
+
+    /// A [`FECEvent`].
+    ///
+    /// [`FECEvent`]: enum.FECEvent.html
+    FEC(FECEvent),
+}
+
+/// The state of the stream of events of a connection.
+#[derive(Default)]
+pub(crate) struct EventsState {
+    waker: Option<Waker>,
+    datagrams_received: bool,
+    recovered: usize,
+}
+
+impl EventsState {
+    pub fn received_datagram(&mut self) {
+        self.datagrams_received = true;
+    }
+
+    /// Wakes the task waiting for the next event, if any.
+    pub fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// An asynchronous stream of the events of a connection.
+///
+/// The stream is woken up when the connection processes incoming packets or
+/// timeouts, and ends once the connection is closed. It is returned by
+/// [`Connection::events()`].
+///
+/// [`Connection::events()`]: struct.Connection.html#method.events
+pub struct ConnectionEvents<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> ConnectionEvents<'a> {
+    pub(crate) fn new(conn: &'a mut Connection) -> ConnectionEvents<'a> {
+        ConnectionEvents { conn }
+    }
+
+    fn next_event(&mut self) -> Option<ConnectionEvent> {
+        let conn = &mut *self.conn;
+
+        if let Some(ev) = conn.path_event_next() {
+            return Some(ConnectionEvent::Path(ev));
+        }
+
+        if let Some(ev) = conn.fec_event_next() {
+            return Some(ConnectionEvent::FEC(ev));
+        }
+
+        if conn.recov_count > conn.events.recovered {
+            let recovered = conn.recov_count - conn.events.recovered;
+            conn.events.recovered = conn.recov_count;
+
+            return Some(ConnectionEvent::SymbolsRecovered(recovered));
+        }
+
+        if conn.events.datagrams_received {
+            conn.events.datagrams_received = false;
+
+            if conn.dgram_recv_queue_len() > 0 {
+                return Some(ConnectionEvent::DatagramsReceived);
+            }
+        }
+
+        conn.stream_readable_next()
+            .map(ConnectionEvent::StreamReadable)
+    }
+}
+
+impl futures_core::Stream for ConnectionEvents<'_> {
+    type Item = ConnectionEvent;
+
+    fn poll_next(
+        self: Pin<&mut Self>, cx: &mut Context<'_>,
+    ) -> Poll<Option<ConnectionEvent>> {
+        let this = self.get_mut();
+
+        if let Some(ev) = this.next_event() {
+            return Poll::Ready(Some(ev));
+        }
+
+        if this.conn.is_closed() {
+            return Poll::Ready(None);
+        }
+
+        this.conn.events.waker = Some(cx.waker().clone());
+
+        Poll::Pending
+    }
+}
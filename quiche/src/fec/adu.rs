@@ -0,0 +1,16 @@
+/// The end of an application data unit (ADU) within a buffer written with
+/// [`stream_send_with_boundaries()`].
+///
+/// An ADU, such as a video frame or a game state update, spans the stream
+/// data from the end of the previous ADU of the stream, or from the start of
+/// the stream, to its end.
+///
+/// [`stream_send_with_boundaries()`]: struct.Connection.html#method.stream_send_with_boundaries
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AduBoundary {
+    /// The identifier of the ADU, reported when the peer recovers its data.
+    pub id: u64,
+
+    /// The offset in the buffer at which the ADU ends.
+    pub end: usize,
+}
@@ -1,7 +1,4 @@
-use networkcoding::Encoder;
-
-use crate::path::Path;
-use crate::Connection;
+use crate::fec::context::SchedulerContext;
 
 const DEFAULT_DELAYING_DURATION: std::time::Duration =
     std::time::Duration::from_millis(2);
@@ -33,12 +30,12 @@ impl BackgroundFECScheduler {
         self.rs_sent_for_this_round = false;
     }
 
-    pub fn should_send_repair(
-        &mut self, conn: &Connection, path: &Path, symbol_size: usize,
-    ) -> bool {
-        let now = std::time::Instant::now();
-        let dgrams_to_emit = conn.dgram_max_writable_len().is_some();
-        let stream_to_emit = conn.streams.has_flushable();
+    pub fn should_send_repair(&mut self, ctx: &SchedulerContext) -> bool {
+        let now = ctx.now;
+        let symbol_size = ctx.symbol_size;
+        let repair_packet_size = ctx.repair_packet_size;
+        let dgrams_to_emit = ctx.dgrams_to_emit;
+        let stream_to_emit = ctx.stream_to_emit;
         if let Ok(val) =
             std::env::var("DEBUG_QUICHE_FEC_BACKGROUND_DELAYING_DURATION_US")
         {
@@ -49,15 +46,17 @@ impl BackgroundFECScheduler {
         }
         // send if no more data to send && we sent less repair than half the cwin
 
-        let bif = path.recovery.bif();
+        let bif = ctx.bif;
+        let packets_lost_per_round_trip = ctx.packets_lost_per_round_trip;
+        let var_packets_lost_per_round_trip = ctx.var_packets_lost_per_round_trip;
         let max_repair_data = if bif < symbol_size {
             0
         } else if bif < 15000 {
             bif * 3 / 5
         } else {
-            match path.recovery.packets_lost_per_round_trip() {
+            match packets_lost_per_round_trip {
                 None => std::cmp::min(
-                    REPAIR_TO_SEND_WITH_NO_LOSS_INFO * symbol_size,
+                    REPAIR_TO_SEND_WITH_NO_LOSS_INFO * repair_packet_size,
                     bif / 4,
                 ),
                 Some(packets_lost_per_round_trip) => {
@@ -65,11 +64,9 @@ impl BackgroundFECScheduler {
                     // avg_lost_packets_per_roundtrip + 4 * variation
                     std::cmp::min(
                         (packets_lost_per_round_trip +
-                            2.0 * path
-                                .recovery
-                                .var_packets_lost_per_round_trip()
-                                .ceil()) as usize *
-                            symbol_size,
+                            2.0 * var_packets_lost_per_round_trip.ceil())
+                            as usize *
+                            repair_packet_size,
                         bif / 3,
                     )
                 },
@@ -77,10 +74,11 @@ impl BackgroundFECScheduler {
         };
 
         trace!("fec_scheduler dgrams_to_emit={} stream_to_emit={} n_repair_in_flight={} max_repair_data={} packets_lost_per_round_trip={:?} variance={}",
-                dgrams_to_emit, stream_to_emit, self.n_repair_in_flight, max_repair_data, path.recovery.packets_lost_per_round_trip(), path.recovery.var_packets_lost_per_round_trip());
+                dgrams_to_emit, stream_to_emit, self.n_repair_in_flight, max_repair_data, packets_lost_per_round_trip, var_packets_lost_per_round_trip);
         let repair_symbol_required = !dgrams_to_emit &&
             !stream_to_emit &&
-            (self.n_repair_in_flight as usize * symbol_size) < max_repair_data;
+            (self.n_repair_in_flight as usize * repair_packet_size) <
+                max_repair_data;
         if !repair_symbol_required {
             self.reset_rs_delaying();
             false
@@ -104,23 +102,27 @@ impl BackgroundFECScheduler {
         }
     }
 
-    pub fn sent_repair_symbol(&mut self, _encoder: &Encoder) {
+    pub fn sent_repair_symbol(&mut self) {
         self.n_repair_in_flight += 1;
         self.rs_sent_for_this_round = true;
     }
 
-    pub fn acked_repair_symbol(&mut self, _encoder: &Encoder) {
+    pub fn acked_repair_symbol(&mut self) {
         self.n_repair_in_flight -= 1;
     }
 
-    pub fn sent_source_symbol(&mut self, _encoder: &Encoder) {
+    pub fn n_repair_in_flight(&self) -> u64 {
+        self.n_repair_in_flight
+    }
+
+    pub fn sent_source_symbol(&mut self) {
         // reset the delaying logic, we start a new round as we send new source
         // symbols
         self.reset_rs_delaying();
     }
 
-    pub fn lost_repair_symbol(&mut self, encoder: &Encoder) {
-        self.acked_repair_symbol(encoder)
+    pub fn lost_repair_symbol(&mut self) {
+        self.acked_repair_symbol()
     }
 
     // returns an Instant at which the stack should wake up to sent new repair
@@ -134,3 +136,146 @@ impl BackgroundFECScheduler {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fec::fec_scheduler::FECSchedulerAlgorithm;
+    use crate::fec::testing;
+    use crate::fec::trace::TraceEvent;
+
+    use std::time::Duration;
+    use std::time::Instant;
+
+    // Returns the number of repair symbols sent once the delaying duration
+    // has elapsed.
+    fn repair_symbols_sent(ctx: SchedulerContext) -> usize {
+        let mut scheduler = BackgroundFECScheduler::new();
+
+        scheduler.should_send_repair(&ctx);
+
+        let ctx = SchedulerContext {
+            now: ctx.now + DEFAULT_DELAYING_DURATION,
+            ..ctx
+        };
+
+        let mut sent = 0;
+
+        while scheduler.should_send_repair(&ctx) {
+            scheduler.sent_repair_symbol();
+            sent += 1;
+        }
+
+        sent
+    }
+
+    #[test]
+    fn repair_budget() {
+        let ctx = testing::context(Instant::now());
+
+        // Less than a symbol in flight.
+        assert_eq!(
+            repair_symbols_sent(SchedulerContext { bif: 1000, ..ctx }),
+            0
+        );
+
+        // Small flights are protected at 60%.
+        assert_eq!(
+            repair_symbols_sent(SchedulerContext { bif: 6000, ..ctx }),
+            3
+        );
+
+        // Without loss information, large flights are capped.
+        assert_eq!(
+            repair_symbols_sent(SchedulerContext { bif: 30000, ..ctx }),
+            REPAIR_TO_SEND_WITH_NO_LOSS_INFO
+        );
+
+        // Otherwise, the budget follows the loss estimations.
+        assert_eq!(
+            repair_symbols_sent(SchedulerContext {
+                bif: 30000,
+                packets_lost_per_round_trip: Some(1.0),
+                var_packets_lost_per_round_trip: 0.25,
+                ..ctx
+            }),
+            3
+        );
+    }
+
+    #[test]
+    fn timeout() {
+        let t0 = Instant::now();
+        let idle = SchedulerContext {
+            bif: 6000,
+            ..testing::context(t0)
+        };
+
+        let mut scheduler = BackgroundFECScheduler::new();
+        assert_eq!(scheduler.timeout(), None);
+
+        assert!(!scheduler.should_send_repair(&idle));
+        assert_eq!(scheduler.timeout(), Some(t0 + DEFAULT_DELAYING_DURATION));
+
+        let idle = SchedulerContext {
+            now: t0 + DEFAULT_DELAYING_DURATION,
+            ..idle
+        };
+
+        assert!(scheduler.should_send_repair(&idle));
+        scheduler.sent_repair_symbol();
+        assert_eq!(scheduler.timeout(), None);
+    }
+
+    #[test]
+    fn golden_trace() {
+        let t0 = Instant::now();
+        let ms = Duration::from_millis;
+
+        let idle = SchedulerContext {
+            bif: 6000,
+            ..testing::context(t0)
+        };
+        let at = |d| SchedulerContext {
+            now: t0 + d,
+            ..idle
+        };
+
+        testing::assert_golden_trace(FECSchedulerAlgorithm::BackgroundOnly, &[
+            // Nothing is sent while the application has data to send.
+            testing::decide(
+                SchedulerContext {
+                    stream_to_emit: true,
+                    ..idle
+                },
+                false,
+            ),
+            // Once idle, repair symbols are delayed.
+            testing::decide(idle, false),
+            testing::decide(at(ms(1)), false),
+            // 60% of the bytes in flight are then protected.
+            testing::decide(at(ms(2)), true),
+            testing::sent_repair(t0 + ms(2)),
+            testing::decide(at(ms(2)), true),
+            testing::sent_repair(t0 + ms(2)),
+            testing::decide(at(ms(2)), true),
+            testing::sent_repair(t0 + ms(2)),
+            testing::decide(at(ms(2)), false),
+            // An acknowledged repair symbol frees some budget, after a new
+            // delay.
+            TraceEvent::AckedRepair { time: t0 + ms(10) },
+            testing::decide(at(ms(10)), false),
+            testing::decide(at(ms(12)), true),
+            testing::sent_repair(t0 + ms(12)),
+            // A new source symbol restarts the delay.
+            TraceEvent::SentSource {
+                time: t0 + ms(13),
+                window: testing::window(0, 5, t0),
+            },
+            TraceEvent::LostRepair { time: t0 + ms(13) },
+            testing::decide(at(ms(13)), false),
+            testing::decide(at(ms(15)), true),
+        ]);
+    }
+}
@@ -0,0 +1,186 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::recovery::Recovery;
+
+enum State {
+    /// Waiting for the next probe, if it is not due yet.
+    Idle { next: Option<Instant> },
+
+    /// Filling the congestion window with repair symbols until `end`.
+    Probing { end: Instant, base_rtt: Duration },
+}
+
+/// Periodically probes the available bandwidth of a path with repair symbols.
+///
+/// A probe saves the congestion window of the path, then fills the
+/// congestion window with repair symbols for one round-trip, so that the
+/// controller sees a saturated path and grows its window. The repair symbols
+/// are padding that still protects the application data.
+///
+/// If the latest RTT rises above the RTT at the start of the probe by more
+/// than the tolerated ratio, the probe filled a queue instead of spare
+/// capacity: it stops, and the growth of the congestion window since the
+/// start of the probe is undone.
+pub(crate) struct BandwidthProbe {
+    interval: Duration,
+    max_delay_increase: f64,
+    state: State,
+    probes: usize,
+    rollbacks: usize,
+    probe_bytes: u64,
+}
+
+impl BandwidthProbe {
+    pub fn new(interval: Duration, max_delay_increase: f64) -> BandwidthProbe {
+        BandwidthProbe {
+            interval,
+            max_delay_increase: max_delay_increase.max(0.0),
+            state: State::Idle { next: None },
+            probes: 0,
+            rollbacks: 0,
+            probe_bytes: 0,
+        }
+    }
+
+    /// Returns whether a repair symbol should be sent at `now` to probe the
+    /// bandwidth of the path of `recovery`, starting and ending the probes.
+    pub fn should_send_repair(
+        &mut self, now: Instant, recovery: &mut Recovery,
+    ) -> bool {
+        match self.state {
+            State::Idle { next } => {
+                if next.map_or(false, |next| now < next) {
+                    return false;
+                }
+
+                let base_rtt = recovery.latest_rtt();
+
+                // There is no delay to compare to yet.
+                if base_rtt.is_zero() {
+                    return false;
+                }
+
+                recovery.checkpoint_congestion();
+
+                self.state = State::Probing {
+                    end: now + recovery.rtt(),
+                    base_rtt,
+                };
+                self.probes += 1;
+
+                true
+            },
+
+            State::Probing { end, base_rtt } => {
+                let max_rtt = base_rtt.mul_f64(1.0 + self.max_delay_increase);
+
+                if recovery.latest_rtt() > max_rtt {
+                    recovery.rollback_congestion();
+
+                    self.rollbacks += 1;
+                    self.state = State::Idle {
+                        next: Some(now + self.interval),
+                    };
+
+                    return false;
+                }
+
+                // The path absorbed the probe, keep the grown window.
+                if now >= end {
+                    self.state = State::Idle {
+                        next: Some(now + self.interval),
+                    };
+
+                    return false;
+                }
+
+                true
+            },
+        }
+    }
+
+    /// Records a repair symbol of `len` bytes sent at `now`, whatever the
+    /// reason it was sent.
+    pub fn sent_repair_symbol(&mut self, len: u64, now: Instant) {
+        if let State::Probing { end, .. } = self.state {
+            if now < end {
+                self.probe_bytes += len;
+            }
+        }
+    }
+
+    /// Returns the number of probes started.
+    pub fn probes(&self) -> usize {
+        self.probes
+    }
+
+    /// Returns the number of probes that were rolled back.
+    pub fn rollbacks(&self) -> usize {
+        self.rollbacks
+    }
+
+    /// Returns the number of repair bytes sent during the probes.
+    pub fn probe_bytes(&self) -> u64 {
+        self.probe_bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::CongestionControlAlgorithm;
+
+    fn recovery() -> Recovery {
+        let mut cfg = crate::Config::new(crate::PROTOCOL_VERSION).unwrap();
+        cfg.set_cc_algorithm(CongestionControlAlgorithm::Reno);
+
+        Recovery::new(&cfg)
+    }
+
+    #[test]
+    fn probe() {
+        let now = Instant::now();
+        let ms = Duration::from_millis(1);
+        let mut r = recovery();
+        let mut probe = BandwidthProbe::new(100 * ms, 0.25);
+
+        // No RTT sample yet.
+        assert!(!probe.should_send_repair(now, &mut r));
+        assert_eq!(probe.probes(), 0);
+
+        r.update_rtt(50 * ms, now);
+        let cwnd = r.cwnd();
+
+        // Only the repair symbols sent during a probe are counted.
+        probe.sent_repair_symbol(100, now);
+        assert_eq!(probe.probe_bytes(), 0);
+
+        assert!(probe.should_send_repair(now, &mut r));
+        assert_eq!(probe.probes(), 1);
+
+        probe.sent_repair_symbol(100, now);
+        probe.sent_repair_symbol(100, now + 50 * ms);
+        assert_eq!(probe.probe_bytes(), 100);
+
+        // The path absorbs the probe for one RTT.
+        r.set_cwnd(cwnd * 2);
+        assert!(probe.should_send_repair(now + 49 * ms, &mut r));
+        assert!(!probe.should_send_repair(now + 50 * ms, &mut r));
+        assert_eq!(r.cwnd(), cwnd * 2);
+
+        // The next probe waits for the interval.
+        assert!(!probe.should_send_repair(now + 149 * ms, &mut r));
+        assert!(probe.should_send_repair(now + 150 * ms, &mut r));
+        assert_eq!(probe.probes(), 2);
+
+        // The delay rises, the probe is rolled back.
+        r.set_cwnd(cwnd * 4);
+        r.update_rtt(70 * ms, now + 160 * ms);
+
+        assert!(!probe.should_send_repair(now + 160 * ms, &mut r));
+        assert_eq!(probe.rollbacks(), 1);
+        assert_eq!(r.cwnd(), cwnd * 2);
+    }
+}
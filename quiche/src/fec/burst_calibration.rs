@@ -0,0 +1,180 @@
+use std::time::Duration;
+use std::time::Instant;
+
+/// The weight of a new burst in the moving averages of the bursts.
+const EWMA_ALPHA: f64 = 0.125;
+
+/// The number of bursts observed before the learned parameters are used.
+const MIN_BURSTS: u64 = 4;
+
+/// The fraction of the mean burst size a burst must reach to be protected.
+const BURST_SIZE_FRACTION: f64 = 0.5;
+
+/// The fraction of the mean interval between bursts during which no new
+/// repair round is started.
+const COOLDOWN_FRACTION: f64 = 0.5;
+
+/// Whether the `bursts` FEC scheduler learns its parameters from the sending
+/// pattern of the application.
+///
+/// See [`Config::set_fec_auto_calibrate()`].
+///
+/// [`Config::set_fec_auto_calibrate()`]: struct.Config.html#method.set_fec_auto_calibrate
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FECAutoCalibrate {
+    /// The minimum size of the protected bursts is fixed, and repair rounds
+    /// may start back to back (default).
+    #[default]
+    Disabled,
+
+    /// The minimum size of the protected bursts and the cooldown between two
+    /// repair rounds are derived from the size and the interval of the
+    /// bursts sent by the application, e.g. the frames of a video.
+    Enabled,
+}
+
+/// The parameters of the `bursts` FEC scheduler learned from the sending
+/// pattern of the application.
+///
+/// See [`Connection::fec_burst_calibration()`].
+///
+/// [`Connection::fec_burst_calibration()`]: struct.Connection.html#method.fec_burst_calibration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FECBurstCalibration {
+    /// The number of bursts observed.
+    pub bursts: u64,
+
+    /// The mean number of stream bytes of a burst.
+    pub mean_burst_bytes: usize,
+
+    /// The mean interval between the starts of two bursts.
+    pub mean_burst_interval: Duration,
+
+    /// The number of stream bytes a burst must reach to be protected.
+    pub burst_size: usize,
+
+    /// The minimum time between the starts of two repair rounds.
+    pub cooldown: Duration,
+}
+
+/// Learns the size and the interval of the bursts of the application.
+///
+/// A burst starts with the first source symbol sent after the application
+/// was idle, and ends when the application has nothing to send anymore.
+#[derive(Default)]
+pub(crate) struct BurstCalibrator {
+    burst_start: Option<Instant>,
+    last_burst_start: Option<Instant>,
+
+    bursts: u64,
+    mean_bytes: Option<f64>,
+    mean_interval: Option<f64>,
+}
+
+impl BurstCalibrator {
+    pub fn sent_source_symbol(&mut self, now: Instant) {
+        if self.burst_start.is_none() {
+            self.burst_start = Some(now);
+        }
+    }
+
+    /// Records the end of the ongoing burst, which carried `bytes` stream
+    /// bytes.
+    pub fn burst_ended(&mut self, bytes: usize) {
+        let start = match self.burst_start.take() {
+            Some(start) => start,
+
+            None => return,
+        };
+
+        // Bursts of DATAGRAMs only don't advance the stream bytes the
+        // scheduler relies on.
+        if bytes == 0 {
+            return;
+        }
+
+        let ewma = |mean: Option<f64>, sample: f64| match mean {
+            Some(mean) => mean + EWMA_ALPHA * (sample - mean),
+
+            None => sample,
+        };
+
+        if let Some(last) = self.last_burst_start {
+            let interval = start.saturating_duration_since(last).as_secs_f64();
+
+            self.mean_interval = Some(ewma(self.mean_interval, interval));
+        }
+
+        self.mean_bytes = Some(ewma(self.mean_bytes, bytes as f64));
+        self.last_burst_start = Some(start);
+        self.bursts += 1;
+    }
+
+    /// Returns the learned parameters, once enough bursts were observed.
+    pub fn calibration(&self) -> Option<FECBurstCalibration> {
+        if self.bursts < MIN_BURSTS {
+            return None;
+        }
+
+        let mean_bytes = self.mean_bytes?;
+        let mean_interval = Duration::from_secs_f64(self.mean_interval?);
+
+        Some(FECBurstCalibration {
+            bursts: self.bursts,
+            mean_burst_bytes: mean_bytes as usize,
+            mean_burst_interval: mean_interval,
+            burst_size: (mean_bytes * BURST_SIZE_FRACTION) as usize,
+            cooldown: mean_interval.mul_f64(COOLDOWN_FRACTION),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn video_frames() {
+        let t0 = Instant::now();
+        let frame_interval = Duration::from_millis(33);
+
+        let mut calibrator = BurstCalibrator::default();
+
+        for i in 0..MIN_BURSTS as u32 {
+            assert_eq!(calibrator.calibration(), None);
+
+            let start = t0 + frame_interval * i;
+            calibrator.sent_source_symbol(start);
+            calibrator.sent_source_symbol(start + Duration::from_millis(2));
+            calibrator.burst_ended(8000);
+        }
+
+        let calibration = calibrator.calibration().unwrap();
+        assert_eq!(calibration.bursts, MIN_BURSTS);
+        assert_eq!(calibration.mean_burst_bytes, 8000);
+        assert_eq!(calibration.burst_size, 4000);
+
+        let error = |a: Duration, b: Duration| {
+            (a.as_secs_f64() - b.as_secs_f64()).abs()
+        };
+        assert!(error(calibration.mean_burst_interval, frame_interval) < 1e-6);
+        assert!(error(calibration.cooldown, frame_interval / 2) < 1e-6);
+    }
+
+    #[test]
+    fn idle_without_burst() {
+        let t0 = Instant::now();
+
+        let mut calibrator = BurstCalibrator::default();
+
+        // The application being idle without sending anything is not a
+        // burst, nor are DATAGRAM-only bursts.
+        calibrator.burst_ended(1000);
+        calibrator.sent_source_symbol(t0);
+        calibrator.burst_ended(0);
+
+        assert_eq!(calibrator.bursts, 0);
+        assert_eq!(calibrator.last_burst_start, None);
+    }
+}
@@ -1,8 +1,9 @@
-use networkcoding::Encoder;
-use networkcoding::SourceSymbolMetadata;
-
-use crate::path::Path;
-use crate::Connection;
+use crate::fec::burst_calibration::BurstCalibrator;
+use crate::fec::burst_calibration::FECBurstCalibration;
+use crate::fec::context::EncoderWindow;
+use crate::fec::context::SchedulerContext;
+use crate::fec::fec_scheduler::BurstDetection;
+use crate::fec::hysteresis::RepairHysteresis;
 use std::env;
 
 #[derive(Debug, Clone, Copy)]
@@ -11,10 +12,12 @@ struct SendingState {
     when: std::time::Instant,
     _burst_start_offset: usize,
     _burst_size: usize,
-    last_metadata_when_triggered: SourceSymbolMetadata,
+    last_metadata_when_triggered: u64,
     repair_bytes_to_send: usize,
-    repair_symbols_sent: usize, /* number of repair symbols sent during this
-                                 * state */
+    raw_repair_bytes_to_send: usize, // budget before smoothing
+    repair_symbols_sent: usize,      /* number of repair symbols sent during
+                                      * this
+                                      * state */
 }
 pub(crate) struct BurstsFECScheduler {
     n_repair_in_flight: u64,
@@ -26,6 +29,15 @@ pub(crate) struct BurstsFECScheduler {
     n_source_symbols_sent_since_last_repair: usize,
     state_sending_repair: Option<SendingState>,
     next_timeout: Option<std::time::Instant>,
+    hysteresis: RepairHysteresis,
+    burst_detection: BurstDetection,
+    // the time at which the last source symbol of the ongoing burst was sent
+    last_source_symbol_sent_time: Option<std::time::Instant>,
+    // learns the burst size and the cooldown from the application's bursts,
+    // if enabled
+    calibrator: Option<BurstCalibrator>,
+    // the time at which the last sending round started
+    last_round_start: Option<std::time::Instant>,
 }
 
 const DEFAULT_BURST_SIZE: usize = 15000;
@@ -35,7 +47,7 @@ const DEFAULT_MINIMUM_ROOM_IN_CWIN: usize = 5000;
 const DEFAULT_STDDEV_FACTOR: f64 = 2.0;
 
 impl BurstsFECScheduler {
-    pub fn new() -> BurstsFECScheduler {
+    pub fn new(burst_detection: BurstDetection) -> BurstsFECScheduler {
         BurstsFECScheduler {
             n_repair_in_flight: 0,
             n_packets_sent_when_nothing_to_send: 0,
@@ -46,13 +58,30 @@ impl BurstsFECScheduler {
             n_source_symbols_sent_since_last_repair: 0,
             state_sending_repair: None,
             next_timeout: None,
+            hysteresis: RepairHysteresis::from_env(),
+            burst_detection,
+            last_source_symbol_sent_time: None,
+            calibrator: None,
+            last_round_start: None,
         }
     }
 
-    pub fn should_send_repair(
-        &mut self, conn: &Connection, path: &Path, symbol_size: usize,
-    ) -> bool {
-        let now = std::time::Instant::now();
+    /// Derives the burst size and the cooldown between two sending rounds
+    /// from the bursts sent by the application.
+    pub fn enable_auto_calibration(&mut self) {
+        self.calibrator = Some(BurstCalibrator::default());
+    }
+
+    /// Returns the parameters learned from the bursts of the application, if
+    /// enough of them were observed.
+    pub fn calibration(&self) -> Option<FECBurstCalibration> {
+        self.calibrator.as_ref().and_then(|c| c.calibration())
+    }
+
+    pub fn should_send_repair(&mut self, ctx: &SchedulerContext) -> bool {
+        let now = ctx.now;
+        let symbol_size = ctx.symbol_size;
+        let repair_packet_size = ctx.repair_packet_size;
         // this variable can be overriden by the DEBUG_QUICHE_FEC_BURST_SIZE_BYTES
         // environment variable for debug purposes
         let threshold_burst_size: usize =
@@ -79,27 +108,59 @@ impl BurstsFECScheduler {
             .parse()
             .unwrap_or(DEFAULT_STDDEV_FACTOR);
 
-        let dgrams_to_emit = conn.dgram_max_writable_len().is_some();
-        let stream_to_emit = conn.streams.has_flushable();
+        // the learned burst size overrides the configured one, and a new
+        // sending round only starts once the cooldown elapsed
+        let calibration = self.calibration();
+        let threshold_burst_size =
+            calibration.map_or(threshold_burst_size, |c| c.burst_size);
+        let cooldown_elapsed = match (calibration, self.last_round_start) {
+            (Some(c), Some(last)) => now >= last + c.cooldown,
+            _ => true,
+        };
+
+        let dgrams_to_emit = ctx.dgrams_to_emit;
+        let stream_to_emit = ctx.stream_to_emit;
         // send if no more data to send && we sent less repair than half the cwin
 
-        let bif = path.recovery.bif();
-        let cwin_available = path.recovery.cwnd_available();
+        let rtt = ctx.rtt;
+        let bif = ctx.bif;
+        let packets_lost_per_round_trip = ctx.packets_lost_per_round_trip;
+        let var_packets_lost_per_round_trip = ctx.var_packets_lost_per_round_trip;
+        let cwin_available = ctx.cwnd_available;
         let enough_room_in_cwin = cwin_available > minimum_room_in_cwin;
-        let nothing_to_send = !dgrams_to_emit && !stream_to_emit;
-        let current_sent_count = conn.sent_count;
-        let current_sent_stream_bytes = conn.tx_data as usize;
+        let nothing_to_send = ctx.nothing_to_send();
+        let current_sent_count = ctx.sent_count;
+        let current_sent_stream_bytes = ctx.tx_data as usize;
         self.current_burst_size = current_sent_stream_bytes -
             self.n_sent_stream_bytes_sent_when_nothing_to_send;
         let sent_enough_protected_data =
             self.current_burst_size > threshold_burst_size;
+        // the time at which the ongoing burst ends if it is detected by the
+        // gap between two source symbols
+        let burst_end = self
+            .last_source_symbol_sent_time
+            .and_then(|t| self.burst_detection.burst_end(t, ctx.pacing_interval));
+        let burst_ended = match (self.burst_detection, burst_end) {
+            (BurstDetection::Bytes, _) =>
+                nothing_to_send && sent_enough_protected_data,
+            (BurstDetection::InterSendGap(_), Some(burst_end)) =>
+                now >= burst_end,
+            // no pacing rate estimate, fall back to the application being idle
+            (BurstDetection::InterSendGap(_), None) =>
+                nothing_to_send && self.last_source_symbol_sent_time.is_some(),
+        };
 
         if let Some(state) = self.state_sending_repair {
-            if state.repair_symbols_sent * symbol_size >=
-                state.repair_bytes_to_send
+            // a round that spent its budget still lasts for the minimum dwell
+            // time, like a round whose symbols left the window
+            if state.repair_symbols_sent * repair_packet_size >=
+                state.repair_bytes_to_send &&
+                self.hysteresis.can_switch(now, rtt)
             {
                 // finished this sending round
                 trace!("clear finished sending round");
+                self.hysteresis
+                    .round_finished(now, state.raw_repair_bytes_to_send);
                 self.state_sending_repair = None;
             }
         }
@@ -108,45 +169,56 @@ impl BurstsFECScheduler {
                 current_sent_bytes={} old_sent_bytes={} current_burst_size={} sent_enough_protected_data={}
                 enough_room_in_cwin={} cwin_available={} minimum_room_in_cwin={}
                 elapsed_since_first_source_symbol={:?} fec_max_jitter={:?}
-                packets_lost_per_rtt={:?} var_packets_lost_per_rtt={:?}",
+                packets_lost_per_rtt={:?} var_packets_lost_per_rtt={:?} hysteresis_active={}",
                 now, dgrams_to_emit, stream_to_emit, self.n_repair_in_flight, self.state_sending_repair, current_sent_count, self.n_packets_sent_when_nothing_to_send,
                 current_sent_stream_bytes, self.n_sent_stream_bytes_sent_when_nothing_to_send, self.current_burst_size, sent_enough_protected_data,
                 enough_room_in_cwin,
                 cwin_available, minimum_room_in_cwin, self.earliest_unprotected_source_symbol_sent_time.map(|t| t.elapsed()), max_jitter,
-                path.recovery.packets_lost_per_round_trip(), path.recovery.var_packets_lost_per_round_trip()
+                packets_lost_per_round_trip, var_packets_lost_per_round_trip,
+                self.hysteresis.active()
             );
 
-        self.state_sending_repair = if conn.fec_encoder.last_metadata().is_some() &&
-            self.state_sending_repair.is_none() &&
-            nothing_to_send &&
-            sent_enough_protected_data
-        {
-            Some(SendingState {
-                _start_time: now,
-                when: now + max_jitter,
-                last_metadata_when_triggered: conn
-                    .fec_encoder
-                    .last_metadata()
-                    .unwrap(),
-                _burst_start_offset: current_sent_stream_bytes,
-                _burst_size: self.current_burst_size,
-                repair_bytes_to_send: 0, // start with 0 and update afterwards
-                repair_symbols_sent: 0,
-            })
-        } else {
-            // the state expires after the considered symbols have all landed
-            if let Some(state) = self.state_sending_repair {
-                if !conn
-                    .fec_encoder
-                    .contains_symbol(state.last_metadata_when_triggered)
-                {
-                    None
+        self.state_sending_repair = match ctx.window.last {
+            Some(last_metadata)
+                if self.state_sending_repair.is_none() &&
+                    burst_ended &&
+                    cooldown_elapsed &&
+                    self.hysteresis.can_switch(now, rtt) =>
+            {
+                self.hysteresis.round_started(now);
+                self.last_source_symbol_sent_time = None;
+                self.last_round_start = Some(now);
+                Some(SendingState {
+                    _start_time: now,
+                    when: now + max_jitter,
+                    last_metadata_when_triggered: last_metadata,
+                    _burst_start_offset: current_sent_stream_bytes,
+                    _burst_size: self.current_burst_size,
+                    // start with 0 and update afterwards
+                    repair_bytes_to_send: 0,
+                    raw_repair_bytes_to_send: 0,
+                    repair_symbols_sent: 0,
+                })
+            },
+
+            // the state expires after the considered symbols have all landed,
+            // allowing for the jitter of the path so that a late RTT sample
+            // does not end the round prematurely
+            _ =>
+                if let Some(state) = self.state_sending_repair {
+                    if !ctx.window.contains(state.last_metadata_when_triggered) &&
+                        self.hysteresis
+                            .can_switch(now, ctx.rtt_with_variation())
+                    {
+                        self.hysteresis
+                            .round_finished(now, state.raw_repair_bytes_to_send);
+                        None
+                    } else {
+                        self.state_sending_repair
+                    }
                 } else {
-                    self.state_sending_repair
-                }
-            } else {
-                None
-            }
+                    None
+                },
         };
 
         // increase the amount of repair symbols to send if needed
@@ -161,7 +233,7 @@ impl BurstsFECScheduler {
             } else {
                 let amount_to_protect_when_no_loss_info =
                     bytes_to_protect / fec_frac_denominator_to_protect;
-                match path.recovery.packets_lost_per_round_trip() {
+                match packets_lost_per_round_trip {
                     None => {
                         // no loss info, protect an arbitrary fraction
                         amount_to_protect_when_no_loss_info
@@ -172,24 +244,30 @@ impl BurstsFECScheduler {
                         std::cmp::min(
                             (packets_lost_per_round_trip +
                                 stddev_factor *
-                                    path.recovery
-                                        .var_packets_lost_per_round_trip()
-                                        .ceil())
+                                    var_packets_lost_per_round_trip.ceil())
                                 as usize *
-                                symbol_size,
+                                repair_packet_size,
                             amount_to_protect_when_no_loss_info,
                         )
                     },
                 }
             };
-            state.repair_bytes_to_send =
-                state.repair_bytes_to_send.max(max_repair_data);
+            state.raw_repair_bytes_to_send =
+                state.raw_repair_bytes_to_send.max(max_repair_data);
+            // smooth the budget with the ones of the previous rounds to avoid
+            // oscillating overhead
+            state.repair_bytes_to_send = state
+                .repair_bytes_to_send
+                .max(self.hysteresis.smoothed_budget(max_repair_data));
         }
 
         if nothing_to_send {
-            self.n_packets_sent_when_nothing_to_send = conn.sent_count;
+            if let Some(calibrator) = &mut self.calibrator {
+                calibrator.burst_ended(self.current_burst_size);
+            }
+            self.n_packets_sent_when_nothing_to_send = ctx.sent_count;
             self.n_sent_stream_bytes_sent_when_nothing_to_send =
-                conn.tx_data as usize;
+                ctx.tx_data as usize;
             self.current_burst_size = 0;
         }
 
@@ -197,7 +275,7 @@ impl BurstsFECScheduler {
         let should_send = match self.state_sending_repair {
             Some(state) =>
                 now >= state.when &&
-                    (state.repair_symbols_sent * symbol_size) <
+                    (state.repair_symbols_sent * repair_packet_size) <
                         state.repair_bytes_to_send,
             None => false,
         };
@@ -210,12 +288,16 @@ impl BurstsFECScheduler {
                 self.next_timeout = None;
             }
         } else {
-            self.next_timeout = None;
+            // wake up when the ongoing burst ends to protect it
+            self.next_timeout = match (self.state_sending_repair, burst_end) {
+                (None, Some(burst_end)) if now < burst_end => Some(burst_end),
+                _ => None,
+            };
         }
         should_send
     }
 
-    pub fn sent_repair_symbol(&mut self, _encoder: &Encoder) {
+    pub fn sent_repair_symbol(&mut self) {
         self.n_repair_in_flight += 1;
         self.earliest_unprotected_source_symbol_sent_time = None;
         self.n_source_symbols_sent_since_last_repair = 0;
@@ -224,11 +306,17 @@ impl BurstsFECScheduler {
         }
     }
 
-    pub fn acked_repair_symbol(&mut self, _encoder: &Encoder) {
+    pub fn acked_repair_symbol(&mut self) {
         self.n_repair_in_flight -= 1;
     }
 
-    pub fn sent_source_symbol(&mut self, encoder: &Encoder) {
+    pub fn n_repair_in_flight(&self) -> u64 {
+        self.n_repair_in_flight
+    }
+
+    pub fn sent_source_symbol(
+        &mut self, now: std::time::Instant, window: &EncoderWindow,
+    ) {
         let threshold_burst_size: usize =
             env::var("DEBUG_QUICHE_FEC_BURST_SIZE_BYTES")
                 .unwrap_or(DEFAULT_BURST_SIZE.to_string())
@@ -239,7 +327,12 @@ impl BurstsFECScheduler {
             .parse()
             .unwrap_or(DEFAULT_MAX_JITTER_US);
         let max_jitter = std::time::Duration::from_micros(max_jitter_us);
-        let now = std::time::Instant::now();
+        let threshold_burst_size = self
+            .calibration()
+            .map_or(threshold_burst_size, |c| c.burst_size);
+        if let Some(calibrator) = &mut self.calibrator {
+            calibrator.sent_source_symbol(now);
+        }
         match self.earliest_unprotected_source_symbol_sent_time {
             None => {
                 // interesting symbols are only symbols that are part of a large
@@ -250,7 +343,7 @@ impl BurstsFECScheduler {
             },
             Some(sent_time) => {
                 // check if that sent_time is still up-to-date
-                if let Some(first_md) = encoder.first_metadata() {
+                if window.first.is_some() {
                     if now > sent_time + max_jitter &&
                         self.current_burst_size > threshold_burst_size
                     {
@@ -258,8 +351,7 @@ impl BurstsFECScheduler {
                         // a new burst candidate to protect
                         self.earliest_unprotected_source_symbol_sent_time =
                             Some(now);
-                    } else if let Some(window_sent_time) =
-                        encoder.get_sent_time(first_md)
+                    } else if let Some(window_sent_time) = window.first_sent_time
                     {
                         if window_sent_time > sent_time {
                             // if the first window symbol has a later sent time
@@ -277,10 +369,11 @@ impl BurstsFECScheduler {
             },
         }
         self.n_source_symbols_sent_since_last_repair += 1;
+        self.last_source_symbol_sent_time = Some(now);
     }
 
-    pub fn lost_repair_symbol(&mut self, encoder: &Encoder) {
-        self.acked_repair_symbol(encoder)
+    pub fn lost_repair_symbol(&mut self) {
+        self.acked_repair_symbol()
     }
 
     // returns an Instant at which the stack should wake up to sent new repair
@@ -289,3 +382,338 @@ impl BurstsFECScheduler {
         self.next_timeout
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fec::fec_scheduler::FECSchedulerAlgorithm;
+    use crate::fec::testing;
+    use crate::fec::trace::TraceEvent;
+
+    use std::time::Duration;
+    use std::time::Instant;
+
+    fn sent_sources(
+        events: &mut Vec<TraceEvent>, time: Instant, first: u64, last: u64,
+    ) {
+        for id in first..=last {
+            events.push(TraceEvent::SentSource {
+                time,
+                window: testing::window(first, id, time),
+            });
+        }
+    }
+
+    fn send_repair_symbols(
+        events: &mut Vec<TraceEvent>, ctx: SchedulerContext, n: usize,
+    ) {
+        for _ in 0..n {
+            events.push(testing::decide(ctx, true));
+            events.push(testing::sent_repair(ctx.now));
+        }
+    }
+
+    #[test]
+    fn small_flights_are_not_protected() {
+        let t0 = Instant::now();
+
+        let mut events = Vec::new();
+        sent_sources(&mut events, t0, 0, 9);
+
+        let busy = SchedulerContext {
+            stream_to_emit: true,
+            sent_count: 10,
+            tx_data: 12000,
+            bif: 12000,
+            window: testing::window(0, 9, t0),
+            ..testing::context(t0)
+        };
+
+        events.push(testing::decide(busy, false));
+        events.push(testing::decide(
+            SchedulerContext {
+                stream_to_emit: false,
+                ..busy
+            },
+            false,
+        ));
+
+        testing::assert_golden_trace(FECSchedulerAlgorithm::BurstsOnly, &events);
+    }
+
+    #[test]
+    fn golden_trace() {
+        let t0 = Instant::now();
+        let ms = Duration::from_millis;
+
+        let mut events = Vec::new();
+
+        // A 20kB burst of 16 source symbols.
+        sent_sources(&mut events, t0, 0, 15);
+
+        let busy = SchedulerContext {
+            stream_to_emit: true,
+            sent_count: 20,
+            tx_data: 20000,
+            bif: 20000,
+            window: testing::window(0, 15, t0),
+            ..testing::context(t0)
+        };
+        events.push(testing::decide(busy, false));
+
+        // Once the application is idle, half of the burst is protected.
+        let idle = SchedulerContext {
+            now: t0 + ms(1),
+            stream_to_emit: false,
+            ..busy
+        };
+        send_repair_symbols(&mut events, idle, 8);
+        events.push(testing::decide(idle, false));
+
+        // A new burst right after is not protected, as the round lasts for at
+        // least a round-trip and already spent its budget.
+        sent_sources(&mut events, t0 + ms(2), 16, 31);
+
+        let busy = SchedulerContext {
+            now: t0 + ms(2),
+            stream_to_emit: true,
+            sent_count: 40,
+            tx_data: 40000,
+            window: testing::window(16, 31, t0 + ms(2)),
+            ..busy
+        };
+        events.push(testing::decide(busy, false));
+        let idle = SchedulerContext {
+            now: t0 + ms(3),
+            stream_to_emit: false,
+            ..busy
+        };
+        events.push(testing::decide(idle, false));
+
+        // The round ends a round-trip after it started, and the scheduler
+        // then stays idle for another round-trip.
+        events.push(testing::decide(
+            SchedulerContext {
+                now: t0 + ms(51),
+                ..idle
+            },
+            false,
+        ));
+
+        // The next burst is protected.
+        sent_sources(&mut events, t0 + ms(110), 32, 47);
+
+        let busy = SchedulerContext {
+            now: t0 + ms(110),
+            stream_to_emit: true,
+            sent_count: 60,
+            tx_data: 60000,
+            window: testing::window(16, 47, t0 + ms(2)),
+            ..busy
+        };
+        events.push(testing::decide(busy, false));
+
+        let idle = SchedulerContext {
+            now: t0 + ms(111),
+            stream_to_emit: false,
+            ..busy
+        };
+        send_repair_symbols(&mut events, idle, 1);
+
+        // The round goes on while its symbols leave the window...
+        let idle = SchedulerContext {
+            now: t0 + ms(112),
+            window: testing::window(48, 50, t0 + ms(112)),
+            ..idle
+        };
+        send_repair_symbols(&mut events, idle, 1);
+
+        // ... until a round-trip elapsed since it started.
+        events.push(testing::decide(
+            SchedulerContext {
+                now: t0 + ms(161),
+                ..idle
+            },
+            false,
+        ));
+
+        testing::assert_golden_trace(FECSchedulerAlgorithm::BurstsOnly, &events);
+    }
+
+    #[test]
+    fn inter_send_gap() {
+        let t0 = Instant::now();
+        let us = Duration::from_micros;
+
+        let mut scheduler =
+            BurstsFECScheduler::new(BurstDetection::InterSendGap(2.0));
+
+        // A small burst of 5 source symbols, not protected when detecting
+        // bursts by bytes.
+        for id in 0..5 {
+            scheduler.sent_source_symbol(t0, &testing::window(0, id, t0));
+        }
+
+        let ctx = SchedulerContext {
+            now: t0 + us(100),
+            sent_count: 5,
+            tx_data: 6000,
+            bif: 6000,
+            pacing_interval: Some(us(100)),
+            window: testing::window(0, 4, t0),
+            ..testing::context(t0)
+        };
+
+        // The burst is not over before twice the pacing interval.
+        assert!(!scheduler.should_send_repair(&ctx));
+        assert_eq!(scheduler.timeout(), Some(t0 + us(200)));
+
+        let ctx = SchedulerContext {
+            now: t0 + us(200),
+            ..ctx
+        };
+
+        for _ in 0..3 {
+            assert!(scheduler.should_send_repair(&ctx));
+            scheduler.sent_repair_symbol();
+        }
+
+        assert!(!scheduler.should_send_repair(&ctx));
+        assert_eq!(scheduler.timeout(), None);
+    }
+
+    #[test]
+    fn auto_calibration() {
+        let t0 = Instant::now();
+        let ms = Duration::from_millis;
+
+        let mut scheduler = BurstsFECScheduler::new(BurstDetection::Bytes);
+        scheduler.enable_auto_calibration();
+
+        // 6kB video frames every 33ms, smaller than the default burst size.
+        for i in 0..5 {
+            let start = t0 + ms(33) * i as u32;
+            let (first, last) = (i * 5, i * 5 + 4);
+
+            for id in first..=last {
+                scheduler
+                    .sent_source_symbol(start, &testing::window(first, id, start));
+            }
+
+            let busy = SchedulerContext {
+                now: start,
+                stream_to_emit: true,
+                sent_count: last as usize + 1,
+                tx_data: 6000 * (i + 1),
+                bif: 6000,
+                window: testing::window(first, last, start),
+                ..testing::context(start)
+            };
+            assert!(!scheduler.should_send_repair(&busy));
+
+            // Once enough frames were observed, they are protected.
+            let idle = SchedulerContext {
+                now: start + ms(1),
+                stream_to_emit: false,
+                ..busy
+            };
+            assert_eq!(scheduler.should_send_repair(&idle), i == 4);
+        }
+
+        let calibration = scheduler.calibration().unwrap();
+        assert_eq!(calibration.bursts, 5);
+        assert_eq!(calibration.mean_burst_bytes, 6000);
+        assert_eq!(calibration.burst_size, 3000);
+    }
+
+    #[test]
+    fn spent_round_dwells() {
+        let t0 = Instant::now();
+        let ms = Duration::from_millis;
+
+        let mut scheduler = BurstsFECScheduler::new(BurstDetection::Bytes);
+
+        for id in 0..16 {
+            scheduler.sent_source_symbol(t0, &testing::window(0, id, t0));
+        }
+
+        let idle = SchedulerContext {
+            now: t0 + ms(1),
+            sent_count: 20,
+            tx_data: 20000,
+            bif: 20000,
+            window: testing::window(0, 15, t0),
+            ..testing::context(t0)
+        };
+
+        while scheduler.should_send_repair(&idle) {
+            scheduler.sent_repair_symbol();
+        }
+        assert_eq!(scheduler.n_repair_in_flight(), 8);
+
+        // The budget is spent, but the round lasts for a round-trip.
+        let idle = SchedulerContext {
+            now: t0 + ms(2),
+            ..idle
+        };
+        assert!(!scheduler.should_send_repair(&idle));
+        assert!(scheduler.state_sending_repair.is_some());
+        assert!(scheduler.hysteresis.active());
+
+        let idle = SchedulerContext {
+            now: t0 + ms(1) + testing::RTT,
+            ..idle
+        };
+        assert!(!scheduler.should_send_repair(&idle));
+        assert!(scheduler.state_sending_repair.is_none());
+        assert!(!scheduler.hysteresis.active());
+    }
+
+    #[test]
+    fn expiry_tolerates_jitter() {
+        let t0 = Instant::now();
+        let ms = Duration::from_millis;
+
+        let mut scheduler = BurstsFECScheduler::new(BurstDetection::Bytes);
+
+        for id in 0..16 {
+            scheduler.sent_source_symbol(t0, &testing::window(0, id, t0));
+        }
+
+        let busy = SchedulerContext {
+            stream_to_emit: true,
+            sent_count: 20,
+            tx_data: 20000,
+            bif: 20000,
+            rttvar: ms(10),
+            window: testing::window(0, 15, t0),
+            ..testing::context(t0)
+        };
+        assert!(!scheduler.should_send_repair(&busy));
+
+        let idle = SchedulerContext {
+            now: t0 + ms(1),
+            stream_to_emit: false,
+            ..busy
+        };
+        assert!(scheduler.should_send_repair(&idle));
+        scheduler.sent_repair_symbol();
+
+        // The protected symbols left the window a smoothed RTT after the
+        // start of the round, which goes on within the RTT variation.
+        let idle = SchedulerContext {
+            now: t0 + ms(51),
+            window: testing::window(16, 18, t0 + ms(51)),
+            ..idle
+        };
+        assert!(scheduler.should_send_repair(&idle));
+        scheduler.sent_repair_symbol();
+
+        let idle = SchedulerContext {
+            now: t0 + ms(61),
+            ..idle
+        };
+        assert!(!scheduler.should_send_repair(&idle));
+    }
+}
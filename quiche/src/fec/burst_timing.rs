@@ -0,0 +1,127 @@
+use std::time::Duration;
+use std::time::Instant;
+
+/// Timing of the repair symbols sent after a burst of source symbols.
+///
+/// The delays are measured from the departure of the last source symbol of
+/// the burst, as scheduled by the pacer, to the departure of the repair
+/// symbols. They bound how long the peer waits before recovering the losses
+/// of the burst.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BurstRepairTiming {
+    /// The delay before the departure of the first repair symbol.
+    pub first_repair_delay: Duration,
+
+    /// The delay before the departure of the last repair symbol sent before
+    /// the next burst.
+    pub last_repair_delay: Duration,
+
+    /// The number of repair symbols sent after the burst.
+    pub repair_symbols: usize,
+}
+
+/// Measures the timing of the repair symbols of each burst.
+///
+/// A burst is the sequence of source symbols sent between two repair
+/// symbols, and it ends with the last source symbol sent before a repair
+/// symbol.
+#[derive(Default)]
+pub(crate) struct BurstTimingTracker {
+    burst_end: Option<Instant>,
+    current: Option<BurstRepairTiming>,
+    latest: Option<BurstRepairTiming>,
+    bursts: usize,
+}
+
+impl BurstTimingTracker {
+    /// Records a source symbol leaving the pacer at `departure`.
+    ///
+    /// Returns the timing of the previous burst once the source symbol starts
+    /// a new burst.
+    pub fn sent_source_symbol(
+        &mut self, departure: Instant,
+    ) -> Option<BurstRepairTiming> {
+        self.burst_end = Some(departure);
+
+        let timing = self.current.take();
+
+        if timing.is_some() {
+            self.latest = timing;
+        }
+
+        timing
+    }
+
+    /// Records a repair symbol leaving the pacer at `departure`.
+    pub fn sent_repair_symbol(&mut self, departure: Instant) {
+        let burst_end = match self.burst_end {
+            Some(v) => v,
+
+            None => return,
+        };
+
+        let delay = departure.saturating_duration_since(burst_end);
+
+        match &mut self.current {
+            Some(timing) => {
+                timing.last_repair_delay = delay;
+                timing.repair_symbols += 1;
+            },
+
+            None => {
+                self.bursts += 1;
+
+                self.current = Some(BurstRepairTiming {
+                    first_repair_delay: delay,
+                    last_repair_delay: delay,
+                    repair_symbols: 1,
+                });
+            },
+        }
+    }
+
+    /// Returns the timing of the latest burst followed by repair symbols.
+    pub fn latest(&self) -> Option<BurstRepairTiming> {
+        self.current.or(self.latest)
+    }
+
+    /// Returns the number of bursts followed by repair symbols.
+    pub fn bursts(&self) -> usize {
+        self.bursts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timing() {
+        let now = Instant::now();
+        let ms = Duration::from_millis(1);
+        let mut tracker = BurstTimingTracker::default();
+
+        // Repair symbols without a burst are not measured.
+        tracker.sent_repair_symbol(now);
+        assert_eq!(tracker.latest(), None);
+
+        assert_eq!(tracker.sent_source_symbol(now), None);
+        assert_eq!(tracker.sent_source_symbol(now + ms), None);
+
+        tracker.sent_repair_symbol(now + 3 * ms);
+        tracker.sent_repair_symbol(now + 5 * ms);
+
+        let timing = BurstRepairTiming {
+            first_repair_delay: 2 * ms,
+            last_repair_delay: 4 * ms,
+            repair_symbols: 2,
+        };
+        assert_eq!(tracker.latest(), Some(timing));
+
+        // The next burst completes the timing of the previous one.
+        assert_eq!(tracker.sent_source_symbol(now + 6 * ms), Some(timing));
+        assert_eq!(tracker.latest(), Some(timing));
+        assert_eq!(tracker.bursts(), 1);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::frame;
+
+/// Description of the frames of a packet, used to annotate packet captures.
+///
+/// Packet captures only hold encrypted payloads, so the note records, in
+/// order, the frames of the packet: the FEC frames delimit the source symbol
+/// and the repair symbols carried by the packet.
+pub(crate) struct PacketNote {
+    frames: Vec<String>,
+    has_fec_frames: bool,
+}
+
+impl PacketNote {
+    pub fn new() -> PacketNote {
+        PacketNote {
+            frames: Vec::new(),
+            has_fec_frames: false,
+        }
+    }
+
+    pub fn push(&mut self, frame: &frame::Frame) {
+        if is_fec_frame(frame) {
+            self.has_fec_frames = true;
+        }
+
+        self.frames.push(format!("{frame:?}"));
+    }
+
+    /// Returns the note of the packet `pkt_num`, or `None` if the packet does
+    /// not carry any FEC frame.
+    pub fn finish(self, direction: &str, pkt_num: u64) -> Option<String> {
+        if !self.has_fec_frames {
+            return None;
+        }
+
+        Some(format!(
+            "{} pn={} frames=[{}]",
+            direction,
+            pkt_num,
+            self.frames.join(" | ")
+        ))
+    }
+}
+
+fn is_fec_frame(frame: &frame::Frame) -> bool {
+    matches!(
+        frame,
+        frame::Frame::Repair { .. } |
+            frame::Frame::SourceSymbolHeader { .. } |
+            frame::Frame::SourceSymbol { .. } |
+            frame::Frame::SourceSymbolACK { .. } |
+            frame::Frame::WindowSync { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fec_frames_only() {
+        let mut note = PacketNote::new();
+        note.push(&frame::Frame::Ping { mtu_probe: None });
+        assert_eq!(note.finish("tx", 1), None);
+
+        let mut note = PacketNote::new();
+        note.push(&frame::Frame::Ping { mtu_probe: None });
+        note.push(&frame::Frame::WindowSync { first_symbol: 4 });
+        assert_eq!(
+            note.finish("rx", 2),
+            Some(
+                "rx pn=2 frames=[PING mtu_probe=None | WINDOW_SYNC first_symbol=4]"
+                    .to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,42 @@
+use std::time::Instant;
+
+/// Protection of the last flight before a graceful close.
+///
+/// Once the CONNECTION_CLOSE frame is sent, losses of the last flight can
+/// never be repaired. When the application closes the connection, the close
+/// is thus delayed until enough repair symbols are sent to cover all the
+/// source symbols still in the encoding window, or until the deadline
+/// expires.
+pub(crate) struct CloseFlush {
+    deadline: Instant,
+    repair_symbols_to_send: usize,
+}
+
+impl CloseFlush {
+    pub fn new(deadline: Instant, repair_symbols_to_send: usize) -> CloseFlush {
+        CloseFlush {
+            deadline,
+            repair_symbols_to_send,
+        }
+    }
+
+    pub fn should_send_repair(&self) -> bool {
+        self.repair_symbols_to_send > 0
+    }
+
+    pub fn sent_repair_symbol(&mut self) {
+        self.repair_symbols_to_send =
+            self.repair_symbols_to_send.saturating_sub(1);
+    }
+
+    /// Returns whether the connection can now be closed.
+    pub fn done(&self, now: Instant) -> bool {
+        !self.should_send_repair() || now >= self.deadline
+    }
+
+    // returns an Instant at which the stack should wake up to close the
+    // connection
+    pub fn timeout(&self) -> Option<Instant> {
+        Some(self.deadline)
+    }
+}
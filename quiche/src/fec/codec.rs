@@ -0,0 +1,187 @@
+use core::str::FromStr;
+
+use networkcoding::vandermonde_lc::decoder::VLCDecoder;
+use networkcoding::vandermonde_lc::encoder::VLCEncoder;
+use networkcoding::Decoder;
+use networkcoding::Encoder;
+
+/// Available FEC codecs.
+///
+/// This enum provides the list of codecs known to quiche. Only the codecs
+/// returned by [`available_fec_codecs()`] are provided by the networkcoding
+/// backend quiche is built with.
+///
+/// [`available_fec_codecs()`]: fn.available_fec_codecs.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub enum FECCodec {
+    /// Sliding-window linear code using Vandermonde coefficients (default).
+    /// `vlc` in a string form.
+    VandermondeLC = 0,
+}
+
+const AVAILABLE_FEC_CODECS: &[FECCodec] = &[FECCodec::VandermondeLC];
+
+/// Returns the FEC codecs provided by the networkcoding backend quiche is
+/// built with.
+pub fn available_fec_codecs() -> &'static [FECCodec] {
+    AVAILABLE_FEC_CODECS
+}
+
+impl FECCodec {
+    /// Converts the wire ID of a codec to `FECCodec`, if the codec is
+    /// available.
+    pub fn from_u64(v: u64) -> Option<FECCodec> {
+        available_fec_codecs()
+            .iter()
+            .find(|codec| **codec as u64 == v)
+            .copied()
+    }
+
+    /// Returns the string form of the codec.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FECCodec::VandermondeLC => "vlc",
+        }
+    }
+}
+
+impl FromStr for FECCodec {
+    type Err = crate::Error;
+
+    /// Converts a string to `FECCodec`.
+    ///
+    /// If `name` does not match any of the available codecs,
+    /// `Error::FECCodecUnavailable` is returned.
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        available_fec_codecs()
+            .iter()
+            .find(|codec| codec.name() == name)
+            .copied()
+            .ok_or_else(|| {
+                warn!(
+                    "FEC codec {} is unavailable, available codecs: {}",
+                    name,
+                    available_fec_codec_names()
+                );
+
+                crate::Error::FECCodecUnavailable
+            })
+    }
+}
+
+/// Returns the comma-separated list of the available codecs.
+pub(crate) fn available_fec_codec_names() -> String {
+    available_fec_codecs()
+        .iter()
+        .map(|codec| codec.name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns a reserved codec ID to grease the list of codecs advertised in the
+/// transport parameters.
+///
+/// The IDs of the form `31 * N + 27` are never assigned to a codec, so that
+/// peers have to ignore the IDs they don't know.
+pub(crate) fn grease_codec_id() -> u64 {
+    31 * crate::rand::rand_u64_uniform(1 << 16) + 27
+}
+
+/// Returns the IDs of the codecs to advertise in the transport parameters,
+/// starting with the `preferred` one.
+pub(crate) fn advertised_codec_ids(preferred: FECCodec) -> Vec<u64> {
+    let mut ids = vec![preferred as u64];
+
+    ids.extend(
+        available_fec_codecs()
+            .iter()
+            .filter(|codec| **codec != preferred)
+            .map(|codec| *codec as u64),
+    );
+
+    ids
+}
+
+/// Returns the first of the `client` codecs that the server supports,
+/// ignoring the IDs that are unknown or reserved for greasing.
+///
+/// Both endpoints follow the client's order of preference, so that they
+/// agree on the codec of the symbols.
+pub(crate) fn negotiate(client: &[u64], server: &[u64]) -> Option<FECCodec> {
+    client
+        .iter()
+        .filter(|id| server.contains(id))
+        .find_map(|id| FECCodec::from_u64(*id))
+}
+
+/// Returns a new encoder of `codec` protecting up to `window_size` source
+/// symbols of `symbol_size` bytes.
+pub(crate) fn new_encoder(
+    codec: FECCodec, symbol_size: usize, window_size: usize,
+) -> Encoder {
+    match codec {
+        FECCodec::VandermondeLC =>
+            Encoder::VLC(VLCEncoder::new(symbol_size, window_size)),
+    }
+}
+
+/// Returns a new decoder of `codec` decoding up to `window_size` source
+/// symbols of `symbol_size` bytes.
+pub(crate) fn new_decoder(
+    codec: FECCodec, symbol_size: usize, window_size: usize,
+) -> Decoder {
+    match codec {
+        FECCodec::VandermondeLC =>
+            Decoder::VLC(VLCDecoder::new(symbol_size, window_size)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codec_from_str() {
+        assert_eq!(FECCodec::from_str("vlc"), Ok(FECCodec::VandermondeLC));
+        assert_eq!(
+            FECCodec::from_str("rlc"),
+            Err(crate::Error::FECCodecUnavailable)
+        );
+
+        for codec in available_fec_codecs() {
+            assert_eq!(FECCodec::from_str(codec.name()), Ok(*codec));
+        }
+    }
+
+    #[test]
+    fn negotiation() {
+        let vlc = FECCodec::VandermondeLC as u64;
+        let grease = grease_codec_id();
+
+        assert_eq!(grease % 31, 27);
+        assert_eq!(FECCodec::from_u64(grease), None);
+
+        assert_eq!(
+            negotiate(&[grease, vlc], &[42, vlc, grease_codec_id()]),
+            Some(FECCodec::VandermondeLC)
+        );
+
+        // No codec in common.
+        assert_eq!(negotiate(&[grease, vlc], &[grease, 42]), None);
+        assert_eq!(negotiate(&[vlc], &[]), None);
+    }
+
+    #[test]
+    fn advertised_codecs() {
+        let ids = advertised_codec_ids(FECCodec::VandermondeLC);
+        assert_eq!(ids[0], FECCodec::VandermondeLC as u64);
+
+        // All the available codecs are advertised, once.
+        assert_eq!(ids.len(), available_fec_codecs().len());
+        for codec in available_fec_codecs() {
+            assert!(ids.contains(&(*codec as u64)));
+        }
+    }
+}
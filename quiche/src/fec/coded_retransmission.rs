@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::time::Instant;
+
+use crate::frame;
+
+/// Lost source symbols waiting to be recovered by the peer.
+struct LostSymbol {
+    /// The STREAM frames carried by the source symbol.
+    frames: Vec<frame::Frame>,
+
+    /// The time after which the frames are retransmitted verbatim.
+    deadline: Instant,
+}
+
+/// Coded retransmission of lost stream data.
+///
+/// Instead of retransmitting the STREAM frames of a lost packet verbatim,
+/// its source symbol is kept in the encoding window and a fresh repair
+/// symbol is sent for each lost source symbol, so that a single repair
+/// symbol can repair any of the losses of the window. The frames are only
+/// retransmitted verbatim if the peer did not report the source symbol as
+/// recovered before the deadline.
+pub(crate) struct CodedRetransmissions {
+    lost_symbols: BTreeMap<u64, LostSymbol>,
+    repair_symbols_to_send: usize,
+}
+
+impl CodedRetransmissions {
+    pub fn new() -> CodedRetransmissions {
+        CodedRetransmissions {
+            lost_symbols: BTreeMap::new(),
+            repair_symbols_to_send: 0,
+        }
+    }
+
+    /// Records the loss of the source symbol `id`, to be recovered before
+    /// `deadline`.
+    pub fn symbol_lost(&mut self, id: u64, deadline: Instant) {
+        let symbol = self.lost_symbols.entry(id).or_insert(LostSymbol {
+            frames: Vec::new(),
+            deadline,
+        });
+
+        symbol.deadline = deadline;
+
+        self.repair_symbols_to_send += 1;
+    }
+
+    /// Records a lost frame carried by the source symbol `id`.
+    pub fn frame_lost(
+        &mut self, id: u64, frame: frame::Frame, deadline: Instant,
+    ) {
+        self.lost_symbols
+            .entry(id)
+            .or_insert(LostSymbol {
+                frames: Vec::new(),
+                deadline,
+            })
+            .frames
+            .push(frame);
+    }
+
+    /// Records that the peer recovered the source symbols in `range`.
+    /// Returns the ones that were waiting for a coded retransmission.
+    pub fn symbols_recovered(&mut self, range: Range<u64>) -> Vec<u64> {
+        let recovered: Vec<u64> =
+            self.lost_symbols.range(range).map(|(id, _)| *id).collect();
+
+        for id in &recovered {
+            self.lost_symbols.remove(id);
+        }
+
+        recovered
+    }
+
+    /// Removes and returns the source symbols whose frames must now be
+    /// retransmitted verbatim.
+    pub fn take_expired(
+        &mut self, now: Instant,
+    ) -> Vec<(u64, Vec<frame::Frame>)> {
+        let expired: Vec<u64> = self
+            .lost_symbols
+            .iter()
+            .filter(|(_, s)| s.deadline <= now)
+            .map(|(id, _)| *id)
+            .collect();
+
+        expired
+            .into_iter()
+            .filter_map(|id| {
+                self.lost_symbols.remove(&id).map(|s| (id, s.frames))
+            })
+            .collect()
+    }
+
+    pub fn should_send_repair(&self) -> bool {
+        self.repair_symbols_to_send > 0 && !self.lost_symbols.is_empty()
+    }
+
+    pub fn sent_repair_symbol(&mut self) {
+        self.repair_symbols_to_send =
+            self.repair_symbols_to_send.saturating_sub(1);
+    }
+
+    // returns an Instant at which the stack should wake up to retransmit
+    // the frames that were not recovered in time
+    pub fn timeout(&self) -> Option<Instant> {
+        self.lost_symbols.values().map(|s| s.deadline).min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    fn stream_frame(offset: u64) -> frame::Frame {
+        frame::Frame::StreamHeader {
+            stream_id: 4,
+            offset,
+            length: 1000,
+            fin: false,
+        }
+    }
+
+    #[test]
+    fn recovered() {
+        let now = Instant::now();
+        let deadline = now + Duration::from_millis(100);
+        let mut coded = CodedRetransmissions::new();
+
+        coded.symbol_lost(3, deadline);
+        coded.frame_lost(3, stream_frame(0), deadline);
+        coded.symbol_lost(4, deadline);
+        coded.frame_lost(4, stream_frame(1000), deadline);
+
+        assert!(coded.should_send_repair());
+        assert_eq!(coded.timeout(), Some(deadline));
+
+        coded.sent_repair_symbol();
+        coded.sent_repair_symbol();
+        assert!(!coded.should_send_repair());
+
+        assert_eq!(coded.symbols_recovered(0..4), vec![3]);
+        assert!(coded.symbols_recovered(3..4).is_empty());
+        assert_eq!(coded.symbols_recovered(4..1_000_000), vec![4]);
+
+        assert_eq!(coded.timeout(), None);
+        assert!(coded.take_expired(deadline).is_empty());
+    }
+
+    #[test]
+    fn expired() {
+        let now = Instant::now();
+        let deadline = now + Duration::from_millis(100);
+        let mut coded = CodedRetransmissions::new();
+
+        coded.symbol_lost(3, deadline);
+        coded.frame_lost(3, stream_frame(0), deadline);
+        coded.symbol_lost(4, deadline + Duration::from_millis(10));
+
+        assert!(coded.take_expired(now).is_empty());
+
+        let expired = coded.take_expired(deadline);
+        assert_eq!(expired, vec![(3, vec![stream_frame(0)])]);
+
+        assert_eq!(coded.timeout(), Some(deadline + Duration::from_millis(10)));
+    }
+}
@@ -0,0 +1,144 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use networkcoding::source_symbol_metadata_to_u64;
+use networkcoding::Encoder;
+
+use crate::path::Path;
+use crate::Connection;
+
+/// State of the FEC encoding window, as seen by the schedulers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct EncoderWindow {
+    /// The ID of the first source symbol of the window.
+    pub first: Option<u64>,
+
+    /// The ID of the last source symbol of the window.
+    pub last: Option<u64>,
+
+    /// The time at which the first source symbol of the window was sent.
+    pub first_sent_time: Option<Instant>,
+
+    /// The number of source symbols in the window.
+    pub n_symbols: usize,
+}
+
+impl EncoderWindow {
+    pub fn new(encoder: &Encoder) -> EncoderWindow {
+        let first = encoder.first_metadata();
+
+        EncoderWindow {
+            first: first.map(source_symbol_metadata_to_u64),
+            last: encoder.last_metadata().map(source_symbol_metadata_to_u64),
+            first_sent_time: first.and_then(|md| encoder.get_sent_time(md)),
+            n_symbols: encoder.n_protected_symbols(),
+        }
+    }
+
+    /// Returns whether the source symbol `id` is still in the window.
+    pub fn contains(&self, id: u64) -> bool {
+        match (self.first, self.last) {
+            (Some(first), Some(last)) => first <= id && id <= last,
+
+            _ => false,
+        }
+    }
+}
+
+/// Snapshot of the connection state a FEC scheduler bases its decisions on.
+///
+/// Decoupling the schedulers from `Connection` and `Path` allows to feed them
+/// recorded or synthetic states.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SchedulerContext {
+    pub now: Instant,
+
+    /// Whether there are DATAGRAM frames waiting to be sent.
+    pub dgrams_to_emit: bool,
+
+    /// Whether there are stream data waiting to be sent.
+    pub stream_to_emit: bool,
+
+    /// The number of packets sent on the connection.
+    pub sent_count: usize,
+
+    /// The number of stream bytes sent on the connection.
+    pub tx_data: u64,
+
+    /// The number of bytes in flight on the path.
+    pub bif: usize,
+
+    /// The number of bytes available in the congestion window of the path.
+    pub cwnd_available: usize,
+
+    /// The smoothed round-trip time of the path.
+    pub rtt: Duration,
+
+    /// The mean deviation of the round-trip time of the path.
+    pub rttvar: Duration,
+
+    /// The time the pacer takes to send a full-sized packet on the path, if
+    /// the pacing rate is known.
+    pub pacing_interval: Option<Duration>,
+
+    /// Whether the congestion controller of the path is in slow start.
+    pub in_slow_start: bool,
+
+    /// The estimated number of packets lost per round-trip on the path.
+    pub packets_lost_per_round_trip: Option<f64>,
+
+    /// The variance of the number of packets lost per round-trip.
+    pub var_packets_lost_per_round_trip: f64,
+
+    /// The size of the source and repair symbols.
+    pub symbol_size: usize,
+
+    /// The number of bytes a repair symbol takes in the congestion window:
+    /// a whole packet when repair symbols are sent in dedicated packets, the
+    /// size of the symbol otherwise.
+    pub repair_packet_size: usize,
+
+    /// The state of the encoding window.
+    pub window: EncoderWindow,
+}
+
+impl SchedulerContext {
+    pub fn new(conn: &Connection, path: &Path, now: Instant) -> SchedulerContext {
+        let (packets_lost_per_round_trip, var_packets_lost_per_round_trip) =
+            conn.fec_loss_estimates(path);
+
+        SchedulerContext {
+            now,
+            dgrams_to_emit: conn.dgram_max_writable_len().is_some(),
+            stream_to_emit: conn.streams.has_flushable(),
+            sent_count: conn.sent_count,
+            tx_data: conn.tx_data,
+            bif: path.recovery.bif(),
+            cwnd_available: path.recovery.cwnd_available(now),
+            rtt: path.recovery.rtt(),
+            rttvar: path.recovery.rttvar(),
+            pacing_interval: path.recovery.pacing_interval(),
+            in_slow_start: path.recovery.in_slow_start(),
+            packets_lost_per_round_trip,
+            var_packets_lost_per_round_trip,
+            symbol_size: conn.fec_encoder.symbol_size(),
+            repair_packet_size: if conn.fec_dedicated_packets {
+                path.recovery.max_datagram_size()
+            } else {
+                conn.fec_encoder.symbol_size()
+            },
+            window: EncoderWindow::new(conn.fec_encoder.get()),
+        }
+    }
+
+    /// Returns the smoothed round-trip time of the path plus its mean
+    /// deviation, an upper bound of the RTT samples that tolerates jitter.
+    pub fn rtt_with_variation(&self) -> Duration {
+        self.rtt + self.rttvar
+    }
+
+    /// Returns whether the application has no data waiting to be sent.
+    pub fn nothing_to_send(&self) -> bool {
+        !self.dgrams_to_emit && !self.stream_to_emit
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::Instant;
+
+use crate::fec::context::SchedulerContext;
+use crate::path::PathView;
+
+/// The inputs of a [`FECRedundancyScheduler`] decision.
+///
+/// [`FECRedundancyScheduler`]: trait.FECRedundancyScheduler.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FECSchedulerInput {
+    /// The time of the decision.
+    pub now: Instant,
+
+    /// A view of the path on which the repair symbol would be sent.
+    pub path: PathView,
+
+    /// Whether the application has stream data or datagrams waiting to be
+    /// sent.
+    pub data_pending: bool,
+
+    /// The number of source symbols in the encoding window, that is the
+    /// source symbols a repair symbol sent now would protect.
+    pub window_symbols: usize,
+
+    /// The size of the source symbols in bytes.
+    pub symbol_size: usize,
+
+    /// The number of repair symbols sent and not yet acknowledged or lost.
+    pub repair_in_flight: u64,
+}
+
+/// Application-supplied FEC redundancy scheduler.
+///
+/// The scheduler is consulted each time a repair symbol could be sent, and
+/// decides whether one should be. It replaces the built-in scheduler of the
+/// connection, see [`set_fec_redundancy_scheduler()`].
+///
+/// [`set_fec_redundancy_scheduler()`]:
+/// struct.Connection.html#method.set_fec_redundancy_scheduler
+pub trait FECRedundancyScheduler: Send + Sync {
+    /// Returns whether a repair symbol should be sent now.
+    fn should_send_repair(&mut self, input: &FECSchedulerInput) -> bool;
+
+    /// Called when a repair symbol was sent.
+    fn sent_repair_symbol(&mut self) {}
+
+    /// Called when a repair symbol was acknowledged.
+    fn acked_repair_symbol(&mut self) {}
+
+    /// Called when a repair symbol was declared lost.
+    fn lost_repair_symbol(&mut self) {}
+
+    /// Called when a source symbol was sent at `now`.
+    fn sent_source_symbol(&mut self, _now: Instant) {}
+
+    /// Returns the time at which the scheduler should be consulted again, if
+    /// it is waiting to send a repair symbol.
+    fn timeout(&self) -> Option<Instant> {
+        None
+    }
+}
+
+/// Adapts a [`FECRedundancyScheduler`] to the internal scheduler interface.
+pub(crate) struct CustomScheduler {
+    scheduler: Box<dyn FECRedundancyScheduler>,
+    n_repair_in_flight: u64,
+}
+
+impl CustomScheduler {
+    pub fn new(scheduler: Box<dyn FECRedundancyScheduler>) -> CustomScheduler {
+        CustomScheduler {
+            scheduler,
+            n_repair_in_flight: 0,
+        }
+    }
+
+    pub fn should_send_repair(
+        &mut self, ctx: &SchedulerContext, path: &PathView,
+    ) -> bool {
+        let input = FECSchedulerInput {
+            now: ctx.now,
+            path: *path,
+            data_pending: !ctx.nothing_to_send(),
+            window_symbols: ctx.window.n_symbols,
+            symbol_size: ctx.symbol_size,
+            repair_in_flight: self.n_repair_in_flight,
+        };
+
+        self.scheduler.should_send_repair(&input)
+    }
+
+    pub fn sent_repair_symbol(&mut self) {
+        self.n_repair_in_flight += 1;
+        self.scheduler.sent_repair_symbol();
+    }
+
+    pub fn acked_repair_symbol(&mut self) {
+        self.n_repair_in_flight = self.n_repair_in_flight.saturating_sub(1);
+        self.scheduler.acked_repair_symbol();
+    }
+
+    pub fn lost_repair_symbol(&mut self) {
+        self.n_repair_in_flight = self.n_repair_in_flight.saturating_sub(1);
+        self.scheduler.lost_repair_symbol();
+    }
+
+    pub fn sent_source_symbol(&mut self, now: Instant) {
+        self.scheduler.sent_source_symbol(now);
+    }
+
+    pub fn n_repair_in_flight(&self) -> u64 {
+        self.n_repair_in_flight
+    }
+
+    pub fn timeout(&self) -> Option<Instant> {
+        self.scheduler.timeout()
+    }
+}
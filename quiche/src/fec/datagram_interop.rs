@@ -0,0 +1,418 @@
+use std::collections::BTreeSet;
+use std::time::Instant;
+
+use networkcoding::source_symbol_metadata_from_u64;
+use networkcoding::source_symbol_metadata_to_u64;
+use networkcoding::vandermonde_lc::decoder::VLCDecoder;
+use networkcoding::vandermonde_lc::encoder::VLCEncoder;
+use networkcoding::Decoder;
+use networkcoding::DecoderError;
+use networkcoding::Encoder;
+use networkcoding::SourceSymbol;
+
+use crate::Error;
+use crate::Result;
+
+/// The type of a DATAGRAM carrying a source symbol.
+const SOURCE_DATAGRAM: u8 = 0x00;
+
+/// The type of a DATAGRAM carrying a repair symbol.
+const REPAIR_DATAGRAM: u8 = 0x01;
+
+/// The size of the header of a repair symbol: the ID of the first protected
+/// source symbol (8 bytes), the number of protected source symbols (4 bytes)
+/// and the sequence number of the repair symbol (8 bytes).
+const REPAIR_SYMBOL_HEADER_LEN: usize = 20;
+
+/// The overhead of a repair DATAGRAM over the symbol size: the type and the
+/// symbol size, as a 2-byte varint, followed by the repair symbol header.
+const REPAIR_DATAGRAM_OVERHEAD: usize = 1 + 2 + REPAIR_SYMBOL_HEADER_LEN;
+
+/// The length of the payload length prefixed to the source symbols.
+const PAYLOAD_LEN_PREFIX: usize = 2;
+
+/// The largest symbol size, so that it can be encoded as a 2-byte varint and
+/// the payload length as 2 bytes.
+const MAX_SYMBOL_SIZE: usize = 16383;
+
+/// Returns the symbol size used to protect DATAGRAMs of at most
+/// `max_dgram_len` bytes, if they are large enough.
+pub(crate) fn symbol_size(max_dgram_len: usize) -> Option<usize> {
+    let symbol_size = max_dgram_len
+        .checked_sub(REPAIR_DATAGRAM_OVERHEAD)?
+        .min(MAX_SYMBOL_SIZE);
+
+    if symbol_size <= PAYLOAD_LEN_PREFIX {
+        return None;
+    }
+
+    Some(symbol_size)
+}
+
+/// Returns the largest application payload of the DATAGRAMs protected with
+/// symbols of `symbol_size` bytes.
+pub(crate) fn max_payload_len(symbol_size: usize) -> usize {
+    symbol_size - PAYLOAD_LEN_PREFIX
+}
+
+/// Protects the DATAGRAMs sent to a peer that does not support the FEC
+/// frames.
+///
+/// The source and repair symbols are carried in DATAGRAM frames, behind a
+/// small header that the peer decodes with a [`FECDatagramDecoder`]:
+///
+/// ```text
+/// Source DATAGRAM {
+///   Type (8) = 0x00,
+///   Symbol Size (i),
+///   Source Symbol ID (i),
+///   Payload (..),
+/// }
+///
+/// Repair DATAGRAM {
+///   Type (8) = 0x01,
+///   Symbol Size (i),
+///   First Source Symbol ID (64),
+///   Protected Source Symbols (32),
+///   Repair Symbol Sequence Number (64),
+///   Repair Symbol Data (Symbol Size * 8),
+/// }
+/// ```
+///
+/// The source symbols protected by the repair symbols are the payloads
+/// prefixed with their length on 2 bytes, and padded with zeros up to the
+/// symbol size.
+pub(crate) struct FECDatagramEncoder {
+    encoder: Encoder,
+    symbol_size: usize,
+    window_size: u64,
+    repair_interval: usize,
+    since_repair: usize,
+}
+
+impl FECDatagramEncoder {
+    /// Creates an encoder sending a repair symbol every `repair_interval`
+    /// source symbols, protecting the last `window_size` ones.
+    pub fn new(
+        symbol_size: usize, window_size: usize, repair_interval: usize,
+    ) -> FECDatagramEncoder {
+        FECDatagramEncoder {
+            encoder: Encoder::VLC(VLCEncoder::new(symbol_size, window_size)),
+            symbol_size,
+            window_size: window_size.max(1) as u64,
+            repair_interval: repair_interval.max(1),
+            since_repair: 0,
+        }
+    }
+
+    pub fn max_payload_len(&self) -> usize {
+        max_payload_len(self.symbol_size)
+    }
+
+    /// Protects `payload` and returns the source DATAGRAM carrying it.
+    pub fn protect(&mut self, payload: &[u8]) -> Result<Vec<u8>> {
+        if payload.len() > self.max_payload_len() {
+            return Err(Error::BufferTooShort);
+        }
+
+        let mut data = vec![0; self.symbol_size];
+        data[..PAYLOAD_LEN_PREFIX]
+            .copy_from_slice(&(payload.len() as u16).to_be_bytes());
+        data[PAYLOAD_LEN_PREFIX..PAYLOAD_LEN_PREFIX + payload.len()]
+            .copy_from_slice(payload);
+
+        let mut md = source_symbol_metadata_from_u64(0);
+        self.encoder.protect_data(data, &mut md)?;
+
+        let id = source_symbol_metadata_to_u64(md);
+
+        // Slide the window over the most recent source symbols.
+        if id >= self.window_size {
+            self.encoder.remove_up_to(source_symbol_metadata_from_u64(
+                id - self.window_size,
+            ));
+        }
+
+        self.since_repair += 1;
+
+        let mut dgram = vec![
+            0;
+            1 + octets::varint_len(self.symbol_size as u64) +
+                octets::varint_len(id) +
+                payload.len()
+        ];
+        let mut b = octets::OctetsMut::with_slice(&mut dgram);
+        b.put_u8(SOURCE_DATAGRAM)?;
+        b.put_varint(self.symbol_size as u64)?;
+        b.put_varint(id)?;
+        b.put_bytes(payload)?;
+
+        Ok(dgram)
+    }
+
+    /// Returns whether protecting one more source symbol makes a repair
+    /// DATAGRAM due.
+    pub fn repair_due_next(&self) -> bool {
+        self.since_repair + 1 >= self.repair_interval
+    }
+
+    /// Returns a repair DATAGRAM if enough source symbols were protected
+    /// since the previous one.
+    pub fn repair(&mut self) -> Result<Option<Vec<u8>>> {
+        if self.since_repair < self.repair_interval {
+            return Ok(None);
+        }
+
+        self.since_repair = 0;
+
+        let rs = self.encoder.generate_and_serialize_repair_symbol()?;
+
+        let mut dgram = vec![
+            0;
+            1 + octets::varint_len(self.symbol_size as u64) +
+                rs.get().len()
+        ];
+        let mut b = octets::OctetsMut::with_slice(&mut dgram);
+        b.put_u8(REPAIR_DATAGRAM)?;
+        b.put_varint(self.symbol_size as u64)?;
+        b.put_bytes(rs.get())?;
+
+        Ok(Some(dgram))
+    }
+}
+
+/// Decodes the DATAGRAMs protected by a peer in FEC DATAGRAM interop mode.
+///
+/// The application passes the payloads of all the DATAGRAMs it receives to
+/// [`receive()`], which returns the application payloads they carry or
+/// allowed to recover, each once. The peer only protects its DATAGRAMs this
+/// way if [`accept_fec_datagram_interop()`] is enabled. See
+/// [`set_fec_datagram_interop()`] for the sending side.
+///
+/// ## Examples:
+///
+/// ```no_run
+/// # let mut buf = [0; 512];
+/// # let mut conn: quiche::Connection = unimplemented!();
+/// let mut decoder = quiche::FECDatagramDecoder::new(64);
+///
+/// while let Ok(len) = conn.dgram_recv(&mut buf) {
+///     for payload in decoder.receive(&buf[..len])? {
+///         // Process the payload.
+///     }
+/// }
+/// # Ok::<(), quiche::Error>(())
+/// ```
+///
+/// [`receive()`]: struct.FECDatagramDecoder.html#method.receive
+/// [`accept_fec_datagram_interop()`]: struct.Config.html#method.accept_fec_datagram_interop
+/// [`set_fec_datagram_interop()`]: struct.Config.html#method.set_fec_datagram_interop
+pub struct FECDatagramDecoder {
+    decoder: Option<Decoder>,
+    symbol_size: usize,
+    window_size: u64,
+    delivered: BTreeSet<u64>,
+}
+
+impl FECDatagramDecoder {
+    /// Creates a decoder keeping the last `window_size` source symbols.
+    pub fn new(window_size: usize) -> FECDatagramDecoder {
+        FECDatagramDecoder {
+            decoder: None,
+            symbol_size: 0,
+            window_size: window_size.max(1) as u64,
+            delivered: BTreeSet::new(),
+        }
+    }
+
+    /// Processes a received DATAGRAM payload, and returns the application
+    /// payloads it carries or allowed to recover.
+    pub fn receive(&mut self, dgram: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let mut b = octets::Octets::with_slice(dgram);
+
+        let ty = b.get_u8()?;
+        let symbol_size = b.get_varint()? as usize;
+
+        if symbol_size <= PAYLOAD_LEN_PREFIX || symbol_size > MAX_SYMBOL_SIZE {
+            return Err(Error::InvalidFrame);
+        }
+
+        // The symbol size is fixed for the whole connection.
+        if self.decoder.is_none() {
+            self.symbol_size = symbol_size;
+            self.decoder = Some(Decoder::VLC(VLCDecoder::new(
+                symbol_size,
+                self.window_size as usize,
+            )));
+        } else if symbol_size != self.symbol_size {
+            return Err(Error::InvalidFrame);
+        }
+
+        let mut payloads = Vec::new();
+
+        let recovered = match ty {
+            SOURCE_DATAGRAM => {
+                let id = b.get_varint()?;
+                let payload = b.get_bytes(b.cap())?.to_vec();
+
+                if payload.len() > max_payload_len(symbol_size) {
+                    return Err(Error::InvalidFrame);
+                }
+
+                let mut data = vec![0; symbol_size];
+                data[..PAYLOAD_LEN_PREFIX]
+                    .copy_from_slice(&(payload.len() as u16).to_be_bytes());
+                data[PAYLOAD_LEN_PREFIX..PAYLOAD_LEN_PREFIX + payload.len()]
+                    .copy_from_slice(&payload);
+
+                if self.deliver(id) {
+                    payloads.push(payload);
+                }
+
+                let decoder = self.decoder.as_mut().unwrap();
+
+                // Slide the window over the most recent source symbols.
+                if id >= self.window_size {
+                    decoder.remove_up_to(
+                        source_symbol_metadata_from_u64(id - self.window_size),
+                        None,
+                    );
+                }
+
+                let symbol =
+                    SourceSymbol::new(source_symbol_metadata_from_u64(id), data);
+
+                match decoder.receive_source_symbol(symbol, Instant::now()) {
+                    Ok(v) => v,
+
+                    // The symbol was recovered already or is too old.
+                    Err(DecoderError::UnusedSourceSymbol) => Vec::new(),
+
+                    Err(e) => return Err(e.into()),
+                }
+            },
+
+            REPAIR_DATAGRAM => {
+                let decoder = self.decoder.as_mut().unwrap();
+
+                let (_, rs) = decoder.read_repair_symbol(b.as_ref())?;
+
+                match decoder.receive_and_deserialize_repair_symbol(rs) {
+                    Ok((_, v)) => v,
+
+                    Err(DecoderError::UnusedRepairSymbol) => Vec::new(),
+
+                    Err(e) => return Err(e.into()),
+                }
+            },
+
+            _ => return Err(Error::InvalidFrame),
+        };
+
+        for symbol in recovered {
+            let id = source_symbol_metadata_to_u64(symbol.metadata());
+            let data = symbol.get();
+
+            let len = u16::from_be_bytes([data[0], data[1]]) as usize;
+
+            if PAYLOAD_LEN_PREFIX + len > data.len() {
+                return Err(Error::InvalidFrame);
+            }
+
+            if self.deliver(id) {
+                payloads.push(
+                    data[PAYLOAD_LEN_PREFIX..PAYLOAD_LEN_PREFIX + len].to_vec(),
+                );
+            }
+        }
+
+        Ok(payloads)
+    }
+
+    /// Returns whether the payload of the source symbol `id` was not
+    /// delivered yet, and marks it as delivered.
+    fn deliver(&mut self, id: u64) -> bool {
+        let largest = self.delivered.iter().next_back().copied().unwrap_or(0);
+
+        if id.saturating_add(self.window_size) <= largest {
+            return false;
+        }
+
+        if !self.delivered.insert(id) {
+            return false;
+        }
+
+        // Forget the source symbols that left the window.
+        let largest = largest.max(id);
+
+        if largest >= self.window_size {
+            self.delivered =
+                self.delivered.split_off(&(largest - self.window_size));
+        }
+
+        true
+    }
+}
+
+impl std::fmt::Debug for FECDatagramDecoder {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "FECDatagramDecoder symbol_size={}", self.symbol_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        assert_eq!(symbol_size(20), None);
+        assert_eq!(symbol_size(1200), Some(1177));
+        assert_eq!(max_payload_len(1177), 1175);
+
+        let mut encoder = FECDatagramEncoder::new(1177, 16, 2);
+        let dgram = encoder.protect(&[0xaa; 1175]).unwrap();
+        assert!(dgram.len() <= 1200);
+
+        assert_eq!(encoder.protect(&[0; 1176]), Err(Error::BufferTooShort));
+    }
+
+    #[test]
+    fn recover() {
+        let mut encoder = FECDatagramEncoder::new(32, 16, 2);
+        let mut decoder = FECDatagramDecoder::new(16);
+
+        let first = encoder.protect(b"hello").unwrap();
+        assert_eq!(encoder.repair(), Ok(None));
+
+        let second = encoder.protect(b"world").unwrap();
+        let repair = encoder.repair().unwrap().unwrap();
+
+        assert_eq!(&second[..3], &[SOURCE_DATAGRAM, 32, 1]);
+        assert_eq!(&repair[..2], &[REPAIR_DATAGRAM, 32]);
+        assert_eq!(repair.len(), 2 + REPAIR_SYMBOL_HEADER_LEN + 32);
+
+        // The first DATAGRAM is lost.
+        drop(first);
+
+        assert_eq!(decoder.receive(&second), Ok(vec![b"world".to_vec()]));
+        assert_eq!(decoder.receive(&repair), Ok(vec![b"hello".to_vec()]));
+
+        // Each payload is delivered once.
+        assert_eq!(decoder.receive(&second), Ok(vec![]));
+    }
+
+    #[test]
+    fn invalid() {
+        let mut decoder = FECDatagramDecoder::new(16);
+
+        assert_eq!(decoder.receive(&[]), Err(Error::BufferTooShort));
+        assert_eq!(decoder.receive(&[0x02, 32]), Err(Error::InvalidFrame));
+        assert_eq!(decoder.receive(&[0x00, 2, 0]), Err(Error::InvalidFrame));
+
+        assert_eq!(decoder.receive(&[0x00, 32, 0, 1]), Ok(vec![vec![1]]));
+
+        // The symbol size cannot change.
+        assert_eq!(decoder.receive(&[0x00, 16, 1, 1]), Err(Error::InvalidFrame));
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::VecDeque;
+
+/// A command altering the FEC behavior of a connection at a given packet, to
+/// reproduce the conditions of a reported bug deterministically.
+///
+/// See [`Connection::fec_debug_command()`].
+///
+/// [`Connection::fec_debug_command()`]: struct.Connection.html#method.fec_debug_command
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FECDebugCommand {
+    /// The next repair symbol is generated and accounted as sent by the
+    /// scheduler, but not written to the packet, as if it was lost.
+    DropNextRepair,
+
+    /// The next packet carries a repair symbol, whatever the decision of the
+    /// scheduler.
+    ForceRepair,
+
+    /// A new generation of the encoding window is started, as with
+    /// [`Connection::rotate_fec_window()`].
+    ///
+    /// [`Connection::rotate_fec_window()`]: struct.Connection.html#method.rotate_fec_window
+    RotateWindow,
+}
+
+/// The debug commands scheduled on a connection.
+#[derive(Default)]
+pub(crate) struct DebugControl {
+    /// The commands not applied yet, with the number of packets sent at which
+    /// they apply, in order.
+    scheduled: VecDeque<(usize, FECDebugCommand)>,
+
+    drop_next_repair: bool,
+    force_repair: bool,
+}
+
+impl DebugControl {
+    /// Schedules `command` for when `at_packet` packets have been sent.
+    pub fn schedule(&mut self, at_packet: usize, command: FECDebugCommand) {
+        let pos = self
+            .scheduled
+            .iter()
+            .position(|(at, _)| *at > at_packet)
+            .unwrap_or(self.scheduled.len());
+
+        self.scheduled.insert(pos, (at_packet, command));
+    }
+
+    /// Returns the next command due now that `sent_count` packets have been
+    /// sent. The repair commands are applied by the control itself.
+    pub fn next_due(&mut self, sent_count: usize) -> Option<FECDebugCommand> {
+        while matches!(self.scheduled.front(), Some((at, _)) if *at <= sent_count)
+        {
+            let (_, command) = self.scheduled.pop_front()?;
+
+            trace!("fec debug command {:?} at packet {}", command, sent_count);
+
+            match command {
+                FECDebugCommand::DropNextRepair => self.drop_next_repair = true,
+
+                FECDebugCommand::ForceRepair => self.force_repair = true,
+
+                _ => return Some(command),
+            }
+        }
+
+        None
+    }
+
+    /// Returns whether a repair symbol must be sent in the next packet.
+    pub fn take_force_repair(&mut self) -> bool {
+        std::mem::take(&mut self.force_repair)
+    }
+
+    /// Returns whether the repair symbol just generated must be dropped.
+    pub fn take_drop_repair(&mut self) -> bool {
+        std::mem::take(&mut self.drop_next_repair)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_in_order() {
+        let mut control = DebugControl::default();
+
+        control.schedule(5, FECDebugCommand::RotateWindow);
+        control.schedule(2, FECDebugCommand::DropNextRepair);
+        control.schedule(5, FECDebugCommand::ForceRepair);
+
+        assert_eq!(control.next_due(1), None);
+        assert!(!control.take_drop_repair());
+
+        assert_eq!(control.next_due(3), None);
+        assert!(control.take_drop_repair());
+        assert!(!control.take_drop_repair());
+
+        assert_eq!(control.next_due(5), Some(FECDebugCommand::RotateWindow));
+        assert!(!control.take_force_repair());
+        assert_eq!(control.next_due(5), None);
+        assert!(control.take_force_repair());
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::ranges::RangeSet;
+
+// The maximum number of repair symbols kept by the decoder awaiting a
+// verdict.
+const MAX_KEPT_REPAIR_SYMBOLS: usize = 1024;
+
+const MAX_SYMBOL_RANGES: usize = 1024;
+
+/// Statistics of the FEC decoder.
+///
+/// The receiver reports them to the sender with FEC_STATS frames, see
+/// [`set_fec_decoder_stats()`] and [`fec_peer_decoder_stats()`].
+///
+/// [`set_fec_decoder_stats()`]:
+/// struct.Config.html#method.set_fec_decoder_stats
+/// [`fec_peer_decoder_stats()`]:
+/// struct.Connection.html#method.fec_peer_decoder_stats
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FECDecoderStats {
+    /// The number of source symbols received.
+    pub source_symbols_received: u64,
+
+    /// The number of repair symbols received.
+    pub repair_received: u64,
+
+    /// The number of repair symbols received that were useless, because
+    /// all the source symbols they protect were received.
+    pub repair_useless: u64,
+
+    /// The number of repair symbols that did not allow to recover any
+    /// source symbol before the source symbols they protect left the
+    /// decoding window, because the decoder lacked enough repair symbols.
+    pub repair_rank_deficient: u64,
+
+    /// The number of source symbols recovered.
+    pub recovered: u64,
+}
+
+/// Receiver-side tracking of the [`FECDecoderStats`].
+///
+/// A repair symbol kept by the decoder without recovering anything is
+/// expected to be combined with the next ones. Its verdict is only given
+/// once all the source symbols it protects left the decoding window: it is
+/// rank-deficient if some of them were neither received nor recovered, and
+/// useless if they all eventually arrived.
+///
+/// [`FECDecoderStats`]: struct.FECDecoderStats.html
+pub(crate) struct DecoderStatsTracker {
+    stats: FECDecoderStats,
+    kept_repair_symbols: VecDeque<Range<u64>>,
+    received: RangeSet,
+    recovered: RangeSet,
+    next_report: Option<Instant>,
+    reported: Option<FECDecoderStats>,
+}
+
+impl DecoderStatsTracker {
+    pub fn new() -> DecoderStatsTracker {
+        DecoderStatsTracker {
+            stats: FECDecoderStats::default(),
+            kept_repair_symbols: VecDeque::new(),
+            received: RangeSet::new(MAX_SYMBOL_RANGES),
+            recovered: RangeSet::new(MAX_SYMBOL_RANGES),
+            next_report: None,
+            reported: None,
+        }
+    }
+
+    pub fn source_symbol_received(&mut self, id: u64) {
+        self.stats.source_symbols_received += 1;
+        self.received.push_item(id);
+    }
+
+    pub fn repair_symbol_received(&mut self) {
+        self.stats.repair_received += 1;
+    }
+
+    /// Records a repair symbol that the decoder could not use.
+    pub fn repair_symbol_useless(&mut self) {
+        self.stats.repair_useless += 1;
+    }
+
+    /// Records a repair symbol protecting the source symbols `protected`,
+    /// kept by the decoder without recovering any source symbol yet.
+    ///
+    /// If too many repair symbols await a verdict, the oldest one is given
+    /// its verdict right away.
+    pub fn repair_symbol_kept(&mut self, protected: Range<u64>) {
+        if self.kept_repair_symbols.len() >= MAX_KEPT_REPAIR_SYMBOLS {
+            if let Some(oldest) = self.kept_repair_symbols.pop_front() {
+                self.verdict(oldest);
+            }
+        }
+
+        self.kept_repair_symbols.push_back(protected);
+    }
+
+    pub fn source_symbol_recovered(&mut self, id: u64) {
+        self.stats.recovered += 1;
+        self.recovered.push_item(id);
+    }
+
+    /// Gives a verdict for the kept repair symbols whose protected source
+    /// symbols all left the decoding window, `window_first` being the first
+    /// source symbol of the window.
+    pub fn update(&mut self, window_first: Option<u64>) {
+        while let Some(protected) = self.kept_repair_symbols.front() {
+            if matches!(window_first, Some(first) if protected.end > first) {
+                break;
+            }
+
+            if let Some(protected) = self.kept_repair_symbols.pop_front() {
+                self.verdict(protected);
+            }
+        }
+
+        // The received and recovered source symbols are still needed for the
+        // verdict of the kept repair symbols.
+        let first = match (window_first, self.kept_repair_symbols.front()) {
+            (Some(first), Some(protected)) => first.min(protected.start),
+
+            (Some(first), None) => first,
+
+            (None, _) => return,
+        };
+
+        if first > 0 {
+            self.received.remove_until(first - 1);
+            self.recovered.remove_until(first - 1);
+        }
+    }
+
+    fn verdict(&mut self, protected: Range<u64>) {
+        let overlap = |set: &RangeSet| -> u64 {
+            set.iter()
+                .map(|r| {
+                    r.end
+                        .min(protected.end)
+                        .saturating_sub(r.start.max(protected.start))
+                })
+                .sum()
+        };
+
+        // The repair symbol was combined with others to recover data.
+        if overlap(&self.recovered) > 0 {
+            return;
+        }
+
+        if overlap(&self.received) >= protected.end - protected.start {
+            self.stats.repair_useless += 1;
+        } else {
+            self.stats.repair_rank_deficient += 1;
+        }
+    }
+
+    pub fn stats(&self) -> FECDecoderStats {
+        self.stats
+    }
+
+    /// Returns the statistics to report to the peer, if a report is due at
+    /// `now` and they changed since the previous one.
+    ///
+    /// The counters are cumulative, so a lost report that was superseded by
+    /// a newer one is not sent again, see [`lost()`].
+    ///
+    /// [`lost()`]: struct.DecoderStatsTracker.html#method.lost
+    pub fn report(
+        &mut self, now: Instant, rtt: Duration,
+    ) -> Option<FECDecoderStats> {
+        if self.next_report.map_or(false, |t| now < t) ||
+            self.reported == Some(self.stats)
+        {
+            return None;
+        }
+
+        self.next_report = Some(now + rtt);
+        self.reported = Some(self.stats);
+
+        Some(self.stats)
+    }
+
+    /// Records the loss of a report of `stats`. It is sent again right away
+    /// if it was the latest one, so that the peer eventually gets the final
+    /// statistics.
+    pub fn lost(&mut self, stats: FECDecoderStats) {
+        if self.reported == Some(stats) {
+            self.reported = None;
+            self.next_report = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verdict_once_out_of_window() {
+        let mut tracker = DecoderStatsTracker::new();
+
+        for id in [0, 1, 3, 5, 6, 7] {
+            tracker.source_symbol_received(id);
+        }
+
+        // Symbols 2 and 4 are missing, and a single repair symbol arrives.
+        tracker.repair_symbol_received();
+        tracker.repair_symbol_kept(0..8);
+
+        tracker.update(Some(0));
+        assert_eq!(tracker.stats().repair_rank_deficient, 0);
+
+        tracker.update(Some(8));
+        assert_eq!(tracker.stats().repair_rank_deficient, 1);
+        assert_eq!(tracker.stats().repair_useless, 0);
+    }
+
+    #[test]
+    fn combined_repair_symbols() {
+        let mut tracker = DecoderStatsTracker::new();
+
+        for id in [0, 1, 3, 5] {
+            tracker.source_symbol_received(id);
+        }
+
+        tracker.repair_symbol_kept(0..6);
+
+        // The second repair symbol recovers both missing symbols.
+        tracker.source_symbol_recovered(2);
+        tracker.source_symbol_recovered(4);
+
+        tracker.update(None);
+
+        let stats = tracker.stats();
+        assert_eq!(stats.recovered, 2);
+        assert_eq!(stats.repair_rank_deficient, 0);
+        assert_eq!(stats.repair_useless, 0);
+    }
+
+    #[test]
+    fn late_source_symbols() {
+        let mut tracker = DecoderStatsTracker::new();
+
+        tracker.source_symbol_received(0);
+        tracker.repair_symbol_kept(0..2);
+
+        // The missing symbol eventually arrives.
+        tracker.source_symbol_received(1);
+        tracker.update(Some(2));
+
+        assert_eq!(tracker.stats().repair_useless, 1);
+        assert_eq!(tracker.stats().repair_rank_deficient, 0);
+    }
+
+    #[test]
+    fn report_once_per_round_trip() {
+        let now = Instant::now();
+        let rtt = Duration::from_millis(50);
+
+        let mut tracker = DecoderStatsTracker::new();
+
+        tracker.source_symbol_received(0);
+        assert_eq!(tracker.report(now, rtt).unwrap().source_symbols_received, 1);
+
+        tracker.source_symbol_received(1);
+        assert_eq!(tracker.report(now, rtt), None);
+
+        let stats = tracker.report(now + rtt, rtt).unwrap();
+        assert_eq!(stats.source_symbols_received, 2);
+
+        // Nothing changed.
+        assert_eq!(tracker.report(now + rtt * 2, rtt), None);
+    }
+
+    #[test]
+    fn lost_report() {
+        let now = Instant::now();
+        let rtt = Duration::from_millis(50);
+
+        let mut tracker = DecoderStatsTracker::new();
+
+        tracker.source_symbol_received(0);
+        let first = tracker.report(now, rtt).unwrap();
+
+        tracker.source_symbol_received(1);
+        let second = tracker.report(now + rtt, rtt).unwrap();
+
+        // The superseded report is not sent again.
+        tracker.lost(first);
+        assert_eq!(tracker.report(now + rtt, rtt), None);
+
+        // The latest one is, without waiting for the next round-trip.
+        tracker.lost(second);
+        assert_eq!(tracker.report(now + rtt, rtt), Some(second));
+        assert_eq!(tracker.report(now + rtt, rtt), None);
+    }
+}
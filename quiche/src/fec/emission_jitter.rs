@@ -0,0 +1,123 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use super::rng::FecRng;
+
+/// Random delay before the emission of repair symbols.
+///
+/// Used for experiments on the correlation between the losses of source and
+/// repair symbols: once a repair symbol is needed, its emission is delayed
+/// by a random duration of at most `max`.
+pub(crate) struct EmissionJitter {
+    max: Duration,
+    rng: FecRng,
+    release_at: Option<Instant>,
+    next_timeout: Option<Instant>,
+}
+
+impl EmissionJitter {
+    pub fn new(max: Duration, rng: FecRng) -> EmissionJitter {
+        EmissionJitter {
+            max,
+            rng,
+            release_at: None,
+            next_timeout: None,
+        }
+    }
+
+    /// Returns whether a repair symbol can be sent at `now`, given
+    /// `should_send_repair`, the decision taken without jitter.
+    pub fn should_send_repair(
+        &mut self, should_send_repair: bool, now: Instant,
+    ) -> bool {
+        self.next_timeout = None;
+
+        if !should_send_repair {
+            self.release_at = None;
+            return false;
+        }
+
+        let release_at = match self.release_at {
+            Some(v) => v,
+
+            None => {
+                let v = now + self.rng.duration_up_to(self.max);
+                self.release_at = Some(v);
+                v
+            },
+        };
+
+        if now < release_at {
+            self.next_timeout = Some(release_at);
+
+            return false;
+        }
+
+        true
+    }
+
+    pub fn sent_repair_symbol(&mut self) {
+        self.release_at = None;
+        self.next_timeout = None;
+    }
+
+    // returns an Instant at which the stack should wake up to send the
+    // delayed repair symbol
+    pub fn timeout(&self) -> Option<Instant> {
+        self.next_timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delays(seed: u64, max: Duration) -> Vec<Duration> {
+        let now = Instant::now();
+        let mut jitter = EmissionJitter::new(max, FecRng::new(Some(seed)));
+
+        (0..16)
+            .map(|_| {
+                let delay = if jitter.should_send_repair(true, now) {
+                    Duration::ZERO
+                } else {
+                    jitter.timeout().unwrap() - now
+                };
+
+                jitter.sent_repair_symbol();
+
+                delay
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bounded_and_reproducible() {
+        let max = Duration::from_millis(20);
+
+        let a = delays(7, max);
+        assert!(a.iter().all(|d| *d <= max));
+        assert_eq!(a, delays(7, max));
+        assert_ne!(a, delays(8, max));
+    }
+
+    #[test]
+    fn delay_kept_until_sent() {
+        let now = Instant::now();
+        let max = Duration::from_millis(20);
+        let mut jitter = EmissionJitter::new(max, FecRng::new(Some(3)));
+
+        assert!(!jitter.should_send_repair(true, now));
+        let release_at = jitter.timeout().unwrap();
+
+        // The delay is not drawn again.
+        assert!(!jitter.should_send_repair(true, now));
+        assert_eq!(jitter.timeout(), Some(release_at));
+
+        assert!(jitter.should_send_repair(true, release_at));
+
+        // Nothing to delay.
+        assert!(!jitter.should_send_repair(false, release_at));
+        assert_eq!(jitter.timeout(), None);
+    }
+}
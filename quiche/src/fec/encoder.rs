@@ -0,0 +1,248 @@
+use std::ops::Range;
+
+use networkcoding::source_symbol_metadata_from_u64;
+use networkcoding::source_symbol_metadata_to_u64;
+use networkcoding::Encoder;
+use networkcoding::EncoderError;
+use networkcoding::SourceSymbolMetadata;
+
+use crate::fec::repair_offload::OffloadedEncoder;
+use crate::fec::repair_offload::RepairWorkerPool;
+
+/// The FEC encoder of a connection.
+///
+/// A single encoder protects the source symbols sent on all the paths, as
+/// the peer has a single decoder and the FEC frames don't identify the
+/// encoder of a symbol.
+///
+/// When the repair symbols are offloaded, the updates of the encoder are
+/// mirrored to an [`OffloadedEncoder`] generating them on worker threads.
+///
+/// The landed source symbols only leave the window once its first symbol
+/// landed, so that processing an ACK doesn't scan a large window that can't
+/// shrink.
+pub(crate) struct FECEncoder {
+    encoder: Encoder,
+    offloaded: Option<OffloadedEncoder>,
+
+    /// Whether a source symbol was protected by the encoder.
+    used: bool,
+
+    /// Whether the first source symbol of the window may have landed since
+    /// the window was last pruned.
+    prune_pending: bool,
+}
+
+impl FECEncoder {
+    pub fn new(encoder: Encoder) -> FECEncoder {
+        FECEncoder {
+            encoder,
+            offloaded: None,
+            used: false,
+            prune_pending: false,
+        }
+    }
+
+    /// Replaces the encoder, and its offloaded mirror, with new ones, if no
+    /// source symbol was protected yet. Returns whether it was replaced.
+    pub fn replace(&mut self, new_encoder: impl Fn() -> Encoder) -> bool {
+        if self.used {
+            return false;
+        }
+
+        self.encoder = new_encoder();
+
+        if let Some(offloaded) = &mut self.offloaded {
+            offloaded.replace_encoder(new_encoder());
+        }
+
+        true
+    }
+
+    /// Generates the repair symbols on the threads of `pool`, with `encoder`
+    /// mirroring the encoder protecting the packets.
+    ///
+    /// It must be called before any source symbol is protected.
+    pub fn offload(&mut self, pool: &RepairWorkerPool, encoder: Encoder) {
+        self.offloaded = Some(OffloadedEncoder::new(pool, encoder));
+    }
+
+    pub fn offloaded(&self) -> Option<&OffloadedEncoder> {
+        self.offloaded.as_ref()
+    }
+
+    pub fn offloaded_mut(&mut self) -> Option<&mut OffloadedEncoder> {
+        self.offloaded.as_mut()
+    }
+
+    pub fn get(&self) -> &Encoder {
+        &self.encoder
+    }
+
+    pub fn get_mut(&mut self) -> &mut Encoder {
+        &mut self.encoder
+    }
+
+    /// Protects `data`, writing the metadata of the new source symbol to
+    /// `md`.
+    pub fn protect_data(
+        &mut self, data: Vec<u8>, md: &mut SourceSymbolMetadata,
+    ) -> Result<usize, EncoderError> {
+        if let Some(offloaded) = &mut self.offloaded {
+            offloaded.protect_data(data.clone());
+        }
+
+        self.used = true;
+
+        let first = self.encoder.first_metadata();
+        let res = self.encoder.protect_data(data, md);
+
+        // A full window evicting its first symbol may expose landed ones.
+        if first.is_some() && self.encoder.first_metadata() != first {
+            self.prune_pending = true;
+        }
+
+        res
+    }
+
+    /// Removes the source symbols up to `md` from the window.
+    pub fn remove_up_to(&mut self, md: SourceSymbolMetadata) {
+        if let Some(offloaded) = &mut self.offloaded {
+            offloaded.remove_up_to(md);
+        }
+
+        self.encoder.remove_up_to(md);
+        self.prune_pending = true;
+    }
+
+    /// Marks the source symbol `md` as received or given up on.
+    pub fn symbol_landed(&mut self, md: SourceSymbolMetadata) {
+        if let Some(offloaded) = &mut self.offloaded {
+            offloaded.symbol_landed(md);
+        }
+
+        self.encoder.symbol_landed(md);
+
+        if self.encoder.first_metadata() == Some(md) {
+            self.prune_pending = true;
+        }
+    }
+
+    /// Marks the source symbols in `range` as received by the peer.
+    ///
+    /// The symbols that already left the window are skipped, so the cost
+    /// only depends on the symbols still protected, however wide `range` is.
+    pub fn acked_source_symbols(&mut self, range: Range<u64>) {
+        let (first, last) = match (
+            self.encoder.first_metadata(),
+            self.encoder.last_metadata(),
+        ) {
+            (Some(first), Some(last)) => (
+                source_symbol_metadata_to_u64(first),
+                source_symbol_metadata_to_u64(last),
+            ),
+
+            _ => return,
+        };
+
+        let start = range.start.max(first);
+        let end = range.end.min(last + 1);
+
+        for id in start..end {
+            self.symbol_landed(source_symbol_metadata_from_u64(id));
+        }
+    }
+
+    /// Removes the landed source symbols at the start of the window, if its
+    /// first symbol landed since the last call.
+    pub fn remove_landed_symbols(&mut self) {
+        if !self.prune_pending {
+            return;
+        }
+
+        if let Some(offloaded) = &mut self.offloaded {
+            offloaded.remove_landed_symbols();
+        }
+
+        self.encoder.remove_landed_symbols();
+
+        self.prune_pending = false;
+    }
+
+    /// Returns the number of source symbols in the window.
+    pub fn n_protected_symbols(&self) -> usize {
+        self.encoder.n_protected_symbols()
+    }
+
+    pub fn symbol_size(&self) -> usize {
+        self.encoder.symbol_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use networkcoding::vandermonde_lc::encoder::VLCEncoder;
+
+    fn new_encoder() -> FECEncoder {
+        FECEncoder::new(Encoder::VLC(VLCEncoder::new(16, 100)))
+    }
+
+    #[test]
+    fn symbol_landed() {
+        let mut encoder = new_encoder();
+
+        let mut md = source_symbol_metadata_from_u64(0);
+        encoder.protect_data(vec![0; 16], &mut md).unwrap();
+        assert_eq!(encoder.n_protected_symbols(), 1);
+
+        encoder.symbol_landed(md);
+        encoder.remove_landed_symbols();
+        assert_eq!(encoder.n_protected_symbols(), 0);
+    }
+
+    #[test]
+    fn replace() {
+        let mut encoder = new_encoder();
+
+        assert!(encoder.replace(|| Encoder::VLC(VLCEncoder::new(16, 10))));
+
+        let mut md = source_symbol_metadata_from_u64(0);
+        encoder.protect_data(vec![0; 16], &mut md).unwrap();
+
+        // The peer may already have received symbols of the encoder.
+        assert!(!encoder.replace(|| Encoder::VLC(VLCEncoder::new(16, 10))));
+        assert_eq!(encoder.n_protected_symbols(), 1);
+    }
+
+    #[test]
+    fn acked_source_symbols() {
+        let mut encoder = new_encoder();
+
+        let mut md = source_symbol_metadata_from_u64(0);
+
+        for _ in 0..10 {
+            encoder.protect_data(vec![0; 16], &mut md).unwrap();
+        }
+
+        // Symbols past the end of the window are ignored, and the window
+        // can't shrink while its first symbol is in flight.
+        encoder.acked_source_symbols(1..1_000_000);
+        assert!(!encoder.prune_pending);
+        encoder.remove_landed_symbols();
+        assert_eq!(encoder.n_protected_symbols(), 10);
+
+        encoder.acked_source_symbols(0..1);
+        assert!(encoder.prune_pending);
+        encoder.remove_landed_symbols();
+        assert!(!encoder.prune_pending);
+        assert_eq!(encoder.n_protected_symbols(), 0);
+
+        // Symbols that already left the window are skipped.
+        encoder.protect_data(vec![0; 16], &mut md).unwrap();
+        encoder.acked_source_symbols(0..10);
+        assert!(!encoder.prune_pending);
+        assert_eq!(encoder.n_protected_symbols(), 1);
+    }
+}
@@ -0,0 +1,27 @@
+/// An FEC event: a change of the emission of FEC requested with
+/// [`set_fec_enabled()`], reported once it took effect, or an ADU recovered
+/// by the peer.
+///
+/// [`set_fec_enabled()`]: struct.Connection.html#method.set_fec_enabled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FECEvent {
+    /// The connection protects the packets it sends again.
+    Enabled,
+
+    /// The connection stopped sending FEC: the peer was told to drop the
+    /// source symbols of the encoding window, and all the repair symbols in
+    /// flight were acknowledged or declared lost.
+    Disabled,
+
+    /// Data of an ADU written with [`stream_send_with_boundaries()`] was lost
+    /// and recovered by the peer with FEC, without a retransmission.
+    ///
+    /// [`stream_send_with_boundaries()`]: struct.Connection.html#method.stream_send_with_boundaries
+    AduRecovered {
+        /// The stream the ADU was written to.
+        stream_id: u64,
+
+        /// The identifier of the ADU.
+        adu: u64,
+    },
+}
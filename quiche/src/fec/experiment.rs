@@ -0,0 +1,251 @@
+use std::io::Write;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::fec::context::SchedulerContext;
+
+/// The inputs and the output of an evaluation of the FEC scheduler.
+///
+/// One record is produced each time the scheduler decides whether to send a
+/// repair symbol, to feed the offline training and analysis of redundancy
+/// policies.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FECDecisionRecord {
+    /// The time elapsed since the sink was registered.
+    pub time: Duration,
+
+    /// The ID of the path the decision is taken for.
+    pub path_id: usize,
+
+    /// Whether there are DATAGRAM frames waiting to be sent.
+    pub dgrams_to_emit: bool,
+
+    /// Whether there are stream data waiting to be sent.
+    pub stream_to_emit: bool,
+
+    /// The number of packets sent on the connection.
+    pub sent_count: usize,
+
+    /// The number of stream bytes sent on the connection.
+    pub tx_data: u64,
+
+    /// The number of bytes in flight on the path.
+    pub bytes_in_flight: usize,
+
+    /// The number of bytes available in the congestion window of the path.
+    pub cwnd_available: usize,
+
+    /// The smoothed round-trip time of the path.
+    pub rtt: Duration,
+
+    /// The time the pacer takes to send a full-sized packet on the path.
+    pub pacing_interval: Option<Duration>,
+
+    /// Whether the congestion controller of the path is in slow start.
+    pub in_slow_start: bool,
+
+    /// The estimated number of packets lost per round-trip on the path.
+    pub packets_lost_per_round_trip: Option<f64>,
+
+    /// The variance of the number of packets lost per round-trip.
+    pub var_packets_lost_per_round_trip: f64,
+
+    /// The size of the source and repair symbols.
+    pub symbol_size: usize,
+
+    /// The ID of the first source symbol of the encoding window.
+    pub window_first: Option<u64>,
+
+    /// The ID of the last source symbol of the encoding window.
+    pub window_last: Option<u64>,
+
+    /// The number of source symbols in the encoding window.
+    pub window_symbols: usize,
+
+    /// Whether the scheduler decided to send a repair symbol.
+    pub decision: bool,
+}
+
+/// The names of the columns of [`FECDecisionRecord::to_csv()`].
+///
+/// [`FECDecisionRecord::to_csv()`]: struct.FECDecisionRecord.html#method.to_csv
+pub const FEC_DECISION_CSV_HEADER: &str = "time_us,path_id,dgrams_to_emit,\
+stream_to_emit,sent_count,tx_data,bytes_in_flight,cwnd_available,rtt_us,\
+pacing_interval_ns,in_slow_start,packets_lost_per_round_trip,\
+var_packets_lost_per_round_trip,symbol_size,window_first,window_last,\
+window_symbols,decision";
+
+impl FECDecisionRecord {
+    pub(crate) fn new(
+        ctx: &SchedulerContext, path_id: usize, decision: bool, start: Instant,
+    ) -> FECDecisionRecord {
+        FECDecisionRecord {
+            time: ctx.now.saturating_duration_since(start),
+            path_id,
+            dgrams_to_emit: ctx.dgrams_to_emit,
+            stream_to_emit: ctx.stream_to_emit,
+            sent_count: ctx.sent_count,
+            tx_data: ctx.tx_data,
+            bytes_in_flight: ctx.bif,
+            cwnd_available: ctx.cwnd_available,
+            rtt: ctx.rtt,
+            pacing_interval: ctx.pacing_interval,
+            in_slow_start: ctx.in_slow_start,
+            packets_lost_per_round_trip: ctx.packets_lost_per_round_trip,
+            var_packets_lost_per_round_trip: ctx.var_packets_lost_per_round_trip,
+            symbol_size: ctx.symbol_size,
+            window_first: ctx.window.first,
+            window_last: ctx.window.last,
+            window_symbols: ctx.window.n_symbols,
+            decision,
+        }
+    }
+
+    /// Returns the record as a CSV line, without the trailing newline.
+    ///
+    /// The columns are listed in [`FEC_DECISION_CSV_HEADER`]. Booleans are
+    /// written as `0` or `1`, and missing values as empty fields.
+    ///
+    /// [`FEC_DECISION_CSV_HEADER`]: constant.FEC_DECISION_CSV_HEADER.html
+    pub fn to_csv(&self) -> String {
+        fn opt<T: ToString>(v: Option<T>) -> String {
+            v.map(|v| v.to_string()).unwrap_or_default()
+        }
+
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.time.as_micros(),
+            self.path_id,
+            self.dgrams_to_emit as u8,
+            self.stream_to_emit as u8,
+            self.sent_count,
+            self.tx_data,
+            self.bytes_in_flight,
+            self.cwnd_available,
+            self.rtt.as_micros(),
+            opt(self.pacing_interval.map(|v| v.as_nanos())),
+            self.in_slow_start as u8,
+            opt(self.packets_lost_per_round_trip),
+            self.var_packets_lost_per_round_trip,
+            self.symbol_size,
+            opt(self.window_first),
+            opt(self.window_last),
+            self.window_symbols,
+            self.decision as u8,
+        )
+    }
+}
+
+/// A receiver of the decisions of the FEC scheduler.
+///
+/// See [`set_fec_decision_sink()`].
+///
+/// [`set_fec_decision_sink()`]: struct.Connection.html#method.set_fec_decision_sink
+pub trait FECDecisionSink: Send + Sync {
+    /// Receives the inputs and the output of an evaluation of the scheduler.
+    fn record(&mut self, record: &FECDecisionRecord);
+}
+
+/// A sink writing the decisions of the FEC scheduler as CSV lines, preceded
+/// by the [`FEC_DECISION_CSV_HEADER`] line.
+///
+/// Writing stops at the first I/O error.
+///
+/// [`FEC_DECISION_CSV_HEADER`]: constant.FEC_DECISION_CSV_HEADER.html
+pub struct FECDecisionCsvWriter<W: Write + Send + Sync> {
+    writer: W,
+    header_written: bool,
+    failed: bool,
+}
+
+impl<W: Write + Send + Sync> FECDecisionCsvWriter<W> {
+    /// Creates a sink writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        FECDecisionCsvWriter {
+            writer,
+            header_written: false,
+            failed: false,
+        }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + Send + Sync> FECDecisionSink for FECDecisionCsvWriter<W> {
+    fn record(&mut self, record: &FECDecisionRecord) {
+        if self.failed {
+            return;
+        }
+
+        let res = if self.header_written {
+            Ok(())
+        } else {
+            self.header_written = true;
+
+            writeln!(self.writer, "{}", FEC_DECISION_CSV_HEADER)
+        }
+        .and_then(|_| writeln!(self.writer, "{}", record.to_csv()));
+
+        if let Err(e) = res {
+            error!("failed to write FEC scheduler decision: {:?}", e);
+            self.failed = true;
+        }
+    }
+}
+
+/// The sink registered on a connection, with the time it was registered at.
+pub(crate) struct DecisionSink {
+    sink: Box<dyn FECDecisionSink>,
+    start: Instant,
+}
+
+impl DecisionSink {
+    pub fn new(sink: Box<dyn FECDecisionSink>, start: Instant) -> DecisionSink {
+        DecisionSink { sink, start }
+    }
+
+    pub fn record(
+        &mut self, ctx: &SchedulerContext, path_id: usize, decision: bool,
+    ) {
+        self.sink
+            .record(&FECDecisionRecord::new(ctx, path_id, decision, self.start));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fec::testing;
+
+    #[test]
+    fn csv() {
+        let start = Instant::now();
+        let ctx = SchedulerContext {
+            now: start + Duration::from_millis(2),
+            stream_to_emit: true,
+            bif: 2400,
+            window: testing::window(3, 4, start),
+            ..testing::context(start)
+        };
+
+        let mut writer = FECDecisionCsvWriter::new(Vec::new());
+        writer.record(&FECDecisionRecord::new(&ctx, 1, true, start));
+        writer.record(&FECDecisionRecord::new(&ctx, 1, false, start));
+
+        let csv = String::from_utf8(writer.into_inner()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], FEC_DECISION_CSV_HEADER);
+        assert_eq!(
+            lines[1],
+            "2000,1,0,1,0,0,2400,20000,50000,,0,,0,1200,3,4,2,1"
+        );
+        assert_eq!(lines[0].split(',').count(), lines[2].split(',').count());
+        assert!(lines[2].ends_with(",0"));
+    }
+}
@@ -1,19 +1,25 @@
 use core::str::FromStr;
 
-use networkcoding::Encoder;
+use std::fmt;
+use std::time::Duration;
 
 use crate::fec::background_fec_scheduler::BackgroundFECScheduler;
+use crate::fec::burst_calibration::FECBurstCalibration;
 use crate::fec::burst_protecting_fec_scheduler::BurstsFECScheduler;
+use crate::fec::context::EncoderWindow;
+use crate::fec::context::SchedulerContext;
+use crate::fec::custom_scheduler::CustomScheduler;
 use crate::fec::fec_scheduler::FECScheduler::BackgroundOnly;
 use crate::fec::fec_scheduler::FECScheduler::Bursty;
+use crate::fec::fec_scheduler::FECScheduler::Custom;
 use crate::fec::fec_scheduler::FECScheduler::NoRedundancy;
-use crate::path::Path;
-use crate::Connection;
+use crate::path::PathView;
 
 /// Available FEC redundancy schedulers.
 ///
 /// This enum provides currently available list of FEC redundancy schedulers.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub enum FECSchedulerAlgorithm {
     /// Never sends redundancy (default). `noredundancy` in a string form.
@@ -26,19 +32,90 @@ pub enum FECSchedulerAlgorithm {
     BurstsOnly     = 2,
 }
 
+const ALL_FEC_SCHEDULER_ALGORITHMS: &[FECSchedulerAlgorithm] = &[
+    FECSchedulerAlgorithm::NoRedundancy,
+    FECSchedulerAlgorithm::BackgroundOnly,
+    FECSchedulerAlgorithm::BurstsOnly,
+];
+
+impl FECSchedulerAlgorithm {
+    /// Returns all the available FEC redundancy schedulers.
+    pub fn all() -> &'static [FECSchedulerAlgorithm] {
+        ALL_FEC_SCHEDULER_ALGORITHMS
+    }
+
+    /// Returns the string form of the scheduler.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FECSchedulerAlgorithm::NoRedundancy => "noredundancy",
+            FECSchedulerAlgorithm::BackgroundOnly => "background",
+            FECSchedulerAlgorithm::BurstsOnly => "bursts",
+        }
+    }
+
+    /// Converts the value carried by FEC_REQUEST frames to
+    /// `FECSchedulerAlgorithm`.
+    pub(crate) fn from_u64(v: u64) -> Option<Self> {
+        Self::all().iter().find(|alg| **alg as u64 == v).copied()
+    }
+}
+
+impl fmt::Display for FECSchedulerAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
 impl FromStr for FECSchedulerAlgorithm {
     type Err = crate::Error;
 
-    /// Converts a string to `FECSchedulerAlgorighm`.
+    /// Converts a string to `FECSchedulerAlgorithm`.
+    ///
+    /// If `name` does not match any of the schedulers returned by
+    /// [`all()`], `Error::FECScheduler` is returned.
     ///
-    /// If `name` is not valid, `Error::FECSchedulerAlgorighm` is returned.
+    /// [`all()`]: enum.FECSchedulerAlgorithm.html#method.all
     fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
-        match name {
-            "noredundancy" => Ok(FECSchedulerAlgorithm::NoRedundancy),
-            "background" => Ok(FECSchedulerAlgorithm::BackgroundOnly),
-            "bursts" => Ok(FECSchedulerAlgorithm::BurstsOnly),
+        Self::all()
+            .iter()
+            .find(|alg| alg.name() == name)
+            .copied()
+            .ok_or(crate::Error::FECScheduler)
+    }
+}
+
+/// Ways for the `bursts` scheduler to detect the end of a burst.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BurstDetection {
+    /// A burst ends when the application has no more data to send, and is
+    /// only protected once enough stream bytes were sent since the
+    /// application was last idle (default).
+    Bytes,
+
+    /// A burst ends when no source symbol was sent for the given multiple of
+    /// the pacing interval, that is the time the pacer takes to send a
+    /// full-sized packet.
+    ///
+    /// This matches the framing of variable-bitrate applications such as
+    /// media streaming better, as bursts of any size are detected. Without a
+    /// pacing rate estimate, a burst ends when the application has no more
+    /// data to send.
+    InterSendGap(f64),
+}
+
+impl BurstDetection {
+    /// Returns the time after which a burst whose last source symbol was sent
+    /// at `last_sent` is over, or `None` if the end of the burst is not based
+    /// on time.
+    pub(crate) fn burst_end(
+        &self, last_sent: std::time::Instant, pacing_interval: Option<Duration>,
+    ) -> Option<std::time::Instant> {
+        match (self, pacing_interval) {
+            (BurstDetection::InterSendGap(k), Some(interval)) =>
+                Some(last_sent + interval.mul_f64(k.max(0.0))),
 
-            _ => Err(crate::Error::FECScheduler),
+            _ => None,
         }
     }
 }
@@ -47,13 +124,17 @@ pub(crate) enum FECScheduler {
     NoRedundancy,
     BackgroundOnly(BackgroundFECScheduler),
     Bursty(BurstsFECScheduler),
+    Custom(CustomScheduler),
 }
 
-pub(crate) fn new_fec_scheduler(alg: FECSchedulerAlgorithm) -> FECScheduler {
+pub(crate) fn new_fec_scheduler(
+    alg: FECSchedulerAlgorithm, burst_detection: BurstDetection,
+) -> FECScheduler {
     match alg {
         FECSchedulerAlgorithm::NoRedundancy => FECScheduler::NoRedundancy,
         FECSchedulerAlgorithm::BackgroundOnly => new_background_scheduler(),
-        FECSchedulerAlgorithm::BurstsOnly => new_bursts_only_scheduler(),
+        FECSchedulerAlgorithm::BurstsOnly =>
+            new_bursts_only_scheduler(burst_detection),
     }
 }
 
@@ -61,62 +142,203 @@ fn new_background_scheduler() -> FECScheduler {
     BackgroundOnly(BackgroundFECScheduler::new())
 }
 
-fn new_bursts_only_scheduler() -> FECScheduler {
-    Bursty(BurstsFECScheduler::new())
+fn new_bursts_only_scheduler(burst_detection: BurstDetection) -> FECScheduler {
+    Bursty(BurstsFECScheduler::new(burst_detection))
 }
 
 impl FECScheduler {
-    pub fn should_send_repair(
-        &mut self, conn: &Connection, path: &Path, symbol_size: usize,
-    ) -> bool {
+    pub fn should_send_repair(&mut self, ctx: &SchedulerContext) -> bool {
         match self {
-            BackgroundOnly(scheduler) =>
-                scheduler.should_send_repair(conn, path, symbol_size),
-            Bursty(scheduler) =>
-                scheduler.should_send_repair(conn, path, symbol_size),
+            BackgroundOnly(scheduler) => scheduler.should_send_repair(ctx),
+            Bursty(scheduler) => scheduler.should_send_repair(ctx),
             NoRedundancy => false,
+
+            // Custom schedulers need a view of the path, see
+            // `should_send_repair_on_path()`. The shadow schedulers and the
+            // trace replay only use built-in schedulers.
+            Custom(_) => false,
         }
     }
 
-    pub fn sent_repair_symbol(&mut self, encoder: &Encoder) {
+    /// Same as `should_send_repair()`, giving custom schedulers a view of the
+    /// path on which the repair symbol would be sent.
+    pub fn should_send_repair_on_path(
+        &mut self, ctx: &SchedulerContext, path: &PathView,
+    ) -> bool {
         match self {
-            BackgroundOnly(scheduler) => scheduler.sent_repair_symbol(encoder),
-            Bursty(scheduler) => scheduler.sent_repair_symbol(encoder),
+            Custom(scheduler) => scheduler.should_send_repair(ctx, path),
+            _ => self.should_send_repair(ctx),
+        }
+    }
+
+    pub fn sent_repair_symbol(&mut self) {
+        match self {
+            BackgroundOnly(scheduler) => scheduler.sent_repair_symbol(),
+            Bursty(scheduler) => scheduler.sent_repair_symbol(),
+            Custom(scheduler) => scheduler.sent_repair_symbol(),
             NoRedundancy => (),
         }
     }
 
-    pub fn acked_repair_symbol(&mut self, encoder: &Encoder) {
+    pub fn acked_repair_symbol(&mut self) {
         match self {
-            BackgroundOnly(scheduler) => scheduler.acked_repair_symbol(encoder),
-            Bursty(scheduler) => scheduler.acked_repair_symbol(encoder),
+            BackgroundOnly(scheduler) => scheduler.acked_repair_symbol(),
+            Bursty(scheduler) => scheduler.acked_repair_symbol(),
+            Custom(scheduler) => scheduler.acked_repair_symbol(),
             NoRedundancy => (),
         }
     }
 
-    pub fn sent_source_symbol(&mut self, encoder: &Encoder) {
+    pub fn sent_source_symbol(
+        &mut self, now: std::time::Instant, window: &EncoderWindow,
+    ) {
         match self {
-            BackgroundOnly(scheduler) => scheduler.sent_source_symbol(encoder),
-            Bursty(scheduler) => scheduler.sent_source_symbol(encoder),
+            BackgroundOnly(scheduler) => scheduler.sent_source_symbol(),
+            Bursty(scheduler) => scheduler.sent_source_symbol(now, window),
+            Custom(scheduler) => scheduler.sent_source_symbol(now),
             NoRedundancy => (),
         }
     }
 
-    pub fn lost_repair_symbol(&mut self, encoder: &Encoder) {
+    pub fn lost_repair_symbol(&mut self) {
         match self {
-            BackgroundOnly(scheduler) => scheduler.lost_repair_symbol(encoder),
-            Bursty(scheduler) => scheduler.lost_repair_symbol(encoder),
+            BackgroundOnly(scheduler) => scheduler.lost_repair_symbol(),
+            Bursty(scheduler) => scheduler.lost_repair_symbol(),
+            Custom(scheduler) => scheduler.lost_repair_symbol(),
             NoRedundancy => (),
         }
     }
 
+    /// Derives the parameters of the `bursts` scheduler from the sending
+    /// pattern of the application. Other schedulers are not affected.
+    pub fn enable_auto_calibration(&mut self) {
+        if let Bursty(scheduler) = self {
+            scheduler.enable_auto_calibration();
+        }
+    }
+
+    /// Returns the parameters learned by the `bursts` scheduler, if any.
+    pub fn burst_calibration(&self) -> Option<FECBurstCalibration> {
+        match self {
+            Bursty(scheduler) => scheduler.calibration(),
+            BackgroundOnly(_) | Custom(_) | NoRedundancy => None,
+        }
+    }
+
+    /// Returns the string form of the scheduler, `custom` for the schedulers
+    /// supplied by the application.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackgroundOnly(_) => FECSchedulerAlgorithm::BackgroundOnly.name(),
+            Bursty(_) => FECSchedulerAlgorithm::BurstsOnly.name(),
+            Custom(_) => "custom",
+            NoRedundancy => FECSchedulerAlgorithm::NoRedundancy.name(),
+        }
+    }
+
+    /// Returns the number of repair symbols in flight, as counted by the
+    /// scheduler.
+    pub fn n_repair_in_flight(&self) -> u64 {
+        match self {
+            BackgroundOnly(scheduler) => scheduler.n_repair_in_flight(),
+            Bursty(scheduler) => scheduler.n_repair_in_flight(),
+            Custom(scheduler) => scheduler.n_repair_in_flight(),
+            NoRedundancy => 0,
+        }
+    }
+
     // returns an Instant at which the stack should wake up to sent new repair
     // symbols
     pub fn timeout(&self) -> Option<std::time::Instant> {
         match self {
             BackgroundOnly(scheduler) => scheduler.timeout(),
             Bursty(scheduler) => scheduler.timeout(),
+            Custom(scheduler) => scheduler.timeout(),
             NoRedundancy => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fec::context::SchedulerContext;
+    use crate::fec::testing;
+
+    use std::time::Duration;
+    use std::time::Instant;
+
+    #[test]
+    fn algorithm_from_str() {
+        assert_eq!(
+            FECSchedulerAlgorithm::from_str("noredundancy"),
+            Ok(FECSchedulerAlgorithm::NoRedundancy)
+        );
+        assert_eq!(
+            FECSchedulerAlgorithm::from_str("background"),
+            Ok(FECSchedulerAlgorithm::BackgroundOnly)
+        );
+        assert_eq!(
+            FECSchedulerAlgorithm::from_str("bursts"),
+            Ok(FECSchedulerAlgorithm::BurstsOnly)
+        );
+        assert_eq!(
+            FECSchedulerAlgorithm::from_str("bursty"),
+            Err(crate::Error::FECScheduler)
+        );
+    }
+
+    #[test]
+    fn algorithm_names() {
+        for alg in FECSchedulerAlgorithm::all() {
+            assert_eq!(
+                FECSchedulerAlgorithm::from_str(&alg.to_string()),
+                Ok(*alg)
+            );
+            assert_eq!(FECSchedulerAlgorithm::from_u64(*alg as u64), Some(*alg));
+        }
+
+        assert_eq!(FECSchedulerAlgorithm::from_u64(42), None);
+    }
+
+    #[test]
+    fn no_redundancy_golden_trace() {
+        let t0 = Instant::now();
+        let window = testing::window(0, 15, t0);
+
+        let busy = SchedulerContext {
+            stream_to_emit: true,
+            sent_count: 20,
+            tx_data: 20000,
+            bif: 20000,
+            window,
+            ..testing::context(t0)
+        };
+
+        let idle = SchedulerContext {
+            now: t0 + Duration::from_millis(10),
+            stream_to_emit: false,
+            ..busy
+        };
+
+        let mut scheduler = new_fec_scheduler(
+            FECSchedulerAlgorithm::NoRedundancy,
+            BurstDetection::Bytes,
+        );
+        scheduler.sent_source_symbol(t0, &window);
+        assert_eq!(scheduler.timeout(), None);
+
+        testing::assert_golden_trace(FECSchedulerAlgorithm::NoRedundancy, &[
+            testing::decide(busy, false),
+            testing::decide(idle, false),
+            testing::decide(
+                SchedulerContext {
+                    now: t0 + Duration::from_millis(100),
+                    ..idle
+                },
+                false,
+            ),
+        ]);
+    }
+}
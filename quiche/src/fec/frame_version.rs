@@ -0,0 +1,137 @@
+use crate::frame::Frame;
+
+/// The highest version of the wire format of the FEC frames supported.
+///
+/// Version 1 is the format of the REPAIR, SOURCE_SYMBOL and SOURCE_SYMBOL_ACK
+/// frames, also used by the peers that don't advertise a version in their
+/// transport parameters. Version 2 adds the
+/// compact REPAIR (type 0x37) and SOURCE_SYMBOL (type 0x38) frames, which
+/// encode the source symbol IDs and the protected window as varints instead
+/// of fixed-size integers, saving up to 17 bytes per repair symbol and 7
+/// bytes per source symbol. Version 3 adds the FEC_HINT frame (type 0x39),
+/// with which the sender hints the receiver about its upcoming traffic,
+/// version 4 the RATE_HINT frame (type 0x3a), with which the receiver reports
+/// the residual loss after FEC, version 5 the FEC_STATS frame (type 0x3b),
+/// with which the receiver reports the statistics of its decoder,
+/// version 6 the WINDOW_SYNC (type 0x35) and FEC_REQUEST (type 0x36) frames,
+/// with which an endpoint announces the moves of its encoding window and
+/// asks the peer to enable or disable FEC, and version 7 the
+/// STREAM_UNRELIABLE frame (type 0x3c), with which the sender tells the
+/// receiver to skip the gaps of a stream instead of waiting for the lost
+/// data.
+///
+/// A version changing the framing of the symbols introduces new frame types
+/// rather than changing the existing ones, so that the endpoints can fall
+/// back to the highest version both of them support.
+pub const FEC_FRAME_VERSION: u64 = 7;
+
+/// The version of the peers that don't advertise one.
+const LEGACY_FEC_FRAME_VERSION: u64 = 1;
+
+/// Returns the version of the FEC frames to use with a peer advertising
+/// `peer`, if any.
+pub(crate) fn negotiate(peer: Option<u64>) -> u64 {
+    peer.unwrap_or(LEGACY_FEC_FRAME_VERSION)
+        .min(FEC_FRAME_VERSION)
+}
+
+/// Returns whether the compact REPAIR and SOURCE_SYMBOL frames are used with
+/// the negotiated `version`.
+pub(crate) fn compact_symbols(version: u64) -> bool {
+    version >= 2
+}
+
+/// Returns the version of the wire format that introduced `frame`, if it is a
+/// FEC frame.
+pub(crate) fn introduced_in(frame: &Frame) -> Option<u64> {
+    match frame {
+        Frame::StreamUnreliable { .. } => Some(7),
+
+        Frame::WindowSync { .. } | Frame::FECRequest { .. } => Some(6),
+
+        Frame::FECStats { .. } => Some(5),
+
+        Frame::RateHint { .. } => Some(4),
+
+        Frame::FECHint { .. } => Some(3),
+
+        Frame::Repair { compact: true, .. } |
+        Frame::SourceSymbol { compact: true, .. } |
+        Frame::SourceSymbolHeader { compact: true, .. } => Some(2),
+
+        Frame::Repair { .. } |
+        Frame::SourceSymbol { .. } |
+        Frame::SourceSymbolHeader { .. } |
+        Frame::SourceSymbolACK { .. } => Some(1),
+
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiate_version() {
+        assert_eq!(negotiate(None), LEGACY_FEC_FRAME_VERSION);
+        assert_eq!(negotiate(Some(1)), 1);
+        assert_eq!(negotiate(Some(2)), 2);
+
+        // A newer peer falls back to the local version.
+        assert_eq!(negotiate(Some(FEC_FRAME_VERSION + 1)), FEC_FRAME_VERSION);
+    }
+
+    #[test]
+    fn frames() {
+        assert_eq!(
+            introduced_in(&Frame::SourceSymbolACK {
+                ranges: Default::default(),
+            }),
+            Some(1)
+        );
+        assert_eq!(
+            introduced_in(&Frame::SourceSymbolHeader {
+                metadata: networkcoding::source_symbol_metadata_from_u64(1),
+                recovered: false,
+                compact: true,
+            }),
+            Some(2)
+        );
+        assert_eq!(
+            introduced_in(&Frame::FECHint {
+                hints: vec![crate::fec::hint::FECHint::BurstSize(3000)],
+            }),
+            Some(3)
+        );
+        assert_eq!(
+            introduced_in(&Frame::RateHint { residual_loss: 0 }),
+            Some(4)
+        );
+        assert_eq!(
+            introduced_in(&Frame::FECStats {
+                stats: Default::default(),
+            }),
+            Some(5)
+        );
+        assert_eq!(
+            introduced_in(&Frame::WindowSync { first_symbol: 1 }),
+            Some(6)
+        );
+        assert_eq!(
+            introduced_in(&Frame::FECRequest {
+                enable: true,
+                scheduler: None,
+            }),
+            Some(6)
+        );
+        assert_eq!(
+            introduced_in(&Frame::StreamUnreliable {
+                stream_id: 0,
+                unreliable: true,
+            }),
+            Some(7)
+        );
+        assert_eq!(introduced_in(&Frame::Ping { mtu_probe: None }), None);
+    }
+}
@@ -0,0 +1,145 @@
+use std::time::Duration;
+use std::time::Instant;
+
+/// Bounds the work spent generating repair symbols during a burst of
+/// [`send()`] calls.
+///
+/// A burst lasts until [`send()`] returns [`Done`]. Once the budget of a burst
+/// is exhausted, the repair symbols authorized by the redundancy scheduler
+/// are deferred to the next burst, so that encoding spikes don't delay the
+/// packets of interactive traffic. The connection's timeout fires
+/// immediately after the burst to send them.
+///
+/// [`send()`]: struct.Connection.html#method.send
+/// [`Done`]: enum.Error.html#variant.Done
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RepairGenerationBudget {
+    /// At most this number of repair symbols are generated per burst.
+    Symbols(usize),
+
+    /// At most this time is spent generating repair symbols per burst.
+    ///
+    /// The repair symbol exceeding the budget is still sent, as the time it
+    /// takes to generate is only known afterwards.
+    Time(Duration),
+}
+
+/// The repair symbols generated during the current burst of `send()` calls.
+pub(crate) struct GenerationBudget {
+    budget: RepairGenerationBudget,
+    symbols: usize,
+    spent: Duration,
+    next_timeout: Option<Instant>,
+}
+
+impl GenerationBudget {
+    pub fn new(budget: RepairGenerationBudget) -> GenerationBudget {
+        GenerationBudget {
+            budget,
+            symbols: 0,
+            spent: Duration::ZERO,
+            next_timeout: None,
+        }
+    }
+
+    fn exhausted(&self) -> bool {
+        match self.budget {
+            RepairGenerationBudget::Symbols(max) => self.symbols >= max,
+
+            RepairGenerationBudget::Time(max) => self.spent >= max,
+        }
+    }
+
+    /// Returns whether a repair symbol can be generated at `now`, given
+    /// `should_send_repair`, the decision taken without the budget.
+    pub fn should_send_repair(
+        &mut self, should_send_repair: bool, now: Instant,
+    ) -> bool {
+        self.next_timeout = None;
+
+        if !should_send_repair {
+            return false;
+        }
+
+        if self.exhausted() {
+            // Wake up as soon as the burst is over.
+            self.next_timeout = Some(now);
+
+            return false;
+        }
+
+        true
+    }
+
+    /// Records a repair symbol that took `elapsed` to generate.
+    pub fn generated_repair_symbol(&mut self, elapsed: Duration) {
+        self.symbols += 1;
+        self.spent += elapsed;
+    }
+
+    /// Starts a new burst.
+    pub fn end_burst(&mut self) {
+        self.symbols = 0;
+        self.spent = Duration::ZERO;
+    }
+
+    // returns an Instant at which the stack should wake up to send the
+    // deferred repair symbols
+    pub fn timeout(&self) -> Option<Instant> {
+        self.next_timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbols() {
+        let now = Instant::now();
+        let mut budget =
+            GenerationBudget::new(RepairGenerationBudget::Symbols(2));
+
+        assert!(!budget.should_send_repair(false, now));
+
+        for _ in 0..2 {
+            assert!(budget.should_send_repair(true, now));
+            budget.generated_repair_symbol(Duration::ZERO);
+        }
+
+        // The next repair symbol is deferred to the next burst.
+        assert!(!budget.should_send_repair(true, now));
+        assert_eq!(budget.timeout(), Some(now));
+
+        budget.end_burst();
+        assert_eq!(budget.timeout(), Some(now));
+
+        assert!(budget.should_send_repair(true, now));
+        assert_eq!(budget.timeout(), None);
+    }
+
+    #[test]
+    fn time() {
+        let now = Instant::now();
+        let ms = Duration::from_millis(1);
+        let mut budget =
+            GenerationBudget::new(RepairGenerationBudget::Time(2 * ms));
+
+        assert!(budget.should_send_repair(true, now));
+        budget.generated_repair_symbol(ms);
+
+        // The budget is only exceeded after the fact.
+        assert!(budget.should_send_repair(true, now));
+        budget.generated_repair_symbol(3 * ms);
+
+        assert!(!budget.should_send_repair(true, now));
+
+        // Nothing to defer.
+        assert!(!budget.should_send_repair(false, now));
+        assert_eq!(budget.timeout(), None);
+
+        budget.end_burst();
+        assert!(budget.should_send_repair(true, now));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::Result;
+
+/// The ID of the hint announcing the size of the next burst.
+const BURST_SIZE_HINT_ID: u64 = 0x00;
+
+/// A hint about the upcoming traffic of the sender, carried in a FEC_HINT
+/// frame.
+///
+/// Each hint is encoded as its ID, the length of its value and the value, so
+/// that the receiver skips the hints it does not know, as well as the fields
+/// a newer version of a known hint appends to its value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FECHint {
+    /// The number of stream bytes of the next burst of the sender.
+    BurstSize(u64),
+
+    /// A hint with a reserved ID and an empty value, sent to make sure that
+    /// the peer skips the hints it does not know. It is never parsed.
+    Grease(u64),
+}
+
+impl FECHint {
+    fn id(&self) -> u64 {
+        match self {
+            FECHint::BurstSize(_) => BURST_SIZE_HINT_ID,
+
+            FECHint::Grease(id) => *id,
+        }
+    }
+
+    fn value_len(&self) -> usize {
+        match self {
+            FECHint::BurstSize(v) => octets::varint_len(*v),
+
+            FECHint::Grease(_) => 0,
+        }
+    }
+
+    pub fn to_bytes(&self, b: &mut octets::OctetsMut) -> Result<()> {
+        b.put_varint(self.id())?;
+        b.put_varint(self.value_len() as u64)?;
+
+        if let FECHint::BurstSize(v) = self {
+            b.put_varint(*v)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn wire_len(&self) -> usize {
+        octets::varint_len(self.id()) + // id
+        octets::varint_len(self.value_len() as u64) + // length
+        self.value_len() // value
+    }
+}
+
+/// Parses the hints of a FEC_HINT frame, skipping the unknown ones.
+pub(crate) fn parse_hints(b: &mut octets::Octets) -> Result<Vec<FECHint>> {
+    let count = b.get_varint()?;
+
+    let mut hints = Vec::new();
+
+    for _ in 0..count {
+        let id = b.get_varint()?;
+        let mut value = b.get_bytes_with_varint_length()?;
+
+        match id {
+            BURST_SIZE_HINT_ID =>
+                hints.push(FECHint::BurstSize(value.get_varint()?)),
+
+            // Unknown and reserved IDs.
+            _ => (),
+        }
+    }
+
+    Ok(hints)
+}
+
+/// Returns a reserved hint ID to grease the FEC_HINT frames.
+///
+/// The IDs of the form `31 * N + 27` are never assigned to a hint, so that
+/// peers have to skip the IDs they don't know.
+pub(crate) fn grease_hint_id() -> u64 {
+    31 * crate::rand::rand_u64_uniform(1 << 16) + 27
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(hints: &[FECHint]) -> Vec<u8> {
+        let len = 1 + hints.iter().map(|h| h.wire_len()).sum::<usize>();
+
+        let mut d = vec![0; len];
+        let mut b = octets::OctetsMut::with_slice(&mut d);
+
+        b.put_varint(hints.len() as u64).unwrap();
+        for hint in hints {
+            hint.to_bytes(&mut b).unwrap();
+        }
+
+        assert_eq!(b.cap(), 0);
+
+        d
+    }
+
+    #[test]
+    fn unknown_hints_skipped() {
+        let grease = FECHint::Grease(grease_hint_id());
+        let d = encode(&[grease, FECHint::BurstSize(3000), grease]);
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert_eq!(parse_hints(&mut b), Ok(vec![FECHint::BurstSize(3000)]));
+        assert_eq!(b.cap(), 0);
+
+        // An unknown hint with a value, and a burst size hint with a field
+        // appended by a newer version.
+        let d = [2, 0x1b, 3, 1, 2, 3, 0x00, 3, 0x4b, 0xb8, 0xff];
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert_eq!(parse_hints(&mut b), Ok(vec![FECHint::BurstSize(3000)]));
+        assert_eq!(b.cap(), 0);
+    }
+
+    #[test]
+    fn truncated_hint() {
+        let d = [1, 0x1b, 3, 1, 2];
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert!(parse_hints(&mut b).is_err());
+
+        // The value of a known hint is too short.
+        let d = [1, 0x00, 1, 0x4b];
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert!(parse_hints(&mut b).is_err());
+    }
+}
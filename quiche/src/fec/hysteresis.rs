@@ -0,0 +1,134 @@
+use std::env;
+use std::time::Duration;
+use std::time::Instant;
+
+const DEFAULT_BUDGET_EWMA_ALPHA: f64 = 0.25;
+const DEFAULT_MIN_DWELL_RTTS: u32 = 1;
+
+/// Hysteresis applied on top of the repair decisions of a FEC scheduler.
+///
+/// The repair budget authorized for a sending round is smoothed with an EWMA
+/// of the budgets of the previous rounds, and the scheduler cannot switch
+/// between sending and not sending repair symbols before a minimum dwell
+/// time, expressed in round-trip times, has elapsed since the last switch.
+pub(crate) struct RepairHysteresis {
+    alpha: f64,
+    min_dwell_rtts: u32,
+    smoothed_budget: Option<f64>,
+    active: bool,
+    last_transition: Option<Instant>,
+}
+
+impl RepairHysteresis {
+    pub fn new(alpha: f64, min_dwell_rtts: u32) -> RepairHysteresis {
+        RepairHysteresis {
+            alpha: alpha.clamp(0.0, 1.0),
+            min_dwell_rtts,
+            smoothed_budget: None,
+            active: false,
+            last_transition: None,
+        }
+    }
+
+    /// Creates the hysteresis with its default parameters, which can be
+    /// overriden by the DEBUG_QUICHE_FEC_BUDGET_EWMA_ALPHA and
+    /// DEBUG_QUICHE_FEC_MIN_DWELL_RTTS environment variables for debug
+    /// purposes.
+    pub fn from_env() -> RepairHysteresis {
+        let alpha = env::var("DEBUG_QUICHE_FEC_BUDGET_EWMA_ALPHA")
+            .unwrap_or(DEFAULT_BUDGET_EWMA_ALPHA.to_string())
+            .parse()
+            .unwrap_or(DEFAULT_BUDGET_EWMA_ALPHA);
+        let min_dwell_rtts = env::var("DEBUG_QUICHE_FEC_MIN_DWELL_RTTS")
+            .unwrap_or(DEFAULT_MIN_DWELL_RTTS.to_string())
+            .parse()
+            .unwrap_or(DEFAULT_MIN_DWELL_RTTS);
+
+        RepairHysteresis::new(alpha, min_dwell_rtts)
+    }
+
+    /// Returns the repair budget to authorize for the current round, given
+    /// the raw budget computed by the scheduler.
+    pub fn smoothed_budget(&self, raw_budget: usize) -> usize {
+        match self.smoothed_budget {
+            None => raw_budget,
+
+            Some(smoothed) =>
+                (self.alpha * raw_budget as f64 + (1.0 - self.alpha) * smoothed)
+                    as usize,
+        }
+    }
+
+    /// Returns whether the scheduler is currently in a sending round.
+    pub fn active(&self) -> bool {
+        self.active
+    }
+
+    /// Returns whether the scheduler is allowed to switch to the opposite
+    /// state.
+    pub fn can_switch(&self, now: Instant, rtt: Duration) -> bool {
+        match self.last_transition {
+            Some(t) => now >= t + rtt * self.min_dwell_rtts,
+
+            None => true,
+        }
+    }
+
+    /// Records the start of a sending round.
+    pub fn round_started(&mut self, now: Instant) {
+        self.active = true;
+        self.last_transition = Some(now);
+    }
+
+    /// Records the end of a sending round and the raw budget that was
+    /// authorized for it.
+    pub fn round_finished(&mut self, now: Instant, raw_budget: usize) {
+        self.smoothed_budget = Some(self.smoothed_budget(raw_budget) as f64);
+        self.active = false;
+        self.last_transition = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_smoothing() {
+        let now = Instant::now();
+        let mut h = RepairHysteresis::new(0.25, 0);
+
+        // The first round is not smoothed.
+        assert_eq!(h.smoothed_budget(8000), 8000);
+
+        h.round_started(now);
+        h.round_finished(now, 8000);
+
+        // A sudden drop of the raw budget is dampened.
+        assert_eq!(h.smoothed_budget(0), 6000);
+
+        h.round_started(now);
+        h.round_finished(now, 0);
+        assert_eq!(h.smoothed_budget(0), 4500);
+    }
+
+    #[test]
+    fn min_dwell_time() {
+        let now = Instant::now();
+        let rtt = Duration::from_millis(50);
+        let mut h = RepairHysteresis::new(0.25, 2);
+
+        assert!(!h.active());
+        assert!(h.can_switch(now, rtt));
+
+        h.round_started(now);
+        assert!(h.active());
+        assert!(!h.can_switch(now + rtt, rtt));
+        assert!(h.can_switch(now + rtt * 2, rtt));
+
+        h.round_finished(now + rtt * 2, 1000);
+        assert!(!h.active());
+        assert!(!h.can_switch(now + rtt * 3, rtt));
+        assert!(h.can_switch(now + rtt * 4, rtt));
+    }
+}
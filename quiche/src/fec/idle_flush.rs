@@ -0,0 +1,84 @@
+use std::time::Instant;
+
+use crate::path::Path;
+use crate::Connection;
+
+/// Pre-emptive protection of the last flight before the connection goes
+/// idle.
+///
+/// When the application has nothing left to send, no subsequent packets will
+/// trigger fast retransmission of the last flight, so its losses can only be
+/// repaired after a PTO. Once the connection stays idle for a quarter of the
+/// smoothed RTT, enough repair symbols are sent to cover all the source
+/// symbols still in the encoding window, whatever the budget of the
+/// scheduler.
+pub(crate) struct IdleFlush {
+    idle_since: Option<Instant>,
+    repair_symbols_to_send: Option<usize>,
+    next_timeout: Option<Instant>,
+}
+
+impl IdleFlush {
+    pub fn new() -> IdleFlush {
+        IdleFlush {
+            idle_since: None,
+            repair_symbols_to_send: None,
+            next_timeout: None,
+        }
+    }
+
+    pub fn should_send_repair(
+        &mut self, conn: &Connection, path: &Path, now: Instant,
+    ) -> bool {
+        let dgrams_to_emit = conn.dgram_max_writable_len().is_some();
+        let stream_to_emit = conn.streams.has_flushable();
+
+        self.next_timeout = None;
+
+        if dgrams_to_emit || stream_to_emit {
+            self.idle_since = None;
+            return false;
+        }
+
+        let idle_since = *self.idle_since.get_or_insert(now);
+        let flush_time = idle_since + path.recovery.rtt() / 4;
+
+        if now < flush_time {
+            if self.repair_symbols_to_send.is_none() {
+                self.next_timeout = Some(flush_time);
+            }
+
+            return false;
+        }
+
+        let to_send = *self
+            .repair_symbols_to_send
+            .get_or_insert_with(|| conn.fec_encoder.n_protected_symbols());
+
+        trace!(
+            "fec idle flush idle_since={:?} repair_symbols_to_send={}",
+            idle_since,
+            to_send
+        );
+
+        to_send > 0
+    }
+
+    pub fn sent_repair_symbol(&mut self) {
+        if let Some(n) = &mut self.repair_symbols_to_send {
+            *n = n.saturating_sub(1);
+        }
+    }
+
+    pub fn sent_source_symbol(&mut self) {
+        // a new flight starts, it will be flushed once idle again
+        self.idle_since = None;
+        self.repair_symbols_to_send = None;
+    }
+
+    // returns an Instant at which the stack should wake up to flush the last
+    // flight
+    pub fn timeout(&self) -> Option<Instant> {
+        self.next_timeout
+    }
+}
@@ -0,0 +1,77 @@
+use std::time::Instant;
+
+use crate::path::Path;
+use crate::path::PathStats;
+
+/// Loss prediction supplied by a [`LossPredictor`].
+///
+/// [`LossPredictor`]: trait.LossPredictor.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LossPrediction {
+    /// The expected probability for a packet to be lost during the next
+    /// round-trip, between 0.0 and 1.0.
+    pub loss_probability: f64,
+
+    /// The expected average number of consecutive packets lost when a loss
+    /// occurs. A value of 1.0 means that losses are independent.
+    pub burstiness: f64,
+}
+
+/// Application-supplied loss predictions.
+///
+/// The predictor is queried once per round-trip time of the path used to
+/// send repair symbols. Its predictions replace the loss statistics measured
+/// by the recovery when the FEC schedulers compute their redundancy budget.
+pub trait LossPredictor: Send + Sync {
+    /// Returns the loss prediction for the next round-trip on the path
+    /// described by `stats`, or `None` to fall back to the measured loss
+    /// statistics.
+    fn predict(&mut self, stats: &PathStats) -> Option<LossPrediction>;
+}
+
+/// Queries a [`LossPredictor`] at most once per round-trip.
+pub(crate) struct LossPredictionProvider {
+    predictor: Box<dyn LossPredictor>,
+    latest: Option<LossPrediction>,
+    next_query: Option<Instant>,
+}
+
+impl LossPredictionProvider {
+    pub fn new(predictor: Box<dyn LossPredictor>) -> LossPredictionProvider {
+        LossPredictionProvider {
+            predictor,
+            latest: None,
+            next_query: None,
+        }
+    }
+
+    pub fn update(&mut self, path: &Path, now: Instant) {
+        if let Some(next_query) = self.next_query {
+            if now < next_query {
+                return;
+            }
+        }
+
+        self.latest = self.predictor.predict(&path.stats());
+        self.next_query = Some(now + path.recovery.rtt());
+    }
+
+    /// Returns the expected number of packets lost per round-trip and its
+    /// variance, given the size of the congestion window in packets.
+    pub fn packets_lost_per_round_trip(
+        &self, cwnd_packets: f64,
+    ) -> Option<(f64, f64)> {
+        let prediction = self.latest?;
+
+        let loss_probability = prediction.loss_probability.clamp(0.0, 1.0);
+        let burstiness = prediction.burstiness.max(1.0);
+
+        let expected = loss_probability * cwnd_packets;
+
+        // Losses are grouped in bursts of `burstiness` packets, which
+        // inflates the variance of the number of losses per round-trip.
+        let variance = expected * (1.0 - loss_probability) * burstiness;
+
+        Some((expected, variance))
+    }
+}
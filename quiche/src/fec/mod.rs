@@ -1,3 +1,49 @@
+pub mod adu;
 mod background_fec_scheduler;
+pub(crate) mod bandwidth_probe;
+pub mod burst_calibration;
 mod burst_protecting_fec_scheduler;
+pub mod burst_timing;
+pub(crate) mod capture;
+pub(crate) mod close_flush;
+pub mod codec;
+pub(crate) mod coded_retransmission;
+pub(crate) mod context;
+pub mod custom_scheduler;
+#[cfg(feature = "fec-debug-control")]
+pub mod debug_control;
+pub mod datagram_interop;
+pub mod decoder_stats;
+pub(crate) mod emission_jitter;
+pub(crate) mod encoder;
+pub mod event;
+#[cfg(feature = "fec-experiment")]
+pub mod experiment;
 pub mod fec_scheduler;
+pub mod frame_version;
+pub mod generation_budget;
+pub(crate) mod hint;
+mod hysteresis;
+pub(crate) mod idle_flush;
+pub mod loss_predictor;
+pub mod padding_policy;
+pub(crate) mod pending_repair;
+pub mod protection;
+pub(crate) mod rate_hint;
+pub(crate) mod recovery_probability;
+pub mod relay;
+pub(crate) mod repair_budget;
+pub(crate) mod repair_caps;
+pub mod repair_offload;
+pub(crate) mod repair_spacing;
+pub(crate) mod repair_symbol;
+pub(crate) mod rng;
+pub mod shadow;
+pub mod slow_start;
+#[cfg(test)]
+pub(crate) mod testing;
+pub mod trace;
+pub mod vectors;
+pub(crate) mod wasted_repair;
+pub mod window_rotation;
+pub(crate) mod window_sync;
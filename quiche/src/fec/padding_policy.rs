@@ -0,0 +1,62 @@
+/// Policies to pad the 1-RTT packets.
+///
+/// The padding required by the protocol, e.g. of the packets coalesced with
+/// an Initial packet, is always added whatever the policy.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PaddingPolicy {
+    /// Does not pad the packets.
+    #[default]
+    Never,
+
+    /// Pads the packets up to [`MIN_CLIENT_INITIAL_LEN`] bytes, so that all
+    /// the packets but the largest ones have the same length.
+    ///
+    /// [`MIN_CLIENT_INITIAL_LEN`]: ../constant.MIN_CLIENT_INITIAL_LEN.html
+    ToMinimum,
+
+    /// Pads the packets up to the maximum datagram size of the path, within
+    /// the congestion window.
+    ToFullMtu,
+
+    /// Uses the space that [`ToFullMtu`] would fill with PADDING frames to
+    /// send a repair symbol when one fits, whatever the decision of the FEC
+    /// scheduler. Only the packets that do not carry a source symbol are
+    /// used, as the space left in a protected packet belongs to its symbol.
+    /// The remaining space is padded.
+    ///
+    /// [`ToFullMtu`]: enum.PaddingPolicy.html#variant.ToFullMtu
+    RepairAsPadding,
+}
+
+impl PaddingPolicy {
+    /// Returns the number of bytes of padding to add to a packet of `len`
+    /// bytes, given that `left` bytes can still be written to it.
+    pub(crate) fn padding_len(&self, len: usize, left: usize) -> usize {
+        match self {
+            PaddingPolicy::Never => 0,
+
+            PaddingPolicy::ToMinimum => std::cmp::min(
+                crate::MIN_CLIENT_INITIAL_LEN.saturating_sub(len),
+                left,
+            ),
+
+            PaddingPolicy::ToFullMtu | PaddingPolicy::RepairAsPadding => left,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padding_len() {
+        assert_eq!(PaddingPolicy::Never.padding_len(100, 1000), 0);
+        assert_eq!(PaddingPolicy::ToMinimum.padding_len(100, 2000), 1100);
+        assert_eq!(PaddingPolicy::ToMinimum.padding_len(100, 500), 500);
+        assert_eq!(PaddingPolicy::ToMinimum.padding_len(1300, 50), 0);
+        assert_eq!(PaddingPolicy::ToFullMtu.padding_len(100, 50), 50);
+        assert_eq!(PaddingPolicy::RepairAsPadding.padding_len(100, 50), 50);
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::time::Duration;
+use std::time::Instant;
+
+use networkcoding::RepairSymbol;
+
+use crate::fec::repair_symbol::protected_symbols;
+
+/// Maximum number of repair symbols buffered while waiting for the source
+/// symbols they protect.
+pub(crate) const MAX_PENDING_REPAIR_SYMBOLS: usize = 64;
+
+/// Returns whether the decoder can use a repair symbol protecting the source
+/// symbols in `range`, given the largest source symbol it received.
+///
+/// A single repair symbol recovers at most one source symbol, so it is only
+/// useful once at most one of the source symbols it protects is still to come.
+pub(crate) fn is_usable(
+    range: &Range<u64>, largest_received: Option<u64>,
+) -> bool {
+    match largest_received {
+        Some(largest) => range.end <= largest.saturating_add(2),
+
+        None => range.end - range.start <= 1,
+    }
+}
+
+/// Repair symbols received before some of the source symbols they protect.
+///
+/// With cross-path FEC, repair symbols frequently overtake the source symbols
+/// they protect. Instead of handing them to the decoder right away, they are
+/// kept until the decoder can use them, see [`is_usable()`].
+///
+/// When the last source symbols protected by a repair symbol are lost, the
+/// repair symbol would wait forever, while it is precisely what the decoder
+/// needs to recover them. A repair symbol is thus also released once it has
+/// waited for longer than the reordering delay expected between the paths.
+///
+/// [`is_usable()`]: fn.is_usable.html
+pub(crate) struct PendingRepairSymbols {
+    symbols: VecDeque<(RepairSymbol, Instant)>,
+    capacity: usize,
+}
+
+impl PendingRepairSymbols {
+    pub fn new(capacity: usize) -> PendingRepairSymbols {
+        PendingRepairSymbols {
+            symbols: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Buffers a repair symbol received at `now`. If the buffer is full, the
+    /// oldest repair symbol is evicted and returned.
+    pub fn push(
+        &mut self, repair_symbol: RepairSymbol, now: Instant,
+    ) -> Option<RepairSymbol> {
+        if self.capacity == 0 {
+            return Some(repair_symbol);
+        }
+
+        let evicted = if self.symbols.len() >= self.capacity {
+            self.symbols.pop_front().map(|(rs, _)| rs)
+        } else {
+            None
+        };
+
+        self.symbols.push_back((repair_symbol, now));
+
+        evicted
+    }
+
+    /// Removes and returns the buffered repair symbols that the decoder can
+    /// use given the largest source symbol it received, and the ones that
+    /// have waited for longer than `max_delay`.
+    pub fn take_ready(
+        &mut self, largest_received: Option<u64>, now: Instant,
+        max_delay: Duration,
+    ) -> Vec<RepairSymbol> {
+        let mut ready = Vec::new();
+        let mut pending = VecDeque::with_capacity(self.symbols.len());
+
+        for (rs, received) in self.symbols.drain(..) {
+            let usable = protected_symbols(&rs)
+                .map_or(true, |range| is_usable(&range, largest_received));
+
+            if usable || now.saturating_duration_since(received) >= max_delay {
+                ready.push(rs);
+            } else {
+                pending.push_back((rs, received));
+            }
+        }
+
+        self.symbols = pending;
+
+        ready
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fec::repair_symbol;
+
+    const MAX_DELAY: Duration = Duration::from_millis(50);
+
+    fn repair_symbol(first_id: u64, n_protected: u32) -> RepairSymbol {
+        repair_symbol::build(first_id, n_protected, 0, 16)
+    }
+
+    #[test]
+    fn usable() {
+        assert!(!is_usable(&(0..5), None));
+        assert!(is_usable(&(0..1), None));
+
+        assert!(!is_usable(&(0..5), Some(2)));
+        assert!(is_usable(&(0..5), Some(3)));
+        assert!(is_usable(&(0..5), Some(4)));
+        assert!(is_usable(&(0..5), Some(10)));
+    }
+
+    #[test]
+    fn take_ready() {
+        let now = Instant::now();
+
+        let mut pending = PendingRepairSymbols::new(MAX_PENDING_REPAIR_SYMBOLS);
+        assert!(pending.is_empty());
+
+        assert!(pending.push(repair_symbol(0, 5), now).is_none());
+        assert!(pending.push(repair_symbol(3, 5), now).is_none());
+        assert_eq!(pending.len(), 2);
+
+        assert!(pending.take_ready(None, now, MAX_DELAY).is_empty());
+        assert!(pending.take_ready(Some(2), now, MAX_DELAY).is_empty());
+
+        // Only the source symbol 4 is missing, which the first repair symbol
+        // can recover.
+        let ready = pending.take_ready(Some(3), now, MAX_DELAY);
+        assert_eq!(ready.len(), 1);
+        assert_eq!(protected_symbols(&ready[0]), Some(0..5));
+        assert_eq!(pending.len(), 1);
+
+        let ready = pending.take_ready(Some(10), now, MAX_DELAY);
+        assert_eq!(ready.len(), 1);
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn take_expired() {
+        let now = Instant::now();
+
+        let mut pending = PendingRepairSymbols::new(MAX_PENDING_REPAIR_SYMBOLS);
+
+        // The last source symbols protected by the repair symbols are lost.
+        assert!(pending.push(repair_symbol(0, 5), now).is_none());
+        assert!(pending.push(repair_symbol(0, 8), now + MAX_DELAY / 2).is_none());
+
+        assert!(pending.take_ready(Some(2), now, MAX_DELAY).is_empty());
+
+        let ready = pending.take_ready(Some(2), now + MAX_DELAY, MAX_DELAY);
+        assert_eq!(ready.len(), 1);
+        assert_eq!(protected_symbols(&ready[0]), Some(0..5));
+        assert_eq!(pending.len(), 1);
+
+        let ready = pending.take_ready(Some(2), now + MAX_DELAY * 2, MAX_DELAY);
+        assert_eq!(ready.len(), 1);
+        assert_eq!(protected_symbols(&ready[0]), Some(0..8));
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn capacity() {
+        let now = Instant::now();
+
+        let mut pending = PendingRepairSymbols::new(2);
+
+        assert!(pending.push(repair_symbol(0, 5), now).is_none());
+        assert!(pending.push(repair_symbol(1, 5), now).is_none());
+
+        let evicted = pending.push(repair_symbol(2, 5), now).unwrap();
+        assert_eq!(protected_symbols(&evicted), Some(0..5));
+        assert_eq!(pending.len(), 2);
+    }
+}
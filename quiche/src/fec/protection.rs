@@ -0,0 +1,93 @@
+use crate::ranges::RangeSet;
+
+/// The number of disjoint ranges of repaired source symbols remembered. The
+/// oldest ones are forgotten first.
+const MAX_REPAIRED_RANGES: usize = 16;
+
+/// How well a range of stream data is protected against losses.
+///
+/// The variants are ordered from the least to the most protected, so that
+/// the status of a range is the minimum of the statuses of its bytes.
+///
+/// See [`Connection::stream_range_protected()`].
+///
+/// [`Connection::stream_range_protected()`]: struct.Connection.html#method.stream_range_protected
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtectionStatus {
+    /// Part of the range was not sent yet, or was declared lost and waits
+    /// to be retransmitted.
+    NotSent,
+
+    /// Part of the range is in flight in packets that no repair symbol
+    /// protects, nor will: the packets were not protected, or their source
+    /// symbols left the encoding window.
+    Unprotected,
+
+    /// The range is in flight in source symbols of the encoding window, but
+    /// some of them are not covered by a repair symbol sent yet.
+    AwaitingRepair,
+
+    /// The range is in flight in source symbols all covered by repair
+    /// symbols already sent.
+    Repaired,
+
+    /// The peer acknowledged the whole range.
+    Acknowledged,
+}
+
+/// The IDs of the source symbols covered by the repair symbols sent.
+pub(crate) struct RepairCoverage {
+    repaired: RangeSet,
+}
+
+impl Default for RepairCoverage {
+    fn default() -> Self {
+        RepairCoverage {
+            repaired: RangeSet::new(MAX_REPAIRED_RANGES),
+        }
+    }
+}
+
+impl RepairCoverage {
+    /// Records that a repair symbol protecting the source symbols `first` to
+    /// `last` included was sent.
+    pub fn repaired(&mut self, first: u64, last: u64) {
+        self.repaired.insert(first..last + 1);
+    }
+
+    /// Returns whether a repair symbol protecting the source symbol `id` was
+    /// sent.
+    pub fn covers(&self, id: u64) -> bool {
+        self.repaired.contains(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coverage() {
+        let mut coverage = RepairCoverage::default();
+
+        assert!(!coverage.covers(0));
+
+        coverage.repaired(0, 3);
+        coverage.repaired(2, 5);
+        coverage.repaired(8, 9);
+
+        assert!(coverage.covers(0));
+        assert!(coverage.covers(5));
+        assert!(!coverage.covers(6));
+        assert!(coverage.covers(9));
+        assert!(!coverage.covers(10));
+    }
+
+    #[test]
+    fn statuses_ordered() {
+        assert!(ProtectionStatus::NotSent < ProtectionStatus::Unprotected);
+        assert!(ProtectionStatus::Unprotected < ProtectionStatus::AwaitingRepair);
+        assert!(ProtectionStatus::AwaitingRepair < ProtectionStatus::Repaired);
+        assert!(ProtectionStatus::Repaired < ProtectionStatus::Acknowledged);
+    }
+}
@@ -0,0 +1,210 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::ranges;
+
+/// The unit of the residual loss carried by RATE_HINT frames.
+pub(crate) const PARTS_PER_MILLION: u64 = 1_000_000;
+
+/// The residual loss after FEC the sender aims for, in parts per million.
+const TARGET_RESIDUAL_LOSS: u64 = 1_000;
+
+/// The factor applied to the gain when the residual loss is above target.
+const GAIN_INCREASE: f64 = 1.25;
+
+/// The factor applied to the gain when the residual loss is on target.
+const GAIN_DECREASE: f64 = 0.95;
+
+const MIN_GAIN: f64 = 0.5;
+
+const MAX_GAIN: f64 = 4.0;
+
+/// The number of source symbol ranges tracked between two reports.
+const MAX_DELIVERED_RANGES: usize = 1024;
+
+/// Measures, on the receiver, the fraction of the source symbols that were
+/// neither received nor recovered with FEC.
+///
+/// A report is due every round-trip. It accounts for the source symbols sent
+/// before the previous report, so that the lost ones had a round-trip to be
+/// recovered.
+pub(crate) struct ResidualLossMonitor {
+    /// The source symbols received or recovered, not accounted for yet.
+    delivered: ranges::RangeSet,
+
+    /// The first source symbol not accounted for yet.
+    start: Option<u64>,
+
+    /// The end of the source symbols accounted for in the next report.
+    end: u64,
+
+    largest: Option<u64>,
+
+    next_report: Option<Instant>,
+}
+
+impl ResidualLossMonitor {
+    pub fn new() -> ResidualLossMonitor {
+        ResidualLossMonitor {
+            delivered: ranges::RangeSet::new(MAX_DELIVERED_RANGES),
+            start: None,
+            end: 0,
+            largest: None,
+            next_report: None,
+        }
+    }
+
+    /// Records that the source symbol `id` was received or recovered.
+    pub fn delivered(&mut self, id: u64) {
+        let start = *self.start.get_or_insert(id);
+
+        if id < start {
+            return;
+        }
+
+        self.delivered.push_item(id);
+        self.largest = Some(self.largest.map_or(id, |l| l.max(id)));
+    }
+
+    /// Returns the residual loss to report in parts per million, if a report
+    /// is due at `now` and source symbols were accounted for.
+    ///
+    /// The reports are best effort: the measurement moves on whether the
+    /// returned value is sent or not.
+    pub fn report(&mut self, now: Instant, rtt: Duration) -> Option<u64> {
+        if self.next_report.map_or(false, |t| now < t) {
+            return None;
+        }
+
+        let start = self.start?;
+        let largest = self.largest?;
+
+        self.next_report = Some(now + rtt);
+
+        let end = self.end;
+
+        self.start = Some(start.max(end));
+        self.end = largest + 1;
+
+        if end <= start {
+            return None;
+        }
+
+        let delivered: u64 = self
+            .delivered
+            .iter()
+            .map(|r| r.end.min(end).saturating_sub(r.start.max(start)))
+            .sum();
+
+        self.delivered.remove_until(end - 1);
+
+        let expected = end - start;
+
+        Some((expected - delivered) * PARTS_PER_MILLION / expected)
+    }
+}
+
+/// The gain the sender applies to its loss estimates, steered by the
+/// residual loss the receiver reports.
+///
+/// The gain grows quickly while the residual loss is above the target, and
+/// decays slowly otherwise, so that the redundancy converges to the least
+/// one reaching the target.
+pub(crate) struct RepairGain {
+    gain: f64,
+
+    /// The latest residual loss reported by the peer, in parts per million.
+    latest: Option<u64>,
+}
+
+impl Default for RepairGain {
+    fn default() -> Self {
+        RepairGain {
+            gain: 1.0,
+            latest: None,
+        }
+    }
+}
+
+impl RepairGain {
+    pub fn on_rate_hint(&mut self, residual_loss: u64) {
+        self.gain = if residual_loss > TARGET_RESIDUAL_LOSS {
+            (self.gain * GAIN_INCREASE).min(MAX_GAIN)
+        } else {
+            (self.gain * GAIN_DECREASE).max(MIN_GAIN)
+        };
+
+        self.latest = Some(residual_loss);
+    }
+
+    /// Scales the expected number of packets lost per round-trip and its
+    /// variance.
+    pub fn apply(&self, estimates: (Option<f64>, f64)) -> (Option<f64>, f64) {
+        (estimates.0.map(|v| v * self.gain), estimates.1 * self.gain)
+    }
+
+    pub fn gain(&self) -> f64 {
+        self.gain
+    }
+
+    pub fn latest(&self) -> Option<u64> {
+        self.latest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn residual_loss() {
+        let t0 = Instant::now();
+        let rtt = Duration::from_millis(50);
+
+        let mut monitor = ResidualLossMonitor::new();
+        assert_eq!(monitor.report(t0, rtt), None);
+
+        // The symbol 3 is lost and never recovered, the symbol 7 is
+        // recovered late.
+        for id in (0..10).filter(|id| *id != 3 && *id != 7) {
+            monitor.delivered(id);
+        }
+
+        // Nothing was sent before the first report.
+        assert_eq!(monitor.report(t0, rtt), None);
+
+        monitor.delivered(7);
+        for id in 10..20 {
+            monitor.delivered(id);
+        }
+
+        // Reports are sent once per round-trip.
+        assert_eq!(monitor.report(t0 + rtt / 2, rtt), None);
+        assert_eq!(monitor.report(t0 + rtt, rtt), Some(100_000));
+
+        assert_eq!(monitor.report(t0 + rtt * 2, rtt), Some(0));
+        assert_eq!(monitor.report(t0 + rtt * 3, rtt), None);
+    }
+
+    #[test]
+    fn repair_gain() {
+        let mut gain = RepairGain::default();
+        assert_eq!(gain.apply((Some(2.0), 1.0)), (Some(2.0), 1.0));
+
+        gain.on_rate_hint(50_000);
+        assert_eq!(gain.gain(), GAIN_INCREASE);
+        assert_eq!(gain.apply((Some(2.0), 1.0)), (Some(2.5), 1.25));
+        assert_eq!(gain.apply((None, 0.0)), (None, 0.0));
+
+        for _ in 0..100 {
+            gain.on_rate_hint(50_000);
+        }
+        assert_eq!(gain.gain(), MAX_GAIN);
+
+        for _ in 0..100 {
+            gain.on_rate_hint(0);
+        }
+        assert_eq!(gain.gain(), MIN_GAIN);
+        assert_eq!(gain.latest(), Some(0));
+    }
+}
@@ -0,0 +1,74 @@
+/// Returns the probability that the receiver gets or recovers all the packets
+/// of a flight, given the probability for a packet to be lost.
+///
+/// The flight is made of `unprotected` packets that cannot be recovered,
+/// `source_symbols` packets carrying source symbols and `repair_symbols`
+/// packets carrying repair symbols. Losses are assumed independent, and all
+/// the source symbols are recovered as long as no more packets carrying
+/// source or repair symbols are lost than there are repair symbols.
+pub(crate) fn flight_recovery_probability(
+    loss_probability: f64, unprotected: usize, source_symbols: usize,
+    repair_symbols: usize,
+) -> f64 {
+    let p = loss_probability.clamp(0.0, 1.0);
+
+    if p == 0.0 {
+        return 1.0;
+    }
+
+    if p == 1.0 {
+        return if unprotected + source_symbols == 0 {
+            1.0
+        } else {
+            0.0
+        };
+    }
+
+    let no_unprotected_loss = (1.0 - p).powi(unprotected as i32);
+
+    let n = source_symbols + repair_symbols;
+
+    // Cumulative distribution of the binomial law B(n, p) up to the number
+    // of repair symbols, with the probabilities computed in log space.
+    let log_ratio = (p / (1.0 - p)).ln();
+    let mut log_pmf = n as f64 * (1.0 - p).ln();
+    let mut cdf = 0.0;
+
+    for k in 0..=repair_symbols.min(n) {
+        cdf += log_pmf.exp();
+
+        log_pmf += ((n - k) as f64 / (k + 1) as f64).ln() + log_ratio;
+    }
+
+    no_unprotected_loss * cdf.min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probability() {
+        assert_eq!(flight_recovery_probability(0.0, 10, 10, 0), 1.0);
+        assert_eq!(flight_recovery_probability(1.0, 0, 10, 2), 0.0);
+
+        // Without repair symbols, all the packets must be received.
+        let p = flight_recovery_probability(0.1, 2, 3, 0);
+        assert!((p - 0.9f64.powi(5)).abs() < 1e-9);
+
+        // A single source symbol protected by a repair symbol is only lost
+        // when both packets are.
+        let p = flight_recovery_probability(0.1, 0, 1, 1);
+        assert!((p - 0.99).abs() < 1e-9);
+
+        // Repair symbols increase the probability.
+        assert!(
+            flight_recovery_probability(0.05, 0, 20, 2) >
+                flight_recovery_probability(0.05, 0, 20, 1)
+        );
+
+        // Large flights don't underflow.
+        let p = flight_recovery_probability(0.5, 0, 1000, 1000);
+        assert!((p - 0.5).abs() < 0.05);
+    }
+}
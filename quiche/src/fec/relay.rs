@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+
+use crate::frame::Frame;
+
+/// A source symbol received on a connection, to be relayed on another one.
+///
+/// See [`Connection::enable_fec_relay_capture()`] and
+/// [`Connection::fec_relay_symbol()`].
+///
+/// [`Connection::enable_fec_relay_capture()`]: struct.Connection.html#method.enable_fec_relay_capture
+/// [`Connection::fec_relay_symbol()`]: struct.Connection.html#method.fec_relay_symbol
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RelayedSymbol {
+    /// The ID of the source symbol on the connection it was received on.
+    pub id: u64,
+
+    /// The payload of the source symbol, that is, its protected frames
+    /// preceded by PADDING frames.
+    pub payload: Vec<u8>,
+
+    /// Whether the source symbol was recovered by the FEC decoder rather than
+    /// received.
+    pub recovered: bool,
+}
+
+impl RelayedSymbol {
+    /// Returns the frames of the payload, without the leading PADDING
+    /// frames.
+    pub(crate) fn frames(&self) -> &[u8] {
+        let start = self
+            .payload
+            .iter()
+            .position(|b| *b != 0)
+            .unwrap_or(self.payload.len());
+
+        &self.payload[start..]
+    }
+}
+
+/// The source symbols captured on the upstream connection of a relay.
+///
+/// Once `capacity` symbols are queued, the oldest ones are dropped, as they
+/// would likely be too late for the downstream connection.
+pub(crate) struct RelayCapture {
+    symbols: VecDeque<RelayedSymbol>,
+    capacity: usize,
+}
+
+impl RelayCapture {
+    pub fn new(capacity: usize) -> RelayCapture {
+        RelayCapture {
+            symbols: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, id: u64, payload: &[u8], recovered: bool) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.symbols.len() >= self.capacity {
+            self.symbols.pop_front();
+        }
+
+        self.symbols.push_back(RelayedSymbol {
+            id,
+            payload: payload.to_vec(),
+            recovered,
+        });
+    }
+
+    pub fn pop(&mut self) -> Option<RelayedSymbol> {
+        self.symbols.pop_front()
+    }
+}
+
+/// The mapping between the IDs of the relayed source symbols on the upstream
+/// and downstream connections of a relay.
+///
+/// Only the `capacity` most recent downstream symbols are remembered, which
+/// should cover the encoding window of the downstream connection.
+pub(crate) struct SymbolIdMap {
+    upstream: BTreeMap<u64, u64>,
+    downstream: BTreeMap<u64, u64>,
+    capacity: usize,
+}
+
+impl SymbolIdMap {
+    pub fn new(capacity: usize) -> SymbolIdMap {
+        SymbolIdMap {
+            upstream: BTreeMap::new(),
+            downstream: BTreeMap::new(),
+            capacity,
+        }
+    }
+
+    /// Records that the upstream symbol `upstream` was sent as the downstream
+    /// symbol `downstream`.
+    pub fn insert(&mut self, upstream: u64, downstream: u64) {
+        if let Some(old) = self.upstream.insert(upstream, downstream) {
+            self.downstream.remove(&old);
+        }
+
+        self.downstream.insert(downstream, upstream);
+
+        while self.downstream.len() > self.capacity {
+            if let Some((_, upstream)) = self.downstream.pop_first() {
+                self.upstream.remove(&upstream);
+            }
+        }
+    }
+
+    pub fn downstream(&self, upstream: u64) -> Option<u64> {
+        self.upstream.get(&upstream).copied()
+    }
+
+    pub fn upstream(&self, downstream: u64) -> Option<u64> {
+        self.downstream.get(&downstream).copied()
+    }
+}
+
+/// The DATAGRAM frames of a relayed source symbol, waiting to be sent on the
+/// downstream connection.
+pub(crate) struct PendingSymbol {
+    /// The ID of the source symbol on the upstream connection.
+    pub id: u64,
+
+    pub frames: Vec<Frame>,
+}
+
+impl PendingSymbol {
+    pub fn wire_len(&self) -> usize {
+        self.frames.iter().map(|f| f.wire_len()).sum()
+    }
+}
+
+/// The symbols waiting to be relayed on the downstream connection.
+pub(crate) struct RelayQueue {
+    pub pending: VecDeque<PendingSymbol>,
+    pub ids: SymbolIdMap,
+}
+
+impl RelayQueue {
+    pub fn new(capacity: usize) -> RelayQueue {
+        RelayQueue {
+            pending: VecDeque::new(),
+            ids: SymbolIdMap::new(capacity),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_skip_padding() {
+        let symbol = RelayedSymbol {
+            id: 0,
+            payload: vec![0, 0, 0, 0x08, 0x04, 0x00],
+            recovered: false,
+        };
+
+        assert_eq!(symbol.frames(), &[0x08, 0x04, 0x00]);
+    }
+
+    #[test]
+    fn capture_drops_oldest() {
+        let mut capture = RelayCapture::new(2);
+
+        capture.push(1, &[1], false);
+        capture.push(2, &[2], true);
+        capture.push(3, &[3], false);
+
+        assert_eq!(capture.pop().map(|s| s.id), Some(2));
+        assert_eq!(capture.pop().map(|s| s.id), Some(3));
+        assert_eq!(capture.pop(), None);
+    }
+
+    #[test]
+    fn id_map() {
+        let mut ids = SymbolIdMap::new(2);
+
+        ids.insert(10, 0);
+        ids.insert(12, 1);
+        assert_eq!(ids.downstream(10), Some(0));
+        assert_eq!(ids.upstream(1), Some(12));
+
+        // The oldest downstream symbol is forgotten.
+        ids.insert(11, 2);
+        assert_eq!(ids.downstream(10), None);
+        assert_eq!(ids.upstream(0), None);
+        assert_eq!(ids.downstream(11), Some(2));
+        assert_eq!(ids.downstream(12), Some(1));
+    }
+}
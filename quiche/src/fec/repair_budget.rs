@@ -0,0 +1,92 @@
+/// The number of repair symbols the token bucket holds at most.
+const BUCKET_SYMBOLS: usize = 10;
+
+/// Limits the repair symbols sent by a server.
+///
+/// Until the peer address is validated, the repair symbols are limited to a
+/// fixed number of bytes, so that young connections cannot be used to amplify
+/// traffic with redundancy. Afterwards, a token bucket refilled with a share
+/// of the bytes sent limits the repair symbols to that share of the traffic.
+pub(crate) struct RepairBudget {
+    unvalidated_left: usize,
+    ratio: f64,
+    tokens: f64,
+    capacity: f64,
+}
+
+impl RepairBudget {
+    pub fn new(
+        unvalidated_bytes: usize, validated_ratio: f64, symbol_size: usize,
+    ) -> RepairBudget {
+        RepairBudget {
+            unvalidated_left: unvalidated_bytes,
+            ratio: validated_ratio.clamp(0.0, 1.0),
+            tokens: 0.0,
+            capacity: (BUCKET_SYMBOLS * symbol_size) as f64,
+        }
+    }
+
+    /// Returns whether a repair symbol of `len` bytes fits in the budget.
+    pub fn can_send(&self, len: usize, validated: bool) -> bool {
+        if validated {
+            self.tokens >= len as f64
+        } else {
+            self.unvalidated_left >= len
+        }
+    }
+
+    /// Refills the bucket with the share of a packet of `len` bytes.
+    pub fn sent_packet(&mut self, len: usize) {
+        self.tokens = (self.tokens + self.ratio * len as f64).min(self.capacity);
+    }
+
+    pub fn sent_repair_symbol(&mut self, len: usize, validated: bool) {
+        if validated {
+            self.tokens = (self.tokens - len as f64).max(0.0);
+        } else {
+            self.unvalidated_left = self.unvalidated_left.saturating_sub(len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unvalidated() {
+        let mut budget = RepairBudget::new(1000, 0.5, 100);
+
+        assert!(budget.can_send(600, false));
+        budget.sent_repair_symbol(600, false);
+
+        assert!(!budget.can_send(600, false));
+        assert!(budget.can_send(400, false));
+
+        // The bucket is still empty.
+        assert!(!budget.can_send(100, true));
+    }
+
+    #[test]
+    fn validated() {
+        let mut budget = RepairBudget::new(0, 0.1, 100);
+        assert!(!budget.can_send(100, true));
+
+        budget.sent_packet(500);
+        assert!(!budget.can_send(100, true));
+
+        budget.sent_packet(500);
+        assert!(budget.can_send(100, true));
+
+        budget.sent_repair_symbol(100, true);
+        assert!(!budget.can_send(100, true));
+
+        // The bucket holds at most 10 symbols.
+        budget.sent_packet(1_000_000);
+        for _ in 0..BUCKET_SYMBOLS {
+            assert!(budget.can_send(100, true));
+            budget.sent_repair_symbol(100, true);
+        }
+        assert!(!budget.can_send(100, true));
+    }
+}
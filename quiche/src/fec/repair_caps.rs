@@ -0,0 +1,142 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+use std::time::Instant;
+
+const SECOND: Duration = Duration::from_secs(1);
+
+/// Caps on the repair bytes sent over the last round-trip time and over the
+/// last second.
+///
+/// A repair symbol is only sent if it fits in both caps, so that the
+/// stricter one applies. On long-RTT paths, a per-RTT cap alone allows
+/// bursts of repair symbols that rate-limited links cannot absorb, while a
+/// per-second cap alone lets short-RTT paths spend a whole second of
+/// redundancy in a single round-trip.
+pub(crate) struct RepairCaps {
+    per_rtt: Option<usize>,
+    per_second: Option<usize>,
+
+    /// The repair symbols sent, with their size, over the longest window.
+    sent: VecDeque<(Instant, usize)>,
+
+    next_timeout: Option<Instant>,
+}
+
+impl RepairCaps {
+    pub fn new(per_rtt: Option<usize>, per_second: Option<usize>) -> RepairCaps {
+        RepairCaps {
+            per_rtt,
+            per_second,
+            sent: VecDeque::new(),
+            next_timeout: None,
+        }
+    }
+
+    /// Returns whether a repair symbol of `len` bytes fits in the caps at
+    /// `now`, on a path with a round-trip time of `rtt`.
+    pub fn can_send(&mut self, len: usize, now: Instant, rtt: Duration) -> bool {
+        let horizon = rtt.max(SECOND);
+        while matches!(self.sent.front(), Some((t, _)) if *t + horizon <= now) {
+            self.sent.pop_front();
+        }
+
+        let mut fits = true;
+        self.next_timeout = None;
+
+        let caps = [(self.per_rtt, rtt), (self.per_second, SECOND)];
+
+        for (cap, window) in caps.iter() {
+            let cap = match cap {
+                Some(cap) => *cap,
+
+                None => continue,
+            };
+
+            let (bytes, oldest) = self.window_bytes(now, *window);
+            if bytes + len <= cap {
+                continue;
+            }
+
+            fits = false;
+
+            // Wake up once the oldest repair symbol of the window leaves it.
+            // Without any, the repair symbol never fits.
+            if let Some(oldest) = oldest {
+                let t = oldest + *window;
+                self.next_timeout =
+                    Some(self.next_timeout.map_or(t, |next| next.min(t)));
+            }
+        }
+
+        fits
+    }
+
+    /// Returns the repair bytes sent within `window` before `now`, and the
+    /// time the oldest of them was sent.
+    fn window_bytes(
+        &self, now: Instant, window: Duration,
+    ) -> (usize, Option<Instant>) {
+        self.sent
+            .iter()
+            .filter(|(t, _)| *t + window > now)
+            .fold((0, None), |(bytes, oldest), (t, len)| {
+                (bytes + len, oldest.or(Some(*t)))
+            })
+    }
+
+    pub fn sent_repair_symbol(&mut self, len: usize, now: Instant) {
+        self.sent.push_back((now, len));
+        self.next_timeout = None;
+    }
+
+    // returns an Instant at which the stack should wake up to send the
+    // repair symbol deferred by the caps
+    pub fn timeout(&self) -> Option<Instant> {
+        self.next_timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stricter_cap_applies() {
+        let t0 = Instant::now();
+        let ms = Duration::from_millis;
+
+        // On a long-RTT path, the per-second cap is the stricter one.
+        let rtt = ms(600);
+        let mut caps = RepairCaps::new(Some(10_000), Some(2_000));
+
+        assert!(caps.can_send(1000, t0, rtt));
+        caps.sent_repair_symbol(1000, t0);
+        assert!(caps.can_send(1000, t0 + ms(10), rtt));
+        caps.sent_repair_symbol(1000, t0 + ms(10));
+
+        assert!(!caps.can_send(1000, t0 + ms(20), rtt));
+        assert_eq!(caps.timeout(), Some(t0 + SECOND));
+
+        assert!(caps.can_send(1000, t0 + SECOND, rtt));
+        assert!(caps.timeout().is_none());
+
+        // On a short-RTT path, the per-RTT cap is the stricter one.
+        let rtt = ms(10);
+        let mut caps = RepairCaps::new(Some(1_000), Some(10_000));
+
+        caps.sent_repair_symbol(1000, t0);
+        assert!(!caps.can_send(1000, t0 + ms(5), rtt));
+        assert_eq!(caps.timeout(), Some(t0 + ms(10)));
+        assert!(caps.can_send(1000, t0 + ms(10), rtt));
+    }
+
+    #[test]
+    fn symbol_larger_than_cap() {
+        let t0 = Instant::now();
+
+        let mut caps = RepairCaps::new(None, Some(500));
+
+        assert!(!caps.can_send(1000, t0, Duration::from_millis(10)));
+        assert_eq!(caps.timeout(), None);
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::VecDeque;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::thread;
+
+use networkcoding::source_symbol_metadata_from_u64;
+use networkcoding::Encoder;
+use networkcoding::RepairSymbol;
+use networkcoding::SourceSymbolMetadata;
+
+/// The number of encoder updates buffered before they are handed to the
+/// workers, when no repair symbol is requested.
+const MAX_BUFFERED_OPS: usize = 32;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A pool of threads computing repair symbols.
+///
+/// Generating a repair symbol over a large encoding window can take
+/// milliseconds. When a pool is set with [`set_fec_repair_offload()`], the
+/// connections compute their repair symbols on the threads of the pool
+/// instead of blocking their send path, and send them once ready. The pool
+/// can be shared by many configurations and connections, and its threads stop
+/// once all of them are dropped.
+///
+/// [`set_fec_repair_offload()`]: struct.Config.html#method.set_fec_repair_offload
+#[derive(Clone)]
+pub struct RepairWorkerPool {
+    jobs: Arc<Mutex<mpsc::Sender<Job>>>,
+}
+
+impl RepairWorkerPool {
+    /// Creates a pool of `threads` worker threads, at least one.
+    pub fn new(threads: usize) -> RepairWorkerPool {
+        let (tx, rx) = mpsc::channel::<Job>();
+        let rx = Arc::new(Mutex::new(rx));
+
+        for _ in 0..threads.max(1) {
+            let rx = Arc::clone(&rx);
+
+            thread::spawn(move || loop {
+                let job = match lock(&rx).recv() {
+                    Ok(v) => v,
+
+                    // All the handles to the pool were dropped.
+                    Err(_) => return,
+                };
+
+                job();
+            });
+        }
+
+        RepairWorkerPool {
+            jobs: Arc::new(Mutex::new(tx)),
+        }
+    }
+
+    fn submit(&self, job: Job) {
+        // The workers only stop once all the senders are dropped.
+        lock(&self.jobs).send(job).ok();
+    }
+}
+
+impl std::fmt::Debug for RepairWorkerPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "RepairWorkerPool")
+    }
+}
+
+/// An update of the encoder, replayed by the workers in order.
+enum Op {
+    Protect(Vec<u8>),
+    Landed(SourceSymbolMetadata),
+    RemoveLanded,
+    RemoveUpTo(SourceSymbolMetadata),
+    Generate(SourceSymbolMetadata),
+}
+
+struct Shared {
+    encoder: Mutex<Encoder>,
+    ops: Mutex<Vec<Op>>,
+    done: Mutex<VecDeque<Option<RepairSymbol>>>,
+}
+
+impl Shared {
+    /// Replays the buffered updates on the encoder.
+    ///
+    /// The encoder is locked first, so that the updates are replayed in order
+    /// even if several workers run jobs of the same encoder.
+    fn run(&self) {
+        let mut encoder = lock(&self.encoder);
+        let ops = std::mem::take(&mut *lock(&self.ops));
+
+        for op in ops {
+            match op {
+                Op::Protect(data) => {
+                    let mut md = source_symbol_metadata_from_u64(0);
+                    encoder.protect_data(data, &mut md).ok();
+                },
+
+                Op::Landed(md) => encoder.symbol_landed(md),
+
+                Op::RemoveLanded => encoder.remove_landed_symbols(),
+
+                Op::RemoveUpTo(md) => encoder.remove_up_to(md),
+
+                Op::Generate(md) => {
+                    let rs = encoder
+                        .generate_and_serialize_repair_symbol_up_to(md)
+                        .ok();
+
+                    lock(&self.done).push_back(rs);
+                },
+            }
+        }
+    }
+}
+
+/// A mirror of an encoder whose repair symbols are computed by a
+/// [`RepairWorkerPool`].
+///
+/// The mirror receives the same updates as the encoder it mirrors, and as
+/// the source symbol IDs are sequential, the repair symbols it generates
+/// protect the same source symbols.
+pub(crate) struct OffloadedEncoder {
+    shared: Arc<Shared>,
+    pool: RepairWorkerPool,
+    computing: usize,
+}
+
+impl OffloadedEncoder {
+    pub fn new(pool: &RepairWorkerPool, encoder: Encoder) -> OffloadedEncoder {
+        OffloadedEncoder {
+            shared: Arc::new(Shared {
+                encoder: Mutex::new(encoder),
+                ops: Mutex::new(Vec::new()),
+                done: Mutex::new(VecDeque::new()),
+            }),
+            pool: pool.clone(),
+            computing: 0,
+        }
+    }
+
+    fn push(&mut self, op: Op) {
+        let flush = {
+            let mut ops = lock(&self.shared.ops);
+            let flush = matches!(op, Op::Generate(_)) ||
+                ops.len() + 1 >= MAX_BUFFERED_OPS;
+
+            ops.push(op);
+
+            flush
+        };
+
+        if flush {
+            let shared = Arc::clone(&self.shared);
+
+            self.pool.submit(Box::new(move || shared.run()));
+        }
+    }
+
+    /// Replaces the mirrored encoder, before any source symbol is protected.
+    pub fn replace_encoder(&mut self, encoder: Encoder) {
+        *lock(&self.shared.encoder) = encoder;
+    }
+
+    pub fn protect_data(&mut self, data: Vec<u8>) {
+        self.push(Op::Protect(data));
+    }
+
+    pub fn symbol_landed(&mut self, md: SourceSymbolMetadata) {
+        self.push(Op::Landed(md));
+    }
+
+    pub fn remove_landed_symbols(&mut self) {
+        self.push(Op::RemoveLanded);
+    }
+
+    pub fn remove_up_to(&mut self, md: SourceSymbolMetadata) {
+        self.push(Op::RemoveUpTo(md));
+    }
+
+    /// Requests a repair symbol protecting the source symbols up to `md`.
+    pub fn request_repair_symbol(&mut self, md: SourceSymbolMetadata) {
+        self.computing += 1;
+
+        self.push(Op::Generate(md));
+    }
+
+    /// Returns the number of repair symbols requested and not taken yet.
+    pub fn computing(&self) -> usize {
+        self.computing
+    }
+
+    /// Returns whether a requested repair symbol is ready.
+    pub fn has_ready(&self) -> bool {
+        !lock(&self.shared.done).is_empty()
+    }
+
+    /// Takes the next ready repair symbol.
+    ///
+    /// Requests that could not be fulfilled, because the source symbols were
+    /// removed from the window in the meantime, are skipped.
+    pub fn take_ready(&mut self) -> Option<RepairSymbol> {
+        let mut done = lock(&self.shared.done);
+
+        while let Some(rs) = done.pop_front() {
+            self.computing -= 1;
+
+            if rs.is_some() {
+                return rs;
+            }
+        }
+
+        None
+    }
+
+    /// Blocks until all the requested repair symbols are computed.
+    #[cfg(test)]
+    pub fn wait(&self) {
+        while lock(&self.shared.done).len() < self.computing {
+            thread::yield_now();
+        }
+    }
+}
+
+/// Locks `mutex`, ignoring a panic of a previous holder as the protected
+/// state stays consistent between the updates.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use networkcoding::vandermonde_lc::encoder::VLCEncoder;
+
+    fn encoder() -> Encoder {
+        Encoder::VLC(VLCEncoder::new(16, 100))
+    }
+
+    #[test]
+    fn mirror() {
+        let pool = RepairWorkerPool::new(2);
+        let mut offloaded = OffloadedEncoder::new(&pool, encoder());
+        let mut local = encoder();
+
+        let mut md = source_symbol_metadata_from_u64(0);
+
+        for i in 0..4 {
+            local.protect_data(vec![i; 16], &mut md).unwrap();
+            offloaded.protect_data(vec![i; 16]);
+        }
+
+        offloaded.request_repair_symbol(md);
+        assert_eq!(offloaded.computing(), 1);
+
+        offloaded.wait();
+        assert!(offloaded.has_ready());
+
+        let expected = local.generate_and_serialize_repair_symbol_up_to(md);
+        assert!(offloaded.take_ready() == expected.ok());
+        assert_eq!(offloaded.computing(), 0);
+        assert!(offloaded.take_ready().is_none());
+    }
+
+    #[test]
+    fn updates_in_order() {
+        let pool = RepairWorkerPool::new(4);
+        let mut offloaded = OffloadedEncoder::new(&pool, encoder());
+
+        // Enough updates to be flushed before the request.
+        for i in 0..MAX_BUFFERED_OPS as u8 {
+            offloaded.protect_data(vec![i; 16]);
+        }
+
+        let first = source_symbol_metadata_from_u64(0);
+        let last = source_symbol_metadata_from_u64(MAX_BUFFERED_OPS as u64 - 1);
+
+        offloaded.symbol_landed(first);
+        offloaded.remove_landed_symbols();
+        offloaded.request_repair_symbol(last);
+
+        // The whole window is removed before the second request.
+        offloaded.remove_up_to(last);
+        offloaded.request_repair_symbol(last);
+        assert_eq!(offloaded.computing(), 2);
+
+        offloaded.wait();
+
+        let rs = offloaded.take_ready().unwrap();
+        assert_eq!(
+            crate::fec::repair_symbol::protected_symbols(&rs),
+            Some(1..MAX_BUFFERED_OPS as u64)
+        );
+
+        // The second request could not be fulfilled.
+        assert!(offloaded.take_ready().is_none());
+        assert_eq!(offloaded.computing(), 0);
+    }
+}
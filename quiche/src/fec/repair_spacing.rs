@@ -0,0 +1,83 @@
+use std::time::Duration;
+use std::time::Instant;
+
+/// Minimum spacing in time between two repair symbols.
+///
+/// Repair symbols sent back-to-back are likely to be lost together in a
+/// single microburst of losses. Whatever the reason for sending a repair
+/// symbol, it is delayed until the minimum interval elapsed since the
+/// previous one.
+pub(crate) struct RepairSpacing {
+    min_interval: Duration,
+    last_repair_sent: Option<Instant>,
+    next_timeout: Option<Instant>,
+}
+
+impl RepairSpacing {
+    pub fn new(min_interval: Duration) -> RepairSpacing {
+        RepairSpacing {
+            min_interval,
+            last_repair_sent: None,
+            next_timeout: None,
+        }
+    }
+
+    /// Returns whether a repair symbol can be sent at `now`, given
+    /// `should_send_repair`, the decision taken without spacing.
+    pub fn should_send_repair(
+        &mut self, should_send_repair: bool, now: Instant,
+    ) -> bool {
+        self.next_timeout = None;
+
+        if !should_send_repair {
+            return false;
+        }
+
+        match self.last_repair_sent {
+            Some(last) if now < last + self.min_interval => {
+                self.next_timeout = Some(last + self.min_interval);
+
+                false
+            },
+
+            _ => true,
+        }
+    }
+
+    pub fn sent_repair_symbol(&mut self, now: Instant) {
+        self.last_repair_sent = Some(now);
+        self.next_timeout = None;
+    }
+
+    // returns an Instant at which the stack should wake up to send the
+    // delayed repair symbol
+    pub fn timeout(&self) -> Option<Instant> {
+        self.next_timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spacing() {
+        let now = Instant::now();
+        let interval = Duration::from_millis(10);
+        let mut spacing = RepairSpacing::new(interval);
+
+        assert!(!spacing.should_send_repair(false, now));
+        assert!(spacing.should_send_repair(true, now));
+        spacing.sent_repair_symbol(now);
+
+        // The next repair symbol is delayed.
+        assert!(!spacing.should_send_repair(true, now));
+        assert_eq!(spacing.timeout(), Some(now + interval));
+
+        // Nothing to delay.
+        assert!(!spacing.should_send_repair(false, now));
+        assert_eq!(spacing.timeout(), None);
+
+        assert!(spacing.should_send_repair(true, now + interval));
+    }
+}
@@ -0,0 +1,133 @@
+//! Layout of the repair symbols of the Vandermonde linear codec.
+//!
+//! A repair symbol starts with the ID of the first source symbol it protects
+//! (8 bytes), followed by the number of protected source symbols (4 bytes)
+//! and its sequence number (8 bytes), all in network byte order. The coded
+//! data follows.
+
+use std::ops::Range;
+
+use networkcoding::RepairSymbol;
+
+#[cfg(test)]
+use networkcoding::vandermonde_lc::decoder::VLCDecoder;
+
+/// Length of the header of a repair symbol.
+pub(crate) const HEADER_LEN: usize = 8 + 4 + 8;
+
+/// The header of a repair symbol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct RepairSymbolHeader {
+    /// The ID of the first protected source symbol.
+    pub first_id: u64,
+
+    /// The number of protected source symbols.
+    pub n_protected: u32,
+
+    /// The sequence number of the repair symbol.
+    pub seq: u64,
+}
+
+impl RepairSymbolHeader {
+    /// Parses the header at the start of `buf`, and returns it along with the
+    /// data that follows.
+    pub fn from_slice(buf: &[u8]) -> Option<(RepairSymbolHeader, &[u8])> {
+        let mut b = octets::Octets::with_slice(buf);
+
+        let first_id = b.get_u64().ok()?;
+        let n_protected = b.get_u32().ok()?;
+        let seq = b.get_u64().ok()?;
+
+        let hdr = RepairSymbolHeader {
+            first_id,
+            n_protected,
+            seq,
+        };
+
+        Some((hdr, &buf[b.off()..]))
+    }
+
+    /// Returns the encoding of the header followed by `data`.
+    pub fn to_bytes(&self, data: &[u8]) -> Vec<u8> {
+        let mut raw = Vec::with_capacity(HEADER_LEN + data.len());
+        raw.extend_from_slice(&self.first_id.to_be_bytes());
+        raw.extend_from_slice(&self.n_protected.to_be_bytes());
+        raw.extend_from_slice(&self.seq.to_be_bytes());
+        raw.extend_from_slice(data);
+
+        raw
+    }
+
+    /// Returns the range of the protected source symbol IDs, or `None` if it
+    /// overflows the ID space.
+    pub fn protected_symbols(&self) -> Option<Range<u64>> {
+        let end = self.first_id.checked_add(self.n_protected as u64)?;
+
+        Some(self.first_id..end)
+    }
+}
+
+/// Returns the header of `repair_symbol` along with its coded data.
+pub(crate) fn header(
+    repair_symbol: &RepairSymbol,
+) -> Option<(RepairSymbolHeader, &[u8])> {
+    RepairSymbolHeader::from_slice(repair_symbol.get())
+}
+
+/// Returns the range of source symbol IDs protected by `repair_symbol`, or
+/// `None` if the repair symbol is malformed.
+pub(crate) fn protected_symbols(
+    repair_symbol: &RepairSymbol,
+) -> Option<Range<u64>> {
+    header(repair_symbol)?.0.protected_symbols()
+}
+
+/// Builds a repair symbol protecting `n_protected` source symbols from
+/// `first_id`, with zeroed data.
+#[cfg(test)]
+pub(crate) fn build(
+    first_id: u64, n_protected: u32, seq: u64, symbol_size: usize,
+) -> RepairSymbol {
+    let hdr = RepairSymbolHeader {
+        first_id,
+        n_protected,
+        seq,
+    };
+
+    let decoder = VLCDecoder::new(symbol_size, 100);
+
+    decoder
+        .read_repair_symbol(&hdr.to_bytes(&vec![0; symbol_size]))
+        .unwrap()
+        .1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_roundtrip() {
+        let hdr = RepairSymbolHeader {
+            first_id: 1000,
+            n_protected: 4,
+            seq: 7,
+        };
+
+        let raw = hdr.to_bytes(&[0xaa; 16]);
+        assert_eq!(raw.len(), HEADER_LEN + 16);
+        assert_eq!(
+            RepairSymbolHeader::from_slice(&raw),
+            Some((hdr, &[0xaa; 16][..]))
+        );
+
+        assert_eq!(RepairSymbolHeader::from_slice(&raw[..HEADER_LEN - 1]), None);
+    }
+
+    #[test]
+    fn protected_range() {
+        assert_eq!(protected_symbols(&build(10, 5, 0, 16)), Some(10..15));
+        assert_eq!(protected_symbols(&build(0, 1, 0, 16)), Some(0..1));
+        assert_eq!(protected_symbols(&build(u64::MAX - 1, 4, 0, 16)), None);
+    }
+}
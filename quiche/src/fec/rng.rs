@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+/// Seedable pseudo-random number generator for the FEC experiments.
+///
+/// The randomness of the FEC mechanisms does not need to be unpredictable,
+/// but runs must be reproducible when the seed is given. This is SplitMix64,
+/// which is small and good enough for this purpose.
+pub(crate) struct FecRng {
+    state: u64,
+}
+
+impl FecRng {
+    /// Creates a generator seeded with `seed`, or with a random seed if
+    /// `None`.
+    pub fn new(seed: Option<u64>) -> FecRng {
+        FecRng {
+            state: seed.unwrap_or_else(crate::rand::rand_u64),
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a duration uniformly distributed in `[0, max]`, with a
+    /// nanosecond granularity.
+    pub fn duration_up_to(&mut self, max: Duration) -> Duration {
+        let max = std::cmp::min(max.as_nanos(), u128::from(u64::MAX - 1)) as u64;
+
+        Duration::from_nanos(self.next_u64() % (max + 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reproducible() {
+        let mut a = FecRng::new(Some(42));
+        let mut b = FecRng::new(Some(42));
+        let mut c = FecRng::new(Some(43));
+
+        let a: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+        let b: Vec<u64> = (0..8).map(|_| b.next_u64()).collect();
+        let c: Vec<u64> = (0..8).map(|_| c.next_u64()).collect();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn duration_bounds() {
+        let mut rng = FecRng::new(Some(1));
+        let max = Duration::from_millis(5);
+
+        for _ in 0..1000 {
+            assert!(rng.duration_up_to(max) <= max);
+        }
+
+        assert_eq!(rng.duration_up_to(Duration::ZERO), Duration::ZERO);
+    }
+}
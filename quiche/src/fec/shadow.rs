@@ -0,0 +1,195 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::fec::context::EncoderWindow;
+use crate::fec::context::SchedulerContext;
+use crate::fec::fec_scheduler::new_fec_scheduler;
+use crate::fec::fec_scheduler::BurstDetection;
+use crate::fec::fec_scheduler::FECScheduler;
+use crate::fec::fec_scheduler::FECSchedulerAlgorithm;
+
+/// The interval between the reports of the shadow schedulers in the logs.
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The decisions of a FEC scheduler evaluated in shadow mode, compared with
+/// those of the active scheduler.
+///
+/// See [`Config::set_fec_shadow_schedulers()`].
+///
+/// [`Config::set_fec_shadow_schedulers()`]: struct.Config.html#method.set_fec_shadow_schedulers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FECShadowReport {
+    /// The scheduler evaluated in shadow mode.
+    pub algorithm: FECSchedulerAlgorithm,
+
+    /// The number of times the scheduler was evaluated.
+    pub evaluations: u64,
+
+    /// The number of times the scheduler decided to send a repair symbol.
+    pub repair_decisions: u64,
+
+    /// The number of times the active scheduler decided to send a repair
+    /// symbol, over the same evaluations.
+    pub active_repair_decisions: u64,
+
+    /// The number of evaluations where the scheduler took the same decision
+    /// as the active one.
+    pub agreements: u64,
+}
+
+/// The FEC schedulers evaluated alongside the active one.
+///
+/// They are fed the same events as the active scheduler, that is, the
+/// symbols actually sent, acknowledged and lost, so that their decisions are
+/// taken on the same traffic. Their decisions are only logged and counted,
+/// and never change what the connection sends.
+#[derive(Default)]
+pub(crate) struct ShadowSchedulers {
+    schedulers: Vec<(FECScheduler, FECShadowReport)>,
+
+    last_report: Option<Instant>,
+}
+
+impl ShadowSchedulers {
+    pub fn new(
+        algorithms: &[FECSchedulerAlgorithm], burst_detection: BurstDetection,
+    ) -> ShadowSchedulers {
+        let schedulers = algorithms
+            .iter()
+            .map(|alg| {
+                let report = FECShadowReport {
+                    algorithm: *alg,
+                    evaluations: 0,
+                    repair_decisions: 0,
+                    active_repair_decisions: 0,
+                    agreements: 0,
+                };
+
+                (new_fec_scheduler(*alg, burst_detection), report)
+            })
+            .collect();
+
+        ShadowSchedulers {
+            schedulers,
+            last_report: None,
+        }
+    }
+
+    /// Evaluates the shadow schedulers in the context the active scheduler
+    /// took `active_decision` in.
+    pub fn evaluate(&mut self, ctx: &SchedulerContext, active_decision: bool) {
+        if self.schedulers.is_empty() {
+            return;
+        }
+
+        for (scheduler, report) in &mut self.schedulers {
+            let decision = scheduler.should_send_repair(ctx);
+
+            trace!(
+                "shadow fec scheduler {} decision={} active={}",
+                report.algorithm,
+                decision,
+                active_decision
+            );
+
+            report.evaluations += 1;
+            report.repair_decisions += decision as u64;
+            report.active_repair_decisions += active_decision as u64;
+            report.agreements += (decision == active_decision) as u64;
+        }
+
+        if self
+            .last_report
+            .map_or(true, |last| ctx.now >= last + REPORT_INTERVAL)
+        {
+            self.last_report = Some(ctx.now);
+
+            for (_, report) in &self.schedulers {
+                debug!("shadow fec scheduler report {:?}", report);
+            }
+        }
+    }
+
+    pub fn sent_repair_symbol(&mut self) {
+        for (scheduler, _) in &mut self.schedulers {
+            scheduler.sent_repair_symbol();
+        }
+    }
+
+    pub fn acked_repair_symbol(&mut self) {
+        for (scheduler, _) in &mut self.schedulers {
+            scheduler.acked_repair_symbol();
+        }
+    }
+
+    pub fn lost_repair_symbol(&mut self) {
+        for (scheduler, _) in &mut self.schedulers {
+            scheduler.lost_repair_symbol();
+        }
+    }
+
+    pub fn sent_source_symbol(&mut self, now: Instant, window: &EncoderWindow) {
+        for (scheduler, _) in &mut self.schedulers {
+            scheduler.sent_source_symbol(now, window);
+        }
+    }
+
+    pub fn reports(&self) -> Vec<FECShadowReport> {
+        self.schedulers.iter().map(|(_, report)| *report).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fec::testing;
+
+    #[test]
+    fn decisions_compared() {
+        let t0 = Instant::now();
+
+        let idle = SchedulerContext {
+            stream_to_emit: false,
+            sent_count: 20,
+            tx_data: 20000,
+            bif: 20000,
+            window: testing::window(0, 15, t0),
+            ..testing::context(t0)
+        };
+
+        let mut shadows = ShadowSchedulers::new(
+            &[
+                FECSchedulerAlgorithm::NoRedundancy,
+                FECSchedulerAlgorithm::BackgroundOnly,
+            ],
+            BurstDetection::Bytes,
+        );
+
+        shadows.sent_source_symbol(t0, &idle.window);
+        shadows.evaluate(&idle, false);
+        shadows.evaluate(
+            &SchedulerContext {
+                now: t0 + Duration::from_millis(100),
+                ..idle
+            },
+            false,
+        );
+
+        let reports = shadows.reports();
+        assert_eq!(reports.len(), 2);
+
+        assert_eq!(reports[0].algorithm, FECSchedulerAlgorithm::NoRedundancy);
+        assert_eq!(reports[0].evaluations, 2);
+        assert_eq!(reports[0].repair_decisions, 0);
+        assert_eq!(reports[0].agreements, 2);
+
+        // Once the delay elapsed, the background scheduler protects the idle
+        // connection.
+        assert_eq!(reports[1].algorithm, FECSchedulerAlgorithm::BackgroundOnly);
+        assert_eq!(reports[1].evaluations, 2);
+        assert_eq!(reports[1].repair_decisions, 1);
+        assert_eq!(reports[1].active_repair_decisions, 0);
+        assert_eq!(reports[1].agreements, 1);
+    }
+}
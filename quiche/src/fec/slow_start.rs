@@ -0,0 +1,127 @@
+use crate::fec::context::SchedulerContext;
+
+/// Policies applied to the FEC schedulers during slow start.
+///
+/// During slow start, bursts are created by the congestion window doubling
+/// every round-trip rather than by the application, and the loss estimates
+/// are not available yet, so the schedulers' heuristics misfire just after
+/// the handshake.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SlowStartRedundancy {
+    /// The scheduler ignores slow start (default).
+    Unchanged,
+
+    /// The scheduler sends no repair symbols during slow start.
+    Suppress,
+
+    /// On top of the scheduler decisions, a repair symbol is sent for every
+    /// given number of source symbols sent during slow start, as soon as the
+    /// application has nothing to send.
+    Strengthen(u64),
+}
+
+/// The state of the slow start policy of a connection.
+pub(crate) struct SlowStartState {
+    policy: SlowStartRedundancy,
+    unprotected_symbols: u64,
+}
+
+impl SlowStartState {
+    pub fn new(policy: SlowStartRedundancy) -> SlowStartState {
+        SlowStartState {
+            policy,
+            unprotected_symbols: 0,
+        }
+    }
+
+    /// Returns whether a repair symbol must be sent, given
+    /// `should_send_repair`, the decision of the scheduler.
+    pub fn should_send_repair(
+        &mut self, should_send_repair: bool, ctx: &SchedulerContext,
+    ) -> bool {
+        if !ctx.in_slow_start {
+            self.unprotected_symbols = 0;
+
+            return should_send_repair;
+        }
+
+        match self.policy {
+            SlowStartRedundancy::Unchanged => should_send_repair,
+
+            SlowStartRedundancy::Suppress => false,
+
+            SlowStartRedundancy::Strengthen(_) =>
+                should_send_repair ||
+                    (ctx.nothing_to_send() && self.unprotected_symbols > 0),
+        }
+    }
+
+    pub fn sent_source_symbol(&mut self, in_slow_start: bool) {
+        if in_slow_start {
+            self.unprotected_symbols += 1;
+        }
+    }
+
+    pub fn sent_repair_symbol(&mut self) {
+        if let SlowStartRedundancy::Strengthen(n) = self.policy {
+            self.unprotected_symbols =
+                self.unprotected_symbols.saturating_sub(n.max(1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fec::testing;
+
+    use std::time::Instant;
+
+    #[test]
+    fn suppress() {
+        let ctx = SchedulerContext {
+            in_slow_start: true,
+            ..testing::context(Instant::now())
+        };
+
+        let mut state = SlowStartState::new(SlowStartRedundancy::Suppress);
+        assert!(!state.should_send_repair(true, &ctx));
+
+        let ctx = SchedulerContext {
+            in_slow_start: false,
+            ..ctx
+        };
+        assert!(state.should_send_repair(true, &ctx));
+    }
+
+    #[test]
+    fn strengthen() {
+        let ctx = SchedulerContext {
+            in_slow_start: true,
+            ..testing::context(Instant::now())
+        };
+
+        let mut state = SlowStartState::new(SlowStartRedundancy::Strengthen(2));
+        assert!(!state.should_send_repair(false, &ctx));
+
+        for _ in 0..3 {
+            state.sent_source_symbol(true);
+        }
+
+        // Not while the application has data to send.
+        let busy = SchedulerContext {
+            stream_to_emit: true,
+            ..ctx
+        };
+        assert!(!state.should_send_repair(false, &busy));
+
+        // One repair symbol every 2 source symbols.
+        assert!(state.should_send_repair(false, &ctx));
+        state.sent_repair_symbol();
+        assert!(state.should_send_repair(false, &ctx));
+        state.sent_repair_symbol();
+        assert!(!state.should_send_repair(false, &ctx));
+    }
+}
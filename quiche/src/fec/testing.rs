@@ -0,0 +1,94 @@
+//! Fixtures for the FEC schedulers tests.
+//!
+//! A golden trace is a canned sequence of scheduler events in which every
+//! `ShouldSendRepair` event holds the decision the scheduler is expected to
+//! take. Replaying it against a fresh scheduler ensures that any change of
+//! behavior of the scheduler is intentional.
+
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::fec::context::EncoderWindow;
+use crate::fec::context::SchedulerContext;
+use crate::fec::fec_scheduler::new_fec_scheduler;
+use crate::fec::fec_scheduler::BurstDetection;
+use crate::fec::fec_scheduler::FECSchedulerAlgorithm;
+use crate::fec::trace::replay_event;
+use crate::fec::trace::TraceEvent;
+
+pub const SYMBOL_SIZE: usize = 1200;
+
+pub const RTT: Duration = Duration::from_millis(50);
+
+/// Returns the state of an idle connection with an empty encoding window.
+pub fn context(now: Instant) -> SchedulerContext {
+    SchedulerContext {
+        now,
+        dgrams_to_emit: false,
+        stream_to_emit: false,
+        sent_count: 0,
+        tx_data: 0,
+        bif: 0,
+        cwnd_available: 20000,
+        rtt: RTT,
+        rttvar: Duration::ZERO,
+        pacing_interval: None,
+        in_slow_start: false,
+        packets_lost_per_round_trip: None,
+        var_packets_lost_per_round_trip: 0.0,
+        symbol_size: SYMBOL_SIZE,
+        repair_packet_size: SYMBOL_SIZE,
+        window: EncoderWindow::default(),
+    }
+}
+
+/// Returns an encoding window holding the source symbols `first..=last`.
+pub fn window(first: u64, last: u64, first_sent_time: Instant) -> EncoderWindow {
+    EncoderWindow {
+        first: Some(first),
+        last: Some(last),
+        first_sent_time: Some(first_sent_time),
+        n_symbols: (last - first + 1) as usize,
+    }
+}
+
+pub fn decide(ctx: SchedulerContext, decision: bool) -> TraceEvent {
+    TraceEvent::ShouldSendRepair { ctx, decision }
+}
+
+pub fn sent_repair(time: Instant) -> TraceEvent {
+    TraceEvent::SentRepair { time }
+}
+
+/// Feeds `events` to a fresh scheduler and returns its decisions.
+pub fn run(alg: FECSchedulerAlgorithm, events: &[TraceEvent]) -> Vec<bool> {
+    let mut scheduler = new_fec_scheduler(alg, BurstDetection::Bytes);
+
+    events
+        .iter()
+        .filter_map(|event| {
+            let decision = replay_event(&mut scheduler, event);
+
+            match event {
+                TraceEvent::ShouldSendRepair { .. } => Some(decision),
+
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Asserts that a fresh scheduler takes exactly the decisions of the golden
+/// trace `events`.
+pub fn assert_golden_trace(alg: FECSchedulerAlgorithm, events: &[TraceEvent]) {
+    let expected: Vec<bool> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceEvent::ShouldSendRepair { decision, .. } => Some(*decision),
+
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(run(alg, events), expected);
+}
@@ -0,0 +1,610 @@
+use std::io::Write;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::fec::context::EncoderWindow;
+use crate::fec::context::SchedulerContext;
+use crate::fec::fec_scheduler::new_fec_scheduler;
+use crate::fec::fec_scheduler::BurstDetection;
+use crate::fec::fec_scheduler::FECScheduler;
+use crate::fec::fec_scheduler::FECSchedulerAlgorithm;
+use crate::Error;
+use crate::Result;
+
+const TRACE_MAGIC: &[u8] = b"QFTR";
+const TRACE_VERSION: u8 = 3;
+
+const BURST_DETECTION_BYTES: u8 = 0x00;
+const BURST_DETECTION_INTER_SEND_GAP: u8 = 0x01;
+
+const EVENT_SHOULD_SEND_REPAIR: u8 = 0x00;
+const EVENT_SENT_REPAIR: u8 = 0x01;
+const EVENT_ACKED_REPAIR: u8 = 0x02;
+const EVENT_LOST_REPAIR: u8 = 0x03;
+const EVENT_SENT_SOURCE: u8 = 0x04;
+
+const MAX_VARINT: u64 = (1 << 62) - 1;
+
+// Large enough for the biggest event.
+const MAX_EVENT_LEN: usize = 128;
+
+/// An event relevant to the FEC schedulers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum TraceEvent {
+    ShouldSendRepair {
+        ctx: SchedulerContext,
+        decision: bool,
+    },
+
+    SentRepair {
+        time: Instant,
+    },
+
+    AckedRepair {
+        time: Instant,
+    },
+
+    LostRepair {
+        time: Instant,
+    },
+
+    SentSource {
+        time: Instant,
+        window: EncoderWindow,
+    },
+}
+
+/// Records the events fed to the FEC scheduler of a connection.
+///
+/// Events are written in a compact binary format: a header made of the
+/// `QFTR` magic, a version byte and the burst detection of the connection,
+/// followed by the events, each one being a type byte followed by its fields
+/// encoded as QUIC variable-length integers. Timestamps are encoded as the
+/// number of microseconds elapsed since the creation of the recorder.
+pub(crate) struct TraceRecorder {
+    writer: Box<dyn Write + Send + Sync>,
+    start: Instant,
+    failed: bool,
+}
+
+impl TraceRecorder {
+    pub fn new(
+        mut writer: Box<dyn Write + Send + Sync>, start: Instant,
+        burst_detection: BurstDetection,
+    ) -> TraceRecorder {
+        let mut header = TRACE_MAGIC.to_vec();
+        header.push(TRACE_VERSION);
+
+        match burst_detection {
+            BurstDetection::Bytes => header.push(BURST_DETECTION_BYTES),
+
+            BurstDetection::InterSendGap(k) => {
+                header.push(BURST_DETECTION_INTER_SEND_GAP);
+                header.extend_from_slice(&k.to_bits().to_be_bytes());
+            },
+        }
+
+        let failed = writer.write_all(&header).is_err();
+
+        TraceRecorder {
+            writer,
+            start,
+            failed,
+        }
+    }
+
+    pub fn record(&mut self, event: &TraceEvent) {
+        if self.failed {
+            return;
+        }
+
+        let mut buf = [0; MAX_EVENT_LEN];
+        let mut b = octets::OctetsMut::with_slice(&mut buf);
+
+        let len = match encode_event(event, self.start, &mut b) {
+            Ok(()) => b.off(),
+
+            Err(_) => return,
+        };
+
+        if let Err(e) = self.writer.write_all(&buf[..len]) {
+            error!("failed to write FEC scheduler trace: {:?}", e);
+            self.failed = true;
+        }
+    }
+}
+
+/// Outcome of the replay of a FEC scheduler trace.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FECTraceReplay {
+    /// The number of events replayed.
+    pub events: usize,
+
+    /// The number of repair decisions taken by the scheduler.
+    pub decisions: usize,
+
+    /// The number of decisions to send a repair symbol.
+    pub repair_decisions: usize,
+
+    /// The number of decisions that differ from the recorded ones.
+    pub divergences: usize,
+}
+
+/// Re-runs the FEC scheduler `alg` against a trace recorded with
+/// [`set_fec_trace_recorder()`].
+///
+/// The scheduler uses the burst detection of the recorded connection, and is
+/// fed the recorded connection states and events, and its repair decisions
+/// are compared with the recorded ones. This allows to
+/// evaluate a scheduler change on traces captured on real-world
+/// connections.
+///
+/// [`set_fec_trace_recorder()`]: struct.Connection.html#method.set_fec_trace_recorder
+pub fn replay_fec_trace(
+    trace: &[u8], alg: FECSchedulerAlgorithm,
+) -> Result<FECTraceReplay> {
+    let (burst_detection, events) = decode_trace(trace, Instant::now())?;
+
+    let mut scheduler = new_fec_scheduler(alg, burst_detection);
+    let mut replay = FECTraceReplay::default();
+
+    for event in events {
+        replay.events += 1;
+
+        if let TraceEvent::ShouldSendRepair { decision, .. } = event {
+            let replayed = replay_event(&mut scheduler, &event);
+
+            replay.decisions += 1;
+
+            if replayed {
+                replay.repair_decisions += 1;
+            }
+
+            if replayed != decision {
+                replay.divergences += 1;
+            }
+        } else {
+            replay_event(&mut scheduler, &event);
+        }
+    }
+
+    Ok(replay)
+}
+
+/// Feeds `event` to `scheduler`. Returns the decision of the scheduler for
+/// `ShouldSendRepair` events, and `false` for the other events.
+pub(crate) fn replay_event(
+    scheduler: &mut FECScheduler, event: &TraceEvent,
+) -> bool {
+    match event {
+        TraceEvent::ShouldSendRepair { ctx, .. } =>
+            return scheduler.should_send_repair(ctx),
+
+        TraceEvent::SentRepair { .. } => scheduler.sent_repair_symbol(),
+
+        TraceEvent::AckedRepair { .. } => scheduler.acked_repair_symbol(),
+
+        TraceEvent::LostRepair { .. } => scheduler.lost_repair_symbol(),
+
+        TraceEvent::SentSource { time, window } =>
+            scheduler.sent_source_symbol(*time, window),
+    }
+
+    false
+}
+
+/// Decodes a trace, using `start` as the time at which it was started.
+/// Returns the burst detection of the recorded connection along with the
+/// events.
+pub(crate) fn decode_trace(
+    trace: &[u8], start: Instant,
+) -> Result<(BurstDetection, Vec<TraceEvent>)> {
+    let mut b = octets::Octets::with_slice(trace);
+
+    if b.get_bytes(TRACE_MAGIC.len())?.buf() != TRACE_MAGIC ||
+        b.get_u8()? != TRACE_VERSION
+    {
+        return Err(Error::InvalidState);
+    }
+
+    let burst_detection = match b.get_u8()? {
+        BURST_DETECTION_BYTES => BurstDetection::Bytes,
+
+        BURST_DETECTION_INTER_SEND_GAP =>
+            BurstDetection::InterSendGap(f64::from_bits(b.get_u64()?)),
+
+        _ => return Err(Error::InvalidState),
+    };
+
+    let mut events = Vec::new();
+
+    while b.cap() > 0 {
+        events.push(decode_event(&mut b, start)?);
+    }
+
+    Ok((burst_detection, events))
+}
+
+fn encode_event(
+    event: &TraceEvent, start: Instant, b: &mut octets::OctetsMut,
+) -> Result<()> {
+    match event {
+        TraceEvent::ShouldSendRepair { ctx, decision } => {
+            b.put_u8(EVENT_SHOULD_SEND_REPAIR)?;
+
+            let flags = ctx.dgrams_to_emit as u8 |
+                (ctx.stream_to_emit as u8) << 1 |
+                (ctx.packets_lost_per_round_trip.is_some() as u8) << 2 |
+                (*decision as u8) << 3 |
+                (ctx.pacing_interval.is_some() as u8) << 4 |
+                (ctx.in_slow_start as u8) << 5 |
+                ((ctx.repair_packet_size != ctx.symbol_size) as u8) << 6;
+            b.put_u8(flags)?;
+
+            put_time(b, start, ctx.now)?;
+            put_varint(b, ctx.sent_count as u64)?;
+            put_varint(b, ctx.tx_data)?;
+            put_varint(b, ctx.bif as u64)?;
+            put_varint(b, ctx.cwnd_available as u64)?;
+            put_varint(b, ctx.rtt.as_micros() as u64)?;
+            put_varint(b, ctx.rttvar.as_micros() as u64)?;
+
+            if let Some(interval) = ctx.pacing_interval {
+                put_varint(b, interval.as_nanos() as u64)?;
+            }
+
+            if let Some(lost) = ctx.packets_lost_per_round_trip {
+                b.put_u64(lost.to_bits())?;
+            }
+
+            b.put_u64(ctx.var_packets_lost_per_round_trip.to_bits())?;
+            put_varint(b, ctx.symbol_size as u64)?;
+
+            if ctx.repair_packet_size != ctx.symbol_size {
+                put_varint(b, ctx.repair_packet_size as u64)?;
+            }
+
+            put_window(b, start, &ctx.window)?;
+        },
+
+        TraceEvent::SentRepair { time } => {
+            b.put_u8(EVENT_SENT_REPAIR)?;
+            put_time(b, start, *time)?;
+        },
+
+        TraceEvent::AckedRepair { time } => {
+            b.put_u8(EVENT_ACKED_REPAIR)?;
+            put_time(b, start, *time)?;
+        },
+
+        TraceEvent::LostRepair { time } => {
+            b.put_u8(EVENT_LOST_REPAIR)?;
+            put_time(b, start, *time)?;
+        },
+
+        TraceEvent::SentSource { time, window } => {
+            b.put_u8(EVENT_SENT_SOURCE)?;
+            put_time(b, start, *time)?;
+            put_window(b, start, window)?;
+        },
+    }
+
+    Ok(())
+}
+
+fn decode_event(b: &mut octets::Octets, start: Instant) -> Result<TraceEvent> {
+    let event = match b.get_u8()? {
+        EVENT_SHOULD_SEND_REPAIR => {
+            let flags = b.get_u8()?;
+
+            let now = get_time(b, start)?;
+            let sent_count = b.get_varint()? as usize;
+            let tx_data = b.get_varint()?;
+            let bif = b.get_varint()? as usize;
+            let cwnd_available = b.get_varint()? as usize;
+            let rtt = Duration::from_micros(b.get_varint()?);
+            let rttvar = Duration::from_micros(b.get_varint()?);
+
+            let pacing_interval = if flags & 0x10 != 0 {
+                Some(Duration::from_nanos(b.get_varint()?))
+            } else {
+                None
+            };
+
+            let packets_lost_per_round_trip = if flags & 0x04 != 0 {
+                Some(f64::from_bits(b.get_u64()?))
+            } else {
+                None
+            };
+
+            let var_packets_lost_per_round_trip = f64::from_bits(b.get_u64()?);
+            let symbol_size = b.get_varint()? as usize;
+
+            let repair_packet_size = if flags & 0x40 != 0 {
+                b.get_varint()? as usize
+            } else {
+                symbol_size
+            };
+
+            let window = get_window(b, start)?;
+
+            TraceEvent::ShouldSendRepair {
+                ctx: SchedulerContext {
+                    now,
+                    dgrams_to_emit: flags & 0x01 != 0,
+                    stream_to_emit: flags & 0x02 != 0,
+                    sent_count,
+                    tx_data,
+                    bif,
+                    cwnd_available,
+                    rtt,
+                    rttvar,
+                    pacing_interval,
+                    in_slow_start: flags & 0x20 != 0,
+                    packets_lost_per_round_trip,
+                    var_packets_lost_per_round_trip,
+                    symbol_size,
+                    repair_packet_size,
+                    window,
+                },
+                decision: flags & 0x08 != 0,
+            }
+        },
+
+        EVENT_SENT_REPAIR => TraceEvent::SentRepair {
+            time: get_time(b, start)?,
+        },
+
+        EVENT_ACKED_REPAIR => TraceEvent::AckedRepair {
+            time: get_time(b, start)?,
+        },
+
+        EVENT_LOST_REPAIR => TraceEvent::LostRepair {
+            time: get_time(b, start)?,
+        },
+
+        EVENT_SENT_SOURCE => TraceEvent::SentSource {
+            time: get_time(b, start)?,
+            window: get_window(b, start)?,
+        },
+
+        _ => return Err(Error::InvalidState),
+    };
+
+    Ok(event)
+}
+
+fn put_varint(b: &mut octets::OctetsMut, v: u64) -> Result<()> {
+    b.put_varint(v.min(MAX_VARINT))?;
+
+    Ok(())
+}
+
+fn put_time(b: &mut octets::OctetsMut, start: Instant, t: Instant) -> Result<()> {
+    put_varint(b, t.saturating_duration_since(start).as_micros() as u64)
+}
+
+fn get_time(b: &mut octets::Octets, start: Instant) -> Result<Instant> {
+    Ok(start + Duration::from_micros(b.get_varint()?))
+}
+
+fn put_window(
+    b: &mut octets::OctetsMut, start: Instant, window: &EncoderWindow,
+) -> Result<()> {
+    let flags = window.first.is_some() as u8 |
+        (window.last.is_some() as u8) << 1 |
+        (window.first_sent_time.is_some() as u8) << 2;
+    b.put_u8(flags)?;
+
+    if let Some(first) = window.first {
+        put_varint(b, first)?;
+    }
+
+    if let Some(last) = window.last {
+        put_varint(b, last)?;
+    }
+
+    if let Some(t) = window.first_sent_time {
+        put_time(b, start, t)?;
+    }
+
+    put_varint(b, window.n_symbols as u64)
+}
+
+fn get_window(b: &mut octets::Octets, start: Instant) -> Result<EncoderWindow> {
+    let flags = b.get_u8()?;
+
+    let first = if flags & 0x01 != 0 {
+        Some(b.get_varint()?)
+    } else {
+        None
+    };
+
+    let last = if flags & 0x02 != 0 {
+        Some(b.get_varint()?)
+    } else {
+        None
+    };
+
+    let first_sent_time = if flags & 0x04 != 0 {
+        Some(get_time(b, start)?)
+    } else {
+        None
+    };
+
+    Ok(EncoderWindow {
+        first,
+        last,
+        first_sent_time,
+        n_symbols: b.get_varint()? as usize,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn context(now: Instant, stream_to_emit: bool) -> SchedulerContext {
+        SchedulerContext {
+            now,
+            dgrams_to_emit: false,
+            stream_to_emit,
+            sent_count: 10,
+            tx_data: 12000,
+            bif: 6000,
+            cwnd_available: 6000,
+            rtt: Duration::from_millis(50),
+            rttvar: Duration::from_millis(5),
+            pacing_interval: Some(Duration::from_micros(96)),
+            in_slow_start: true,
+            packets_lost_per_round_trip: Some(0.5),
+            var_packets_lost_per_round_trip: 0.25,
+            symbol_size: 1200,
+            repair_packet_size: 1200,
+            window: EncoderWindow {
+                first: Some(0),
+                last: Some(9),
+                first_sent_time: Some(now),
+                n_symbols: 10,
+            },
+        }
+    }
+
+    #[test]
+    fn record_and_decode() {
+        let start = Instant::now();
+        let buf = SharedBuf::default();
+
+        let events = [
+            TraceEvent::SentSource {
+                time: start + Duration::from_millis(1),
+                window: context(start, true).window,
+            },
+            TraceEvent::ShouldSendRepair {
+                ctx: context(start + Duration::from_millis(2), false),
+                decision: true,
+            },
+            TraceEvent::SentRepair {
+                time: start + Duration::from_millis(2),
+            },
+            TraceEvent::ShouldSendRepair {
+                ctx: SchedulerContext {
+                    repair_packet_size: 1350,
+                    ..context(start + Duration::from_millis(3), false)
+                },
+                decision: false,
+            },
+            TraceEvent::LostRepair {
+                time: start + Duration::from_millis(60),
+            },
+            TraceEvent::AckedRepair {
+                time: start + Duration::from_millis(70),
+            },
+        ];
+
+        let burst_detection = BurstDetection::InterSendGap(1.5);
+
+        let mut recorder =
+            TraceRecorder::new(Box::new(buf.clone()), start, burst_detection);
+        for event in &events {
+            recorder.record(event);
+        }
+
+        let trace = buf.0.lock().unwrap().clone();
+        assert_eq!(&trace[..4], TRACE_MAGIC);
+        assert_eq!(
+            decode_trace(&trace, start),
+            Ok((burst_detection, events.to_vec()))
+        );
+    }
+
+    #[test]
+    fn invalid_trace() {
+        let start = Instant::now();
+
+        assert_eq!(
+            decode_trace(b"XXXX\x03\x00", start),
+            Err(Error::InvalidState)
+        );
+
+        // A trace of a previous version.
+        assert_eq!(
+            decode_trace(b"QFTR\x02\x00", start),
+            Err(Error::InvalidState)
+        );
+
+        // Unknown burst detection.
+        assert_eq!(
+            decode_trace(b"QFTR\x03\x02", start),
+            Err(Error::InvalidState)
+        );
+        assert_eq!(
+            decode_trace(b"QFTR\x03\x01\x3f", start),
+            Err(Error::BufferTooShort)
+        );
+
+        assert_eq!(
+            decode_trace(b"QFTR\x03\x00\x07", start),
+            Err(Error::InvalidState)
+        );
+        assert_eq!(
+            decode_trace(b"QFTR\x03\x00\x01", start),
+            Err(Error::BufferTooShort)
+        );
+        assert_eq!(
+            decode_trace(b"QFTR\x03\x00", start),
+            Ok((BurstDetection::Bytes, Vec::new()))
+        );
+    }
+
+    #[test]
+    fn replay() {
+        let start = Instant::now();
+        let buf = SharedBuf::default();
+
+        let mut recorder = TraceRecorder::new(
+            Box::new(buf.clone()),
+            start,
+            BurstDetection::Bytes,
+        );
+        recorder.record(&TraceEvent::ShouldSendRepair {
+            ctx: context(start, true),
+            decision: true,
+        });
+        recorder.record(&TraceEvent::ShouldSendRepair {
+            ctx: context(start, false),
+            decision: false,
+        });
+
+        let trace = buf.0.lock().unwrap().clone();
+
+        // The no-redundancy scheduler never sends repair symbols.
+        let replay =
+            replay_fec_trace(&trace, FECSchedulerAlgorithm::NoRedundancy)
+                .unwrap();
+        assert_eq!(replay, FECTraceReplay {
+            events: 2,
+            decisions: 2,
+            repair_decisions: 0,
+            divergences: 1,
+        });
+    }
+}
@@ -0,0 +1,339 @@
+use std::fmt::Write;
+
+use networkcoding::source_symbol_metadata_from_u64;
+use networkcoding::vandermonde_lc::decoder::VLCDecoder;
+use networkcoding::Decoder;
+use networkcoding::SourceSymbol;
+
+use crate::fec::decoder_stats::FECDecoderStats;
+use crate::fec::fec_scheduler::FECSchedulerAlgorithm;
+use crate::fec::hint::FECHint;
+use crate::fec::repair_symbol::RepairSymbolHeader;
+use crate::frame::Frame;
+use crate::packet;
+use crate::ranges;
+use crate::Error;
+use crate::Result;
+
+/// The size of the source and repair symbols of the test vectors.
+pub const FEC_VECTOR_SYMBOL_SIZE: usize = 16;
+
+/// A canonical encoding of a FEC frame.
+///
+/// The vectors are meant to validate independent implementations of the FEC
+/// extension against this one. The symbols are [`FEC_VECTOR_SYMBOL_SIZE`]
+/// bytes long.
+///
+/// [`FEC_VECTOR_SYMBOL_SIZE`]: constant.FEC_VECTOR_SYMBOL_SIZE.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FECFrameVector {
+    /// The name of the frame.
+    pub name: &'static str,
+
+    /// The frame type.
+    pub frame_type: u64,
+
+    /// The names and encodings of the fields following the frame type, in
+    /// wire order.
+    pub fields: &'static [(&'static str, &'static str)],
+
+    /// The encoded frame, including its type.
+    pub encoded: Vec<u8>,
+}
+
+impl FECFrameVector {
+    fn new(
+        name: &'static str, fields: &'static [(&'static str, &'static str)],
+        frame: &Frame,
+    ) -> Result<FECFrameVector> {
+        let mut d = vec![0; frame.wire_len()];
+        let mut b = octets::OctetsMut::with_slice(&mut d);
+        let len = frame.to_bytes(&mut b)?;
+        d.truncate(len);
+
+        Ok(FECFrameVector {
+            name,
+            frame_type: octets::Octets::with_slice(&d).get_varint()?,
+            fields,
+            encoded: d,
+        })
+    }
+
+    /// Parses the vector and checks that encoding the frame back yields the
+    /// same bytes.
+    pub fn check(&self) -> Result<()> {
+        let mut b = octets::Octets::with_slice(&self.encoded);
+        let frame = Frame::from_bytes(&mut b, packet::Type::Short, &decoder())?;
+
+        let mut d = vec![0; self.encoded.len()];
+        let mut b = octets::OctetsMut::with_slice(&mut d);
+
+        if frame.to_bytes(&mut b)? != self.encoded.len() || d != self.encoded {
+            return Err(Error::InvalidFrame);
+        }
+
+        Ok(())
+    }
+}
+
+fn decoder() -> Decoder {
+    Decoder::VLC(VLCDecoder::new(FEC_VECTOR_SYMBOL_SIZE, 64))
+}
+
+/// Returns the canonical test vectors of the FEC frames.
+pub fn fec_frame_vectors() -> Result<Vec<FECFrameVector>> {
+    // A repair symbol protecting the source symbols 1000 to 1003.
+    let data: Vec<u8> = (0..FEC_VECTOR_SYMBOL_SIZE).map(|v| v as u8).collect();
+    let repair = RepairSymbolHeader {
+        first_id: 1000,
+        n_protected: 4,
+        seq: 7,
+    }
+    .to_bytes(&data);
+
+    let (_, repair_symbol) = decoder().read_repair_symbol(&repair)?;
+    let compact_repair_symbol = repair_symbol.clone();
+
+    // A source symbol protecting a PING frame, padded at its start.
+    let mut source = vec![0; FEC_VECTOR_SYMBOL_SIZE];
+    source[FEC_VECTOR_SYMBOL_SIZE - 1] = 0x01;
+
+    let mut ranges = ranges::RangeSet::default();
+    ranges.insert(0..3);
+    ranges.insert(5..10);
+
+    Ok(vec![
+        FECFrameVector::new(
+            "REPAIR",
+            &[
+                ("First Source Symbol ID", "64 bits"),
+                ("Protected Source Symbols", "32 bits"),
+                ("Repair Symbol Sequence Number", "64 bits"),
+                ("Repair Symbol Data", "symbol size bytes"),
+            ],
+            &Frame::Repair {
+                repair_symbol,
+                compact: false,
+            },
+        )?,
+        FECFrameVector::new(
+            "SOURCE_SYMBOL",
+            &[
+                ("Source Symbol ID", "64 bits"),
+                ("Protected Frames", "symbol size bytes, padded at start"),
+            ],
+            &Frame::SourceSymbol {
+                source_symbol: SourceSymbol::new(
+                    source_symbol_metadata_from_u64(1000),
+                    source.clone(),
+                ),
+                compact: false,
+            },
+        )?,
+        FECFrameVector::new(
+            "SOURCE_SYMBOL_ACK",
+            &[
+                ("Largest Acknowledged", "varint"),
+                ("ACK Range Count", "varint"),
+                ("First ACK Range", "varint"),
+                ("ACK Ranges", "(gap varint, length varint) ..."),
+            ],
+            &Frame::SourceSymbolACK { ranges },
+        )?,
+        FECFrameVector::new(
+            "WINDOW_SYNC",
+            &[("First Source Symbol ID", "varint")],
+            &Frame::WindowSync { first_symbol: 1234 },
+        )?,
+        FECFrameVector::new(
+            "FEC_REQUEST",
+            &[
+                ("Enable", "varint, 0 or 1"),
+                ("Scheduler", "varint, 0 or the scheduler ID plus one"),
+            ],
+            &Frame::FECRequest {
+                enable: true,
+                scheduler: Some(FECSchedulerAlgorithm::BurstsOnly),
+            },
+        )?,
+        FECFrameVector::new(
+            "FEC_HINT",
+            &[
+                ("Hint Count", "varint"),
+                ("Hints", "(ID varint, length varint, value) ..."),
+            ],
+            &Frame::FECHint {
+                hints: vec![FECHint::BurstSize(3000)],
+            },
+        )?,
+        FECFrameVector::new(
+            "RATE_HINT",
+            &[("Residual Loss", "varint, in parts per million")],
+            &Frame::RateHint {
+                residual_loss: 1000,
+            },
+        )?,
+        FECFrameVector::new(
+            "FEC_STATS",
+            &[
+                ("Source Symbols Received", "varint"),
+                ("Repair Symbols Received", "varint"),
+                ("Useless Repair Symbols", "varint"),
+                ("Rank-Deficient Repair Symbols", "varint"),
+                ("Recovered Source Symbols", "varint"),
+            ],
+            &Frame::FECStats {
+                stats: FECDecoderStats {
+                    source_symbols_received: 1000,
+                    repair_received: 100,
+                    repair_useless: 20,
+                    repair_rank_deficient: 3,
+                    recovered: 15,
+                },
+            },
+        )?,
+        FECFrameVector::new(
+            "STREAM_UNRELIABLE",
+            &[("Stream ID", "varint"), ("Unreliable", "varint, 0 or 1")],
+            &Frame::StreamUnreliable {
+                stream_id: 4,
+                unreliable: true,
+            },
+        )?,
+        FECFrameVector::new(
+            "REPAIR (compact)",
+            &[
+                ("First Source Symbol ID", "varint"),
+                ("Protected Source Symbols", "varint"),
+                ("Repair Symbol Sequence Number", "varint"),
+                ("Repair Symbol Data", "symbol size bytes"),
+            ],
+            &Frame::Repair {
+                repair_symbol: compact_repair_symbol,
+                compact: true,
+            },
+        )?,
+        FECFrameVector::new(
+            "SOURCE_SYMBOL (compact)",
+            &[
+                ("Source Symbol ID", "varint"),
+                ("Protected Frames", "symbol size bytes, padded at start"),
+            ],
+            &Frame::SourceSymbol {
+                source_symbol: SourceSymbol::new(
+                    source_symbol_metadata_from_u64(1000),
+                    source,
+                ),
+                compact: true,
+            },
+        )?,
+    ])
+}
+
+/// Returns the documentation of the wire format of the FEC frames, in
+/// Markdown, with the test vectors as examples.
+pub fn fec_wire_format_doc() -> Result<String> {
+    let mut doc = String::new();
+
+    for v in fec_frame_vectors()? {
+        writeln!(doc, "## {} frame (type 0x{:x})", v.name, v.frame_type).ok();
+        writeln!(doc).ok();
+        writeln!(doc, "| Field | Encoding |").ok();
+        writeln!(doc, "|-------|----------|").ok();
+
+        for (field, encoding) in v.fields {
+            writeln!(doc, "| {} | {} |", field, encoding).ok();
+        }
+
+        writeln!(doc).ok();
+        writeln!(doc, "```").ok();
+
+        for line in v.encoded.chunks(16) {
+            let hex: Vec<String> =
+                line.iter().map(|b| format!("{:02x}", b)).collect();
+            writeln!(doc, "{}", hex.join(" ")).ok();
+        }
+
+        writeln!(doc, "```").ok();
+        writeln!(doc).ok();
+    }
+
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(name: &str) -> Vec<u8> {
+        fec_frame_vectors()
+            .unwrap()
+            .into_iter()
+            .find(|v| v.name == name)
+            .unwrap()
+            .encoded
+    }
+
+    #[test]
+    fn round_trip() {
+        for v in fec_frame_vectors().unwrap() {
+            assert_eq!(v.check(), Ok(()), "{}", v.name);
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        for v in fec_frame_vectors().unwrap() {
+            let truncated = FECFrameVector {
+                encoded: v.encoded[..v.encoded.len() - 1].to_vec(),
+                ..v
+            };
+
+            assert!(truncated.check().is_err(), "{}", truncated.name);
+        }
+    }
+
+    #[test]
+    fn wire_format() {
+        let mut repair = vec![0x32];
+        repair.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0x03, 0xe8]);
+        repair.extend_from_slice(&[0, 0, 0, 4]);
+        repair.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 7]);
+        repair.extend(0..16);
+        assert_eq!(encoded("REPAIR"), repair);
+
+        let mut source = vec![0x33];
+        source.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0x03, 0xe8]);
+        source.extend_from_slice(&[0; 15]);
+        source.push(0x01);
+        assert_eq!(encoded("SOURCE_SYMBOL"), source);
+
+        assert_eq!(encoded("SOURCE_SYMBOL_ACK"), [0x34, 9, 1, 4, 1, 2]);
+        assert_eq!(encoded("WINDOW_SYNC"), [0x35, 0x44, 0xd2]);
+        assert_eq!(encoded("FEC_REQUEST"), [0x36, 1, 3]);
+        assert_eq!(encoded("FEC_HINT"), [0x39, 1, 0, 2, 0x4b, 0xb8]);
+        assert_eq!(encoded("RATE_HINT"), [0x3a, 0x43, 0xe8]);
+        assert_eq!(encoded("FEC_STATS"), [
+            0x3b, 0x43, 0xe8, 0x40, 0x64, 20, 3, 15
+        ]);
+        assert_eq!(encoded("STREAM_UNRELIABLE"), [0x3c, 4, 1]);
+
+        let mut repair = vec![0x37, 0x43, 0xe8, 4, 7];
+        repair.extend(0..16);
+        assert_eq!(encoded("REPAIR (compact)"), repair);
+
+        let mut source = vec![0x38, 0x43, 0xe8];
+        source.extend_from_slice(&[0; 15]);
+        source.push(0x01);
+        assert_eq!(encoded("SOURCE_SYMBOL (compact)"), source);
+    }
+
+    #[test]
+    fn doc() {
+        let doc = fec_wire_format_doc().unwrap();
+
+        assert!(doc.contains("## WINDOW_SYNC frame (type 0x35)"));
+        assert!(doc.contains("| First Source Symbol ID | varint |"));
+        assert!(doc.contains("```\n35 44 d2\n```"));
+    }
+}
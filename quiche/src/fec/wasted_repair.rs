@@ -0,0 +1,168 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+
+use crate::ranges::RangeSet;
+
+// The maximum number of acknowledged repair symbols awaiting a verdict.
+const MAX_PENDING_REPAIR_SYMBOLS: usize = 1024;
+
+const MAX_RECOVERED_RANGES: usize = 64;
+
+/// Sender-side estimation of the repair symbols wasted by the peer.
+///
+/// An acknowledged repair symbol is considered useful if the peer reported
+/// the recovery of at least one of the source symbols it protects, and
+/// wasted otherwise. The verdict is only given once all the source symbols it
+/// protects left the encoding window, so that late recovery reports are
+/// still taken into account.
+///
+/// The exact count is the number of useless repair symbols that the peer
+/// reports in its FEC_STATS frames, this estimate covers peers that don't.
+pub(crate) struct WastedRepairTracker {
+    acked_repair_symbols: VecDeque<Range<u64>>,
+    recovered: RangeSet,
+    wasted: usize,
+    useful: usize,
+}
+
+impl WastedRepairTracker {
+    pub fn new() -> WastedRepairTracker {
+        WastedRepairTracker {
+            acked_repair_symbols: VecDeque::new(),
+            recovered: RangeSet::new(MAX_RECOVERED_RANGES),
+            wasted: 0,
+            useful: 0,
+        }
+    }
+
+    /// Records the acknowledgement of a repair symbol protecting the source
+    /// symbols `protected`.
+    ///
+    /// If too many repair symbols await a verdict, the oldest one is given
+    /// its verdict right away.
+    pub fn repair_symbol_acked(&mut self, protected: Range<u64>) {
+        if self.acked_repair_symbols.len() >= MAX_PENDING_REPAIR_SYMBOLS {
+            if let Some(oldest) = self.acked_repair_symbols.pop_front() {
+                self.verdict(oldest);
+            }
+        }
+
+        self.acked_repair_symbols.push_back(protected);
+    }
+
+    /// Records the source symbols reported as recovered by the peer.
+    pub fn symbols_recovered(&mut self, ranges: &RangeSet) {
+        for range in ranges.iter() {
+            self.recovered.insert(range);
+        }
+    }
+
+    /// Gives a verdict for the acknowledged repair symbols whose protected
+    /// source symbols all left the encoding window, `window_first` being the
+    /// first source symbol of the window.
+    pub fn update(&mut self, window_first: Option<u64>) {
+        while let Some(protected) = self.acked_repair_symbols.front() {
+            if matches!(window_first, Some(first) if protected.end > first) {
+                break;
+            }
+
+            if let Some(protected) = self.acked_repair_symbols.pop_front() {
+                self.verdict(protected);
+            }
+        }
+
+        // The recovered source symbols are still needed for the verdict of the
+        // pending repair symbols.
+        let first = match (window_first, self.acked_repair_symbols.front()) {
+            (Some(first), Some(protected)) => first.min(protected.start),
+
+            (Some(first), None) => first,
+
+            (None, _) => return,
+        };
+
+        if first > 0 {
+            self.recovered.remove_until(first - 1);
+        }
+    }
+
+    fn verdict(&mut self, protected: Range<u64>) {
+        let useful = self
+            .recovered
+            .iter()
+            .any(|r| r.start < protected.end && protected.start < r.end);
+
+        if useful {
+            self.useful += 1;
+        } else {
+            self.wasted += 1;
+        }
+    }
+
+    /// Returns the number of repair symbols considered wasted.
+    pub fn wasted(&self) -> usize {
+        self.wasted
+    }
+
+    /// Returns the number of repair symbols considered useful.
+    pub fn useful(&self) -> usize {
+        self.useful
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verdict_once_out_of_window() {
+        let mut tracker = WastedRepairTracker::new();
+
+        tracker.repair_symbol_acked(0..4);
+        tracker.repair_symbol_acked(2..6);
+        tracker.repair_symbol_acked(6..8);
+
+        let mut recovered = RangeSet::default();
+        recovered.insert(5..6);
+        tracker.symbols_recovered(&recovered);
+
+        // The symbols are still in the window.
+        tracker.update(Some(3));
+        assert_eq!((tracker.wasted(), tracker.useful()), (0, 0));
+
+        tracker.update(Some(6));
+        assert_eq!((tracker.wasted(), tracker.useful()), (1, 1));
+
+        // An empty window gives a verdict for every repair symbol.
+        tracker.update(None);
+        assert_eq!((tracker.wasted(), tracker.useful()), (2, 1));
+    }
+
+    #[test]
+    fn verdict_when_full() {
+        let mut tracker = WastedRepairTracker::new();
+
+        for i in 0..MAX_PENDING_REPAIR_SYMBOLS as u64 {
+            tracker.repair_symbol_acked(i..i + 1);
+        }
+
+        let mut recovered = RangeSet::default();
+        recovered.insert(0..1);
+        tracker.symbols_recovered(&recovered);
+
+        tracker.update(Some(0));
+        assert_eq!((tracker.wasted(), tracker.useful()), (0, 0));
+
+        // The oldest repair symbols are given their verdict instead of
+        // ignoring the new ones.
+        tracker.repair_symbol_acked(1024..1025);
+        tracker.repair_symbol_acked(1025..1026);
+        assert_eq!((tracker.wasted(), tracker.useful()), (1, 1));
+
+        tracker.update(None);
+        assert_eq!(
+            tracker.wasted() + tracker.useful(),
+            MAX_PENDING_REPAIR_SYMBOLS + 2
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use std::time::Duration;
+use std::time::Instant;
+
+/// Policies to start a new encoding window ("generation").
+///
+/// With a single encoding window sliding over the whole connection, the
+/// window only shrinks when source symbols are acknowledged. Late repair
+/// symbols then protect an ever larger window, which makes them less useful
+/// and more expensive to decode. Rotating the window drops all the source
+/// symbols of the current generation from the encoder, and the peer is told
+/// to drop them from its decoder with a WINDOW_SYNC frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WindowRotation {
+    /// Starts a new window once the window protects the given number of bytes
+    /// of source symbols.
+    Bytes(u64),
+
+    /// Starts a new window once the window protects the given number of
+    /// source symbols.
+    Packets(u64),
+
+    /// Starts a new window once its first source symbol is older than the
+    /// given duration.
+    Time(Duration),
+
+    /// Only starts a new window when the application calls
+    /// [`Connection::rotate_fec_window()`].
+    ///
+    /// [`Connection::rotate_fec_window()`]: ../struct.Connection.html#method.rotate_fec_window
+    AppControlled,
+}
+
+/// The state of the current generation of the encoding window.
+pub(crate) struct WindowRotationState {
+    policy: Option<WindowRotation>,
+    at_frame_boundaries: bool,
+    generation_start: Option<Instant>,
+    bytes: u64,
+    packets: u64,
+    split_stream_frame: bool,
+    generations: usize,
+}
+
+impl WindowRotationState {
+    pub fn new(
+        policy: Option<WindowRotation>, at_frame_boundaries: bool,
+    ) -> WindowRotationState {
+        WindowRotationState {
+            policy,
+            at_frame_boundaries,
+            generation_start: None,
+            bytes: 0,
+            packets: 0,
+            split_stream_frame: false,
+            generations: 0,
+        }
+    }
+
+    pub fn sent_source_symbol(&mut self, now: Instant, symbol_size: usize) {
+        if self.generation_start.is_none() {
+            self.generation_start = Some(now);
+            self.generations += 1;
+        }
+
+        self.bytes += symbol_size as u64;
+        self.packets += 1;
+        self.split_stream_frame = false;
+    }
+
+    /// Records that the last source symbol ends with a STREAM frame whose
+    /// data continues in the next packet.
+    pub fn split_stream_frame(&mut self) {
+        self.split_stream_frame = true;
+    }
+
+    /// Returns whether a new window must be started before sending the next
+    /// source symbol.
+    ///
+    /// When rotating at frame boundaries, a rotation is deferred while the
+    /// last source symbol ends with a split STREAM frame, so that its data
+    /// does not straddle two windows, but for at most one more period of the
+    /// policy.
+    pub fn should_rotate(&self, now: Instant) -> bool {
+        if !self.due(now, 1) {
+            return false;
+        }
+
+        !(self.at_frame_boundaries && self.split_stream_frame) || self.due(now, 2)
+    }
+
+    /// Returns whether the current generation lasted `periods` periods of
+    /// the policy.
+    fn due(&self, now: Instant, periods: u32) -> bool {
+        let generation_start = match self.generation_start {
+            Some(v) => v,
+
+            None => return false,
+        };
+
+        match self.policy {
+            Some(WindowRotation::Bytes(n)) =>
+                self.bytes >= n.saturating_mul(periods.into()),
+
+            Some(WindowRotation::Packets(n)) =>
+                self.packets >= n.saturating_mul(periods.into()),
+
+            Some(WindowRotation::Time(d)) =>
+                now >= generation_start + d * periods,
+
+            Some(WindowRotation::AppControlled) | None => false,
+        }
+    }
+
+    pub fn rotated(&mut self) {
+        self.generation_start = None;
+        self.bytes = 0;
+        self.packets = 0;
+        self.split_stream_frame = false;
+    }
+
+    /// Returns the number of generations started so far.
+    pub fn generations(&self) -> usize {
+        self.generations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packets() {
+        let now = Instant::now();
+        let mut state =
+            WindowRotationState::new(Some(WindowRotation::Packets(2)), false);
+
+        assert!(!state.should_rotate(now));
+        state.sent_source_symbol(now, 1200);
+        assert!(!state.should_rotate(now));
+        state.sent_source_symbol(now, 1200);
+        assert!(state.should_rotate(now));
+
+        state.rotated();
+        assert!(!state.should_rotate(now));
+        assert_eq!(state.generations(), 1);
+
+        state.sent_source_symbol(now, 1200);
+        assert_eq!(state.generations(), 2);
+    }
+
+    #[test]
+    fn time() {
+        let now = Instant::now();
+        let d = Duration::from_millis(100);
+        let mut state =
+            WindowRotationState::new(Some(WindowRotation::Time(d)), false);
+
+        state.sent_source_symbol(now, 1200);
+        assert!(!state.should_rotate(now + d / 2));
+        assert!(state.should_rotate(now + d));
+    }
+
+    #[test]
+    fn app_controlled() {
+        let now = Instant::now();
+        let mut state =
+            WindowRotationState::new(Some(WindowRotation::AppControlled), false);
+
+        for _ in 0..1000 {
+            state.sent_source_symbol(now, 1200);
+        }
+
+        assert!(!state.should_rotate(now + Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn frame_boundaries() {
+        let now = Instant::now();
+        let mut state =
+            WindowRotationState::new(Some(WindowRotation::Packets(2)), true);
+
+        state.sent_source_symbol(now, 1200);
+        state.sent_source_symbol(now, 1200);
+        state.split_stream_frame();
+
+        // Wait for the STREAM frame to end.
+        assert!(!state.should_rotate(now));
+
+        state.sent_source_symbol(now, 1200);
+        assert!(state.should_rotate(now));
+
+        state.rotated();
+
+        // The rotation is deferred for at most one more period.
+        for _ in 0..3 {
+            state.sent_source_symbol(now, 1200);
+            state.split_stream_frame();
+            assert!(!state.should_rotate(now));
+        }
+
+        state.sent_source_symbol(now, 1200);
+        state.split_stream_frame();
+        assert!(state.should_rotate(now));
+    }
+}
@@ -0,0 +1,180 @@
+use std::time::Duration;
+use std::time::Instant;
+
+/// The number of source symbols the encoding window must move forward by for
+/// a WINDOW_SYNC frame to be sent before a round-trip elapsed since the
+/// previous one.
+const MIN_ADVANCE: u64 = 64;
+
+/// Schedules the WINDOW_SYNC frames announcing the moves of the encoding
+/// window to the peer.
+///
+/// The window moves forward with most ACKs, so the frames are limited to one
+/// per round-trip, unless the window moved by `MIN_ADVANCE` source symbols
+/// since the previous one or was flushed. The peer's decoder only keeps the
+/// source symbols announced since then for a while longer.
+pub(crate) struct WindowSync {
+    /// The first source symbol of the encoding window last announced.
+    first_symbol: u64,
+
+    /// Whether a WINDOW_SYNC frame needs to be sent.
+    pending: bool,
+
+    /// Whether the pending WINDOW_SYNC frame announces a flush of the
+    /// window, and is sent regardless of the rate limit.
+    flush: bool,
+
+    /// The time and first source symbol of the latest frame sent, if any.
+    last_sent: Option<(Instant, u64)>,
+}
+
+impl WindowSync {
+    pub fn new() -> WindowSync {
+        WindowSync {
+            first_symbol: 0,
+            pending: false,
+            flush: false,
+            last_sent: None,
+        }
+    }
+
+    /// Records that the encoding window starts at `first_symbol`.
+    pub fn moved(&mut self, first_symbol: u64) {
+        if first_symbol > self.first_symbol {
+            self.first_symbol = first_symbol;
+            self.pending = true;
+        }
+    }
+
+    /// Records that the encoding window was flushed, so that the pending
+    /// frame is sent right away.
+    pub fn flushed(&mut self) {
+        self.flush = self.pending;
+    }
+
+    /// Records the loss of a frame announcing `first_symbol`. It is only
+    /// sent again if it was not superseded by a newer one.
+    pub fn lost(&mut self, first_symbol: u64) {
+        if first_symbol == self.first_symbol {
+            self.pending = true;
+        }
+    }
+
+    /// Returns the first source symbol to announce, if a frame is due at
+    /// `now`.
+    pub fn due(&self, now: Instant, rtt: Duration) -> Option<u64> {
+        if !self.pending {
+            return None;
+        }
+
+        let due = self.flush ||
+            match self.last_sent {
+                Some((sent, first_symbol)) =>
+                    now >= sent + rtt ||
+                        self.first_symbol >= first_symbol + MIN_ADVANCE,
+
+                None => true,
+            };
+
+        if due {
+            Some(self.first_symbol)
+        } else {
+            None
+        }
+    }
+
+    /// Records that a frame announcing `first_symbol` was sent at `now`.
+    pub fn sent(&mut self, now: Instant, first_symbol: u64) {
+        self.pending = false;
+        self.flush = false;
+        self.last_sent = Some((now, first_symbol));
+    }
+
+    pub fn first_symbol(&self) -> u64 {
+        self.first_symbol
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RTT: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn once_per_round_trip() {
+        let now = Instant::now();
+
+        let mut sync = WindowSync::new();
+        assert_eq!(sync.due(now, RTT), None);
+
+        sync.moved(1);
+        assert_eq!(sync.due(now, RTT), Some(1));
+        sync.sent(now, 1);
+
+        // The window keeps moving with each ACK.
+        for first_symbol in 2..10 {
+            sync.moved(first_symbol);
+            assert_eq!(sync.due(now + RTT / 2, RTT), None);
+        }
+
+        assert!(sync.is_pending());
+        assert_eq!(sync.due(now + RTT, RTT), Some(9));
+    }
+
+    #[test]
+    fn min_advance() {
+        let now = Instant::now();
+
+        let mut sync = WindowSync::new();
+        sync.moved(10);
+        sync.sent(now, 10);
+
+        sync.moved(10 + MIN_ADVANCE - 1);
+        assert_eq!(sync.due(now, RTT), None);
+
+        sync.moved(10 + MIN_ADVANCE);
+        assert_eq!(sync.due(now, RTT), Some(10 + MIN_ADVANCE));
+    }
+
+    #[test]
+    fn flushed() {
+        let now = Instant::now();
+
+        let mut sync = WindowSync::new();
+        sync.moved(1);
+        sync.sent(now, 1);
+
+        sync.moved(2);
+        sync.flushed();
+        assert_eq!(sync.due(now, RTT), Some(2));
+        sync.sent(now, 2);
+
+        // Without a pending frame, there is nothing to flush.
+        sync.flushed();
+        assert_eq!(sync.due(now, RTT), None);
+    }
+
+    #[test]
+    fn lost() {
+        let now = Instant::now();
+
+        let mut sync = WindowSync::new();
+        sync.moved(1);
+        sync.sent(now, 1);
+
+        sync.moved(2);
+        sync.sent(now, 2);
+
+        // The first frame was superseded.
+        sync.lost(1);
+        assert!(!sync.is_pending());
+
+        sync.lost(2);
+        assert_eq!(sync.due(now + RTT, RTT), Some(2));
+    }
+}
@@ -850,6 +850,37 @@ pub extern fn quiche_conn_stream_recv(
     out_len as ssize_t
 }
 
+#[no_mangle]
+pub extern fn quiche_conn_stream_recv_out_of_order(
+    conn: &mut Connection, stream_id: u64, out: *mut u8, out_len: size_t,
+    off: &mut u64, fin: &mut bool, out_error_code: &mut u64,
+) -> ssize_t {
+    if out_len > <ssize_t>::MAX as usize {
+        panic!("The provided buffer is too large");
+    }
+
+    let out = unsafe { slice::from_raw_parts_mut(out, out_len) };
+
+    let (out_len, out_off, out_fin) =
+        match conn.stream_recv_out_of_order(stream_id, out) {
+            Ok(v) => v,
+
+            Err(e) => {
+                match e {
+                    Error::StreamReset(error) => *out_error_code = error,
+                    Error::StreamStopped(error) => *out_error_code = error,
+                    _ => {},
+                }
+                return e.to_c();
+            },
+        };
+
+    *off = out_off;
+    *fin = out_fin;
+
+    out_len as ssize_t
+}
+
 #[no_mangle]
 pub extern fn quiche_conn_stream_send(
     conn: &mut Connection, stream_id: u64, buf: *const u8, buf_len: size_t,
@@ -1308,6 +1339,8 @@ pub struct PathStats {
     stream_retrans_bytes: u64,
     pmtu: usize,
     delivery_rate: u64,
+    data_delivery_rate: u64,
+    max_bandwidth: u64,
 }
 
 #[no_mangle]
@@ -1335,6 +1368,8 @@ pub extern fn quiche_conn_path_stats(
     out.stream_retrans_bytes = stats.stream_retrans_bytes;
     out.pmtu = stats.pmtu;
     out.delivery_rate = stats.delivery_rate;
+    out.data_delivery_rate = stats.data_delivery_rate;
+    out.max_bandwidth = stats.max_bandwidth;
 
     0
 }
@@ -1676,6 +1711,8 @@ pub extern fn quiche_path_event_type(ev: &PathEvent) -> u32 {
         PathEvent::ReusedSourceConnectionId { .. } => 4,
 
         PathEvent::PeerMigrated { .. } => 5,
+
+        PathEvent::RedundancyPathDown { .. } => 6,
     }
 }
 
@@ -1781,6 +1818,22 @@ pub extern fn quiche_path_event_peer_migrated(
     }
 }
 
+#[no_mangle]
+pub extern fn quiche_path_event_redundancy_path_down(
+    ev: &PathEvent, local_addr: &mut sockaddr_storage,
+    local_addr_len: &mut socklen_t, peer_addr: &mut sockaddr_storage,
+    peer_addr_len: &mut socklen_t,
+) {
+    match ev {
+        PathEvent::RedundancyPathDown(local, peer) => {
+            *local_addr_len = std_addr_to_c(local, local_addr);
+            *peer_addr_len = std_addr_to_c(peer, peer_addr);
+        },
+
+        _ => unreachable!(),
+    }
+}
+
 #[no_mangle]
 pub extern fn quiche_path_event_free(ev: *mut PathEvent) {
     drop(unsafe { Box::from_raw(ev) });
@@ -24,6 +24,7 @@
 // NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
 // SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use networkcoding::source_symbol_metadata_from_u64;
 use networkcoding::source_symbol_metadata_to_u64;
 use networkcoding::Decoder;
 use networkcoding::RepairSymbol;
@@ -34,6 +35,10 @@ use std::convert::TryInto;
 use crate::Error;
 use crate::Result;
 
+use crate::fec::decoder_stats::FECDecoderStats;
+use crate::fec::fec_scheduler::FECSchedulerAlgorithm;
+use crate::fec::hint::FECHint;
+use crate::fec::repair_symbol::RepairSymbolHeader;
 use crate::packet;
 use crate::ranges;
 use crate::stream;
@@ -80,6 +85,11 @@ pub enum Frame {
         ecn_counts: Option<EcnCounts>,
     },
 
+    ACKHeader {
+        largest_acked: u64,
+        length: usize,
+    },
+
     ResetStream {
         stream_id: u64,
         error_code: u64,
@@ -192,20 +202,51 @@ pub enum Frame {
 
     Repair {
         repair_symbol: RepairSymbol,
+        // Whether the varint encoding of the header is used.
+        compact: bool,
     },
 
     SourceSymbolHeader {
         metadata: SourceSymbolMetadata,
         recovered: bool,
+        compact: bool,
     },
 
     SourceSymbol {
         source_symbol: SourceSymbol,
+        compact: bool,
     },
 
     SourceSymbolACK {
         ranges: ranges::RangeSet,
     },
+
+    WindowSync {
+        first_symbol: u64,
+    },
+
+    FECRequest {
+        enable: bool,
+        scheduler: Option<FECSchedulerAlgorithm>,
+    },
+
+    FECHint {
+        hints: Vec<FECHint>,
+    },
+
+    RateHint {
+        // In parts per million.
+        residual_loss: u64,
+    },
+
+    FECStats {
+        stats: FECDecoderStats,
+    },
+
+    StreamUnreliable {
+        stream_id: u64,
+        unreliable: bool,
+    },
 }
 
 impl Frame {
@@ -357,13 +398,21 @@ impl Frame {
                 let (read, repair_symbol) =
                     nc_decoder.read_repair_symbol(b.to_vec().as_slice())?;
                 b.skip(read)?;
-                Frame::Repair { repair_symbol }
+                Frame::Repair {
+                    repair_symbol,
+                    compact: false,
+                }
             },
-            0x33 => {
+            0x33 | 0x38 => {
                 let symbol_size = nc_decoder.symbol_size();
-                let (read, source_symbol_metadata) =
-                    nc_decoder.read_source_symbol_metadata(b.as_ref())?;
-                b.skip(read)?;
+                let source_symbol_metadata = if frame_type == 0x38 {
+                    source_symbol_metadata_from_u64(b.get_varint()?)
+                } else {
+                    let (read, metadata) =
+                        nc_decoder.read_source_symbol_metadata(b.as_ref())?;
+                    b.skip(read)?;
+                    metadata
+                };
                 let mut source_symbol_data = vec![0; symbol_size];
                 // copy the remaining payload but be careful to place padding at
                 // the start of the symbol if the remaining paylaod does not match
@@ -375,10 +424,31 @@ impl Frame {
                         source_symbol_metadata,
                         source_symbol_data,
                     ),
+                    compact: frame_type == 0x38,
                 }
             },
             0x34 => parse_source_symbol_ack_frame(b)?,
 
+            0x35 => Frame::WindowSync {
+                first_symbol: b.get_varint()?,
+            },
+
+            0x36 => parse_fec_request_frame(b)?,
+
+            0x37 => parse_compact_repair_frame(b, nc_decoder)?,
+
+            0x39 => Frame::FECHint {
+                hints: crate::fec::hint::parse_hints(b)?,
+            },
+
+            0x3a => Frame::RateHint {
+                residual_loss: b.get_varint()?,
+            },
+
+            0x3b => parse_fec_stats_frame(b)?,
+
+            0x3c => parse_stream_unreliable_frame(b)?,
+
             _ => return Err(Error::InvalidFrame),
         };
 
@@ -396,6 +466,19 @@ impl Frame {
             (packet::Type::ZeroRTT, Frame::RetireConnectionId { .. }) => false,
             (packet::Type::ZeroRTT, Frame::ConnectionClose { .. }) => false,
 
+            // FEC frames are only sent once the handshake is done, as the
+            // FEC state is negotiated with the transport parameters.
+            (packet::Type::ZeroRTT, Frame::Repair { .. }) => false,
+            (packet::Type::ZeroRTT, Frame::SourceSymbolHeader { .. }) => false,
+            (packet::Type::ZeroRTT, Frame::SourceSymbol { .. }) => false,
+            (packet::Type::ZeroRTT, Frame::SourceSymbolACK { .. }) => false,
+            (packet::Type::ZeroRTT, Frame::WindowSync { .. }) => false,
+            (packet::Type::ZeroRTT, Frame::FECRequest { .. }) => false,
+            (packet::Type::ZeroRTT, Frame::FECHint { .. }) => false,
+            (packet::Type::ZeroRTT, Frame::RateHint { .. }) => false,
+            (packet::Type::ZeroRTT, Frame::FECStats { .. }) => false,
+            (packet::Type::ZeroRTT, Frame::StreamUnreliable { .. }) => false,
+
             // ACK, CRYPTO and CONNECTION_CLOSE can be sent on all other packet
             // types.
             (_, Frame::ACK { .. }) => true,
@@ -475,6 +558,8 @@ impl Frame {
                 }
             },
 
+            Frame::ACKHeader { .. } => (),
+
             Frame::ResetStream {
                 stream_id,
                 error_code,
@@ -641,10 +726,35 @@ impl Frame {
             },
 
             Frame::DatagramHeader { .. } => (),
-            Frame::Repair { repair_symbol } => {
+            Frame::Repair {
+                repair_symbol,
+                compact: false,
+            } => {
                 b.put_varint(0x32)?;
                 b.put_bytes(repair_symbol.get())?;
             },
+            Frame::Repair {
+                repair_symbol,
+                compact: true,
+            } => {
+                let (hdr, data) =
+                    crate::fec::repair_symbol::header(repair_symbol)
+                        .ok_or(Error::InvalidFrame)?;
+
+                b.put_varint(0x37)?;
+                b.put_varint(hdr.first_id)?;
+                b.put_varint(hdr.n_protected as u64)?;
+                b.put_varint(hdr.seq)?;
+                b.put_bytes(data)?;
+            },
+            Frame::SourceSymbolHeader {
+                metadata,
+                compact: true,
+                ..
+            } => {
+                b.put_varint(0x38)?;
+                b.put_varint(source_symbol_metadata_to_u64(*metadata))?;
+            },
             Frame::SourceSymbolHeader { metadata, .. } => {
                 // the source symbol frame only writes its metadata and we expect
                 // next protected frames to be written afterwards
@@ -655,15 +765,25 @@ impl Frame {
                 b.put_varint(0x33)?;
                 b.put_bytes(metadata)?;
             },
-            Frame::SourceSymbol { source_symbol } => {
+            Frame::SourceSymbol {
+                source_symbol,
+                compact,
+            } => {
                 // the source symbol frame only writes its metadata and we expect
                 // next protected frames to be written afterwards
                 // This is weird, the best would be to wrap the protected frames
                 // inside the source symbol frame but we would
                 // loose some view on what the packet contains and it would
                 // require many changes to recover that
-                b.put_varint(0x33)?;
-                b.put_bytes(&source_symbol.metadata())?;
+                if *compact {
+                    b.put_varint(0x38)?;
+                    b.put_varint(source_symbol_metadata_to_u64(
+                        source_symbol.metadata(),
+                    ))?;
+                } else {
+                    b.put_varint(0x33)?;
+                    b.put_bytes(&source_symbol.metadata())?;
+                }
                 b.put_bytes(source_symbol.get())?;
             },
             Frame::SourceSymbolACK { ranges } => {
@@ -690,6 +810,55 @@ impl Frame {
                     smallest_ack = block.start;
                 }
             },
+
+            Frame::WindowSync { first_symbol } => {
+                b.put_varint(0x35)?;
+
+                b.put_varint(*first_symbol)?;
+            },
+
+            Frame::FECRequest { enable, scheduler } => {
+                b.put_varint(0x36)?;
+
+                b.put_varint(u64::from(*enable))?;
+                b.put_varint(scheduler.map_or(0, |s| s as u64 + 1))?;
+            },
+
+            Frame::FECHint { hints } => {
+                b.put_varint(0x39)?;
+
+                b.put_varint(hints.len() as u64)?;
+
+                for hint in hints {
+                    hint.to_bytes(b)?;
+                }
+            },
+
+            Frame::RateHint { residual_loss } => {
+                b.put_varint(0x3a)?;
+
+                b.put_varint(*residual_loss)?;
+            },
+
+            Frame::FECStats { stats } => {
+                b.put_varint(0x3b)?;
+
+                b.put_varint(stats.source_symbols_received)?;
+                b.put_varint(stats.repair_received)?;
+                b.put_varint(stats.repair_useless)?;
+                b.put_varint(stats.repair_rank_deficient)?;
+                b.put_varint(stats.recovered)?;
+            },
+
+            Frame::StreamUnreliable {
+                stream_id,
+                unreliable,
+            } => {
+                b.put_varint(0x3c)?;
+
+                b.put_varint(*stream_id)?;
+                b.put_varint(u64::from(*unreliable))?;
+            },
         }
 
         Ok(before - b.cap())
@@ -738,6 +907,8 @@ impl Frame {
                 len
             },
 
+            Frame::ACKHeader { length, .. } => *length,
+
             Frame::ResetStream {
                 stream_id,
                 error_code,
@@ -906,19 +1077,37 @@ impl Frame {
                 *length // data
             },
 
-            Frame::Repair { repair_symbol } => {
-                1 + // frame_type
-                repair_symbol.wire_len()
+            Frame::Repair {
+                repair_symbol,
+                compact,
+            } => match crate::fec::repair_symbol::header(repair_symbol) {
+                Some((hdr, data)) if *compact => {
+                    1 + // frame_type
+                    octets::varint_len(hdr.first_id) + // first source symbol
+                    octets::varint_len(hdr.n_protected as u64) + // protected
+                    octets::varint_len(hdr.seq) + // sequence number
+                    data.len()
+                },
+
+                _ => {
+                    1 + // frame_type
+                    repair_symbol.wire_len()
+                },
             },
 
-            Frame::SourceSymbolHeader { metadata, .. } => {
+            Frame::SourceSymbolHeader {
+                metadata, compact, ..
+            } => {
                 1 + // frame type
-                metadata.len() // metadata
+                metadata_wire_len(metadata, *compact) // metadata
             },
 
-            Frame::SourceSymbol { source_symbol } => {
+            Frame::SourceSymbol {
+                source_symbol,
+                compact,
+            } => {
                 1 + // frame type
-                source_symbol.metadata().len() + // metadata
+                metadata_wire_len(&source_symbol.metadata(), *compact) + // metadata
                 source_symbol.get().len()
             },
             Frame::SourceSymbolACK { ranges } => {
@@ -945,6 +1134,43 @@ impl Frame {
                 }
                 len
             },
+
+            Frame::WindowSync { first_symbol } => {
+                1 + // frame type
+                octets::varint_len(*first_symbol) // first_symbol
+            },
+
+            Frame::FECRequest { scheduler, .. } => {
+                1 + // frame type
+                1 + // enable
+                octets::varint_len(scheduler.map_or(0, |s| s as u64 + 1)) // scheduler
+            },
+
+            Frame::FECHint { hints } => {
+                1 + // frame type
+                octets::varint_len(hints.len() as u64) + // count
+                hints.iter().map(|h| h.wire_len()).sum::<usize>() // hints
+            },
+
+            Frame::RateHint { residual_loss } => {
+                1 + // frame type
+                octets::varint_len(*residual_loss) // residual_loss
+            },
+
+            Frame::FECStats { stats } => {
+                1 + // frame type
+                octets::varint_len(stats.source_symbols_received) +
+                octets::varint_len(stats.repair_received) +
+                octets::varint_len(stats.repair_useless) +
+                octets::varint_len(stats.repair_rank_deficient) +
+                octets::varint_len(stats.recovered)
+            },
+
+            Frame::StreamUnreliable { stream_id, .. } => {
+                1 + // frame type
+                octets::varint_len(*stream_id) + // stream_id
+                1 // unreliable
+            },
         }
     }
 
@@ -954,10 +1180,12 @@ impl Frame {
             self,
             Frame::Padding { .. } |
                 Frame::ACK { .. } |
+                Frame::ACKHeader { .. } |
                 Frame::ApplicationClose { .. } |
                 Frame::SourceSymbol { .. } |
                 Frame::SourceSymbolHeader { .. } |
                 Frame::SourceSymbolACK { .. } |
+                Frame::RateHint { .. } |
                 Frame::ConnectionClose { .. }
         )
     }
@@ -1015,6 +1243,18 @@ impl Frame {
                 }
             },
 
+            Frame::ACKHeader { largest_acked, .. } => QuicFrame::Ack {
+                ack_delay: None,
+                acked_ranges: Some(AckedRanges::Single(vec![vec![
+                    *largest_acked,
+                ]])),
+                ect1: None,
+                ect0: None,
+                ce: None,
+                length: None,
+                payload_length: None,
+            },
+
             Frame::ResetStream {
                 stream_id,
                 error_code,
@@ -1173,30 +1413,127 @@ impl Frame {
                 raw: None,
             },
 
-            Frame::Repair { .. } => QuicFrame::Unknown {
-                raw_frame_type: 0x32,
-                raw: None,
-                frame_type_value: None,
+            Frame::Repair { repair_symbol, .. } => {
+                let protected =
+                    crate::fec::repair_symbol::protected_symbols(repair_symbol);
+
+                QuicFrame::Repair {
+                    length: repair_symbol.wire_len() as u64,
+                    first_protected_symbol: protected.as_ref().map(|r| r.start),
+                    protected_symbols: protected.map(|r| r.end - r.start),
+                    raw: None,
+                }
             },
 
-            Frame::SourceSymbolHeader { .. } => QuicFrame::Unknown {
-                raw_frame_type: 0x33,
+            Frame::SourceSymbolHeader {
+                metadata,
+                recovered,
+                ..
+            } => QuicFrame::SourceSymbolHeader {
+                symbol_id: source_symbol_metadata_to_u64(*metadata),
+                recovered: Some(*recovered),
                 raw: None,
-                frame_type_value: None,
             },
 
-            Frame::SourceSymbol { .. } => QuicFrame::Unknown {
-                raw_frame_type: 0x33,
-                raw: None,
-                frame_type_value: None,
+            Frame::SourceSymbol { source_symbol, .. } =>
+                QuicFrame::SourceSymbolHeader {
+                    symbol_id: source_symbol_metadata_to_u64(
+                        source_symbol.metadata(),
+                    ),
+                    recovered: None,
+                    raw: None,
+                },
+
+            Frame::SourceSymbolACK { ranges } => QuicFrame::SourceSymbolAck {
+                recovered_ranges: Some(AckedRanges::Double(
+                    ranges.iter().map(|r| (r.start, r.end - 1)).collect(),
+                )),
             },
-            Frame::SourceSymbolACK { .. } => QuicFrame::Unknown {
-                raw_frame_type: 0x34,
-                raw: None,
-                frame_type_value: None,
+
+            Frame::WindowSync { first_symbol } => QuicFrame::WindowSync {
+                first_symbol: *first_symbol,
+            },
+
+            Frame::FECRequest { enable, scheduler } => QuicFrame::FecRequest {
+                enable: *enable,
+                scheduler: scheduler.map(|s| s as u64),
+            },
+
+            Frame::FECHint { hints } => QuicFrame::FecHint {
+                burst_size: hints.iter().find_map(|h| match h {
+                    FECHint::BurstSize(v) => Some(*v),
+
+                    _ => None,
+                }),
+            },
+
+            Frame::RateHint { residual_loss } => QuicFrame::RateHint {
+                residual_loss: *residual_loss,
+            },
+
+            Frame::FECStats { stats } => QuicFrame::FecStats {
+                source_symbols_received: stats.source_symbols_received,
+                repair_received: stats.repair_received,
+                repair_useless: stats.repair_useless,
+                repair_rank_deficient: stats.repair_rank_deficient,
+                recovered: stats.recovered,
+            },
+
+            Frame::StreamUnreliable {
+                stream_id,
+                unreliable,
+            } => QuicFrame::StreamUnreliable {
+                stream_id: *stream_id,
+                unreliable: *unreliable,
             },
         }
     }
+
+    /// Converts the frame to qlog, with the payload of the FEC symbols
+    /// recorded according to `capture`.
+    ///
+    /// Returns `None` when the frame is not recorded at all.
+    #[cfg(feature = "qlog")]
+    pub fn to_qlog_with_payload_capture(
+        &self, capture: crate::QlogPayloadCapture,
+    ) -> Option<QuicFrame> {
+        use crate::QlogPayloadCapture;
+
+        let is_symbol = matches!(
+            self,
+            Frame::Repair { .. } |
+                Frame::SourceSymbolHeader { .. } |
+                Frame::SourceSymbol { .. }
+        );
+
+        if is_symbol && capture == QlogPayloadCapture::None {
+            return None;
+        }
+
+        let mut frame = self.to_qlog();
+
+        if capture == QlogPayloadCapture::FullPayload {
+            let payload = match self {
+                Frame::Repair { repair_symbol, .. } =>
+                    Some(repair_symbol.get()),
+
+                Frame::SourceSymbol { source_symbol, .. } =>
+                    Some(source_symbol.get()),
+
+                _ => None,
+            };
+
+            match &mut frame {
+                QuicFrame::Repair { raw, .. } |
+                QuicFrame::SourceSymbolHeader { raw, .. } =>
+                    *raw = payload.map(|p| format!("{}", qlog::HexSlice::new(p))),
+
+                _ => (),
+            }
+        }
+
+        Some(frame)
+    }
 }
 
 impl std::fmt::Debug for Frame {
@@ -1221,6 +1558,10 @@ impl std::fmt::Debug for Frame {
                 )?;
             },
 
+            Frame::ACKHeader { largest_acked, length } => {
+                write!(f, "ACK largest={largest_acked} len={length}")?;
+            },
+
             Frame::ResetStream {
                 stream_id,
                 error_code,
@@ -1363,7 +1704,7 @@ impl std::fmt::Debug for Frame {
                 write!(f, "DATAGRAM len={length}")?;
             },
 
-            Frame::Repair { repair_symbol } => {
+            Frame::Repair { repair_symbol, .. } => {
                 write!(f, "REPAIR len={}", repair_symbol.wire_len())?;
             },
 
@@ -1376,7 +1717,7 @@ impl std::fmt::Debug for Frame {
                 )?;
             },
 
-            Frame::SourceSymbol { source_symbol } => {
+            Frame::SourceSymbol { source_symbol, .. } => {
                 write!(
                     f,
                     "SOURCE_SYMBOL, metadata={} len={}",
@@ -1387,6 +1728,44 @@ impl std::fmt::Debug for Frame {
             Frame::SourceSymbolACK { ranges } => {
                 write!(f, "SOURCE_SYMBOL_ACK blocks={:?}", ranges)?;
             },
+
+            Frame::WindowSync { first_symbol } => {
+                write!(f, "WINDOW_SYNC first_symbol={first_symbol}")?;
+            },
+
+            Frame::FECRequest { enable, scheduler } => {
+                write!(f, "FEC_REQUEST enable={enable} scheduler={scheduler:?}")?;
+            },
+
+            Frame::FECHint { hints } => {
+                write!(f, "FEC_HINT hints={hints:?}")?;
+            },
+
+            Frame::RateHint { residual_loss } => {
+                write!(f, "RATE_HINT residual_loss={residual_loss}")?;
+            },
+
+            Frame::FECStats { stats } => {
+                write!(
+                    f,
+                    "FEC_STATS source_symbols_received={} repair_received={} repair_useless={} repair_rank_deficient={} recovered={}",
+                    stats.source_symbols_received,
+                    stats.repair_received,
+                    stats.repair_useless,
+                    stats.repair_rank_deficient,
+                    stats.recovered,
+                )?;
+            },
+
+            Frame::StreamUnreliable {
+                stream_id,
+                unreliable,
+            } => {
+                write!(
+                    f,
+                    "STREAM_UNRELIABLE stream={stream_id} unreliable={unreliable}"
+                )?;
+            },
         }
 
         Ok(())
@@ -1486,6 +1865,88 @@ fn parse_source_symbol_ack_frame(b: &mut octets::Octets) -> Result<Frame> {
     Ok(Frame::SourceSymbolACK { ranges })
 }
 
+fn parse_fec_request_frame(b: &mut octets::Octets) -> Result<Frame> {
+    let enable = match b.get_varint()? {
+        0 => false,
+        1 => true,
+
+        _ => return Err(Error::InvalidFrame),
+    };
+
+    let scheduler = match b.get_varint()? {
+        0 => None,
+
+        v => Some(
+            FECSchedulerAlgorithm::from_u64(v - 1).ok_or(Error::InvalidFrame)?,
+        ),
+    };
+
+    Ok(Frame::FECRequest { enable, scheduler })
+}
+
+fn parse_fec_stats_frame(b: &mut octets::Octets) -> Result<Frame> {
+    let stats = FECDecoderStats {
+        source_symbols_received: b.get_varint()?,
+        repair_received: b.get_varint()?,
+        repair_useless: b.get_varint()?,
+        repair_rank_deficient: b.get_varint()?,
+        recovered: b.get_varint()?,
+    };
+
+    Ok(Frame::FECStats { stats })
+}
+
+fn parse_stream_unreliable_frame(b: &mut octets::Octets) -> Result<Frame> {
+    let stream_id = b.get_varint()?;
+
+    let unreliable = match b.get_varint()? {
+        0 => false,
+        1 => true,
+
+        _ => return Err(Error::InvalidFrame),
+    };
+
+    Ok(Frame::StreamUnreliable {
+        stream_id,
+        unreliable,
+    })
+}
+
+/// Parses a REPAIR frame with a varint encoded header, and rebuilds the repair
+/// symbol in the fixed-size encoding of the codec.
+fn parse_compact_repair_frame(
+    b: &mut octets::Octets, nc_decoder: &Decoder,
+) -> Result<Frame> {
+    let first_id = b.get_varint()?;
+    let n_protected: u32 =
+        b.get_varint()?.try_into().map_err(|_| Error::InvalidFrame)?;
+    let seq = b.get_varint()?;
+    let data = b.get_bytes(nc_decoder.symbol_size())?;
+
+    let hdr = RepairSymbolHeader {
+        first_id,
+        n_protected,
+        seq,
+    };
+
+    let (_, repair_symbol) =
+        nc_decoder.read_repair_symbol(&hdr.to_bytes(data.as_ref()))?;
+
+    Ok(Frame::Repair {
+        repair_symbol,
+        compact: true,
+    })
+}
+
+/// Returns the length of the encoding of a source symbol ID.
+fn metadata_wire_len(metadata: &SourceSymbolMetadata, compact: bool) -> usize {
+    if compact {
+        octets::varint_len(source_symbol_metadata_to_u64(*metadata))
+    } else {
+        metadata.len()
+    }
+}
+
 pub fn encode_crypto_header(
     offset: u64, length: u64, b: &mut octets::OctetsMut,
 ) -> Result<()> {
@@ -2695,4 +3156,443 @@ mod tests {
 
         assert_eq!(frame_data, data);
     }
+
+    #[test]
+    fn window_sync() {
+        let mut d = [42; 128];
+
+        let frame = Frame::WindowSync { first_symbol: 1234 };
+
+        let wire_len = {
+            let mut b = octets::OctetsMut::with_slice(&mut d);
+            frame.to_bytes(&mut b).unwrap()
+        };
+
+        assert_eq!(wire_len, 3);
+        assert_eq!(frame.wire_len(), wire_len);
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert_eq!(
+            Frame::from_bytes(&mut b, packet::Type::Short, &get_decoder()),
+            Ok(frame)
+        );
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert!(
+            Frame::from_bytes(&mut b, packet::Type::Initial, &get_decoder())
+                .is_err()
+        );
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert!(
+            Frame::from_bytes(&mut b, packet::Type::ZeroRTT, &get_decoder())
+                .is_err()
+        );
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert!(Frame::from_bytes(
+            &mut b,
+            packet::Type::Handshake,
+            &get_decoder()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn repair_only_in_short_packets() {
+        let decoder = get_decoder();
+
+        let data = RepairSymbolHeader {
+            first_id: 1000,
+            n_protected: 4,
+            seq: 0,
+        }
+        .to_bytes(&vec![0; decoder.symbol_size()]);
+
+        let (_, repair_symbol) = decoder.read_repair_symbol(&data).unwrap();
+
+        let frame = Frame::Repair {
+            repair_symbol,
+            compact: false,
+        };
+
+        let mut d = [42; 2048];
+
+        let wire_len = {
+            let mut b = octets::OctetsMut::with_slice(&mut d);
+            frame.to_bytes(&mut b).unwrap()
+        };
+
+        assert_eq!(frame.wire_len(), wire_len);
+
+        let mut b = octets::Octets::with_slice(&d[..wire_len]);
+        assert!(Frame::from_bytes(&mut b, packet::Type::Short, &decoder).is_ok());
+
+        for pkt_type in [
+            packet::Type::Initial,
+            packet::Type::ZeroRTT,
+            packet::Type::Handshake,
+        ] {
+            let mut b = octets::Octets::with_slice(&d[..wire_len]);
+            assert_eq!(
+                Frame::from_bytes(&mut b, pkt_type, &decoder),
+                Err(Error::InvalidPacket)
+            );
+        }
+    }
+
+    #[test]
+    fn compact_symbols() {
+        let decoder = get_decoder();
+
+        let data = RepairSymbolHeader {
+            first_id: 1000,
+            n_protected: 4,
+            seq: 7,
+        }
+        .to_bytes(&vec![3; decoder.symbol_size()]);
+
+        let (_, repair_symbol) = decoder.read_repair_symbol(&data).unwrap();
+
+        let legacy = Frame::Repair {
+            repair_symbol: repair_symbol.clone(),
+            compact: false,
+        };
+
+        let frame = Frame::Repair {
+            repair_symbol,
+            compact: true,
+        };
+
+        let mut d = [42; 2048];
+
+        let wire_len = {
+            let mut b = octets::OctetsMut::with_slice(&mut d);
+            frame.to_bytes(&mut b).unwrap()
+        };
+
+        assert_eq!(frame.wire_len(), wire_len);
+        assert_eq!(legacy.wire_len() - wire_len, 20 - 2 - 1 - 1);
+        assert_eq!(d[0], 0x37);
+
+        let mut b = octets::Octets::with_slice(&d[..wire_len]);
+        assert_eq!(
+            Frame::from_bytes(&mut b, packet::Type::Short, &decoder),
+            Ok(frame)
+        );
+
+        let frame = Frame::SourceSymbolHeader {
+            metadata: source_symbol_metadata_from_u64(1000),
+            recovered: false,
+            compact: true,
+        };
+
+        let wire_len = {
+            let mut b = octets::OctetsMut::with_slice(&mut d);
+            frame.to_bytes(&mut b).unwrap()
+        };
+
+        assert_eq!(frame.wire_len(), wire_len);
+        assert_eq!(&d[..wire_len], [0x38, 0x43, 0xe8]);
+
+        // The protected frames follow the header.
+        d[wire_len] = 0x01;
+
+        let mut b = octets::Octets::with_slice(&d[..wire_len + 1]);
+        match Frame::from_bytes(&mut b, packet::Type::Short, &decoder) {
+            Ok(Frame::SourceSymbol {
+                source_symbol,
+                compact: true,
+            }) => {
+                assert_eq!(
+                    source_symbol_metadata_to_u64(source_symbol.metadata()),
+                    1000
+                );
+                assert_eq!(source_symbol.get().last(), Some(&0x01));
+            },
+
+            f => panic!("unexpected frame {:?}", f),
+        }
+    }
+
+    #[test]
+    fn fec_request() {
+        let mut d = [42; 128];
+
+        let frame = Frame::FECRequest {
+            enable: true,
+            scheduler: Some(FECSchedulerAlgorithm::BurstsOnly),
+        };
+
+        let wire_len = {
+            let mut b = octets::OctetsMut::with_slice(&mut d);
+            frame.to_bytes(&mut b).unwrap()
+        };
+
+        assert_eq!(wire_len, 3);
+        assert_eq!(frame.wire_len(), wire_len);
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert_eq!(
+            Frame::from_bytes(&mut b, packet::Type::Short, &get_decoder()),
+            Ok(frame)
+        );
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert!(
+            Frame::from_bytes(&mut b, packet::Type::Initial, &get_decoder())
+                .is_err()
+        );
+
+        // Unknown scheduler.
+        let d = [0x36, 0x01, 0x20];
+        let mut b = octets::Octets::with_slice(&d);
+        assert_eq!(
+            Frame::from_bytes(&mut b, packet::Type::Short, &get_decoder()),
+            Err(Error::InvalidFrame)
+        );
+    }
+
+    #[test]
+    fn fec_hint() {
+        let mut d = [42; 128];
+
+        let frame = Frame::FECHint {
+            hints: vec![FECHint::BurstSize(3000)],
+        };
+
+        let wire_len = {
+            let mut b = octets::OctetsMut::with_slice(&mut d);
+            frame.to_bytes(&mut b).unwrap()
+        };
+
+        assert_eq!(wire_len, 6);
+        assert_eq!(frame.wire_len(), wire_len);
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert_eq!(
+            Frame::from_bytes(&mut b, packet::Type::Short, &get_decoder()),
+            Ok(frame)
+        );
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert!(
+            Frame::from_bytes(&mut b, packet::Type::ZeroRTT, &get_decoder())
+                .is_err()
+        );
+
+        // GREASE hints are skipped.
+        let frame = Frame::FECHint {
+            hints: vec![FECHint::Grease(27), FECHint::BurstSize(3000)],
+        };
+
+        let wire_len = {
+            let mut b = octets::OctetsMut::with_slice(&mut d);
+            frame.to_bytes(&mut b).unwrap()
+        };
+
+        assert_eq!(frame.wire_len(), wire_len);
+
+        let mut b = octets::Octets::with_slice(&d[..wire_len]);
+        assert_eq!(
+            Frame::from_bytes(&mut b, packet::Type::Short, &get_decoder()),
+            Ok(Frame::FECHint {
+                hints: vec![FECHint::BurstSize(3000)],
+            })
+        );
+        assert_eq!(b.cap(), 0);
+    }
+
+    #[test]
+    fn rate_hint() {
+        let mut d = [42; 128];
+
+        let frame = Frame::RateHint {
+            residual_loss: 1000,
+        };
+
+        let wire_len = {
+            let mut b = octets::OctetsMut::with_slice(&mut d);
+            frame.to_bytes(&mut b).unwrap()
+        };
+
+        assert_eq!(wire_len, 3);
+        assert_eq!(frame.wire_len(), wire_len);
+        assert!(!frame.ack_eliciting());
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert_eq!(
+            Frame::from_bytes(&mut b, packet::Type::Short, &get_decoder()),
+            Ok(frame)
+        );
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert!(
+            Frame::from_bytes(&mut b, packet::Type::ZeroRTT, &get_decoder())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn fec_stats() {
+        let mut d = [42; 128];
+
+        let frame = Frame::FECStats {
+            stats: FECDecoderStats {
+                source_symbols_received: 1000,
+                repair_received: 100,
+                repair_useless: 20,
+                repair_rank_deficient: 3,
+                recovered: 15,
+            },
+        };
+
+        let wire_len = {
+            let mut b = octets::OctetsMut::with_slice(&mut d);
+            frame.to_bytes(&mut b).unwrap()
+        };
+
+        assert_eq!(wire_len, 8);
+        assert_eq!(frame.wire_len(), wire_len);
+        assert!(frame.ack_eliciting());
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert_eq!(
+            Frame::from_bytes(&mut b, packet::Type::Short, &get_decoder()),
+            Ok(frame)
+        );
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert!(
+            Frame::from_bytes(&mut b, packet::Type::ZeroRTT, &get_decoder())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn stream_unreliable() {
+        let mut d = [42; 128];
+
+        let frame = Frame::StreamUnreliable {
+            stream_id: 4,
+            unreliable: true,
+        };
+
+        let wire_len = {
+            let mut b = octets::OctetsMut::with_slice(&mut d);
+            frame.to_bytes(&mut b).unwrap()
+        };
+
+        assert_eq!(wire_len, 3);
+        assert_eq!(frame.wire_len(), wire_len);
+        assert!(frame.ack_eliciting());
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert_eq!(
+            Frame::from_bytes(&mut b, packet::Type::Short, &get_decoder()),
+            Ok(frame)
+        );
+
+        let mut b = octets::Octets::with_slice(&d);
+        assert!(
+            Frame::from_bytes(&mut b, packet::Type::ZeroRTT, &get_decoder())
+                .is_err()
+        );
+
+        // Invalid mode.
+        let d = [0x3c, 0x04, 0x02];
+        let mut b = octets::Octets::with_slice(&d);
+        assert_eq!(
+            Frame::from_bytes(&mut b, packet::Type::Short, &get_decoder()),
+            Err(Error::InvalidFrame)
+        );
+    }
+
+    #[cfg(feature = "qlog")]
+    #[test]
+    fn fec_frames_to_qlog() {
+        let frame = Frame::WindowSync { first_symbol: 1234 };
+        assert_eq!(frame.to_qlog(), QuicFrame::WindowSync {
+            first_symbol: 1234
+        });
+
+        let mut ranges = ranges::RangeSet::default();
+        ranges.insert(3..5);
+        ranges.insert(8..9);
+
+        let frame = Frame::SourceSymbolACK { ranges };
+        assert_eq!(frame.to_qlog(), QuicFrame::SourceSymbolAck {
+            recovered_ranges: Some(AckedRanges::Double(vec![(3, 4), (8, 8)])),
+        });
+
+        let data = RepairSymbolHeader {
+            first_id: 10,
+            n_protected: 5,
+            seq: 0,
+        }
+        .to_bytes(&[0; 16]);
+        let repair_symbol = VLCDecoder::new(16, 100)
+            .read_repair_symbol(&data)
+            .unwrap()
+            .1;
+        let length = repair_symbol.wire_len() as u64;
+
+        let frame = Frame::Repair {
+            repair_symbol,
+            compact: false,
+        };
+        assert_eq!(frame.to_qlog(), QuicFrame::Repair {
+            length,
+            first_protected_symbol: Some(10),
+            protected_symbols: Some(5),
+            raw: None,
+        });
+    }
+
+    #[cfg(feature = "qlog")]
+    #[test]
+    fn qlog_payload_capture() {
+        let data = RepairSymbolHeader {
+            first_id: 10,
+            n_protected: 1,
+            seq: 0,
+        }
+        .to_bytes(&[0xab; 4]);
+        let repair_symbol =
+            VLCDecoder::new(4, 100).read_repair_symbol(&data).unwrap().1;
+
+        let frame = Frame::Repair {
+            repair_symbol,
+            compact: false,
+        };
+
+        assert_eq!(
+            frame.to_qlog_with_payload_capture(
+                crate::QlogPayloadCapture::HeadersOnly
+            ),
+            Some(frame.to_qlog())
+        );
+        assert_eq!(
+            frame.to_qlog_with_payload_capture(crate::QlogPayloadCapture::None),
+            None
+        );
+
+        match frame
+            .to_qlog_with_payload_capture(crate::QlogPayloadCapture::FullPayload)
+        {
+            Some(QuicFrame::Repair { raw: Some(raw), .. }) => {
+                assert_eq!(raw, format!("{}", qlog::HexSlice::new(&data)));
+                assert!(raw.ends_with("abababab"));
+            },
+
+            f => panic!("unexpected frame {:?}", f),
+        }
+
+        // Control frames are always recorded.
+        let frame = Frame::WindowSync { first_symbol: 10 };
+        assert_eq!(
+            frame.to_qlog_with_payload_capture(crate::QlogPayloadCapture::None),
+            Some(frame.to_qlog())
+        );
+    }
 }
@@ -569,6 +569,8 @@ pub struct Config {
     /// additional settings are settings that are not part of the H3
     /// settings explicitly handled above
     additional_settings: Option<Vec<(u64, u64)>>,
+    fec_protect_critical_streams: bool,
+    fec_boost_urgent_streams: bool,
 }
 
 impl Config {
@@ -580,6 +582,8 @@ impl Config {
             qpack_blocked_streams: None,
             connect_protocol_enabled: None,
             additional_settings: None,
+            fec_protect_critical_streams: false,
+            fec_boost_urgent_streams: false,
         })
     }
 
@@ -621,6 +625,39 @@ impl Config {
         }
     }
 
+    /// Configures whether the control and QPACK streams are always protected
+    /// by FEC.
+    ///
+    /// When enabled, the packets carrying data of the local control, QPACK
+    /// encoder and QPACK decoder streams are followed by a repair symbol, see
+    /// [`stream_fec_critical()`]. A lost QPACK encoder instruction blocks all
+    /// the header blocks referencing the dynamic table, so these tiny streams
+    /// are where FEC avoids the most head-of-line blocking.
+    ///
+    /// The default value is `false`.
+    ///
+    /// [`stream_fec_critical()`]: ../struct.Connection.html#method.stream_fec_critical
+    pub fn set_fec_protect_critical_streams(&mut self, v: bool) {
+        self.fec_protect_critical_streams = v;
+    }
+
+    /// Configures whether the urgent responses are always protected by FEC.
+    ///
+    /// When enabled, the streams of the non-incremental responses more urgent
+    /// than the default urgency are marked as critical for FEC, see
+    /// [`stream_fec_critical()`]. Such responses are only useful once
+    /// complete, so a single loss delays them entirely. The priority is taken
+    /// from [`send_response_with_priority()`] and, when the `sfv` feature is
+    /// enabled, from the PRIORITY_UPDATE frames received from the client.
+    ///
+    /// The default value is `false`.
+    ///
+    /// [`stream_fec_critical()`]: ../struct.Connection.html#method.stream_fec_critical
+    /// [`send_response_with_priority()`]: struct.Connection.html#method.send_response_with_priority
+    pub fn set_fec_boost_urgent_streams(&mut self, v: bool) {
+        self.fec_boost_urgent_streams = v;
+    }
+
     /// Sets additional HTTP/3 settings.
     ///
     /// The default value is no additional settings.
@@ -832,6 +869,11 @@ impl Priority {
             incremental,
         }
     }
+
+    /// Returns whether a response with this priority is critical for FEC.
+    fn is_fec_critical(&self) -> bool {
+        self.urgency < PRIORITY_URGENCY_DEFAULT && !self.incremental
+    }
 }
 
 #[cfg(feature = "sfv")]
@@ -944,6 +986,9 @@ pub struct Connection {
 
     local_goaway_id: Option<u64>,
     peer_goaway_id: Option<u64>,
+
+    fec_protect_critical_streams: bool,
+    fec_boost_urgent_streams: bool,
 }
 
 impl Connection {
@@ -1006,6 +1051,9 @@ impl Connection {
 
             local_goaway_id: None,
             peer_goaway_id: None,
+
+            fec_protect_critical_streams: config.fec_protect_critical_streams,
+            fec_boost_urgent_streams: config.fec_boost_urgent_streams,
         })
     }
 
@@ -1174,6 +1222,20 @@ impl Connection {
 
         conn.stream_priority(stream_id, urgency, priority.incremental)?;
 
+        self.boost_fec(conn, stream_id, priority)?;
+
+        Ok(())
+    }
+
+    /// Marks the stream of an urgent response as critical for FEC, if
+    /// configured.
+    fn boost_fec(
+        &self, conn: &mut super::Connection, stream_id: u64, priority: &Priority,
+    ) -> Result<()> {
+        if self.fec_boost_urgent_streams {
+            conn.stream_fec_critical(stream_id, priority.is_fec_critical())?;
+        }
+
         Ok(())
     }
 
@@ -1835,6 +1897,10 @@ impl Connection {
             stream::QPACK_ENCODER_STREAM_TYPE_ID |
             stream::QPACK_DECODER_STREAM_TYPE_ID => {
                 conn.stream_priority(stream_id, 0, false)?;
+
+                if self.fec_protect_critical_streams {
+                    conn.stream_fec_critical(stream_id, true)?;
+                }
             },
 
             // TODO: Server push
@@ -2782,6 +2848,17 @@ impl Connection {
                     return Err(Error::Done);
                 }
 
+                // Only streams the client opened already are boosted, as
+                // creating them would count against the stream limits.
+                #[cfg(feature = "sfv")]
+                if conn.streams.get(prioritized_element_id).is_some() {
+                    if let Ok(priority) =
+                        Priority::try_from(priority_field_value.as_slice())
+                    {
+                        self.boost_fec(conn, prioritized_element_id, &priority)?;
+                    }
+                }
+
                 // If the stream did not yet exist, create it and store.
                 let stream =
                     self.streams.entry(prioritized_element_id).or_insert_with(
@@ -4010,6 +4087,177 @@ mod tests {
         assert_eq!(s.send_request(true), Err(Error::FrameUnexpected));
     }
 
+    /// Returns a QUIC configuration sending and receiving FEC, with a
+    /// scheduler never sending repair symbols by itself.
+    fn fec_config() -> crate::Config {
+        let mut config = crate::Config::new(crate::PROTOCOL_VERSION).unwrap();
+        config
+            .load_cert_chain_from_pem_file("examples/cert.crt")
+            .unwrap();
+        config
+            .load_priv_key_from_pem_file("examples/cert.key")
+            .unwrap();
+        config.set_application_protos(&[b"h3"]).unwrap();
+        config.set_initial_max_data(1500);
+        config.set_initial_max_stream_data_bidi_local(150);
+        config.set_initial_max_stream_data_bidi_remote(150);
+        config.set_initial_max_stream_data_uni(150);
+        config.set_initial_max_streams_bidi(5);
+        config.set_initial_max_streams_uni(5);
+        config.verify_peer(false);
+        config.set_fec_scheduler_algorithm(
+            crate::FECSchedulerAlgorithm::NoRedundancy,
+        );
+        config.send_fec(true);
+        config.receive_fec(true);
+        config
+    }
+
+    #[test]
+    /// Control and QPACK streams are repaired with FEC when configured.
+    fn fec_protect_critical_streams() {
+        let mut config = fec_config();
+
+        let mut h3_config = Config::new().unwrap();
+        h3_config.set_fec_protect_critical_streams(true);
+
+        let mut s = Session::with_configs(&mut config, &h3_config).unwrap();
+        s.handshake().unwrap();
+
+        for stream_id in [
+            s.server.control_stream_id,
+            s.server.local_qpack_streams.encoder_stream_id,
+            s.server.local_qpack_streams.decoder_stream_id,
+        ] {
+            let stream = s.pipe.server.streams.get(stream_id.unwrap()).unwrap();
+            assert!(stream.fec_critical);
+        }
+
+        // Request streams are left to the scheduler.
+        let (stream, _) = s.send_request(true).unwrap();
+        assert!(!s.pipe.client.streams.get(stream).unwrap().fec_critical);
+        s.advance().ok();
+
+        // Drop the packet carrying the GOAWAY frame.
+        s.server.send_goaway(&mut s.pipe.server, 4000).unwrap();
+
+        let repair_sent = s.pipe.server.stats().repair_sent;
+        let mut flight = crate::testing::emit_flight(&mut s.pipe.server).unwrap();
+        assert!(s.pipe.server.stats().repair_sent > repair_sent);
+        flight.remove(0);
+
+        crate::testing::process_flight(&mut s.pipe.client, flight).unwrap();
+
+        // The frame is recovered without waiting for a retransmission.
+        assert_eq!(s.poll_client(), Ok((4000, Event::GoAway)));
+    }
+
+    #[test]
+    /// Urgent responses are marked as critical for FEC when configured.
+    fn fec_boost_urgent_streams() {
+        let mut config = fec_config();
+
+        let mut h3_config = Config::new().unwrap();
+        h3_config.set_fec_boost_urgent_streams(true);
+
+        let mut s = Session::with_configs(&mut config, &h3_config).unwrap();
+        s.handshake().unwrap();
+
+        let (stream, req) = s.send_request(true).unwrap();
+
+        let ev_headers = Event::Headers {
+            list: req,
+            has_body: false,
+        };
+
+        assert_eq!(s.poll_server(), Ok((stream, ev_headers)));
+        assert_eq!(s.poll_server(), Ok((stream, Event::Finished)));
+
+        let resp = vec![Header::new(b":status", b"200")];
+
+        s.server
+            .send_response_with_priority(
+                &mut s.pipe.server,
+                stream,
+                &resp,
+                &Priority::new(0, false),
+                false,
+            )
+            .unwrap();
+        assert!(s.pipe.server.streams.get(stream).unwrap().fec_critical);
+
+        // Incremental responses are usable before they complete.
+        let (stream, _) = s.send_request(true).unwrap();
+        s.poll_server().ok();
+        s.poll_server().ok();
+
+        s.server
+            .send_response_with_priority(
+                &mut s.pipe.server,
+                stream,
+                &resp,
+                &Priority::new(0, true),
+                false,
+            )
+            .unwrap();
+        assert!(!s.pipe.server.streams.get(stream).unwrap().fec_critical);
+    }
+
+    #[test]
+    #[cfg(feature = "sfv")]
+    /// PRIORITY_UPDATE frames boost and unboost the FEC protection of the
+    /// responses when configured.
+    fn fec_boost_urgent_streams_priority_update() {
+        let mut config = fec_config();
+
+        let mut h3_config = Config::new().unwrap();
+        h3_config.set_fec_boost_urgent_streams(true);
+
+        let mut s = Session::with_configs(&mut config, &h3_config).unwrap();
+        s.handshake().unwrap();
+
+        let (stream, _) = s.send_request(false).unwrap();
+        s.poll_server().ok();
+        assert!(!s.pipe.server.streams.get(stream).unwrap().fec_critical);
+
+        s.client
+            .send_priority_update_for_request(
+                &mut s.pipe.client,
+                stream,
+                &Priority::new(1, false),
+            )
+            .unwrap();
+        s.advance().ok();
+
+        assert_eq!(s.poll_server(), Ok((stream, Event::PriorityUpdate)));
+        assert!(s.pipe.server.streams.get(stream).unwrap().fec_critical);
+
+        s.client
+            .send_priority_update_for_request(
+                &mut s.pipe.client,
+                stream,
+                &Priority::new(5, false),
+            )
+            .unwrap();
+        s.advance().ok();
+
+        assert_eq!(s.poll_server(), Err(Error::Done));
+        assert!(!s.pipe.server.streams.get(stream).unwrap().fec_critical);
+
+        // Streams not opened yet are left untouched.
+        s.client
+            .send_priority_update_for_request(
+                &mut s.pipe.client,
+                stream + 4,
+                &Priority::new(0, false),
+            )
+            .unwrap();
+        s.advance().ok();
+
+        assert_eq!(s.poll_server(), Ok((stream + 4, Event::PriorityUpdate)));
+        assert!(s.pipe.server.streams.get(stream + 4).is_none());
+    }
+
     #[test]
     /// Send a GOAWAY frame from the server, using an invalid goaway ID.
     fn goaway_from_server_invalid_id() {
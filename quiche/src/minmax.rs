@@ -57,12 +57,13 @@ use std::ops::Deref;
 use std::time::Duration;
 use std::time::Instant;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 struct MinmaxSample<T> {
     time: Instant,
     value: T,
 }
 
+#[derive(Debug)]
 pub struct Minmax<T> {
     estimate: [MinmaxSample<T>; 3],
 }
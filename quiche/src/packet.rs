@@ -53,10 +53,16 @@ pub const MAX_PKT_NUM_LEN: usize = 4;
 
 const SAMPLE_LEN: usize = 16;
 
+/// A packet number space.
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum Epoch {
+    /// The space of the Initial packets.
     Initial     = 0,
+
+    /// The space of the Handshake packets.
     Handshake   = 1,
+
+    /// The space of the 0-RTT and 1-RTT packets.
     Application = 2,
 }
 
@@ -70,6 +76,7 @@ impl Epoch {
         &EPOCHS[*range.start() as usize..=*range.end() as usize]
     }
 
+    /// Returns the number of packet number spaces.
     pub const fn count() -> usize {
         3
     }
@@ -517,7 +524,7 @@ impl<'a> Header<'a> {
     /// Returns true if the packet has a long header.
     ///
     /// The `b` parameter represents the first byte of the QUIC header.
-    fn is_long(b: u8) -> bool {
+    pub(crate) fn is_long(b: u8) -> bool {
         b & FORM_BIT != 0
     }
 }
@@ -0,0 +1,160 @@
+// Copyright (C) 2024, Cloudflare, Inc.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are
+// met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS
+// IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO,
+// THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR
+// PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::frame;
+use crate::packet::Epoch;
+
+/// A summary of the frames of a packet, as passed to [`PacketHook`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FramesSummary {
+    /// The number of frames.
+    pub frames: usize,
+
+    /// The number of STREAM frames.
+    pub stream_frames: usize,
+
+    /// The number of DATAGRAM frames.
+    pub datagram_frames: usize,
+
+    /// The number of REPAIR frames.
+    pub repair_frames: usize,
+
+    /// Whether the packet carries a source symbol protected by FEC.
+    pub fec_protected: bool,
+
+    /// Whether the packet is ack-eliciting.
+    pub ack_eliciting: bool,
+
+    /// The length of the frames written, in bytes.
+    pub payload_len: usize,
+
+    /// The number of bytes that can still be written to the packet.
+    pub left: usize,
+}
+
+impl FramesSummary {
+    pub(crate) fn new(
+        frames: &[frame::Frame], ack_eliciting: bool, payload_len: usize,
+        left: usize,
+    ) -> FramesSummary {
+        let mut summary = FramesSummary {
+            frames: frames.len(),
+            ack_eliciting,
+            payload_len,
+            left,
+            ..Default::default()
+        };
+
+        for frame in frames {
+            match frame {
+                frame::Frame::StreamHeader { .. } => summary.stream_frames += 1,
+
+                frame::Frame::DatagramHeader { .. } =>
+                    summary.datagram_frames += 1,
+
+                frame::Frame::Repair { .. } => summary.repair_frames += 1,
+
+                frame::Frame::SourceSymbolHeader { .. } =>
+                    summary.fec_protected = true,
+
+                _ => (),
+            }
+        }
+
+        summary
+    }
+
+    /// Returns whether the packet carries both repair symbols and
+    /// application data.
+    pub fn mixes_repair_and_data(&self) -> bool {
+        self.repair_frames > 0 &&
+            (self.stream_frames > 0 || self.datagram_frames > 0)
+    }
+}
+
+/// The padding added to a packet before it is sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PaddingDecision {
+    /// Only the padding required by the protocol is added.
+    #[default]
+    Required,
+
+    /// The rest of the packet is filled with PADDING frames.
+    Fill,
+}
+
+/// Observes the packets of a connection right before they are finalized.
+///
+/// The hook is invoked once all the frames of a packet are written, before
+/// the packet is padded and encrypted, e.g. to check the composition of the
+/// packets in experiments or to hide their size. See [`set_packet_hook()`].
+///
+/// [`set_packet_hook()`]: struct.Connection.html#method.set_packet_hook
+pub trait PacketHook: Send + Sync {
+    /// Called with the summary of the frames of a packet of the `epoch`
+    /// packet number space. The hook can change the padding of the packet,
+    /// which is [`PaddingDecision::Required`] on entry.
+    fn on_packet_built(
+        &mut self, epoch: Epoch, frames: &FramesSummary,
+        padding: &mut PaddingDecision,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let frames = [
+            frame::Frame::SourceSymbolHeader {
+                metadata: networkcoding::source_symbol_metadata_from_u64(0),
+                recovered: false,
+                compact: false,
+            },
+            frame::Frame::StreamHeader {
+                stream_id: 4,
+                offset: 0,
+                length: 100,
+                fin: false,
+            },
+            frame::Frame::Ping { mtu_probe: None },
+        ];
+
+        let summary = FramesSummary::new(&frames, true, 120, 1000);
+        assert_eq!(summary, FramesSummary {
+            frames: 3,
+            stream_frames: 1,
+            datagram_frames: 0,
+            repair_frames: 0,
+            fec_protected: true,
+            ack_eliciting: true,
+            payload_len: 120,
+            left: 1000,
+        });
+        assert!(!summary.mixes_repair_and_data());
+    }
+}
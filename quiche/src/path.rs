@@ -43,6 +43,7 @@ use crate::recovery::HandshakeStatus;
 
 /// The different states of the path validation.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum PathState {
     /// The path failed its validation.
     Failed,
@@ -110,6 +111,25 @@ pub enum PathEvent {
     ///
     /// Note that this event is only raised if the path has been validated.
     PeerMigrated(SocketAddr, SocketAddr),
+
+    /// The related network path between local `SocketAddr` and peer
+    /// `SocketAddr`, which was dedicated to repair symbols, stopped answering
+    /// the liveness probes. The repair symbols are sent on the active path
+    /// again.
+    RedundancyPathDown(SocketAddr, SocketAddr),
+}
+
+/// A path-specific event, including the packet losses detected on the path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathEventDetailed {
+    /// A [`PathEvent`].
+    ///
+    /// [`PathEvent`]: enum.PathEvent.html
+    Path(PathEvent),
+
+    /// A packet sent on the network path between local `SocketAddr` and peer
+    /// `SocketAddr` was declared lost.
+    PacketLost(SocketAddr, SocketAddr, recovery::PacketLoss),
 }
 
 /// A network path on which QUIC packets can be sent.
@@ -199,6 +219,15 @@ pub struct Path {
 
     /// Whether or not we should force eliciting of an ACK (e.g. via PING frame)
     pub needs_ack_eliciting: bool,
+
+    /// Number of repair symbols sent on this path.
+    pub repair_symbols_sent: usize,
+
+    /// Whether the application dedicated this path to repair symbols.
+    pub fec_only: bool,
+
+    /// Last instant when a packet was received on this path.
+    pub last_recv_time: Option<time::Instant>,
 }
 
 impl Path {
@@ -246,6 +275,9 @@ impl Path {
             failure_notified: false,
             migrating: false,
             needs_ack_eliciting: false,
+            repair_symbols_sent: 0,
+            fec_only: false,
+            last_recv_time: None,
         }
     }
 
@@ -332,13 +364,37 @@ impl Path {
         self.challenge_requested
     }
 
+    /// Returns whether the path only carries repair symbols, i.e., it is not
+    /// the active path and it is dedicated to repair symbols.
+    #[inline]
+    pub fn is_redundancy_path(&self) -> bool {
+        !self.active() && self.fec_only
+    }
+
+    /// Returns the instant at which the liveness of this redundancy path must
+    /// be checked with a PATH_CHALLENGE, if nothing was received on it for
+    /// `interval`.
+    pub fn liveness_deadline(
+        &self, interval: time::Duration,
+    ) -> Option<time::Instant> {
+        if !self.is_redundancy_path() ||
+            !self.validated() ||
+            self.challenge_requested ||
+            !self.in_flight_challenges.is_empty()
+        {
+            return None;
+        }
+
+        self.last_recv_time.map(|t| t + interval)
+    }
+
     pub fn should_send_pmtu_probe(
         &mut self, hs_confirmed: bool, hs_done: bool, out_len: usize,
-        is_closing: bool, frames_empty: bool,
+        is_closing: bool, frames_empty: bool, now: time::Instant,
     ) -> bool {
         (hs_confirmed && hs_done) &&
             self.pmtud.get_probe_size() > self.pmtud.get_current() &&
-            self.recovery.cwnd_available() > self.pmtud.get_probe_size() &&
+            self.recovery.cwnd_available(now) > self.pmtud.get_probe_size() &&
             out_len >= self.pmtud.get_probe_size() &&
             self.pmtud.get_probe_status() &&
             !is_closing &&
@@ -474,6 +530,8 @@ impl Path {
     }
 
     pub fn stats(&self) -> PathStats {
+        let byte_counts = self.recovery.byte_counts();
+
         PathStats {
             local_addr: self.local_addr,
             peer_addr: self.peer_addr,
@@ -486,13 +544,49 @@ impl Path {
             rtt: self.recovery.rtt(),
             min_rtt: self.recovery.min_rtt(),
             rttvar: self.recovery.rttvar(),
+            latest_rtt: self.recovery.latest_rtt(),
             cwnd: self.recovery.cwnd(),
             sent_bytes: self.sent_bytes,
             recv_bytes: self.recv_bytes,
             lost_bytes: self.recovery.bytes_lost,
             stream_retrans_bytes: self.stream_retrans_bytes,
+            sent_data_bytes: byte_counts.sent_data,
+            sent_repair_bytes: byte_counts.sent_repair,
+            acked_data_bytes: byte_counts.acked_data,
+            acked_repair_bytes: byte_counts.acked_repair,
+            lost_data_bytes: byte_counts.lost_data,
+            lost_repair_bytes: byte_counts.lost_repair,
             pmtu: self.recovery.max_datagram_size(),
             delivery_rate: self.recovery.delivery_rate(),
+            data_delivery_rate: self.recovery.data_delivery_rate(),
+            max_bandwidth: self.recovery.max_bandwidth(),
+            flight_recovery_probability: self
+                .recovery
+                .flight_recovery_probability(),
+        }
+    }
+
+    pub fn view(&self, now: time::Instant) -> PathView {
+        PathView {
+            local_addr: self.local_addr,
+            peer_addr: self.peer_addr,
+            active: self.active,
+            rtt: self.recovery.rtt(),
+            min_rtt: self.recovery.min_rtt(),
+            rttvar: self.recovery.rttvar(),
+            cwnd: self.recovery.cwnd(),
+            cwnd_available: self.recovery.cwnd_available(now),
+            bytes_in_flight: self.recovery.bif(),
+            app_limited: self.recovery.app_limited(),
+            lost: self.recovery.lost_count(),
+            lost_bytes: self.recovery.bytes_lost,
+            packets_lost_per_round_trip: self
+                .recovery
+                .packets_lost_per_round_trip(),
+            var_packets_lost_per_round_trip: self
+                .recovery
+                .var_packets_lost_per_round_trip(),
+            fec_only: self.fec_only,
         }
     }
 }
@@ -741,6 +835,17 @@ impl PathMap {
                 p.peer_addr,
             ));
 
+            if p.is_redundancy_path() {
+                self.events.push_back(PathEvent::RedundancyPathDown(
+                    p.local_addr,
+                    p.peer_addr,
+                ));
+
+                // The repair symbols fall back to the active path, even if
+                // the path comes back later.
+                p.fec_only = false;
+            }
+
             p.failure_notified = true;
         }
     }
@@ -754,6 +859,15 @@ impl PathMap {
             .map(|(pid, _)| pid)
     }
 
+    /// Returns the identifier of the path dedicated to repair symbols, if it
+    /// can be used to send them.
+    pub fn fec_only_path_id(&self) -> Option<usize> {
+        self.paths
+            .iter()
+            .find(|(_, p)| p.fec_only && !p.active() && p.usable())
+            .map(|(pid, _)| pid)
+    }
+
     /// Handles incoming PATH_RESPONSE data.
     pub fn on_response_received(&mut self, data: [u8; 8]) -> Result<()> {
         let active_pid = self.get_active_path_id()?;
@@ -831,6 +945,7 @@ impl PathMap {
 ///
 /// [`path_stats()`]: struct.Connection.html#method.path_stats
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PathStats {
     /// The local address of the path.
     pub local_addr: SocketAddr,
@@ -866,6 +981,9 @@ pub struct PathStats {
     /// variation.
     pub rttvar: time::Duration,
 
+    /// The latest round-trip time sample.
+    pub latest_rtt: time::Duration,
+
     /// The size of the connection's congestion window in bytes.
     pub cwnd: usize,
 
@@ -881,6 +999,25 @@ pub struct PathStats {
     /// The number of stream bytes retransmitted.
     pub stream_retrans_bytes: u64,
 
+    /// The number of bytes sent in QUIC packets, excluding their REPAIR
+    /// frames.
+    pub sent_data_bytes: u64,
+
+    /// The number of bytes of the REPAIR frames sent.
+    pub sent_repair_bytes: u64,
+
+    /// The number of bytes acknowledged, excluding the REPAIR frames.
+    pub acked_data_bytes: u64,
+
+    /// The number of bytes of the REPAIR frames acknowledged.
+    pub acked_repair_bytes: u64,
+
+    /// The number of bytes lost, excluding the REPAIR frames.
+    pub lost_data_bytes: u64,
+
+    /// The number of bytes of the REPAIR frames lost.
+    pub lost_repair_bytes: u64,
+
     /// The current PMTU for the connection.
     pub pmtu: usize,
 
@@ -893,6 +1030,53 @@ pub struct PathStats {
     /// [`SendInfo.at`]: struct.SendInfo.html#structfield.at
     /// [Pacing]: index.html#pacing
     pub delivery_rate: u64,
+
+    /// The most recent data delivery rate estimate in bytes/s, excluding the
+    /// packets carrying repair symbols.
+    ///
+    /// Applications adapting their bitrate should use this value rather than
+    /// [`delivery_rate`] when redundancy is sent, as the repair symbols do
+    /// not carry application data.
+    ///
+    /// [`delivery_rate`]: struct.PathStats.html#structfield.delivery_rate
+    pub data_delivery_rate: u64,
+
+    /// The maximum delivery rate sampled over the last ten minimum RTTs, in
+    /// bytes/s, including the packets carrying repair symbols.
+    pub max_bandwidth: u64,
+
+    /// The estimated probability that the peer gets or recovers with the
+    /// repair symbols in flight all the packets in flight, or `None` without
+    /// loss estimates.
+    pub flight_recovery_probability: Option<f64>,
+}
+
+impl PathStats {
+    /// Returns the minimum round-trip time observed over the last five
+    /// minutes, or `None` before the first sample.
+    pub fn min_rtt(&self) -> Option<time::Duration> {
+        self.min_rtt
+    }
+
+    /// Returns the exponentially weighted moving average of the round-trip
+    /// time samples.
+    pub fn smoothed_rtt(&self) -> time::Duration {
+        self.rtt
+    }
+
+    /// Returns the latest round-trip time sample.
+    ///
+    /// Unlike the smoothed and minimum RTTs, it follows the jitter of the
+    /// path.
+    pub fn latest_rtt(&self) -> time::Duration {
+        self.latest_rtt
+    }
+
+    /// Returns the mean deviation of the round-trip time samples from the
+    /// smoothed RTT.
+    pub fn rtt_var(&self) -> time::Duration {
+        self.rttvar
+    }
 }
 
 impl std::fmt::Debug for PathStats {
@@ -914,6 +1098,8 @@ impl std::fmt::Debug for PathStats {
             self.recv, self.sent, self.lost, self.retrans, self.rtt, self.min_rtt, self.rttvar, self.cwnd,
         )?;
 
+        write!(f, " latest_rtt={:?}", self.latest_rtt)?;
+
         write!(
             f,
             " sent_bytes={} recv_bytes={} lost_bytes={}",
@@ -924,10 +1110,93 @@ impl std::fmt::Debug for PathStats {
             f,
             " stream_retrans_bytes={} pmtu={} delivery_rate={}",
             self.stream_retrans_bytes, self.pmtu, self.delivery_rate,
+        )?;
+
+        write!(
+            f,
+            " sent_data_bytes={} sent_repair_bytes={} acked_data_bytes={} acked_repair_bytes={}",
+            self.sent_data_bytes, self.sent_repair_bytes, self.acked_data_bytes, self.acked_repair_bytes,
+        )?;
+
+        write!(
+            f,
+            " lost_data_bytes={} lost_repair_bytes={}",
+            self.lost_data_bytes, self.lost_repair_bytes,
+        )?;
+
+        write!(
+            f,
+            " data_delivery_rate={} max_bandwidth={}",
+            self.data_delivery_rate, self.max_bandwidth,
+        )?;
+
+        write!(
+            f,
+            " flight_recovery_probability={:?}",
+            self.flight_recovery_probability,
         )
     }
 }
 
+/// A read-only view of the congestion and loss state of a path.
+///
+/// It provides the information needed to make scheduling decisions outside of
+/// the library, without exposing the internal path and recovery types. A view
+/// of each known path can be collected using the [`path_views()`] method, and
+/// a [`FECRedundancyScheduler`] is given a view of the path it schedules.
+///
+/// [`path_views()`]: struct.Connection.html#method.path_views
+/// [`FECRedundancyScheduler`]: trait.FECRedundancyScheduler.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PathView {
+    /// The local address of the path.
+    pub local_addr: SocketAddr,
+
+    /// The peer address of the path.
+    pub peer_addr: SocketAddr,
+
+    /// Whether the path is marked as active.
+    pub active: bool,
+
+    /// The estimated round-trip time of the path.
+    pub rtt: time::Duration,
+
+    /// The minimum round-trip time observed.
+    pub min_rtt: Option<time::Duration>,
+
+    /// The estimated round-trip time variation.
+    pub rttvar: time::Duration,
+
+    /// The size of the congestion window in bytes.
+    pub cwnd: usize,
+
+    /// The number of bytes that can currently be sent on the path.
+    pub cwnd_available: usize,
+
+    /// The number of bytes in flight.
+    pub bytes_in_flight: usize,
+
+    /// Whether the path is currently application-limited.
+    pub app_limited: bool,
+
+    /// The number of QUIC packets that were lost.
+    pub lost: usize,
+
+    /// The number of bytes lost.
+    pub lost_bytes: u64,
+
+    /// The estimated number of packets lost per round-trip, if enough losses
+    /// were observed.
+    pub packets_lost_per_round_trip: Option<f64>,
+
+    /// The variance of the number of packets lost per round-trip.
+    pub var_packets_lost_per_round_trip: f64,
+
+    /// Whether the path is dedicated to repair symbols.
+    pub fec_only: bool,
+}
+
 #[cfg(test)]
 mod tests {
     use crate::rand;
@@ -1266,4 +1535,86 @@ mod tests {
 
         // There will never be a response for fourth probe...
     }
+
+    #[test]
+    fn redundancy_path_liveness() {
+        let client_addr = "127.0.0.1:1234".parse().unwrap();
+        let client_addr_2 = "127.0.0.1:5678".parse().unwrap();
+        let server_addr = "127.0.0.1:4321".parse().unwrap();
+
+        let config = Config::new(crate::PROTOCOL_VERSION).unwrap();
+        let recovery_config = RecoveryConfig::from_config(&config);
+
+        let path = Path::new(
+            client_addr,
+            server_addr,
+            &recovery_config,
+            config.path_challenge_recv_max_queue_len,
+            1200,
+            true,
+        );
+        let mut path_mgr = PathMap::new(path, 2, false, true, 1200);
+
+        let mut redundancy_path = Path::new(
+            client_addr_2,
+            server_addr,
+            &recovery_config,
+            config.path_challenge_recv_max_queue_len,
+            1200,
+            true,
+        );
+
+        let now = time::Instant::now();
+        let interval = time::Duration::from_millis(100);
+        redundancy_path.last_recv_time = Some(now);
+
+        let pid = path_mgr.insert_path(redundancy_path, false).unwrap();
+
+        // The path is not dedicated to repair symbols yet.
+        assert_eq!(path_mgr.get(pid).unwrap().liveness_deadline(interval), None);
+
+        path_mgr.get_mut(pid).unwrap().fec_only = true;
+        assert!(path_mgr.get(pid).unwrap().is_redundancy_path());
+        assert_eq!(
+            path_mgr.get(pid).unwrap().liveness_deadline(interval),
+            Some(now + interval)
+        );
+
+        // The active path is never probed.
+        let active_pid = path_mgr.get_active_path_id().unwrap();
+        path_mgr.get_mut(active_pid).unwrap().fec_only = true;
+        assert_eq!(
+            path_mgr
+                .get(active_pid)
+                .unwrap()
+                .liveness_deadline(interval),
+            None
+        );
+
+        // No deadline while the probe is in flight.
+        let data = rand::rand_u64().to_be_bytes();
+        path_mgr.get_mut(pid).unwrap().add_challenge_sent(
+            data,
+            MIN_CLIENT_INITIAL_LEN,
+            now + interval,
+        );
+        assert_eq!(path_mgr.get(pid).unwrap().liveness_deadline(interval), None);
+
+        // The path fails to answer the probes.
+        path_mgr.get_mut(pid).unwrap().on_failed_validation();
+        path_mgr.notify_failed_validations();
+
+        assert_eq!(
+            path_mgr.pop_event(),
+            Some(PathEvent::FailedValidation(client_addr_2, server_addr))
+        );
+        assert_eq!(
+            path_mgr.pop_event(),
+            Some(PathEvent::RedundancyPathDown(client_addr_2, server_addr))
+        );
+
+        // The path is not dedicated to repair symbols anymore.
+        assert!(!path_mgr.get(pid).unwrap().fec_only);
+        assert_eq!(path_mgr.fec_only_path_id(), None);
+    }
 }
@@ -0,0 +1,202 @@
+// Copyright (C) 2024, Cloudflare, Inc.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are
+// met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS
+// IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO,
+// THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR
+// PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::path::PathState;
+use crate::Connection;
+use crate::Result;
+
+/// What happens to the paths that lose a [`PathRace`].
+///
+/// [`PathRace`]: struct.PathRace.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PathRaceLoser {
+    /// The losing paths are not sent on anymore (default).
+    #[default]
+    Unused,
+
+    /// The fastest losing path is dedicated to repair symbols, as with
+    /// [`Connection::set_fec_only_path()`], so that it keeps being useful as
+    /// redundancy. The other losing paths are not sent on anymore.
+    ///
+    /// [`Connection::set_fec_only_path()`]: struct.Connection.html#method.set_fec_only_path
+    FECOnly,
+}
+
+/// Races the paths towards several addresses of a server, e.g. its IPv4 and
+/// IPv6 ones, and promotes the fastest one, in the spirit of Happy Eyeballs.
+///
+/// The handshake is performed on one of the candidate 4-tuples. Once the
+/// connection is established, the other candidates are probed, and the race
+/// is decided when all of them are validated or failed, or when the race
+/// timeout expires. The winner is the validated path with the lowest
+/// round-trip time. The connection migrates to it if it isn't the active
+/// path already, and the losers are handled as per [`PathRaceLoser`].
+///
+/// Probing a path requires a spare destination connection ID, so the peer
+/// must provide enough of them, see
+/// [`Config::set_active_connection_id_limit()`].
+///
+/// [`PathRaceLoser`]: enum.PathRaceLoser.html
+/// [`Config::set_active_connection_id_limit()`]: struct.Config.html#method.set_active_connection_id_limit
+pub struct PathRace {
+    candidates: Vec<(SocketAddr, SocketAddr)>,
+
+    probed: Vec<bool>,
+
+    loser: PathRaceLoser,
+
+    race_timeout: Duration,
+
+    /// When the connection was first seen established.
+    started: Option<Instant>,
+
+    winner: Option<(SocketAddr, SocketAddr)>,
+}
+
+impl PathRace {
+    /// Creates a race between the `candidates` (local address, peer address)
+    /// 4-tuples, which is decided at the latest `race_timeout` after the
+    /// connection is established.
+    pub fn new(
+        candidates: &[(SocketAddr, SocketAddr)], loser: PathRaceLoser,
+        race_timeout: Duration,
+    ) -> PathRace {
+        PathRace {
+            candidates: candidates.to_vec(),
+            probed: vec![false; candidates.len()],
+            loser,
+            race_timeout,
+            started: None,
+            winner: None,
+        }
+    }
+
+    /// Advances the race.
+    ///
+    /// This should be called after the connection processed received packets
+    /// or timed out, and before it sends packets. The candidates are probed
+    /// as soon as spare connection IDs are available.
+    ///
+    /// The winner is returned by the call that decides the race, after the
+    /// connection was migrated to it. An error is returned if the connection
+    /// can't be migrated to the winner, or the loser dedicated to repair
+    /// symbols.
+    pub fn on_progress(
+        &mut self, conn: &mut Connection, now: Instant,
+    ) -> Result<Option<(SocketAddr, SocketAddr)>> {
+        if self.winner.is_some() || !conn.is_established() {
+            return Ok(None);
+        }
+
+        let started = *self.started.get_or_insert(now);
+
+        for (i, &(local, peer)) in self.candidates.iter().enumerate() {
+            if self.probed[i] {
+                continue;
+            }
+
+            // The handshake path needs no probing.
+            if conn.paths.path_id_from_addrs(&(local, peer)).is_some() {
+                self.probed[i] = true;
+                continue;
+            }
+
+            if conn.available_dcids() == 0 {
+                break;
+            }
+
+            conn.probe_path(local, peer)?;
+            self.probed[i] = true;
+        }
+
+        let states: Vec<_> = conn
+            .path_stats()
+            .filter(|s| self.candidates.contains(&(s.local_addr, s.peer_addr)))
+            .map(|s| {
+                let rtt = s.min_rtt.unwrap_or(s.rtt);
+
+                ((s.local_addr, s.peer_addr), s.validation_state, rtt)
+            })
+            .collect();
+
+        let resolved = self.probed.iter().all(|p| *p) &&
+            states.len() == self.candidates.len() &&
+            states.iter().all(|(_, state, _)| {
+                matches!(state, PathState::Validated | PathState::Failed)
+            });
+
+        if !resolved && now < started + self.race_timeout {
+            return Ok(None);
+        }
+
+        let mut validated: Vec<_> = states
+            .into_iter()
+            .filter(|(_, state, _)| *state == PathState::Validated)
+            .map(|(tuple, _, rtt)| (tuple, rtt))
+            .collect();
+        validated.sort_by_key(|(_, rtt)| *rtt);
+
+        // The handshake path is always validated by now.
+        let (winner, _) = match validated.first() {
+            Some(v) => *v,
+
+            None => return Ok(None),
+        };
+
+        conn.migrate(winner.0, winner.1)?;
+
+        if self.loser == PathRaceLoser::FECOnly {
+            if let Some(((local, peer), _)) = validated.get(1) {
+                conn.set_fec_only_path(*local, *peer, true)?;
+            }
+        }
+
+        self.winner = Some(winner);
+
+        Ok(Some(winner))
+    }
+
+    /// Returns the winner of the race, once decided.
+    pub fn winner(&self) -> Option<(SocketAddr, SocketAddr)> {
+        self.winner
+    }
+
+    /// Returns the time at which the race is decided at the latest, so that
+    /// the event loop wakes up to call [`on_progress()`].
+    ///
+    /// [`on_progress()`]: struct.PathRace.html#method.on_progress
+    pub fn timeout(&self) -> Option<Instant> {
+        if self.winner.is_some() {
+            return None;
+        }
+
+        self.started.map(|started| started + self.race_timeout)
+    }
+}
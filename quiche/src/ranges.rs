@@ -164,6 +164,17 @@ impl RangeSet {
         }
     }
 
+    /// Returns whether `item` is covered by one of the ranges.
+    pub fn contains(&self, item: u64) -> bool {
+        match self {
+            RangeSet::Inline(set) =>
+                set.inner.iter().any(|(s, e)| *s <= item && item < *e),
+
+            RangeSet::BTree(set) =>
+                matches!(set.prev_to(item), Some(r) if item < r.end),
+        }
+    }
+
     #[inline]
     pub fn remove_until(&mut self, largest: u64) {
         match self {
@@ -723,6 +734,32 @@ mod tests {
         assert_eq!(r.last(), Some(19));
     }
 
+    #[test]
+    fn contains() {
+        let mut r = RangeSet::default();
+        assert!(!r.contains(0));
+
+        r.insert(3..6);
+        r.insert(10..11);
+        assert!(r.contains(3));
+        assert!(r.contains(5));
+        assert!(!r.contains(6));
+        assert!(r.contains(10));
+        assert!(!r.contains(11));
+
+        // Same lookups with the BTree representation.
+        r.insert(13..14);
+        r.insert(16..20);
+        assert!(matches!(r, RangeSet::BTree(_)));
+        assert!(!r.contains(2));
+        assert!(r.contains(5));
+        assert!(!r.contains(6));
+        assert!(r.contains(13));
+        assert!(!r.contains(15));
+        assert!(r.contains(19));
+        assert!(!r.contains(20));
+    }
+
     #[test]
     fn capacity() {
         let mut r = RangeSet::new(3);
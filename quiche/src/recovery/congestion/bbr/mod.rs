@@ -44,6 +44,7 @@ pub(crate) static BBR: CongestionControlOps = CongestionControlOps {
     checkpoint,
     rollback,
     has_custom_pacing,
+    in_slow_start,
     debug_fmt,
 };
 
@@ -337,6 +338,10 @@ fn has_custom_pacing() -> bool {
     true
 }
 
+fn in_slow_start(r: &Congestion) -> bool {
+    r.bbr_state.state == BBRStateMachine::Startup
+}
+
 fn debug_fmt(r: &Congestion, f: &mut std::fmt::Formatter) -> std::fmt::Result {
     let bbr = &r.bbr_state;
 
@@ -45,6 +45,7 @@ pub(crate) static BBR2: CongestionControlOps = CongestionControlOps {
     checkpoint,
     rollback,
     has_custom_pacing,
+    in_slow_start,
     debug_fmt,
 };
 
@@ -611,6 +612,10 @@ fn has_custom_pacing() -> bool {
     true
 }
 
+fn in_slow_start(r: &Congestion) -> bool {
+    r.bbr2_state.state == BBR2StateMachine::Startup
+}
+
 // rate -> kbit/sec. if inf, return -1
 fn rate_kbps(rate: u64) -> isize {
     if rate == u64::MAX {
@@ -53,6 +53,7 @@ pub(crate) static CUBIC: CongestionControlOps = CongestionControlOps {
     checkpoint,
     rollback,
     has_custom_pacing,
+    in_slow_start,
     debug_fmt,
 };
 
@@ -73,6 +74,10 @@ const MIN_ROLLBACK_THRESHOLD: usize = 2;
 /// Default value of alpha_aimd in the beginning of congestion avoidance.
 const ALPHA_AIMD: f64 = 3.0 * (1.0 - BETA_CUBIC) / (1.0 + BETA_CUBIC);
 
+/// Multiplicative decrease factor applied when all the lost data was
+/// recovered by the peer with FEC, with the experimental CC responses.
+const BETA_CUBIC_FEC_RECOVERED: f64 = 0.85;
+
 /// CUBIC State Variables.
 ///
 /// We need to keep those variables across the connection.
@@ -344,15 +349,26 @@ fn congestion_event(
     if !in_congestion_recovery {
         r.congestion_recovery_start_time = Some(now);
 
+        // Losses recovered by FEC are less likely caused by congestion.
+        let (beta, alpha_aimd) =
+            if r.experimental_cc_responses && r.fec_recovered_loss {
+                (
+                    BETA_CUBIC_FEC_RECOVERED,
+                    3.0 * (1.0 - BETA_CUBIC_FEC_RECOVERED) /
+                        (1.0 + BETA_CUBIC_FEC_RECOVERED),
+                )
+            } else {
+                (BETA_CUBIC, ALPHA_AIMD)
+            };
+
         // Fast convergence
         if (r.congestion_window as f64) < r.cubic_state.w_max {
-            r.cubic_state.w_max =
-                r.congestion_window as f64 * (1.0 + BETA_CUBIC) / 2.0;
+            r.cubic_state.w_max = r.congestion_window as f64 * (1.0 + beta) / 2.0;
         } else {
             r.cubic_state.w_max = r.congestion_window as f64;
         }
 
-        r.ssthresh = (r.congestion_window as f64 * BETA_CUBIC) as usize;
+        r.ssthresh = (r.congestion_window as f64 * beta) as usize;
         r.ssthresh = cmp::max(
             r.ssthresh,
             r.max_datagram_size * recovery::MINIMUM_WINDOW_PACKETS,
@@ -366,11 +382,10 @@ fn congestion_event(
                 .cubic_k(r.congestion_window, r.max_datagram_size)
         };
 
-        r.cubic_state.cwnd_inc =
-            (r.cubic_state.cwnd_inc as f64 * BETA_CUBIC) as usize;
+        r.cubic_state.cwnd_inc = (r.cubic_state.cwnd_inc as f64 * beta) as usize;
 
         r.cubic_state.w_est = r.congestion_window as f64;
-        r.cubic_state.alpha_aimd = ALPHA_AIMD;
+        r.cubic_state.alpha_aimd = alpha_aimd;
 
         if r.hystart.in_css() {
             r.hystart.congestion_event();
@@ -412,6 +427,10 @@ fn has_custom_pacing() -> bool {
     false
 }
 
+fn in_slow_start(r: &Congestion) -> bool {
+    r.congestion_window < r.ssthresh
+}
+
 fn debug_fmt(r: &Congestion, f: &mut std::fmt::Formatter) -> std::fmt::Result {
     write!(
         f,
@@ -504,6 +523,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cubic_congestion_event_fec_recovered() {
+        let mut sender = test_sender();
+        let size = sender.max_datagram_size;
+
+        sender.send_packet(size);
+        sender.send_packet(size);
+
+        let cwnd_prev = sender.congestion_window;
+
+        // The milder decrease is only applied with the experimental responses.
+        sender.fec_recovered_loss = true;
+        sender.lose_n_packets(1, size, None);
+
+        assert_eq!(
+            (cwnd_prev as f64 * BETA_CUBIC) as usize,
+            sender.congestion_window
+        );
+
+        let mut sender = test_sender();
+        sender.experimental_cc_responses = true;
+
+        sender.send_packet(size);
+        sender.send_packet(size);
+
+        sender.fec_recovered_loss = true;
+        sender.lose_n_packets(1, size, None);
+
+        assert_eq!(
+            (cwnd_prev as f64 * BETA_CUBIC_FEC_RECOVERED) as usize,
+            sender.congestion_window
+        );
+
+        // Losses that were not recovered get the regular decrease.
+        let cwnd_prev = sender.congestion_window;
+        let rtt = Duration::from_millis(100);
+        sender.update_rtt(rtt);
+        sender.advance_time(rtt);
+        sender.send_packet(size);
+
+        sender.fec_recovered_loss = false;
+        sender.lose_n_packets(2, size, None);
+
+        assert_eq!(
+            (cwnd_prev as f64 * BETA_CUBIC) as usize,
+            sender.congestion_window
+        );
+    }
+
     #[test]
     fn cubic_congestion_avoidance() {
         let mut sender = test_sender();
@@ -766,6 +834,38 @@ mod tests {
         assert_eq!(sender.congestion_window(), prev_cwnd);
     }
 
+    #[test]
+    fn cubic_probe_checkpoint_keeps_prior() {
+        let mut sender = test_sender();
+        let size = sender.max_datagram_size;
+
+        for _ in 0..sender.initial_congestion_window_packets {
+            sender.send_packet(size);
+        }
+
+        // The congestion event saves the state to roll back to if it turns
+        // out to be spurious.
+        sender.lose_n_packets(1, size, None);
+
+        let prior_cwnd = sender.cubic_state.prior.congestion_window;
+        let prior_lost_count = sender.cubic_state.prior.lost_count;
+
+        let cwnd = sender.congestion_window();
+
+        // A bandwidth probe doesn't overwrite it.
+        sender.checkpoint();
+        sender.congestion_window = cwnd * 2;
+
+        assert_eq!(sender.cubic_state.prior.congestion_window, prior_cwnd);
+        assert_eq!(sender.cubic_state.prior.lost_count, prior_lost_count);
+
+        assert!(sender.rollback());
+        assert_eq!(sender.congestion_window(), cwnd);
+
+        assert_eq!(sender.cubic_state.prior.congestion_window, prior_cwnd);
+        assert_eq!(sender.cubic_state.prior.lost_count, prior_lost_count);
+    }
+
     #[test]
     fn cubic_fast_convergence() {
         let mut sender = test_sender();
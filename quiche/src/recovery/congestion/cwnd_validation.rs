@@ -0,0 +1,212 @@
+// Copyright (C) 2024, Cloudflare, Inc.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are
+// met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS
+// IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO,
+// THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR
+// PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Congestion window validation.
+//!
+//! This implements the non-validated phase of RFC 7661 for window-based
+//! congestion controllers: when the congestion window is not used by an
+//! application-limited sender, it can no longer be considered as a valid
+//! estimate of the capacity of the path. The amount of data acknowledged
+//! per round-trip, `pipeACK`, is sampled, and if it stays below half of the
+//! congestion window for a non-validated period, the congestion window is
+//! reduced.
+//!
+//! Repair symbols sent to protect the data, e.g. by a FEC scheduler filling
+//! the congestion window when the application is idle, are an exception:
+//! while repair symbols are being sent, the path is considered validated, so
+//! that FEC probing and the cwnd decay do not fight with each other.
+
+use std::cmp;
+use std::time::Duration;
+use std::time::Instant;
+
+// The duration of the non-validated phase after which the congestion window
+// is reduced.
+const NON_VALIDATED_PERIOD: Duration = Duration::from_secs(300);
+
+// The minimum duration of a pipeACK sampling period.
+const MIN_SAMPLING_PERIOD: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default)]
+pub struct CwndValidation {
+    // The number of bytes acknowledged per round-trip during the last
+    // complete sampling period, and the start of this period.
+    pipe_ack: Option<(usize, Instant)>,
+
+    sample_start: Option<Instant>,
+
+    sample_bytes: usize,
+
+    // The start of the current non-validated phase.
+    nvp_start: Option<Instant>,
+
+    last_repair_sent: Option<Instant>,
+}
+
+impl CwndValidation {
+    pub fn on_repair_sent(&mut self, now: Instant) {
+        self.last_repair_sent = Some(now);
+    }
+
+    /// Returns whether the path is in the non-validated phase.
+    #[cfg(test)]
+    pub fn non_validated(&self) -> bool {
+        self.nvp_start.is_some()
+    }
+
+    /// Updates the pipeACK sample with `acked_bytes`. Returns the reduced
+    /// congestion window and slow start threshold when the non-validated
+    /// phase expires.
+    pub fn on_packets_acked(
+        &mut self, acked_bytes: usize, cwnd: usize, ssthresh: usize,
+        initial_cwnd: usize, rtt: Duration, now: Instant,
+    ) -> Option<(usize, usize)> {
+        let period = cmp::max(rtt * 3, MIN_SAMPLING_PERIOD);
+
+        let sample_start = *self.sample_start.get_or_insert(now);
+
+        self.sample_bytes += acked_bytes;
+
+        let elapsed = now.saturating_duration_since(sample_start);
+
+        if elapsed >= period {
+            let pipe_ack = self.sample_bytes as f64 * rtt.as_secs_f64() /
+                elapsed.as_secs_f64();
+
+            self.pipe_ack = Some((pipe_ack as usize, sample_start));
+            self.sample_start = Some(now);
+            self.sample_bytes = 0;
+        }
+
+        let (pipe_ack, pipe_ack_start) = self.pipe_ack?;
+
+        let fec_probing = match self.last_repair_sent {
+            Some(t) => now < t + period,
+
+            None => false,
+        };
+
+        if pipe_ack >= cwnd / 2 || fec_probing {
+            self.nvp_start = None;
+            return None;
+        }
+
+        // The path became non-validated at the start of the sampling period
+        // that measured the low utilization.
+        let nvp_start = *self.nvp_start.get_or_insert(pipe_ack_start);
+
+        if now < nvp_start + NON_VALIDATED_PERIOD {
+            return None;
+        }
+
+        // A new non-validated phase starts with the reduced window.
+        self.nvp_start = Some(now);
+
+        Some((
+            cmp::max(cwnd / 2, initial_cwnd),
+            cmp::max(ssthresh, cwnd * 3 / 4),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CWND: usize = 100_000;
+    const INITIAL_CWND: usize = 12_000;
+    const RTT: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn validated() {
+        let mut cv = CwndValidation::default();
+        let now = Instant::now();
+
+        // 60kB per RTT is acknowledged during the first sampling period.
+        for i in 0..=10 {
+            let now = now + RTT * i;
+
+            assert_eq!(
+                cv.on_packets_acked(
+                    60_000,
+                    CWND,
+                    usize::MAX,
+                    INITIAL_CWND,
+                    RTT,
+                    now
+                ),
+                None
+            );
+        }
+
+        assert!(!cv.non_validated());
+    }
+
+    #[test]
+    fn non_validated_period() {
+        let mut cv = CwndValidation::default();
+        let now = Instant::now();
+
+        assert_eq!(
+            cv.on_packets_acked(1000, CWND, usize::MAX, INITIAL_CWND, RTT, now),
+            None
+        );
+
+        let now = now + MIN_SAMPLING_PERIOD;
+        assert_eq!(
+            cv.on_packets_acked(1000, CWND, usize::MAX, INITIAL_CWND, RTT, now),
+            None
+        );
+        assert!(cv.non_validated());
+
+        let now = now + NON_VALIDATED_PERIOD;
+        assert_eq!(
+            cv.on_packets_acked(1000, CWND, usize::MAX, INITIAL_CWND, RTT, now),
+            Some((CWND / 2, usize::MAX))
+        );
+    }
+
+    #[test]
+    fn fec_probing() {
+        let mut cv = CwndValidation::default();
+        let now = Instant::now();
+
+        cv.on_packets_acked(1000, CWND, usize::MAX, INITIAL_CWND, RTT, now);
+
+        // Repair symbols keep being sent while the application is idle.
+        let mut t = now;
+        while t < now + NON_VALIDATED_PERIOD + MIN_SAMPLING_PERIOD * 2 {
+            t += MIN_SAMPLING_PERIOD;
+            cv.on_repair_sent(t);
+
+            assert_eq!(
+                cv.on_packets_acked(1000, CWND, usize::MAX, INITIAL_CWND, RTT, t),
+                None
+            );
+            assert!(!cv.non_validated());
+        }
+    }
+}
@@ -29,12 +29,18 @@
 //! This implements the algorithm for estimating delivery rate as described in
 //! <https://tools.ietf.org/html/draft-cheng-iccrg-delivery-rate-estimation-01>
 
+use std::collections::VecDeque;
 use std::time::Duration;
 use std::time::Instant;
 
+use crate::frame;
+use crate::minmax::Minmax;
 use crate::recovery::Acked;
 use crate::recovery::Sent;
 
+// The length of the max bandwidth filter window, in minimum RTTs.
+const MAX_BANDWIDTH_WINDOW_RTTS: u32 = 10;
+
 #[derive(Debug)]
 pub struct Rate {
     delivered: usize,
@@ -54,6 +60,17 @@ pub struct Rate {
 
     // Sample of rate estimation.
     rate_sample: RateSample,
+
+    // Number of bytes delivered in packets carrying repair symbols.
+    delivered_repair: usize,
+
+    // For each sent packet not acked yet, its packet number, the value of
+    // `delivered_repair` when it was sent and whether it carries repair
+    // symbols.
+    sent_repair: VecDeque<(u64, usize, bool)>,
+
+    // Windowed max of the delivery rate samples.
+    max_bandwidth: Minmax<u64>,
 }
 
 impl Default for Rate {
@@ -74,6 +91,12 @@ impl Default for Rate {
             largest_acked: 0,
 
             rate_sample: RateSample::default(),
+
+            delivered_repair: 0,
+
+            sent_repair: VecDeque::new(),
+
+            max_bandwidth: Minmax::new(0),
         }
     }
 }
@@ -96,19 +119,44 @@ impl Rate {
         pkt.lost = bytes_lost;
 
         self.last_sent_packet = pkt.pkt_num;
+
+        let is_repair = pkt
+            .frames
+            .iter()
+            .any(|f| matches!(f, frame::Frame::Repair { .. }));
+
+        self.sent_repair.push_back((
+            pkt.pkt_num,
+            self.delivered_repair,
+            is_repair,
+        ));
     }
 
     // Update the delivery rate sample when a packet is acked.
     pub fn update_rate_sample(&mut self, pkt: &Acked, now: Instant) {
+        let (prior_delivered_repair, is_repair) = match self
+            .sent_repair
+            .binary_search_by_key(&pkt.pkt_num, |(pn, ..)| *pn)
+        {
+            Ok(i) => (self.sent_repair[i].1, self.sent_repair[i].2),
+
+            Err(_) => (self.delivered_repair, false),
+        };
+
         self.delivered += pkt.size;
         self.delivered_time = now;
 
+        if is_repair {
+            self.delivered_repair += pkt.size;
+        }
+
         // Update info using the newest packet. If rate_sample is not yet
         // initialized, initialize with the first packet.
         if self.rate_sample.prior_time.is_none() ||
             pkt.delivered > self.rate_sample.prior_delivered
         {
             self.rate_sample.prior_delivered = pkt.delivered;
+            self.rate_sample.prior_delivered_repair = prior_delivered_repair;
             self.rate_sample.prior_time = Some(pkt.delivered_time);
             self.rate_sample.is_app_limited = pkt.is_app_limited;
             self.rate_sample.send_elapsed =
@@ -130,6 +178,14 @@ impl Rate {
             self.update_app_limited(false);
         }
 
+        while let Some((pn, ..)) = self.sent_repair.front() {
+            if *pn > self.largest_acked {
+                break;
+            }
+
+            self.sent_repair.pop_front();
+        }
+
         if self.rate_sample.prior_time.is_some() {
             let interval = self
                 .rate_sample
@@ -152,6 +208,26 @@ impl Rate {
                 self.rate_sample.delivery_rate =
                     (self.rate_sample.delivered as f64 / interval.as_secs_f64())
                         as u64;
+
+                let delivered_repair = self.delivered_repair -
+                    self.rate_sample.prior_delivered_repair;
+
+                self.rate_sample.data_delivery_rate =
+                    (self.rate_sample.delivered.saturating_sub(delivered_repair)
+                        as f64 /
+                        interval.as_secs_f64()) as u64;
+
+                // App-limited samples only count if they exceed the current
+                // max, as they underestimate the bandwidth.
+                if !self.rate_sample.is_app_limited ||
+                    self.rate_sample.delivery_rate > *self.max_bandwidth
+                {
+                    self.max_bandwidth.running_max(
+                        min_rtt * MAX_BANDWIDTH_WINDOW_RTTS,
+                        self.delivered_time,
+                        self.rate_sample.delivery_rate,
+                    );
+                }
             }
         }
     }
@@ -172,6 +248,16 @@ impl Rate {
         self.rate_sample.delivery_rate
     }
 
+    /// Returns the delivery rate of the latest sample, excluding the packets
+    /// carrying repair symbols.
+    pub fn sample_data_delivery_rate(&self) -> u64 {
+        self.rate_sample.data_delivery_rate
+    }
+
+    pub fn max_bandwidth(&self) -> u64 {
+        *self.max_bandwidth
+    }
+
     pub fn sample_rtt(&self) -> Duration {
         self.rate_sample.rtt
     }
@@ -193,6 +279,8 @@ impl Rate {
 struct RateSample {
     delivery_rate: u64,
 
+    data_delivery_rate: u64,
+
     is_app_limited: bool,
 
     interval: Duration,
@@ -201,6 +289,8 @@ struct RateSample {
 
     prior_delivered: usize,
 
+    prior_delivered_repair: usize,
+
     prior_time: Option<Instant>,
 
     send_elapsed: Duration,
@@ -285,6 +375,84 @@ mod tests {
         assert_eq!(r.delivery_rate(), 48000);
     }
 
+    #[test]
+    fn data_rate_check() {
+        let config = Config::new(0xbabababa).unwrap();
+        let mut r = Recovery::new(&config);
+
+        let now = Instant::now();
+        let mss = r.max_datagram_size();
+
+        // A repair symbol protecting source symbols 0 and 1.
+        let repair_symbol = crate::fec::repair_symbol::build(0, 2, 0, 16);
+
+        // Send a data packet and a repair packet.
+        let frames = [
+            frame::Frame::Ping { mtu_probe: None },
+            frame::Frame::Repair {
+                repair_symbol,
+                compact: false,
+            },
+        ];
+
+        for (pn, frame) in frames.iter().enumerate() {
+            let pkt = Sent {
+                pkt_num: pn as u64,
+                frames: smallvec![frame.clone()],
+                time_sent: now,
+                time_acked: None,
+                time_lost: None,
+                size: mss,
+                ack_eliciting: true,
+                in_flight: true,
+                delivered: 0,
+                delivered_time: now,
+                first_sent_time: now,
+                is_app_limited: false,
+                has_data: false,
+                tx_in_flight: 0,
+                lost: 0,
+                pmtud: false,
+            };
+
+            r.on_packet_sent(
+                pkt,
+                packet::Epoch::Application,
+                HandshakeStatus::default(),
+                now,
+                "",
+            );
+        }
+
+        let rtt = Duration::from_millis(50);
+        let now = now + rtt;
+
+        // Ack 2 packets.
+        for pn in 0..2 {
+            let acked = Acked {
+                pkt_num: pn,
+                time_sent: now,
+                size: mss,
+                rtt,
+                delivered: 0,
+                delivered_time: now,
+                first_sent_time: now.checked_sub(rtt).unwrap(),
+                is_app_limited: false,
+            };
+
+            r.congestion.delivery_rate.update_rate_sample(&acked, now);
+        }
+
+        r.congestion.delivery_rate.generate_rate_sample(rtt);
+
+        assert_eq!(r.delivery_rate(), 48000);
+
+        // Only the data packet is accounted: 1200 / 0.05s = 24000.
+        assert_eq!(r.data_delivery_rate(), 24000);
+
+        assert_eq!(r.max_bandwidth(), 48000);
+    }
+
     #[test]
     fn app_limited_cwnd_full() {
         let config = Config::new(0xbabababa).unwrap();
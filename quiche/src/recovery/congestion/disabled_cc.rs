@@ -24,9 +24,11 @@
 // NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
 // SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-//! Reno Congestion Control
+//! Disabled Congestion Control
 //!
-//! Note that Slow Start can use HyStart++ when enabled.
+//! The congestion window is never limiting, whether losses happened or not.
+//! The sending rate can still be bounded with
+//! `Config::set_disabled_cc_max_rate()`.
 
 use std::time::Instant;
 
@@ -37,6 +39,9 @@ use crate::recovery::Sent;
 use super::Congestion;
 use super::CongestionControlOps;
 
+/// The congestion window of the disabled congestion controller.
+const UNLIMITED_CWND: usize = usize::MAX - 1;
+
 pub static DISABLED_CC: CongestionControlOps = CongestionControlOps {
     on_init,
     on_packet_sent,
@@ -45,10 +50,13 @@ pub static DISABLED_CC: CongestionControlOps = CongestionControlOps {
     checkpoint,
     rollback,
     has_custom_pacing,
+    in_slow_start,
     debug_fmt,
 };
 
-pub fn on_init(_r: &mut Congestion) {}
+pub fn on_init(r: &mut Congestion) {
+    r.congestion_window = UNLIMITED_CWND;
+}
 
 fn on_packet_sent(
     _r: &mut Congestion, _sent_bytes: usize, _bytes_in_flight: usize,
@@ -75,7 +83,7 @@ fn congestion_event(
     r: &mut Congestion, _bytes_in_flight: usize, _lost_bytes: usize,
     _largest_lost_pkt: &Sent, _now: Instant,
 ) {
-    r.congestion_window = usize::MAX - 1;
+    r.congestion_window = UNLIMITED_CWND;
 }
 
 fn checkpoint(_r: &mut Congestion) {}
@@ -88,6 +96,73 @@ fn has_custom_pacing() -> bool {
     false
 }
 
+fn in_slow_start(_r: &Congestion) -> bool {
+    false
+}
+
 fn debug_fmt(_r: &Congestion, _f: &mut std::fmt::Formatter) -> std::fmt::Result {
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::recovery::congestion::test_sender::TestSender;
+    use crate::recovery::Recovery;
+    use crate::CongestionControlAlgorithm;
+
+    fn test_sender() -> TestSender {
+        TestSender::new(CongestionControlAlgorithm::DISABLED, false)
+    }
+
+    #[test]
+    fn disabled_init() {
+        let mut cfg = crate::Config::new(crate::PROTOCOL_VERSION).unwrap();
+        cfg.set_cc_algorithm(CongestionControlAlgorithm::DISABLED);
+
+        let r = Recovery::new(&cfg);
+
+        assert_eq!(r.cwnd(), UNLIMITED_CWND);
+        assert_eq!(r.cwnd_available(Instant::now()), UNLIMITED_CWND);
+    }
+
+    #[test]
+    fn disabled_init_rate_limited() {
+        let mut cfg = crate::Config::new(crate::PROTOCOL_VERSION).unwrap();
+        cfg.set_cc_algorithm(CongestionControlAlgorithm::DISABLED);
+        cfg.set_disabled_cc_max_rate(1_000_000);
+
+        let r = Recovery::new(&cfg);
+
+        // The rate limiter bounds the bytes sent instead of the window.
+        assert_eq!(r.cwnd(), UNLIMITED_CWND);
+        assert_eq!(
+            r.cwnd_available(Instant::now()),
+            r.max_datagram_size() * 10
+        );
+    }
+
+    #[test]
+    fn disabled_acks_and_losses() {
+        let mut sender = test_sender();
+        let size = sender.max_datagram_size;
+
+        assert_eq!(sender.congestion_window, UNLIMITED_CWND);
+        assert!(!sender.in_slow_start());
+
+        for _ in 0..4 {
+            sender.send_packet(size);
+        }
+
+        sender.ack_n_packets(1, size);
+        assert_eq!(sender.congestion_window, UNLIMITED_CWND);
+
+        sender.lose_n_packets(1, size, None);
+        assert_eq!(sender.congestion_window, UNLIMITED_CWND);
+
+        // There is no congestion response to undo.
+        assert!((sender.cc_ops.rollback)(&mut sender));
+        assert_eq!(sender.congestion_window, UNLIMITED_CWND);
+    }
+}
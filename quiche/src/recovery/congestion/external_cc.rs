@@ -0,0 +1,216 @@
+// Copyright (C) 2024, Cloudflare, Inc.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are
+// met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS
+// IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO,
+// THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR
+// PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! External Congestion Control
+//!
+//! The congestion window and the pacing rate are dictated by a congestion
+//! controller running outside of the connection, e.g. the Google Congestion
+//! Control of a WebRTC stack fed with Transport-CC feedback. Losses are still
+//! detected and retransmitted, and repair symbols are still sent, but neither
+//! acknowledgments nor losses change the window by themselves.
+//!
+//! Until the first feedback is ingested, the initial congestion window
+//! applies, without pacing.
+
+use std::time::Instant;
+
+use crate::recovery;
+use crate::recovery::rtt::RttStats;
+use crate::recovery::Acked;
+use crate::recovery::Sent;
+
+use super::Congestion;
+use super::CongestionControlOps;
+
+/// The congestion window relative to the bandwidth-delay product of the
+/// target rate, when the external controller only dictates a rate.
+const BDP_WINDOW_FACTOR: f64 = 2.0;
+
+/// The decision of an external congestion controller.
+///
+/// See [`Connection::ingest_external_cc_feedback()`].
+///
+/// [`Connection::ingest_external_cc_feedback()`]: struct.Connection.html#method.ingest_external_cc_feedback
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExternalCCFeedback {
+    /// The target sending rate in bytes per second, used as pacing rate.
+    pub target_rate: u64,
+
+    /// The congestion window in bytes. If `None`, the window is twice the
+    /// bandwidth-delay product of `target_rate`, so that the pacer is what
+    /// bounds the sending rate.
+    pub congestion_window: Option<usize>,
+}
+
+pub static EXTERNAL_CC: CongestionControlOps = CongestionControlOps {
+    on_init,
+    on_packet_sent,
+    on_packets_acked,
+    congestion_event,
+    checkpoint,
+    rollback,
+    has_custom_pacing,
+    in_slow_start,
+    debug_fmt,
+};
+
+pub fn on_init(_r: &mut Congestion) {}
+
+fn on_packet_sent(
+    _r: &mut Congestion, _sent_bytes: usize, _bytes_in_flight: usize,
+    _now: Instant,
+) {
+}
+
+fn on_packets_acked(
+    _r: &mut Congestion, _bytes_in_flight: usize, _packets: &mut Vec<Acked>,
+    _now: Instant, _rtt_stats: &RttStats,
+) {
+}
+
+fn congestion_event(
+    _r: &mut Congestion, _bytes_in_flight: usize, _lost_bytes: usize,
+    _largest_lost_pkt: &Sent, _now: Instant,
+) {
+}
+
+fn checkpoint(_r: &mut Congestion) {}
+
+fn rollback(_r: &mut Congestion) -> bool {
+    false
+}
+
+fn has_custom_pacing() -> bool {
+    true
+}
+
+fn in_slow_start(_r: &Congestion) -> bool {
+    false
+}
+
+fn debug_fmt(_r: &Congestion, _f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    Ok(())
+}
+
+/// Applies the decision of the external congestion controller.
+pub(crate) fn on_feedback(
+    r: &mut Congestion, feedback: &ExternalCCFeedback, rtt_stats: &RttStats,
+    now: Instant,
+) {
+    let congestion_window = feedback.congestion_window.unwrap_or_else(|| {
+        let bdp =
+            feedback.target_rate as f64 * rtt_stats.smoothed_rtt.as_secs_f64();
+
+        (bdp * BDP_WINDOW_FACTOR) as usize
+    });
+
+    r.congestion_window = congestion_window
+        .max(r.max_datagram_size * recovery::MINIMUM_WINDOW_PACKETS);
+
+    r.set_pacing_rate(feedback.target_rate, now);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use crate::recovery::congestion::test_sender::TestSender;
+    use crate::recovery::Recovery;
+    use crate::CongestionControlAlgorithm;
+
+    fn test_sender() -> TestSender {
+        TestSender::new(CongestionControlAlgorithm::External, false)
+    }
+
+    #[test]
+    fn external_init() {
+        let mut cfg = crate::Config::new(crate::PROTOCOL_VERSION).unwrap();
+        cfg.set_cc_algorithm(CongestionControlAlgorithm::External);
+
+        let r = Recovery::new(&cfg);
+
+        // The initial window applies until the first feedback.
+        assert_eq!(r.cwnd(), r.max_datagram_size() * 10);
+        assert_eq!(r.pacing_rate(), 0);
+    }
+
+    #[test]
+    fn external_acks_and_losses() {
+        let mut sender = test_sender();
+        let size = sender.max_datagram_size;
+        let initial_cwnd = sender.congestion_window;
+
+        for _ in 0..4 {
+            sender.send_packet(size);
+        }
+
+        sender.ack_n_packets(1, size);
+        assert_eq!(sender.congestion_window, initial_cwnd);
+
+        sender.lose_n_packets(1, size, None);
+        assert_eq!(sender.congestion_window, initial_cwnd);
+    }
+
+    #[test]
+    fn external_feedback() {
+        let mut sender = test_sender();
+        let size = sender.max_datagram_size;
+        let now = Instant::now();
+
+        let mut rtt_stats = RttStats::new(Duration::from_millis(25));
+        rtt_stats.update_rtt(
+            Duration::from_millis(100),
+            Duration::ZERO,
+            now,
+            true,
+        );
+
+        // A rate only sets the window from the bandwidth-delay product.
+        let feedback = ExternalCCFeedback {
+            target_rate: 1_000_000,
+            congestion_window: None,
+        };
+        on_feedback(&mut sender, &feedback, &rtt_stats, now);
+
+        assert_eq!(sender.congestion_window, 200_000);
+        assert_eq!(sender.pacer.rate(), 1_000_000);
+
+        // The window is never smaller than the minimum one.
+        let feedback = ExternalCCFeedback {
+            target_rate: 10_000,
+            congestion_window: Some(100),
+        };
+        on_feedback(&mut sender, &feedback, &rtt_stats, now);
+
+        assert_eq!(
+            sender.congestion_window,
+            size * recovery::MINIMUM_WINDOW_PACKETS
+        );
+        assert_eq!(sender.pacer.rate(), 10_000);
+    }
+}
@@ -24,6 +24,7 @@
 // NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
 // SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use std::cmp;
 use std::str::FromStr;
 use std::time::Instant;
 
@@ -32,6 +33,8 @@ use super::Acked;
 use super::RecoveryConfig;
 use super::Sent;
 
+use crate::frame;
+
 pub const PACING_MULTIPLIER: f64 = 1.25;
 pub struct Congestion {
     // Congestion control.
@@ -48,9 +51,31 @@ pub struct Congestion {
     // RFC6937 PRR.
     pub(crate) prr: prr::PRR,
 
+    // Rate limiter of the disabled congestion controller.
+    pub(crate) rate_limiter: Option<rate_limiter::RateLimiter>,
+
+    // Whether the window and the pacing rate are dictated by an external
+    // congestion controller.
+    external: bool,
+
+    // RFC7661 congestion window validation.
+    cwnd_validation: Option<cwnd_validation::CwndValidation>,
+
     // used in a real-time setup (e.g. video-conferencing)
     real_time: bool,
 
+    // Whether the experimental responses to FEC are enabled.
+    pub(crate) experimental_cc_responses: bool,
+
+    // Whether all the data lost in the current congestion event was
+    // recovered by the peer with FEC.
+    pub(crate) fec_recovered_loss: bool,
+
+    // The congestion window and slow start threshold saved before a
+    // bandwidth probe. This is kept apart from the state saved by the
+    // congestion controllers to undo spurious congestion events.
+    probe_checkpoint: Option<(usize, usize)>,
+
     // The maximum size of a data aggregate scheduled and
     // transmitted together.
     send_quantum: usize,
@@ -140,8 +165,40 @@ impl Congestion {
 
             real_time: recovery_config.real_time,
 
+            experimental_cc_responses: recovery_config.experimental_cc_responses,
+
+            fec_recovered_loss: false,
+
+            probe_checkpoint: None,
+
             prr: prr::PRR::default(),
 
+            rate_limiter: match (
+                recovery_config.cc_algorithm,
+                recovery_config.disabled_cc_max_rate,
+            ) {
+                (CongestionControlAlgorithm::DISABLED, Some(rate)) =>
+                    Some(rate_limiter::RateLimiter::new(
+                        rate,
+                        initial_congestion_window,
+                    )),
+
+                _ => None,
+            },
+
+            external: recovery_config.cc_algorithm ==
+                CongestionControlAlgorithm::External,
+
+            // Only window-based congestion controllers are validated.
+            cwnd_validation: match recovery_config.cc_algorithm {
+                CongestionControlAlgorithm::Reno |
+                CongestionControlAlgorithm::CUBIC
+                    if recovery_config.cwnd_validation =>
+                    Some(cwnd_validation::CwndValidation::default()),
+
+                _ => None,
+            },
+
             bbr_state: bbr::State::new(),
 
             bbr2_state: bbr2::State::new(),
@@ -152,6 +209,12 @@ impl Congestion {
         cc
     }
 
+    /// Returns whether the congestion controller is in slow start, or in the
+    /// startup phase for BBR.
+    pub(crate) fn in_slow_start(&self) -> bool {
+        (self.cc_ops.in_slow_start)(self)
+    }
+
     pub(crate) fn in_congestion_recovery(&self, sent_time: Instant) -> bool {
         match self.congestion_recovery_start_time {
             Some(congestion_recovery_start_time) =>
@@ -165,6 +228,14 @@ impl Congestion {
         self.delivery_rate.sample_delivery_rate()
     }
 
+    pub(crate) fn data_delivery_rate(&self) -> u64 {
+        self.delivery_rate.sample_data_delivery_rate()
+    }
+
+    pub(crate) fn max_bandwidth(&self) -> u64 {
+        self.delivery_rate.max_bandwidth()
+    }
+
     pub(crate) fn send_quantum(&self) -> usize {
         self.send_quantum
     }
@@ -181,6 +252,53 @@ impl Congestion {
         self.congestion_window
     }
 
+    /// Saves the congestion window and slow start threshold before a
+    /// bandwidth probe.
+    ///
+    /// The state the congestion controller saves to undo spurious congestion
+    /// events is left untouched.
+    pub(crate) fn checkpoint(&mut self) {
+        self.probe_checkpoint = Some((self.congestion_window, self.ssthresh));
+    }
+
+    /// Restores the state saved by [`checkpoint()`] if the congestion window
+    /// grew since then.
+    ///
+    /// Returns whether the state changed.
+    ///
+    /// [`checkpoint()`]: struct.Congestion.html#method.checkpoint
+    pub(crate) fn rollback(&mut self) -> bool {
+        let (congestion_window, ssthresh) = match self.probe_checkpoint.take() {
+            Some(v) => v,
+
+            None => return false,
+        };
+
+        if self.congestion_window <= congestion_window {
+            return false;
+        }
+
+        self.congestion_window = congestion_window;
+        self.ssthresh = ssthresh;
+
+        true
+    }
+
+    /// Applies the decision of an external congestion controller, and
+    /// returns whether the path uses one.
+    pub(crate) fn on_external_feedback(
+        &mut self, feedback: &external_cc::ExternalCCFeedback,
+        rtt_stats: &RttStats, now: Instant,
+    ) -> bool {
+        if !self.external {
+            return false;
+        }
+
+        external_cc::on_feedback(self, feedback, rtt_stats, now);
+
+        true
+    }
+
     fn update_app_limited(&mut self, v: bool) {
         self.app_limited = v;
     }
@@ -199,6 +317,20 @@ impl Congestion {
 
             self.prr.on_packet_sent(sent_bytes);
 
+            if let Some(rate_limiter) = &mut self.rate_limiter {
+                rate_limiter.on_packet_sent(sent_bytes, now);
+            }
+
+            if let Some(cwnd_validation) = &mut self.cwnd_validation {
+                if pkt
+                    .frames
+                    .iter()
+                    .any(|f| matches!(f, frame::Frame::Repair { .. }))
+                {
+                    cwnd_validation.on_repair_sent(now);
+                }
+            }
+
             // HyStart++: Start of the round in a slow start.
             if self.hystart.enabled() && self.congestion_window < self.ssthresh {
                 self.hystart.start_round(pkt.pkt_num);
@@ -232,6 +364,8 @@ impl Congestion {
             self.delivery_rate.update_rate_sample(pkt, now);
         }
 
+        let acked_bytes = acked.iter().map(|pkt| pkt.size).sum();
+
         // Fill in a rate sample.
         self.delivery_rate.generate_rate_sample(*rtt_stats.min_rtt);
 
@@ -287,6 +421,26 @@ impl Congestion {
             now,
             rtt_stats,
         );
+
+        if let Some(cwnd_validation) = &mut self.cwnd_validation {
+            if let Some((cwnd, ssthresh)) = cwnd_validation.on_packets_acked(
+                acked_bytes,
+                self.congestion_window,
+                self.ssthresh,
+                self.max_datagram_size * self.initial_congestion_window_packets,
+                rtt_stats.smoothed_rtt,
+                now,
+            ) {
+                trace!(
+                    "cwnd validation: non-validated period expired cwnd={} ssthresh={}",
+                    cwnd,
+                    ssthresh
+                );
+
+                self.congestion_window = cwnd;
+                self.ssthresh = ssthresh;
+            }
+        }
     }
 
     fn schedule_next_packet(&mut self, now: Instant, packet_size: usize) {
@@ -307,7 +461,26 @@ impl Congestion {
     }
 
     pub(crate) fn get_packet_send_time(&self) -> Instant {
-        self.pacer.next_time()
+        let next_time = self.pacer.next_time();
+
+        match &self.rate_limiter {
+            Some(rate_limiter) => cmp::max(
+                next_time,
+                rate_limiter.next_time(self.max_datagram_size, next_time),
+            ),
+
+            None => next_time,
+        }
+    }
+
+    /// Returns the time at which the next packet should be released, if
+    /// packets are paced.
+    pub(crate) fn get_packet_release_time(&self) -> Option<Instant> {
+        if self.pacer.enabled() || self.rate_limiter.is_some() {
+            return Some(self.get_packet_send_time());
+        }
+
+        None
     }
 }
 
@@ -316,6 +489,7 @@ impl Congestion {
 /// This enum provides currently available list of congestion control
 /// algorithms.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub enum CongestionControlAlgorithm {
     /// Reno congestion control algorithm. `reno` in a string form.
@@ -327,7 +501,54 @@ pub enum CongestionControlAlgorithm {
     /// BBRv2 congestion control algorithm. `bbr2` in a string form.
     BBR2     = 3,
     /// DISABLED congestion control. `disabled` in a string form.
+    ///
+    /// The sending rate can still be limited to a fixed rate with
+    /// [`Config::set_disabled_cc_max_rate()`].
+    ///
+    /// [`Config::set_disabled_cc_max_rate()`]: struct.Config.html#method.set_disabled_cc_max_rate
     DISABLED = 4,
+    /// External congestion control. `external` in a string form.
+    ///
+    /// The congestion window and the pacing rate are dictated by a
+    /// congestion controller running outside of the connection, with
+    /// [`Connection::ingest_external_cc_feedback()`].
+    ///
+    /// [`Connection::ingest_external_cc_feedback()`]: struct.Connection.html#method.ingest_external_cc_feedback
+    External = 5,
+}
+
+const ALL_CONGESTION_CONTROL_ALGORITHMS: &[CongestionControlAlgorithm] = &[
+    CongestionControlAlgorithm::Reno,
+    CongestionControlAlgorithm::CUBIC,
+    CongestionControlAlgorithm::BBR,
+    CongestionControlAlgorithm::BBR2,
+    CongestionControlAlgorithm::DISABLED,
+    CongestionControlAlgorithm::External,
+];
+
+impl CongestionControlAlgorithm {
+    /// Returns all the available congestion control algorithms.
+    pub fn all() -> &'static [CongestionControlAlgorithm] {
+        ALL_CONGESTION_CONTROL_ALGORITHMS
+    }
+
+    /// Returns the string form of the algorithm.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CongestionControlAlgorithm::Reno => "reno",
+            CongestionControlAlgorithm::CUBIC => "cubic",
+            CongestionControlAlgorithm::BBR => "bbr",
+            CongestionControlAlgorithm::BBR2 => "bbr2",
+            CongestionControlAlgorithm::DISABLED => "disabled",
+            CongestionControlAlgorithm::External => "external",
+        }
+    }
+}
+
+impl std::fmt::Display for CongestionControlAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
 }
 
 impl FromStr for CongestionControlAlgorithm {
@@ -335,17 +556,16 @@ impl FromStr for CongestionControlAlgorithm {
 
     /// Converts a string to `CongestionControlAlgorithm`.
     ///
-    /// If `name` is not valid, `Error::CongestionControl` is returned.
+    /// If `name` does not match any of the algorithms returned by [`all()`],
+    /// `Error::CongestionControl` is returned.
+    ///
+    /// [`all()`]: enum.CongestionControlAlgorithm.html#method.all
     fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
-        match name {
-            "reno" => Ok(CongestionControlAlgorithm::Reno),
-            "cubic" => Ok(CongestionControlAlgorithm::CUBIC),
-            "bbr" => Ok(CongestionControlAlgorithm::BBR),
-            "bbr2" => Ok(CongestionControlAlgorithm::BBR2),
-            "disabled" => Ok(CongestionControlAlgorithm::DISABLED),
-
-            _ => Err(crate::Error::CongestionControl),
-        }
+        Self::all()
+            .iter()
+            .find(|algo| algo.name() == name)
+            .copied()
+            .ok_or(crate::Error::CongestionControl)
     }
 }
 
@@ -381,6 +601,8 @@ pub(crate) struct CongestionControlOps {
 
     pub has_custom_pacing: fn() -> bool,
 
+    pub in_slow_start: fn(r: &Congestion) -> bool,
+
     pub debug_fmt: fn(
         r: &Congestion,
         formatter: &mut std::fmt::Formatter,
@@ -395,6 +617,7 @@ impl From<CongestionControlAlgorithm> for &'static CongestionControlOps {
             CongestionControlAlgorithm::BBR => &bbr::BBR,
             CongestionControlAlgorithm::BBR2 => &bbr2::BBR2,
             CongestionControlAlgorithm::DISABLED => &disabled_cc::DISABLED_CC,
+            CongestionControlAlgorithm::External => &external_cc::EXTERNAL_CC,
         }
     }
 }
@@ -402,11 +625,14 @@ impl From<CongestionControlAlgorithm> for &'static CongestionControlOps {
 mod bbr;
 mod bbr2;
 mod cubic;
+mod cwnd_validation;
 mod delivery_rate;
 mod disabled_cc;
+pub(crate) mod external_cc;
 mod hystart;
 pub(crate) mod pacer;
 mod prr;
+pub(crate) mod rate_limiter;
 mod reno;
 
 #[cfg(test)]
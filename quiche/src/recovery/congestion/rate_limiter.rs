@@ -0,0 +1,130 @@
+// Copyright (C) 2022, Cloudflare, Inc.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are
+// met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS
+// IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO,
+// THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR
+// PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Token bucket limiting the sending rate of paths running the disabled
+//! congestion controller.
+//!
+//! Without congestion control, the congestion window of a path is virtually
+//! infinite, so nothing prevents the path from flooding a constrained link,
+//! e.g. when it only carries repair symbols. The rate limiter makes the bytes
+//! sent on such paths explicitly accountable: tokens are accumulated at the
+//! configured rate up to the bucket capacity, and each packet sent consumes
+//! as many tokens as its size.
+
+use std::time::Duration;
+use std::time::Instant;
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Maximum sending rate (bytes/sec).
+    rate: u64,
+
+    /// Bucket capacity (bytes).
+    capacity: usize,
+
+    /// Bucket available tokens (bytes), as of `last_update`.
+    tokens: usize,
+
+    /// Timestamp of the last tokens update.
+    last_update: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(rate: u64, capacity: usize) -> Self {
+        RateLimiter {
+            rate,
+
+            capacity,
+
+            tokens: capacity,
+
+            last_update: None,
+        }
+    }
+
+    /// Returns the number of bytes that can be sent at `now`.
+    pub fn available(&self, now: Instant) -> usize {
+        let last_update = match self.last_update {
+            Some(v) => v,
+
+            None => return self.tokens,
+        };
+
+        let elapsed = now.saturating_duration_since(last_update);
+        let refill = (elapsed.as_secs_f64() * self.rate as f64) as usize;
+
+        self.tokens.saturating_add(refill).min(self.capacity)
+    }
+
+    /// Consumes the tokens of a packet of `sent_bytes` sent at `now`.
+    pub fn on_packet_sent(&mut self, sent_bytes: usize, now: Instant) {
+        self.tokens = self.available(now).saturating_sub(sent_bytes);
+        self.last_update = Some(now);
+    }
+
+    /// Returns the time at which `size` bytes can be sent.
+    pub fn next_time(&self, size: usize, now: Instant) -> Instant {
+        let available = self.available(now);
+
+        if available >= size || self.rate == 0 {
+            return now;
+        }
+
+        let missing = (size - available) as f64;
+
+        now + Duration::from_secs_f64(missing / self.rate as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limit() {
+        let now = Instant::now();
+        let mut rl = RateLimiter::new(100_000, 12_000);
+
+        // The bucket starts full.
+        assert_eq!(rl.available(now), 12_000);
+
+        rl.on_packet_sent(12_000, now);
+        assert_eq!(rl.available(now), 0);
+        assert_eq!(rl.next_time(1200, now), now + Duration::from_millis(12));
+
+        // 10ms at 100KB/s refill 1000 bytes.
+        let now = now + Duration::from_millis(10);
+        assert_eq!(rl.available(now), 1000);
+
+        rl.on_packet_sent(1000, now);
+        assert_eq!(rl.available(now), 0);
+
+        // The bucket never exceeds its capacity.
+        let now = now + Duration::from_secs(1);
+        assert_eq!(rl.available(now), 12_000);
+        assert_eq!(rl.next_time(1200, now), now);
+    }
+}
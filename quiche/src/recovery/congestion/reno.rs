@@ -48,6 +48,7 @@ pub(crate) static RENO: CongestionControlOps = CongestionControlOps {
     checkpoint,
     rollback,
     has_custom_pacing,
+    in_slow_start,
     debug_fmt,
 };
 
@@ -146,6 +147,10 @@ fn has_custom_pacing() -> bool {
     false
 }
 
+fn in_slow_start(r: &Congestion) -> bool {
+    r.congestion_window < r.ssthresh
+}
+
 fn debug_fmt(_r: &Congestion, _f: &mut std::fmt::Formatter) -> std::fmt::Result {
     Ok(())
 }
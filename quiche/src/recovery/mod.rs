@@ -29,7 +29,9 @@ use std::cmp;
 use std::time::Duration;
 use std::time::Instant;
 
+use std::collections::BTreeMap;
 use std::collections::VecDeque;
+use std::ops::Range;
 
 use crate::packet::Epoch;
 use crate::ranges::RangeSet;
@@ -37,27 +39,30 @@ use crate::Config;
 use crate::CongestionControlAlgorithm;
 use crate::Result;
 
+use crate::fec::recovery_probability;
 use crate::frame;
 use crate::frame::Frame;
 use crate::packet;
 use crate::ranges;
 
 use networkcoding::source_symbol_metadata_to_u64;
+use networkcoding::SourceSymbolMetadata;
 #[cfg(feature = "qlog")]
 use qlog::events::EventData;
 
 use smallvec::SmallVec;
 
 use self::congestion::pacer;
+use self::congestion::external_cc::ExternalCCFeedback;
 use self::congestion::Congestion;
 use self::rtt::RttStats;
 
 // Loss Recovery
-const INITIAL_PACKET_THRESHOLD: u64 = 3;
+pub(crate) const INITIAL_PACKET_THRESHOLD: u64 = 3;
 
 const MAX_PACKET_THRESHOLD: u64 = 20;
 
-const INITIAL_TIME_THRESHOLD: f64 = 9.0 / 8.0;
+pub(crate) const INITIAL_TIME_THRESHOLD: f64 = 9.0 / 8.0;
 
 const GRANULARITY: Duration = Duration::from_millis(1);
 
@@ -65,6 +70,9 @@ const MAX_PTO_PROBES_COUNT: usize = 2;
 
 const MINIMUM_WINDOW_PACKETS: usize = 2;
 
+// The maximum number of loss events kept until the application reads them.
+const MAX_LOSS_EVENTS: usize = 1024;
+
 const LOSS_REDUCTION_FACTOR: f64 = 0.5;
 
 // How many non ACK eliciting packets we send before including a PING to solicit
@@ -74,6 +82,75 @@ pub(super) const MAX_OUTSTANDING_NON_ACK_ELICITING: usize = 24;
 pub enum LostFrame {
     Lost(Frame),
     LostAndRecovered(Frame),
+    /// A frame of a lost packet carrying the given source symbol, to be
+    /// repaired by coded retransmission.
+    Coded(Frame, SourceSymbolMetadata),
+}
+
+/// What caused a packet to be declared lost.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LossTrigger {
+    /// Enough later packets were acknowledged (packet reordering threshold).
+    Reordering,
+
+    /// The packet was not acknowledged in time (time threshold).
+    Timeout,
+
+    /// A later repair symbol protecting the packet was acknowledged.
+    RepairAcked,
+}
+
+/// How the probe timeout grows with the number of consecutive probe
+/// timeouts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PtoBackoff {
+    /// The probe timeout doubles on each probe timeout, as specified in
+    /// RFC 9002.
+    #[default]
+    Exponential,
+
+    /// The probe timeout grows by its initial duration on each probe
+    /// timeout.
+    Linear,
+
+    /// The probe timeout doubles on each probe timeout, up to the given
+    /// number of doublings.
+    Capped(u32),
+}
+
+impl PtoBackoff {
+    /// Returns the factor applied to the probe timeout after `pto_count`
+    /// consecutive probe timeouts.
+    fn multiplier(&self, pto_count: u32) -> u32 {
+        match self {
+            PtoBackoff::Exponential => 2_u32.saturating_pow(pto_count),
+
+            PtoBackoff::Linear => pto_count.saturating_add(1),
+
+            PtoBackoff::Capped(max) =>
+                2_u32.saturating_pow(cmp::min(pto_count, *max)),
+        }
+    }
+}
+
+/// A packet declared lost on a path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PacketLoss {
+    /// The packet number of the lost packet.
+    pub pkt_num: u64,
+
+    /// The size of the lost packet, in bytes.
+    pub size: usize,
+
+    /// The time at which the packet was sent.
+    pub time_sent: Instant,
+
+    /// The time at which the packet was declared lost.
+    pub time_lost: Instant,
+
+    /// What caused the packet to be declared lost.
+    pub trigger: LossTrigger,
 }
 
 #[derive(Default)]
@@ -96,13 +173,76 @@ struct RecoveryEpoch {
     loss_probes: usize,
     in_flight_count: usize,
 
+    /// The number of packets in flight carrying a source symbol.
+    source_symbols_in_flight: usize,
+
+    /// The number of packets in flight carrying a repair symbol.
+    repair_symbols_in_flight: usize,
+
     acked_frames: Vec<frame::Frame>,
     lost_frames: Vec<LostFrame>,
+
+    /// The packet number of the largest acked packet carrying a repair
+    /// symbol, and the source symbols protected by that repair symbol, until
+    /// the next loss detection.
+    largest_acked_repair: Option<(u64, Range<u64>)>,
+
+    /// The size of the repair symbols of the packets declared lost, by packet
+    /// number, until the packets are forgotten.
+    lost_repair_symbol_bytes: BTreeMap<u64, u64>,
+}
+
+// Returns the number of bytes of the REPAIR frames in `frames`.
+fn repair_bytes(frames: &[frame::Frame]) -> usize {
+    frames
+        .iter()
+        .filter(|f| matches!(f, frame::Frame::Repair { .. }))
+        .map(|f| f.wire_len())
+        .sum()
+}
+
+/// The bytes of the packets sent on a path, split between their REPAIR
+/// frames and the rest of the packets.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ByteCounts {
+    pub sent_data: u64,
+    pub sent_repair: u64,
+    pub acked_data: u64,
+    pub acked_repair: u64,
+    pub lost_data: u64,
+    pub lost_repair: u64,
+}
+
+// Returns the size of the repair symbols carried by `frames`, as counted
+// against the connection-level flow control limit.
+fn repair_symbol_bytes(frames: &[frame::Frame]) -> u64 {
+    frames
+        .iter()
+        .map(|f| match f {
+            frame::Frame::Repair { repair_symbol, .. } =>
+                repair_symbol.wire_len() as u64,
+
+            _ => 0,
+        })
+        .sum()
+}
+
+// Returns the number of source symbols and repair symbols in `frames`.
+fn fec_symbols(frames: &[frame::Frame]) -> (usize, usize) {
+    frames.iter().fold((0, 0), |(source, repair), f| match f {
+        frame::Frame::SourceSymbolHeader { .. } => (source + 1, repair),
+
+        frame::Frame::Repair { .. } => (source, repair + 1),
+
+        _ => (source, repair),
+    })
 }
 
 struct AckedDetectionResult {
     acked_bytes: usize,
+    acked_repair_bytes: usize,
     spurious_losses: usize,
+    spurious_repair_symbol_bytes: u64,
     spurious_pkt_thresh: Option<u64>,
     has_ack_eliciting: bool,
     has_in_flight_spurious_loss: bool,
@@ -110,8 +250,11 @@ struct AckedDetectionResult {
 
 struct LossDetectionResult {
     largest_lost_pkt: Option<Sent>,
+    losses: Vec<PacketLoss>,
     lost_packets: usize,
     lost_bytes: usize,
+    lost_repair_bytes: usize,
+    recovered_lost_bytes: usize,
     pmtud_lost_bytes: usize,
 }
 
@@ -123,7 +266,9 @@ impl RecoveryEpoch {
         newly_acked.clear();
 
         let mut acked_bytes = 0;
+        let mut acked_repair_bytes = 0;
         let mut spurious_losses = 0;
+        let mut spurious_repair_symbol_bytes = 0;
         let mut spurious_pkt_thresh = None;
         let mut has_ack_eliciting = false;
         let mut has_in_flight_spurious_loss = false;
@@ -161,13 +306,23 @@ impl RecoveryEpoch {
                         .get_or_insert(largest_acked - unacked.pkt_num + 1);
                     unacked.time_acked = Some(now);
 
+                    spurious_repair_symbol_bytes += self
+                        .lost_repair_symbol_bytes
+                        .remove(&unacked.pkt_num)
+                        .unwrap_or(0);
+
                     if unacked.in_flight {
                         has_in_flight_spurious_loss = true;
                     }
                 } else {
                     if unacked.in_flight {
+                        let (source, repair) = fec_symbols(&unacked.frames);
+
                         self.in_flight_count -= 1;
+                        self.source_symbols_in_flight -= source;
+                        self.repair_symbols_in_flight -= repair;
                         acked_bytes += unacked.size;
+                        acked_repair_bytes += repair_bytes(&unacked.frames);
                     }
 
                     newly_acked.push(Acked {
@@ -184,6 +339,26 @@ impl RecoveryEpoch {
 
                     trace!("{} packet newly acked {}", trace_id, unacked.pkt_num);
 
+                    for frame in &unacked.frames {
+                        if let frame::Frame::Repair { repair_symbol, .. } = frame {
+                            let protected =
+                                crate::fec::repair_symbol::protected_symbols(
+                                    repair_symbol,
+                                );
+
+                            if let Some(range) = protected {
+                                if self
+                                    .largest_acked_repair
+                                    .as_ref()
+                                    .map_or(true, |(pn, _)| unacked.pkt_num > *pn)
+                                {
+                                    self.largest_acked_repair =
+                                        Some((unacked.pkt_num, range));
+                                }
+                            }
+                        }
+                    }
+
                     self.acked_frames
                         .extend(std::mem::take(&mut unacked.frames));
 
@@ -197,16 +372,20 @@ impl RecoveryEpoch {
 
         AckedDetectionResult {
             acked_bytes,
+            acked_repair_bytes,
             spurious_losses,
+            spurious_repair_symbol_bytes,
             spurious_pkt_thresh,
             has_ack_eliciting,
             has_in_flight_spurious_loss,
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn detect_lost_packets(
-        &mut self, loss_delay: Duration, pkt_thresh: u64, now: Instant,
-        trace_id: &str, epoch: Epoch,
+        &mut self, loss_delay: Duration, pkt_thresh: u64,
+        fec_assisted_loss_detection: bool, coded_retransmissions: bool,
+        now: Instant, trace_id: &str, epoch: Epoch,
     ) -> LossDetectionResult {
         self.loss_time = None;
 
@@ -219,9 +398,20 @@ impl RecoveryEpoch {
 
         let mut lost_packets = 0;
         let mut lost_bytes = 0;
+        let mut lost_repair_bytes = 0;
+        let mut recovered_lost_bytes = 0;
         let mut pmtud_lost_bytes = 0;
 
         let mut largest_lost_pkt = None;
+        let mut losses = Vec::new();
+
+        // An acked repair symbol proves that the packets sent before it and
+        // carrying the source symbols it protects should have been received.
+        // They are all declared lost below, so it is only used once.
+        let largest_acked_repair = self
+            .largest_acked_repair
+            .take()
+            .filter(|_| fec_assisted_loss_detection);
 
         let unacked_iter = self.sent_packets
         .iter_mut()
@@ -232,9 +422,75 @@ impl RecoveryEpoch {
 
         for unacked in unacked_iter {
             // Mark packet as lost, or set time when it should be marked.
-            if unacked.time_sent <= lost_send_time ||
-                largest_acked >= unacked.pkt_num + pkt_thresh
-            {
+            let proven_lost_by_repair = largest_acked_repair.as_ref().map_or(
+                false,
+                |(repair_pkt_num, protected)| {
+                    unacked.pkt_num < *repair_pkt_num &&
+                        unacked.frames.iter().any(|f| match f {
+                            frame::Frame::SourceSymbolHeader {
+                                metadata, ..
+                            } => protected.contains(
+                                &source_symbol_metadata_to_u64(*metadata),
+                            ),
+
+                            _ => false,
+                        })
+                },
+            );
+
+            if proven_lost_by_repair {
+                trace!(
+                    "{} packet {} proven lost by an acked repair symbol",
+                    trace_id,
+                    unacked.pkt_num
+                );
+            }
+
+            let trigger = if largest_acked >= unacked.pkt_num + pkt_thresh {
+                Some(LossTrigger::Reordering)
+            } else if unacked.time_sent <= lost_send_time {
+                Some(LossTrigger::Timeout)
+            } else if proven_lost_by_repair {
+                Some(LossTrigger::RepairAcked)
+            } else {
+                None
+            };
+
+            if let Some(trigger) = trigger {
+                let unacked_repair_bytes = repair_bytes(&unacked.frames);
+
+                // Remember the repair symbols of the packet in case it turns
+                // out not to be lost.
+                let lost_repair_symbol_bytes =
+                    repair_symbol_bytes(&unacked.frames);
+                if lost_repair_symbol_bytes > 0 {
+                    self.lost_repair_symbol_bytes
+                        .insert(unacked.pkt_num, lost_repair_symbol_bytes);
+                }
+
+                if unacked.in_flight {
+                    let (source, repair) = fec_symbols(&unacked.frames);
+
+                    self.source_symbols_in_flight -= source;
+                    self.repair_symbols_in_flight -= repair;
+                }
+
+                // The source symbol of the packet, if it must be repaired by
+                // coded retransmission.
+                let coded_symbol = if coded_retransmissions {
+                    unacked.frames.iter().find_map(|f| match f {
+                        frame::Frame::SourceSymbolHeader {
+                            metadata,
+                            recovered: false,
+                            ..
+                        } => Some(*metadata),
+
+                        _ => None,
+                    })
+                } else {
+                    None
+                };
+
                 let mut contains_recovered_source_symbol = false;
                 for frame in &mut unacked.frames.drain(..) {
                     if let frame::Frame::SourceSymbolHeader {
@@ -246,6 +502,18 @@ impl RecoveryEpoch {
                         }
                     }
 
+                    if let Some(metadata) = coded_symbol {
+                        if matches!(
+                            frame,
+                            frame::Frame::SourceSymbolHeader { .. } |
+                                frame::Frame::StreamHeader { .. }
+                        ) {
+                            self.lost_frames
+                                .push(LostFrame::Coded(frame.clone(), metadata));
+                            continue;
+                        }
+                    }
+
                     if contains_recovered_source_symbol {
                         self.lost_frames
                             .push(LostFrame::LostAndRecovered(frame.clone()))
@@ -264,8 +532,21 @@ impl RecoveryEpoch {
                     continue;
                 }
 
+                losses.push(PacketLoss {
+                    pkt_num: unacked.pkt_num,
+                    size: unacked.size,
+                    time_sent: unacked.time_sent,
+                    time_lost: now,
+                    trigger,
+                });
+
                 if unacked.in_flight {
                     lost_bytes += unacked.size;
+                    lost_repair_bytes += unacked_repair_bytes;
+
+                    if contains_recovered_source_symbol {
+                        recovered_lost_bytes += unacked.size;
+                    }
 
                     // Frames have already been removed from the packet, so
                     // cloning the whole packet should be relatively cheap.
@@ -291,14 +572,25 @@ impl RecoveryEpoch {
                 };
 
                 self.loss_time = Some(loss_time);
-                break;
+
+                // Later packets may still be proven lost by the repair symbol.
+                let repair_pending = largest_acked_repair
+                    .as_ref()
+                    .map_or(false, |(pkt_num, _)| unacked.pkt_num < *pkt_num);
+
+                if !repair_pending {
+                    break;
+                }
             }
         }
 
         LossDetectionResult {
             largest_lost_pkt,
+            losses,
             lost_packets,
             lost_bytes,
+            lost_repair_bytes,
+            recovered_lost_bytes,
             pmtud_lost_bytes,
         }
     }
@@ -325,6 +617,18 @@ impl RecoveryEpoch {
 
             self.sent_packets.pop_front();
         }
+
+        // Packets acked after they are forgotten are not detected as
+        // spuriously lost anymore.
+        match self.sent_packets.front() {
+            Some(front) => {
+                let first = front.pkt_num;
+
+                self.lost_repair_symbol_bytes.retain(|pn, _| *pn >= first);
+            },
+
+            None => self.lost_repair_symbol_bytes.clear(),
+        }
     }
 }
 
@@ -358,12 +662,26 @@ pub struct Recovery {
 
     time_thresh: f64,
 
+    pto_backoff: PtoBackoff,
+
+    fec_assisted_loss_detection: bool,
+
+    coded_retransmissions: bool,
+
+    fec_pto_postponement: bool,
+
+    /// The probability for a packet to be lost, as estimated by the FEC
+    /// mechanisms.
+    loss_probability: Option<f64>,
+
     bytes_in_flight: usize,
 
     bytes_sent: usize,
 
     pub bytes_lost: u64,
 
+    byte_counts: ByteCounts,
+
     max_datagram_size: usize,
 
     #[cfg(feature = "qlog")]
@@ -376,6 +694,13 @@ pub struct Recovery {
 
     /// A resusable list of acks.
     newly_acked: Vec<Acked>,
+
+    /// The packets declared lost, not yet read by the application.
+    loss_events: VecDeque<PacketLoss>,
+
+    /// The size of the repair symbols of the packets acked after they were
+    /// declared lost, not yet read by the connection.
+    spurious_repair_symbol_bytes: u64,
 }
 
 pub struct RecoveryConfig {
@@ -386,7 +711,16 @@ pub struct RecoveryConfig {
     pacing: bool,
     max_pacing_rate: Option<u64>,
     initial_congestion_window_packets: usize,
+    packet_reorder_threshold: u64,
+    time_reorder_threshold: f64,
+    pto_backoff: PtoBackoff,
     real_time: bool,
+    fec_assisted_loss_detection: bool,
+    coded_retransmissions: bool,
+    fec_pto_postponement: bool,
+    disabled_cc_max_rate: Option<u64>,
+    cwnd_validation: bool,
+    experimental_cc_responses: bool,
 }
 
 impl RecoveryConfig {
@@ -400,7 +734,16 @@ impl RecoveryConfig {
             max_pacing_rate: config.max_pacing_rate,
             initial_congestion_window_packets: config
                 .initial_congestion_window_packets,
+            packet_reorder_threshold: config.packet_reorder_threshold,
+            time_reorder_threshold: config.time_reorder_threshold,
+            pto_backoff: config.pto_backoff,
             real_time: config.real_time,
+            fec_assisted_loss_detection: config.fec_assisted_loss_detection,
+            coded_retransmissions: config.coded_retransmissions,
+            fec_pto_postponement: config.fec_pto_postponement,
+            disabled_cc_max_rate: config.disabled_cc_max_rate,
+            cwnd_validation: config.cwnd_validation,
+            experimental_cc_responses: config.experimental_cc_responses,
         }
     }
 }
@@ -418,9 +761,20 @@ impl Recovery {
 
             lost_spurious_count: 0,
 
-            pkt_thresh: INITIAL_PACKET_THRESHOLD,
+            pkt_thresh: recovery_config.packet_reorder_threshold,
+
+            time_thresh: recovery_config.time_reorder_threshold,
 
-            time_thresh: INITIAL_TIME_THRESHOLD,
+            pto_backoff: recovery_config.pto_backoff,
+
+            fec_assisted_loss_detection: recovery_config
+                .fec_assisted_loss_detection,
+
+            coded_retransmissions: recovery_config.coded_retransmissions,
+
+            fec_pto_postponement: recovery_config.fec_pto_postponement,
+
+            loss_probability: None,
 
             bytes_in_flight: 0,
 
@@ -428,6 +782,8 @@ impl Recovery {
 
             bytes_lost: 0,
 
+            byte_counts: ByteCounts::default(),
+
             max_datagram_size: recovery_config.max_send_udp_payload_size,
 
             #[cfg(feature = "qlog")]
@@ -438,6 +794,10 @@ impl Recovery {
             congestion: Congestion::from_config(recovery_config),
 
             newly_acked: Vec::new(),
+
+            loss_events: VecDeque::new(),
+
+            spurious_repair_symbol_bytes: 0,
         }
     }
 
@@ -472,6 +832,15 @@ impl Recovery {
         self.epochs[epoch].largest_acked_packet
     }
 
+    /// Schedules `frames` for retransmission as if they were just lost.
+    pub fn requeue_lost_frames(
+        &mut self, epoch: packet::Epoch, frames: Vec<frame::Frame>,
+    ) {
+        self.epochs[epoch]
+            .lost_frames
+            .extend(frames.into_iter().map(LostFrame::Lost));
+    }
+
     pub fn has_lost_frames(&self, epoch: packet::Epoch) -> bool {
         !self.epochs[epoch].lost_frames.is_empty()
     }
@@ -485,6 +854,17 @@ impl Recovery {
         self.epochs[epoch].loss_probes += 1;
     }
 
+    #[cfg(test)]
+    pub fn update_rtt(&mut self, latest_rtt: Duration, now: Instant) {
+        self.rtt_stats
+            .update_rtt(latest_rtt, Duration::ZERO, now, true);
+    }
+
+    #[cfg(test)]
+    pub fn set_cwnd(&mut self, cwnd: usize) {
+        self.congestion.congestion_window = cwnd;
+    }
+
     pub fn ping_sent(&mut self, epoch: packet::Epoch) {
         self.epochs[epoch].loss_probes =
             self.epochs[epoch].loss_probes.saturating_sub(1);
@@ -519,7 +899,11 @@ impl Recovery {
         );
 
         if in_flight {
+            let (source_symbols, repair_symbols) = fec_symbols(&pkt.frames);
+
             self.epochs[epoch].in_flight_count += 1;
+            self.epochs[epoch].source_symbols_in_flight += source_symbols;
+            self.epochs[epoch].repair_symbols_in_flight += repair_symbols;
             self.bytes_in_flight += sent_bytes;
 
             self.set_loss_detection_timer(handshake_status, now);
@@ -527,15 +911,34 @@ impl Recovery {
 
         self.bytes_sent += sent_bytes;
 
+        let sent_repair_bytes = repair_bytes(&pkt.frames);
+        self.byte_counts.sent_repair += sent_repair_bytes as u64;
+        self.byte_counts.sent_data += (sent_bytes - sent_repair_bytes) as u64;
+
         self.epochs[epoch].sent_packets.push_back(pkt);
 
         trace!("{} {:?}", trace_id, self);
     }
 
+    /// Returns the packets sent in the `epoch` space that were neither
+    /// acknowledged nor declared lost yet.
+    pub fn unacked_packets(
+        &self, epoch: packet::Epoch,
+    ) -> impl Iterator<Item = &Sent> {
+        self.epochs[epoch]
+            .sent_packets
+            .iter()
+            .filter(|p| p.time_acked.is_none() && p.time_lost.is_none())
+    }
+
     pub fn get_packet_send_time(&self) -> Instant {
         self.congestion.get_packet_send_time()
     }
 
+    pub fn get_packet_release_time(&self) -> Option<Instant> {
+        self.congestion.get_packet_release_time()
+    }
+
     /// here, the ranges concern source symbol metadata, not packet numbers
     pub fn on_source_symbol_ack_received(
         &mut self, ranges: &ranges::RangeSet, epoch: packet::Epoch,
@@ -543,39 +946,45 @@ impl Recovery {
     ) {
         // Detect and mark recovered source symbols, without considering them
         // acked or anything
-        for r in ranges.iter() {
-            let lowest_recovered_in_block = r.start;
-            let largest_recovered_in_block = r.end - 1;
+        let (lowest_recovered, largest_recovered) =
+            match (ranges.first(), ranges.last()) {
+                (Some(first), Some(last)) => (first, last),
 
-            let epoch = &mut self.epochs[epoch];
+                _ => return,
+            };
 
-            // search in the unacked packets, the one containing source symbols
-            // that have been recovered here
-            let unacked_iter = epoch.sent_packets
-                .iter_mut()
-                // Skip packets that have already been acked or lost.
-                .filter(|p| p.time_acked.is_none());
+        // Search the unacked packets containing source symbols that have
+        // been recovered here in a single pass, whatever the number of
+        // ranges.
+        let unacked_iter = self.epochs[epoch]
+            .sent_packets
+            .iter_mut()
+            // Skip packets that have already been acked or lost.
+            .filter(|p| p.time_acked.is_none());
 
-            for unacked in unacked_iter {
-                for frame in &mut unacked.frames {
-                    if let frame::Frame::SourceSymbolHeader {
-                        metadata,
-                        recovered,
-                    } = frame
+        for unacked in unacked_iter {
+            for frame in &mut unacked.frames {
+                if let frame::Frame::SourceSymbolHeader {
+                    metadata,
+                    recovered,
+                    ..
+                } = frame
+                {
+                    let mdu64 = source_symbol_metadata_to_u64(*metadata);
+                    if mdu64 < lowest_recovered ||
+                        mdu64 > largest_recovered ||
+                        !ranges.contains(mdu64)
                     {
-                        let mdu64 = source_symbol_metadata_to_u64(*metadata);
-                        if lowest_recovered_in_block <= mdu64 &&
-                            mdu64 <= largest_recovered_in_block
-                        {
-                            *recovered = true;
-                            trace!(
-                                "{} source symbol newly recovered {} in pkt {}",
-                                trace_id,
-                                mdu64,
-                                unacked.pkt_num
-                            );
-                        }
+                        continue;
                     }
+
+                    *recovered = true;
+                    trace!(
+                        "{} source symbol newly recovered {} in pkt {}",
+                        trace_id,
+                        mdu64,
+                        unacked.pkt_num
+                    );
                 }
             }
         }
@@ -599,7 +1008,9 @@ impl Recovery {
 
         let AckedDetectionResult {
             acked_bytes,
+            acked_repair_bytes,
             spurious_losses,
+            spurious_repair_symbol_bytes,
             spurious_pkt_thresh,
             has_ack_eliciting,
             has_in_flight_spurious_loss,
@@ -612,6 +1023,7 @@ impl Recovery {
         );
 
         self.lost_spurious_count += spurious_losses;
+        self.spurious_repair_symbol_bytes += spurious_repair_symbol_bytes;
         if let Some(thresh) = spurious_pkt_thresh {
             self.pkt_thresh =
                 self.pkt_thresh.max(thresh.min(MAX_PACKET_THRESHOLD));
@@ -652,6 +1064,9 @@ impl Recovery {
 
         self.bytes_in_flight -= acked_bytes;
 
+        self.byte_counts.acked_repair += acked_repair_bytes as u64;
+        self.byte_counts.acked_data += (acked_bytes - acked_repair_bytes) as u64;
+
         self.pto_count = 0;
 
         self.set_loss_detection_timer(handshake_status, now);
@@ -769,6 +1184,8 @@ impl Recovery {
         epoch.loss_time = None;
         epoch.loss_probes = 0;
         epoch.in_flight_count = 0;
+        epoch.source_symbols_in_flight = 0;
+        epoch.repair_symbols_in_flight = 0;
 
         self.set_loss_detection_timer(handshake_status, now);
     }
@@ -788,17 +1205,29 @@ impl Recovery {
         self.congestion.congestion_window()
     }
 
-    pub fn cwnd_available(&self) -> usize {
+    pub fn cwnd_available(&self, now: Instant) -> usize {
         // Ignore cwnd when sending probe packets.
         if self.epochs.iter().any(|e| e.loss_probes > 0) {
             return usize::MAX;
         }
 
+        // Without congestion control, the rate limiter bounds the amount of
+        // bytes that can be sent.
+        if let Some(rate_limiter) = &self.congestion.rate_limiter {
+            return rate_limiter.available(now);
+        }
+
         // Open more space (snd_cnt) for PRR when allowed.
         self.cwnd().saturating_sub(self.bytes_in_flight) +
             self.congestion.prr.snd_cnt
     }
 
+    /// Returns the bytes sent, acknowledged and lost on the path, split
+    /// between the REPAIR frames and the rest of the packets.
+    pub fn byte_counts(&self) -> ByteCounts {
+        self.byte_counts
+    }
+
     pub fn bif(&self) -> usize {
         self.bytes_in_flight
     }
@@ -815,6 +1244,26 @@ impl Recovery {
         self.rtt_stats.rttvar
     }
 
+    pub fn latest_rtt(&self) -> Duration {
+        self.rtt_stats.latest_rtt
+    }
+
+    /// Saves the state of the congestion controller, to be restored with
+    /// [`rollback_congestion()`] if a bandwidth probe fails.
+    ///
+    /// [`rollback_congestion()`]: struct.Recovery.html#method.rollback_congestion
+    pub fn checkpoint_congestion(&mut self) {
+        self.congestion.checkpoint();
+    }
+
+    /// Restores the state of the congestion controller saved by
+    /// [`checkpoint_congestion()`], and returns whether it changed.
+    ///
+    /// [`checkpoint_congestion()`]: struct.Recovery.html#method.checkpoint_congestion
+    pub fn rollback_congestion(&mut self) -> bool {
+        self.congestion.rollback()
+    }
+
     pub fn packets_lost_per_round_trip(&self) -> Option<f64> {
         self.congestion.smoothed_lost_packets_per_epoch
     }
@@ -823,6 +1272,56 @@ impl Recovery {
         self.congestion.var_lost_packets_per_epoch
     }
 
+    /// Sets the probability for a packet to be lost, used to estimate the
+    /// probability that the peer recovers the current flight.
+    pub fn set_loss_probability(&mut self, v: Option<f64>) {
+        self.loss_probability = v;
+    }
+
+    /// Returns the probability that the peer gets or recovers with FEC all
+    /// the application packets in flight, or `None` without loss estimates.
+    pub fn flight_recovery_probability(&self) -> Option<f64> {
+        let loss_probability = self.loss_probability?;
+
+        let epoch = &self.epochs[packet::Epoch::Application];
+
+        let unprotected = epoch
+            .in_flight_count
+            .saturating_sub(epoch.source_symbols_in_flight)
+            .saturating_sub(epoch.repair_symbols_in_flight);
+
+        Some(recovery_probability::flight_recovery_probability(
+            loss_probability,
+            unprotected,
+            epoch.source_symbols_in_flight,
+            epoch.repair_symbols_in_flight,
+        ))
+    }
+
+    pub fn ssthresh(&self) -> usize {
+        self.congestion.ssthresh
+    }
+
+    pub fn pacing_rate(&self) -> u64 {
+        self.congestion.pacer.rate()
+    }
+
+    pub fn in_slow_start(&self) -> bool {
+        self.congestion.in_slow_start()
+    }
+
+    /// Returns the time the pacer takes to send a full-sized packet, or `None`
+    /// if the pacing rate is not known yet.
+    pub fn pacing_interval(&self) -> Option<Duration> {
+        match self.congestion.pacer.rate() {
+            0 => None,
+
+            rate => Some(Duration::from_secs_f64(
+                self.max_datagram_size as f64 / rate as f64,
+            )),
+        }
+    }
+
     pub fn pto(&self) -> Duration {
         self.rtt() + cmp::max(self.rtt_stats.rttvar * 4, GRANULARITY)
     }
@@ -831,6 +1330,14 @@ impl Recovery {
         self.congestion.delivery_rate()
     }
 
+    pub fn data_delivery_rate(&self) -> u64 {
+        self.congestion.data_delivery_rate()
+    }
+
+    pub fn max_bandwidth(&self) -> u64 {
+        self.congestion.max_bandwidth()
+    }
+
     pub fn max_datagram_size(&self) -> usize {
         self.max_datagram_size
     }
@@ -884,7 +1391,8 @@ impl Recovery {
     fn pto_time_and_space(
         &self, handshake_status: HandshakeStatus, now: Instant,
     ) -> (Option<Instant>, packet::Epoch) {
-        let mut duration = self.pto() * 2_u32.pow(self.pto_count);
+        let backoff = self.pto_backoff.multiplier(self.pto_count);
+        let mut duration = self.pto() * backoff;
 
         // Arm PTO from now when there are no inflight packets.
         if self.bytes_in_flight == 0 {
@@ -916,8 +1424,16 @@ impl Recovery {
                 }
 
                 // Include max_ack_delay and backoff for Application Data.
-                duration +=
-                    self.rtt_stats.max_ack_delay * 2_u32.pow(self.pto_count);
+                duration += self.rtt_stats.max_ack_delay * backoff;
+
+                // Give the peer time to recover the losses with the repair
+                // symbols in flight before probing.
+                if self.fec_pto_postponement && epoch.repair_symbols_in_flight > 0
+                {
+                    if let Some(p) = self.flight_recovery_probability() {
+                        duration = duration.mul_f64(1.0 + p);
+                    }
+                }
             }
 
             let new_time = epoch
@@ -965,6 +1481,8 @@ impl Recovery {
         let loss = self.epochs[epoch].detect_lost_packets(
             loss_delay,
             self.pkt_thresh,
+            self.fec_assisted_loss_detection,
+            self.coded_retransmissions,
             now,
             trace_id,
             epoch,
@@ -989,6 +1507,10 @@ impl Recovery {
                 (self.congestion.cc_ops.checkpoint)(&mut self.congestion);
             }
 
+            // All the lost data was recovered by the peer with FEC.
+            self.congestion.fec_recovered_loss =
+                loss.recovered_lost_bytes == loss.lost_bytes;
+
             (self.congestion.cc_ops.congestion_event)(
                 &mut self.congestion,
                 self.bytes_in_flight,
@@ -1000,6 +1522,11 @@ impl Recovery {
             self.bytes_in_flight -= loss.lost_bytes;
         };
 
+        self.bytes_lost += loss.lost_bytes as u64;
+        self.byte_counts.lost_repair += loss.lost_repair_bytes as u64;
+        self.byte_counts.lost_data +=
+            (loss.lost_bytes - loss.lost_repair_bytes) as u64;
+
         self.bytes_in_flight -= loss.pmtud_lost_bytes;
 
         self.epochs[epoch]
@@ -1007,14 +1534,34 @@ impl Recovery {
 
         self.congestion.lost_count += loss.lost_packets;
 
+        for packet_loss in loss.losses {
+            if self.loss_events.len() >= MAX_LOSS_EVENTS {
+                self.loss_events.pop_front();
+            }
+
+            self.loss_events.push_back(packet_loss);
+        }
+
         (loss.lost_packets, loss.lost_bytes)
     }
 
+    /// Returns the size of the repair symbols of the packets acked after they
+    /// were declared lost, since the last call.
+    pub fn take_spurious_repair_symbol_bytes(&mut self) -> u64 {
+        std::mem::take(&mut self.spurious_repair_symbol_bytes)
+    }
+
+    /// Removes and returns the packets declared lost since the last call.
+    ///
+    /// Only the latest `MAX_LOSS_EVENTS` losses are kept.
+    pub fn take_loss_events(&mut self) -> VecDeque<PacketLoss> {
+        std::mem::take(&mut self.loss_events)
+    }
+
     pub fn update_app_limited(&mut self, v: bool) {
         self.congestion.app_limited = v;
     }
 
-    #[cfg(test)]
     pub fn app_limited(&self) -> bool {
         self.congestion.app_limited
     }
@@ -1051,6 +1598,15 @@ impl Recovery {
         self.congestion.set_send_quantum(v);
     }
 
+    /// Applies the decision of an external congestion controller, and
+    /// returns whether the path uses one.
+    pub fn on_external_cc_feedback(
+        &mut self, feedback: &ExternalCCFeedback, now: Instant,
+    ) -> bool {
+        self.congestion
+            .on_external_feedback(feedback, &self.rtt_stats, now)
+    }
+
     pub fn lost_count(&self) -> usize {
         self.congestion.lost_count
     }
@@ -1330,6 +1886,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cc_algo_names() {
+        for algo in CongestionControlAlgorithm::all() {
+            assert_eq!(
+                CongestionControlAlgorithm::from_str(&algo.to_string()),
+                Ok(*algo)
+            );
+        }
+
+        // The values are part of the FFI, and must not change.
+        let values: Vec<u32> = CongestionControlAlgorithm::all()
+            .iter()
+            .map(|algo| *algo as u32)
+            .collect();
+        assert_eq!(values, [0, 1, 2, 3, 4, 5]);
+    }
+
     #[test]
     fn loss_on_pto() {
         let mut cfg = crate::Config::new(crate::PROTOCOL_VERSION).unwrap();
@@ -1747,6 +2320,14 @@ mod tests {
 
         assert_eq!(r.congestion.lost_count, 1);
 
+        let losses: Vec<PacketLoss> = r.take_loss_events().into();
+        assert_eq!(losses.len(), 1);
+        assert_eq!(losses[0].pkt_num, 2);
+        assert_eq!(losses[0].size, 1000);
+        assert_eq!(losses[0].time_lost, now);
+        assert_eq!(losses[0].trigger, LossTrigger::Timeout);
+        assert!(r.take_loss_events().is_empty());
+
         // Wait 1 RTT.
         now += r.rtt();
 
@@ -1755,6 +2336,41 @@ mod tests {
         assert_eq!(r.epochs[packet::Epoch::Application].sent_packets.len(), 0);
     }
 
+    #[test]
+    fn configured_reorder_thresholds() {
+        let mut cfg = crate::Config::new(crate::PROTOCOL_VERSION).unwrap();
+
+        let r = Recovery::new(&cfg);
+        assert_eq!(r.pkt_thresh, INITIAL_PACKET_THRESHOLD);
+        assert_eq!(r.time_thresh, INITIAL_TIME_THRESHOLD);
+
+        cfg.set_packet_reorder_threshold(5);
+        cfg.set_time_reorder_threshold(1.5);
+
+        let r = Recovery::new(&cfg);
+        assert_eq!(r.pkt_thresh, 5);
+        assert_eq!(r.time_thresh, 1.5);
+
+        cfg.set_packet_reorder_threshold(0);
+        cfg.set_time_reorder_threshold(0.5);
+
+        let r = Recovery::new(&cfg);
+        assert_eq!(r.pkt_thresh, 1);
+        assert_eq!(r.time_thresh, 1.0);
+    }
+
+    #[test]
+    fn pto_backoff() {
+        let multipliers = |backoff: PtoBackoff| {
+            (0..5).map(|n| backoff.multiplier(n)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(multipliers(PtoBackoff::Exponential), [1, 2, 4, 8, 16]);
+        assert_eq!(multipliers(PtoBackoff::Linear), [1, 2, 3, 4, 5]);
+        assert_eq!(multipliers(PtoBackoff::Capped(2)), [1, 2, 4, 4, 4]);
+        assert_eq!(PtoBackoff::Exponential.multiplier(40), u32::MAX);
+    }
+
     #[test]
     fn loss_on_reordering() {
         let mut cfg = crate::Config::new(crate::PROTOCOL_VERSION).unwrap();
@@ -1902,6 +2518,11 @@ mod tests {
             Ok((1, 1000, 1000 * 2))
         );
 
+        let losses: Vec<PacketLoss> = r.take_loss_events().into();
+        assert_eq!(losses.len(), 1);
+        assert_eq!(losses[0].pkt_num, 0);
+        assert_eq!(losses[0].trigger, LossTrigger::Reordering);
+
         now += Duration::from_millis(10);
 
         let mut acked = ranges::RangeSet::default();
@@ -1939,6 +2560,262 @@ mod tests {
         assert_eq!(r.epochs[packet::Epoch::Application].sent_packets.len(), 0);
     }
 
+    #[test]
+    fn loss_on_acked_repair() {
+        let mut cfg = crate::Config::new(crate::PROTOCOL_VERSION).unwrap();
+        cfg.set_cc_algorithm(CongestionControlAlgorithm::Reno);
+        cfg.enable_fec_assisted_loss_detection(true);
+
+        let mut r = Recovery::new(&cfg);
+
+        let mut now = Instant::now();
+
+        // A repair symbol protecting source symbols 0 and 1.
+        let repair_symbol = crate::fec::repair_symbol::build(0, 2, 0, 16);
+
+        let frames = [
+            frame::Frame::SourceSymbolHeader {
+                metadata: networkcoding::source_symbol_metadata_from_u64(0),
+                recovered: false,
+                compact: false,
+            },
+            frame::Frame::SourceSymbolHeader {
+                metadata: networkcoding::source_symbol_metadata_from_u64(1),
+                recovered: false,
+                compact: false,
+            },
+            frame::Frame::Repair {
+                repair_symbol,
+                compact: false,
+            },
+        ];
+
+        for (pkt_num, frame) in frames.iter().enumerate() {
+            let p = Sent {
+                pkt_num: pkt_num as u64,
+                frames: smallvec![frame.clone()],
+                time_sent: now,
+                time_acked: None,
+                time_lost: None,
+                size: 1000,
+                ack_eliciting: true,
+                in_flight: true,
+                delivered: 0,
+                delivered_time: now,
+                first_sent_time: now,
+                is_app_limited: false,
+                tx_in_flight: 0,
+                lost: 0,
+                has_data: false,
+                pmtud: false,
+            };
+
+            r.on_packet_sent(
+                p,
+                packet::Epoch::Application,
+                HandshakeStatus::default(),
+                now,
+                "",
+            );
+        }
+
+        assert_eq!(r.bytes_in_flight, 3000);
+
+        now += Duration::from_millis(10);
+
+        // The repair symbol is acked, but not the first source symbol. The
+        // packet threshold is not reached, yet the first packet is lost.
+        let mut acked = ranges::RangeSet::default();
+        acked.insert(1..3);
+
+        assert_eq!(
+            r.on_ack_received(
+                &acked,
+                25,
+                packet::Epoch::Application,
+                HandshakeStatus::default(),
+                now,
+                "",
+            ),
+            Ok((1, 1000, 1000 * 2))
+        );
+
+        assert_eq!(r.bytes_in_flight, 0);
+        assert_eq!(r.lost_count(), 1);
+
+        // The acked repair symbol is not scanned for again.
+        assert!(r.epochs[packet::Epoch::Application]
+            .largest_acked_repair
+            .is_none());
+    }
+
+    #[test]
+    fn source_symbol_ack() {
+        let mut cfg = crate::Config::new(crate::PROTOCOL_VERSION).unwrap();
+        cfg.set_cc_algorithm(CongestionControlAlgorithm::Reno);
+
+        let mut r = Recovery::new(&cfg);
+
+        let now = Instant::now();
+
+        for pkt_num in 0..10 {
+            let p = Sent {
+                pkt_num,
+                frames: smallvec![frame::Frame::SourceSymbolHeader {
+                    metadata: networkcoding::source_symbol_metadata_from_u64(
+                        pkt_num
+                    ),
+                    recovered: false,
+                    compact: false,
+                }],
+                time_sent: now,
+                time_acked: None,
+                time_lost: None,
+                size: 1000,
+                ack_eliciting: true,
+                in_flight: true,
+                delivered: 0,
+                delivered_time: now,
+                first_sent_time: now,
+                is_app_limited: false,
+                tx_in_flight: 0,
+                lost: 0,
+                has_data: false,
+                pmtud: false,
+            };
+
+            r.on_packet_sent(
+                p,
+                packet::Epoch::Application,
+                HandshakeStatus::default(),
+                now,
+                "",
+            );
+        }
+
+        let mut recovered = ranges::RangeSet::default();
+        recovered.insert(2..4);
+        recovered.insert(7..8);
+        recovered.insert(20..30);
+
+        r.on_source_symbol_ack_received(
+            &recovered,
+            packet::Epoch::Application,
+            "",
+        );
+
+        let recovered: Vec<u64> = r.epochs[packet::Epoch::Application]
+            .sent_packets
+            .iter()
+            .filter(|p| {
+                matches!(p.frames[0], frame::Frame::SourceSymbolHeader {
+                    recovered: true,
+                    ..
+                })
+            })
+            .map(|p| p.pkt_num)
+            .collect();
+
+        assert_eq!(recovered, vec![2, 3, 7]);
+    }
+
+    #[test]
+    fn flight_recovery_probability() {
+        let mut cfg = crate::Config::new(crate::PROTOCOL_VERSION).unwrap();
+        cfg.set_cc_algorithm(CongestionControlAlgorithm::Reno);
+        cfg.enable_fec_pto_postponement(true);
+
+        let mut r = Recovery::new(&cfg);
+
+        let mut now = Instant::now();
+
+        // A repair symbol protecting source symbols 0 and 1.
+        let repair_symbol = crate::fec::repair_symbol::build(0, 2, 0, 16);
+
+        let frames = [
+            frame::Frame::SourceSymbolHeader {
+                metadata: networkcoding::source_symbol_metadata_from_u64(0),
+                recovered: false,
+                compact: false,
+            },
+            frame::Frame::SourceSymbolHeader {
+                metadata: networkcoding::source_symbol_metadata_from_u64(1),
+                recovered: false,
+                compact: false,
+            },
+            frame::Frame::Repair {
+                repair_symbol,
+                compact: false,
+            },
+        ];
+
+        for (pkt_num, frame) in frames.iter().enumerate() {
+            let p = Sent {
+                pkt_num: pkt_num as u64,
+                frames: smallvec![frame.clone()],
+                time_sent: now,
+                time_acked: None,
+                time_lost: None,
+                size: 1000,
+                ack_eliciting: true,
+                in_flight: true,
+                delivered: 0,
+                delivered_time: now,
+                first_sent_time: now,
+                is_app_limited: false,
+                tx_in_flight: 0,
+                lost: 0,
+                has_data: false,
+                pmtud: false,
+            };
+
+            r.on_packet_sent(
+                p,
+                packet::Epoch::Application,
+                HandshakeStatus::default(),
+                now,
+                "",
+            );
+        }
+
+        // Without loss estimates, the PTO is not postponed.
+        assert_eq!(r.flight_recovery_probability(), None);
+        let pto_timeout = r.loss_detection_timer().unwrap();
+
+        // The flight is recovered unless two packets are lost.
+        r.set_loss_probability(Some(0.1));
+        let p = r.flight_recovery_probability().unwrap();
+        assert!((p - 0.972).abs() < 1e-9);
+
+        r.set_loss_detection_timer(HandshakeStatus::default(), now);
+        assert_eq!(
+            r.loss_detection_timer(),
+            Some(now + (pto_timeout - now).mul_f64(1.0 + p))
+        );
+
+        now += Duration::from_millis(10);
+
+        // Once the repair symbol is acked, both source symbols must be
+        // received.
+        let mut acked = ranges::RangeSet::default();
+        acked.insert(2..3);
+
+        assert_eq!(
+            r.on_ack_received(
+                &acked,
+                25,
+                packet::Epoch::Application,
+                HandshakeStatus::default(),
+                now,
+                "",
+            ),
+            Ok((0, 0, 1000))
+        );
+
+        let p = r.flight_recovery_probability().unwrap();
+        assert!((p - 0.81).abs() < 1e-9);
+    }
+
     #[test]
     fn pacing() {
         let mut cfg = crate::Config::new(crate::PROTOCOL_VERSION).unwrap();
@@ -2267,6 +3144,37 @@ mod tests {
         assert_eq!(r.bytes_in_flight, 0);
         assert_eq!(r.congestion.lost_count, 0);
     }
+
+    #[test]
+    fn checkpoint_rollback_congestion() {
+        let mut cfg = crate::Config::new(crate::PROTOCOL_VERSION).unwrap();
+        cfg.set_cc_algorithm(CongestionControlAlgorithm::CUBIC);
+
+        let mut r = Recovery::new(&cfg);
+        let cwnd = r.cwnd();
+
+        // Nothing to roll back to.
+        assert!(!r.rollback_congestion());
+
+        // The window grown since the checkpoint is undone.
+        r.checkpoint_congestion();
+        r.set_cwnd(cwnd * 2);
+
+        assert!(r.rollback_congestion());
+        assert_eq!(r.cwnd(), cwnd);
+
+        // The checkpoint is consumed.
+        r.set_cwnd(cwnd * 2);
+        assert!(!r.rollback_congestion());
+        assert_eq!(r.cwnd(), cwnd * 2);
+
+        // A smaller window is left as is.
+        r.checkpoint_congestion();
+        r.set_cwnd(cwnd);
+
+        assert!(!r.rollback_congestion());
+        assert_eq!(r.cwnd(), cwnd);
+    }
 }
 
 pub mod congestion;
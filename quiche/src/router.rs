@@ -0,0 +1,224 @@
+// Copyright (C) 2024, Cloudflare, Inc.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are
+// met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS
+// IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO,
+// THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR
+// PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::SocketAddr;
+
+use crate::packet::Header;
+use crate::Connection;
+use crate::ConnectionId;
+
+/// The connection IDs and the 4-tuples registered for a connection.
+struct Routes {
+    ids: Vec<ConnectionId<'static>>,
+    tuples: Vec<(SocketAddr, SocketAddr)>,
+}
+
+/// Maps incoming datagrams to the connection they belong to, in a server
+/// receiving on several sockets.
+///
+/// Datagrams are routed by the destination connection ID of their packet
+/// header, whatever the socket they were received on. Short header packets
+/// whose connection ID is unknown, e.g. because the server uses zero-length
+/// connection IDs, are routed by the (local, peer) 4-tuple they were received
+/// on instead, which covers all the paths of the connections, including the
+/// one dedicated to repair symbols.
+///
+/// Connections are identified by a handle of type `T` chosen by the
+/// application, e.g. the index of the connection in a slab. The routes of a
+/// connection are refreshed by [`update()`] after it processed or sent
+/// packets, so that new connection IDs and new paths are picked up, and
+/// retired ones are dropped.
+///
+/// ## Examples:
+///
+/// ```no_run
+/// # let mut buf = [0; 512];
+/// # let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+/// # let mut config = quiche::Config::new(quiche::PROTOCOL_VERSION)?;
+/// # let scid = quiche::ConnectionId::from_ref(&[0xba; 16]);
+/// # let local = socket.local_addr().unwrap();
+/// # let peer = "127.0.0.1:1234".parse().unwrap();
+/// # let mut conns = vec![quiche::accept(&scid, None, local, peer, &mut config)?];
+/// let mut router = quiche::ConnectionIdRouter::new(16);
+/// router.update(&conns[0], 0);
+///
+/// let (len, from) = socket.recv_from(&mut buf).unwrap();
+///
+/// if let Some(&i) = router.route(&buf[..len], local, from) {
+///     let recv_info = quiche::RecvInfo { from, to: local };
+///     conns[i].recv(&mut buf[..len], recv_info)?;
+///     router.update(&conns[i], i);
+/// }
+/// # Ok::<(), quiche::Error>(())
+/// ```
+///
+/// [`update()`]: struct.ConnectionIdRouter.html#method.update
+pub struct ConnectionIdRouter<T> {
+    /// The length of the connection IDs issued by the server.
+    cid_len: usize,
+
+    ids: HashMap<ConnectionId<'static>, T>,
+
+    tuples: HashMap<(SocketAddr, SocketAddr), T>,
+
+    routes: HashMap<T, Routes>,
+}
+
+impl<T: Clone + Eq + Hash> ConnectionIdRouter<T> {
+    /// Creates a router for connection IDs of `cid_len` bytes, the length of
+    /// the source connection IDs of the server.
+    pub fn new(cid_len: usize) -> Self {
+        ConnectionIdRouter {
+            cid_len,
+            ids: HashMap::new(),
+            tuples: HashMap::new(),
+            routes: HashMap::new(),
+        }
+    }
+
+    /// Registers the active source connection IDs and the paths of `conn`
+    /// under `handle`.
+    ///
+    /// The connection IDs and the 4-tuples previously registered under
+    /// `handle` that `conn` doesn't use anymore are unregistered.
+    pub fn update(&mut self, conn: &Connection, handle: T) {
+        let ids: Vec<ConnectionId<'static>> = conn
+            .source_ids()
+            .filter(|cid| !cid.is_empty())
+            .map(|cid| cid.clone().into_owned())
+            .collect();
+
+        let tuples: Vec<(SocketAddr, SocketAddr)> = conn
+            .path_views()
+            .map(|p| (p.local_addr, p.peer_addr))
+            .collect();
+
+        self.remove(&handle);
+
+        for cid in &ids {
+            self.ids.insert(cid.clone(), handle.clone());
+        }
+
+        for tuple in &tuples {
+            self.tuples.insert(*tuple, handle.clone());
+        }
+
+        self.routes.insert(handle, Routes { ids, tuples });
+    }
+
+    /// Unregisters the connection IDs and the 4-tuples of the connection
+    /// identified by `handle`, e.g. once it is closed.
+    pub fn remove(&mut self, handle: &T) {
+        let routes = match self.routes.remove(handle) {
+            Some(routes) => routes,
+
+            None => return,
+        };
+
+        // Another connection may have taken over some of the routes since.
+        for cid in &routes.ids {
+            if self.ids.get(cid) == Some(handle) {
+                self.ids.remove(cid);
+            }
+        }
+
+        for tuple in &routes.tuples {
+            if self.tuples.get(tuple) == Some(handle) {
+                self.tuples.remove(tuple);
+            }
+        }
+    }
+
+    /// Returns the handle of the connection the datagram in `buf`, received
+    /// on `local` from `peer`, belongs to.
+    ///
+    /// `None` is returned if the datagram doesn't belong to a known
+    /// connection, e.g. because it opens a new one. Long header packets are
+    /// only routed by their connection ID.
+    pub fn route(
+        &self, buf: &[u8], local: SocketAddr, peer: SocketAddr,
+    ) -> Option<&T> {
+        let (dcid, is_long) = dcid_from_slice(buf, self.cid_len)?;
+
+        if let Some(handle) = self.ids.get(&ConnectionId::from_ref(dcid)) {
+            return Some(handle);
+        }
+
+        if is_long {
+            return None;
+        }
+
+        self.tuples.get(&(local, peer))
+    }
+
+    /// Returns the number of connections registered.
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Returns true if no connection is registered.
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+}
+
+/// Returns the destination connection ID of the packet in `buf`, and whether
+/// the packet has a long header.
+fn dcid_from_slice(buf: &[u8], cid_len: usize) -> Option<(&[u8], bool)> {
+    let mut b = octets::Octets::with_slice(buf);
+
+    let first = b.get_u8().ok()?;
+
+    if !Header::is_long(first) {
+        return Some((b.get_bytes(cid_len).ok()?.buf(), false));
+    }
+
+    let _version = b.get_u32().ok()?;
+    let dcid = b.get_bytes_with_u8_length().ok()?;
+
+    Some((dcid.buf(), true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dcid_from_headers() {
+        let short = [0x40, 1, 2, 3, 4, 0xff];
+        assert_eq!(dcid_from_slice(&short, 4), Some((&short[1..5], false)));
+        assert_eq!(dcid_from_slice(&short, 0), Some((&short[1..1], false)));
+        assert_eq!(dcid_from_slice(&short, 8), None);
+
+        let long = [0xc0, 0, 0, 0, 1, 2, 0xaa, 0xbb, 0];
+        assert_eq!(dcid_from_slice(&long, 4), Some((&long[6..8], true)));
+        assert_eq!(dcid_from_slice(&long[..7], 4), None);
+
+        assert_eq!(dcid_from_slice(&[], 4), None);
+    }
+}
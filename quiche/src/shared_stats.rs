@@ -0,0 +1,315 @@
+// Copyright (C) 2024, Cloudflare, Inc.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are
+// met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS
+// IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO,
+// THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR
+// PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::sync::atomic::fence;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// The number of counters in a [`StatsSnapshot`].
+const COUNTERS: usize = 25;
+
+/// The counters of the statistics of a connection, read from a
+/// [`SharedStats`].
+///
+/// The fields have the same meaning as the ones of [`Stats`].
+///
+/// [`SharedStats`]: struct.SharedStats.html
+/// [`Stats`]: struct.Stats.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StatsSnapshot {
+    /// The number of QUIC packets received.
+    pub recv: usize,
+
+    /// The number of QUIC packets sent.
+    pub sent: usize,
+
+    /// The number of QUIC packets that were lost.
+    pub lost: usize,
+
+    /// The number of sent QUIC packets with retransmitted data.
+    pub retrans: usize,
+
+    /// The number of source symbols recovered using FEC.
+    pub recov: usize,
+
+    /// The number of repair symbols sent.
+    pub repair_sent: usize,
+
+    /// The number of repair symbols received.
+    pub repair_received: usize,
+
+    /// The number of source symbols received.
+    pub source_symbols_received: usize,
+
+    /// The number of received repair symbols that were useless.
+    pub repair_useless: usize,
+
+    /// The number of received repair symbols that did not allow to recover
+    /// any source symbol.
+    pub repair_rank_deficient: usize,
+
+    /// The estimated number of acknowledged repair symbols that were wasted.
+    pub repair_wasted: usize,
+
+    /// The estimated number of acknowledged repair symbols that allowed the
+    /// peer to recover at least one source symbol.
+    pub repair_useful: usize,
+
+    /// The number of distinct encoding windows used to protect the sent
+    /// source symbols.
+    pub fec_generations: usize,
+
+    /// The number of bursts of source symbols followed by repair symbols.
+    pub fec_protected_bursts: usize,
+
+    /// The number of sent bytes.
+    pub sent_bytes: u64,
+
+    /// The number of received bytes.
+    pub recv_bytes: u64,
+
+    /// The number of bytes sent acked.
+    pub acked_bytes: u64,
+
+    /// The number of bytes sent lost.
+    pub lost_bytes: u64,
+
+    /// The number of stream bytes retransmitted.
+    pub stream_retrans_bytes: u64,
+
+    /// The number of known paths for the connection.
+    pub paths_count: usize,
+
+    /// The number of streams reset by local.
+    pub reset_stream_count_local: u64,
+
+    /// The number of streams stopped by local.
+    pub stopped_stream_count_local: u64,
+
+    /// The number of streams reset by remote.
+    pub reset_stream_count_remote: u64,
+
+    /// The number of streams stopped by remote.
+    pub stopped_stream_count_remote: u64,
+
+    /// The total number of PATH_CHALLENGE frames that were received.
+    pub path_challenge_rx_count: u64,
+}
+
+impl StatsSnapshot {
+    fn to_array(self) -> [u64; COUNTERS] {
+        [
+            self.recv as u64,
+            self.sent as u64,
+            self.lost as u64,
+            self.retrans as u64,
+            self.recov as u64,
+            self.repair_sent as u64,
+            self.repair_received as u64,
+            self.source_symbols_received as u64,
+            self.repair_useless as u64,
+            self.repair_rank_deficient as u64,
+            self.repair_wasted as u64,
+            self.repair_useful as u64,
+            self.fec_generations as u64,
+            self.fec_protected_bursts as u64,
+            self.sent_bytes,
+            self.recv_bytes,
+            self.acked_bytes,
+            self.lost_bytes,
+            self.stream_retrans_bytes,
+            self.paths_count as u64,
+            self.reset_stream_count_local,
+            self.stopped_stream_count_local,
+            self.reset_stream_count_remote,
+            self.stopped_stream_count_remote,
+            self.path_challenge_rx_count,
+        ]
+    }
+
+    fn from_array(v: [u64; COUNTERS]) -> StatsSnapshot {
+        StatsSnapshot {
+            recv: v[0] as usize,
+            sent: v[1] as usize,
+            lost: v[2] as usize,
+            retrans: v[3] as usize,
+            recov: v[4] as usize,
+            repair_sent: v[5] as usize,
+            repair_received: v[6] as usize,
+            source_symbols_received: v[7] as usize,
+            repair_useless: v[8] as usize,
+            repair_rank_deficient: v[9] as usize,
+            repair_wasted: v[10] as usize,
+            repair_useful: v[11] as usize,
+            fec_generations: v[12] as usize,
+            fec_protected_bursts: v[13] as usize,
+            sent_bytes: v[14],
+            recv_bytes: v[15],
+            acked_bytes: v[16],
+            lost_bytes: v[17],
+            stream_retrans_bytes: v[18],
+            paths_count: v[19] as usize,
+            reset_stream_count_local: v[20],
+            stopped_stream_count_local: v[21],
+            reset_stream_count_remote: v[22],
+            stopped_stream_count_remote: v[23],
+            path_challenge_rx_count: v[24],
+        }
+    }
+}
+
+struct Counters {
+    /// Odd while the counters are being updated.
+    seq: AtomicU64,
+
+    values: [AtomicU64; COUNTERS],
+}
+
+/// The statistics of a connection, readable from any thread.
+///
+/// The handle is obtained with the [`shared_stats()`] method and can be
+/// cloned and sent to other threads. Reading the counters never blocks the
+/// connection, nor requires access to it. The counters are published by the
+/// connection at the end of each [`recv()`], [`send()`] and [`on_timeout()`]
+/// call.
+///
+/// [`shared_stats()`]: struct.Connection.html#method.shared_stats
+/// [`recv()`]: struct.Connection.html#method.recv
+/// [`send()`]: struct.Connection.html#method.send
+/// [`on_timeout()`]: struct.Connection.html#method.on_timeout
+#[derive(Clone)]
+pub struct SharedStats {
+    counters: Arc<Counters>,
+}
+
+impl SharedStats {
+    pub(crate) fn new() -> SharedStats {
+        SharedStats {
+            counters: Arc::new(Counters {
+                seq: AtomicU64::new(0),
+                values: Default::default(),
+            }),
+        }
+    }
+
+    /// Publishes new values of the counters.
+    ///
+    /// This must only be called by the connection owning the handle.
+    pub(crate) fn publish(&self, stats: StatsSnapshot) {
+        let counters = &self.counters;
+
+        // The connection is the only writer, so the sequence number can be
+        // updated without a read-modify-write loop.
+        let seq = counters.seq.load(Ordering::Relaxed);
+        counters.seq.store(seq.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        for (counter, v) in counters.values.iter().zip(stats.to_array()) {
+            counter.store(v, Ordering::Relaxed);
+        }
+
+        counters.seq.store(seq.wrapping_add(2), Ordering::Release);
+    }
+
+    /// Returns a consistent snapshot of the counters.
+    ///
+    /// The snapshot is retried when it overlaps with an update of the
+    /// counters by the connection.
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let counters = &self.counters;
+
+        loop {
+            let seq = counters.seq.load(Ordering::Acquire);
+
+            if seq % 2 == 1 {
+                std::hint::spin_loop();
+                continue;
+            }
+
+            let mut values = [0; COUNTERS];
+
+            for (v, counter) in values.iter_mut().zip(counters.values.iter()) {
+                *v = counter.load(Ordering::Relaxed);
+            }
+
+            fence(Ordering::Acquire);
+
+            if counters.seq.load(Ordering::Relaxed) == seq {
+                return StatsSnapshot::from_array(values);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot() {
+        let stats = SharedStats::new();
+        assert_eq!(stats.snapshot(), StatsSnapshot::default());
+
+        let published = StatsSnapshot {
+            recv: 1,
+            repair_sent: 6,
+            path_challenge_rx_count: 25,
+            ..Default::default()
+        };
+
+        stats.publish(published);
+        assert_eq!(stats.clone().snapshot(), published);
+    }
+
+    #[test]
+    fn concurrent_snapshots() {
+        let stats = SharedStats::new();
+        let reader = stats.clone();
+
+        let handle = std::thread::spawn(move || {
+            for _ in 0..1000 {
+                let snapshot = reader.snapshot();
+
+                // Snapshots never mix values of different updates.
+                assert_eq!(snapshot.recv, snapshot.sent);
+                assert_eq!(snapshot.sent_bytes, snapshot.recv as u64 * 1200);
+            }
+        });
+
+        for i in 0..1000 {
+            stats.publish(StatsSnapshot {
+                recv: i,
+                sent: i,
+                sent_bytes: i as u64 * 1200,
+                ..Default::default()
+            });
+        }
+
+        handle.join().unwrap();
+    }
+}
@@ -28,9 +28,12 @@ use std::cmp;
 
 use std::sync::Arc;
 
+use std::time;
+
 use std::collections::hash_map;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
 
 use intrusive_collections::intrusive_adapter;
 use intrusive_collections::KeyAdapter;
@@ -42,6 +45,10 @@ use smallvec::SmallVec;
 use crate::Error;
 use crate::Result;
 
+pub use scheduler::DeficitRoundRobinScheduler;
+pub use scheduler::StreamScheduler;
+pub use scheduler::DEFAULT_STREAM_QUANTUM;
+
 const DEFAULT_URGENCY: u8 = 127;
 
 // The default size of the receiver stream flow control window.
@@ -122,10 +129,10 @@ pub struct StreamMap {
     /// The total number of unidirectional streams opened by the local endpoint.
     local_opened_streams_uni: u64,
 
-    /// Queue of stream IDs corresponding to streams that have buffered data
-    /// ready to be sent to the peer. This also implies that the stream has
-    /// enough flow control credits to send at least some of that data.
-    flushable: RBTree<StreamFlushablePriorityAdapter>,
+    /// Scheduler of the streams that have buffered data ready to be sent to
+    /// the peer. This also implies that the stream has enough flow control
+    /// credits to send at least some of that data.
+    flushable: Box<dyn StreamScheduler>,
 
     /// Set of stream IDs corresponding to streams that have outstanding data
     /// to read. This is used to generate a `StreamIter` of streams without
@@ -159,6 +166,11 @@ pub struct StreamMap {
     /// map elements is the error code to include in the STOP_SENDING frame.
     stopped: StreamIdHashMap<u64>,
 
+    /// Set of stream IDs corresponding to streams whose reliability mode
+    /// changed, and need to send a STREAM_UNRELIABLE frame. The value of the
+    /// map elements is the mode to include in the STREAM_UNRELIABLE frame.
+    unreliable: StreamIdHashMap<bool>,
+
     /// The maximum size of a stream window.
     max_stream_window: u64,
 }
@@ -190,6 +202,11 @@ impl StreamMap {
         self.streams.get_mut(&id)
     }
 
+    /// Returns an iterator over the open streams, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&u64, &Stream)> {
+        self.streams.iter()
+    }
+
     /// Returns the mutable stream with the given ID if it exists, or creates
     /// a new one otherwise.
     ///
@@ -380,27 +397,52 @@ impl StreamMap {
     ///
     /// If the stream was already in the list, this does nothing.
     pub fn insert_flushable(&mut self, priority_key: &Arc<StreamPriorityKey>) {
-        if !priority_key.flushable.is_linked() {
-            self.flushable.insert(Arc::clone(priority_key));
+        if !self.flushable.contains(priority_key.id) {
+            self.flushable.insert(
+                priority_key.id,
+                priority_key.urgency,
+                priority_key.incremental,
+            );
         }
     }
 
     /// Removes the stream ID from the flushable streams set.
-    pub fn remove_flushable(&mut self, priority_key: &Arc<StreamPriorityKey>) {
-        if !priority_key.flushable.is_linked() {
-            return;
-        }
+    pub fn remove_flushable(&mut self, stream_id: u64) {
+        self.flushable.remove(stream_id);
+    }
 
-        let mut c = {
-            let ptr = Arc::as_ptr(priority_key);
-            unsafe { self.flushable.cursor_mut_from_ptr(ptr) }
-        };
+    /// Returns the ID of the flushable stream to send data from next.
+    pub fn peek_flushable(&mut self) -> Option<u64> {
+        self.flushable.peek()
+    }
 
-        c.remove();
+    /// Records that `len` bytes of a stream that remains flushable were sent.
+    pub fn flushed(&mut self, stream_id: u64, len: usize) {
+        self.flushable.sent(stream_id, len);
     }
 
-    pub fn peek_flushable(&self) -> Option<Arc<StreamPriorityKey>> {
-        self.flushable.front().clone_pointer()
+    /// Replaces the scheduler of the flushable streams, moving the flushable
+    /// streams to the new scheduler.
+    pub fn set_scheduler(&mut self, scheduler: Box<dyn StreamScheduler>) {
+        let old = std::mem::replace(&mut self.flushable, scheduler);
+
+        let mut flushable: Vec<&Stream> = self
+            .streams
+            .iter()
+            .filter(|(id, _)| old.contains(**id))
+            .map(|(_, stream)| stream)
+            .collect();
+        flushable.sort_by_key(|s| s.priority_key.id);
+
+        for stream in flushable {
+            let priority_key = &stream.priority_key;
+
+            self.flushable.insert(
+                priority_key.id,
+                priority_key.urgency,
+                priority_key.incremental,
+            );
+        }
     }
 
     /// Updates the priorities of a stream.
@@ -417,9 +459,9 @@ impl StreamMap {
             self.writable.insert(Arc::clone(new));
         }
 
-        if old.flushable.is_linked() {
-            self.remove_flushable(old);
-            self.flushable.insert(Arc::clone(new));
+        if self.flushable.contains(old.id) {
+            self.flushable.remove(old.id);
+            self.insert_flushable(new);
         }
     }
 
@@ -476,6 +518,20 @@ impl StreamMap {
         self.stopped.remove(&stream_id);
     }
 
+    /// Adds the stream ID to the set of streams whose reliability mode needs
+    /// to be sent to the peer.
+    ///
+    /// If the stream was already in the list, its mode is replaced.
+    pub fn insert_unreliable(&mut self, stream_id: u64, unreliable: bool) {
+        self.unreliable.insert(stream_id, unreliable);
+    }
+
+    /// Removes the stream ID from the set of streams whose reliability mode
+    /// needs to be sent to the peer.
+    pub fn remove_unreliable(&mut self, stream_id: u64) {
+        self.unreliable.remove(&stream_id);
+    }
+
     /// Updates the peer's maximum bidirectional stream count limit.
     pub fn update_peer_max_streams_bidi(&mut self, v: u64) {
         self.peer_max_streams_bidi = cmp::max(self.peer_max_streams_bidi, v);
@@ -546,7 +602,7 @@ impl StreamMap {
 
         self.remove_writable(&s.priority_key);
 
-        self.remove_flushable(&s.priority_key);
+        self.remove_flushable(stream_id);
 
         self.collected.insert(stream_id);
     }
@@ -587,6 +643,11 @@ impl StreamMap {
         self.stopped.iter()
     }
 
+    /// Creates an iterator over streams that need to send STREAM_UNRELIABLE.
+    pub fn unreliable(&self) -> hash_map::Iter<u64, bool> {
+        self.unreliable.iter()
+    }
+
     /// Returns true if the stream has been collected.
     pub fn is_collected(&self, stream_id: u64) -> bool {
         self.collected.contains(&stream_id)
@@ -623,6 +684,12 @@ impl StreamMap {
         !self.stopped.is_empty()
     }
 
+    /// Returns true if there are any streams that need to send
+    /// STREAM_UNRELIABLE.
+    pub fn has_unreliable(&self) -> bool {
+        !self.unreliable.is_empty()
+    }
+
     /// Returns true if the max bidirectional streams count needs to be updated
     /// by sending a MAX_STREAMS frame to the peer.
     pub fn should_update_max_streams_bidi(&self) -> bool {
@@ -668,6 +735,43 @@ pub struct Stream {
     /// Whether the stream can be flushed incrementally. Default is `true`.
     pub incremental: bool,
 
+    /// Whether a repair symbol is sent right after the stream's data. Default
+    /// is `false`.
+    pub fec_critical: bool,
+
+    /// Whether the local endpoint marked the stream as unreliable, so that
+    /// lost data is given up on rather than retransmitted, and gaps in the
+    /// received data are skipped. Default is `false`.
+    pub unreliable: bool,
+
+    /// Whether the peer acknowledged the STREAM_UNRELIABLE frame carrying the
+    /// current value of `unreliable`.
+    pub unreliable_confirmed: bool,
+
+    /// Whether the peer marked the stream as unreliable, so that gaps in the
+    /// received data are skipped. Default is `false`.
+    pub peer_unreliable: bool,
+
+    /// When the gap at the read offset of an unreliable stream can be
+    /// skipped, if the data past it is buffered.
+    pub gap_deadline: Option<time::Instant>,
+
+    /// Whether the gap at the read offset of an unreliable stream can be
+    /// skipped.
+    pub gap_expired: bool,
+
+    /// The offsets at which the messages sent with `msg_send()` and the ADUs
+    /// sent with `stream_send_with_boundaries()` end, until they are
+    /// acknowledged.
+    pub msg_ends: VecDeque<u64>,
+
+    /// The ADUs sent with `stream_send_with_boundaries()` and the range of
+    /// data they span, until they are acknowledged or recovered by the peer.
+    pub adus: VecDeque<(u64, std::ops::Range<u64>)>,
+
+    /// The offset at which the last ADU ends.
+    pub adu_off: u64,
+
     pub priority_key: Arc<StreamPriorityKey>,
 }
 
@@ -690,13 +794,61 @@ impl Stream {
             local,
             urgency: priority_key.urgency,
             incremental: priority_key.incremental,
+            fec_critical: false,
+            unreliable: false,
+            unreliable_confirmed: false,
+            peer_unreliable: false,
+            gap_deadline: None,
+            gap_expired: false,
+            msg_ends: VecDeque::new(),
+            adus: VecDeque::new(),
+            adu_off: 0,
             priority_key,
         }
     }
 
     /// Returns true if the stream has data to read.
     pub fn is_readable(&self) -> bool {
-        self.recv.ready()
+        self.recv.ready() || (self.gap_expired && self.recv.ready_out_of_order())
+    }
+
+    /// Returns true if gaps in the received data are skipped, because either
+    /// endpoint marked the stream as unreliable.
+    pub fn skips_gaps(&self) -> bool {
+        self.unreliable || self.peer_unreliable
+    }
+
+    /// Returns true if lost data is given up on rather than retransmitted,
+    /// which requires the peer to know that it must skip the gaps.
+    pub fn drops_lost(&self) -> bool {
+        self.unreliable && self.unreliable_confirmed
+    }
+
+    /// Tracks the gap at the read offset of an unreliable stream.
+    ///
+    /// If data is buffered past a gap that is not tracked yet, the gap can be
+    /// skipped from `deadline` on, which is returned. The tracking stops once
+    /// the gap is filled.
+    pub fn track_gap(
+        &mut self, deadline: time::Instant,
+    ) -> Option<time::Instant> {
+        if !self.skips_gaps() ||
+            self.recv.ready() ||
+            !self.recv.ready_out_of_order()
+        {
+            self.gap_deadline = None;
+            self.gap_expired = false;
+
+            return None;
+        }
+
+        if self.gap_deadline.is_some() || self.gap_expired {
+            return None;
+        }
+
+        self.gap_deadline = Some(deadline);
+
+        Some(deadline)
     }
 
     /// Returns true if the stream has enough flow control capacity to be
@@ -718,6 +870,53 @@ impl Stream {
             off_front < self.send.max_off()
     }
 
+    /// Returns the offset at which the message including the data at `off`
+    /// ends, if that data was sent as a message.
+    pub fn msg_end(&mut self, off: u64) -> Option<u64> {
+        let ack_off = self.send.ack_off();
+
+        while self.msg_ends.front().map_or(false, |&end| end <= ack_off) {
+            self.msg_ends.pop_front();
+        }
+
+        self.msg_ends.iter().copied().find(|&end| end > off)
+    }
+
+    /// Records an ADU ending at `end`, from the end of the previous one.
+    ///
+    /// The STREAM frames are stopped at the end of the ADU, like at the end
+    /// of a message.
+    pub fn push_adu(&mut self, id: u64, end: u64) {
+        self.adus.push_back((id, self.adu_off..end));
+        self.msg_ends.push_back(end);
+        self.adu_off = end;
+    }
+
+    /// Returns the ADUs including data in `off..off + len`, recovered by the
+    /// peer with FEC, and forgets them so that they are only reported once.
+    pub fn adus_recovered(&mut self, off: u64, len: usize) -> Vec<u64> {
+        let end = off + len as u64;
+        let ack_off = self.send.ack_off();
+
+        let mut recovered = Vec::new();
+
+        self.adus.retain(|(id, range)| {
+            // The acknowledged ADUs are forgotten as well.
+            if range.end <= ack_off {
+                return false;
+            }
+
+            if range.start < end && off < range.end {
+                recovered.push(*id);
+                return false;
+            }
+
+            true
+        });
+
+        recovered
+    }
+
     /// Returns true if the stream is complete.
     ///
     /// For bidirectional streams this happens when both the receive and send
@@ -762,7 +961,6 @@ pub struct StreamPriorityKey {
 
     pub readable: RBTreeAtomicLink,
     pub writable: RBTreeAtomicLink,
-    pub flushable: RBTreeAtomicLink,
 }
 
 impl Default for StreamPriorityKey {
@@ -773,7 +971,6 @@ impl Default for StreamPriorityKey {
             id: Default::default(),
             readable: Default::default(),
             writable: Default::default(),
-            flushable: Default::default(),
         }
     }
 }
@@ -848,16 +1045,6 @@ impl<'a> KeyAdapter<'a> for StreamReadablePriorityAdapter {
     }
 }
 
-intrusive_adapter!(pub StreamFlushablePriorityAdapter = Arc<StreamPriorityKey>: StreamPriorityKey { flushable: RBTreeAtomicLink });
-
-impl<'a> KeyAdapter<'a> for StreamFlushablePriorityAdapter {
-    type Key = StreamPriorityKey;
-
-    fn get_key(&self, s: &StreamPriorityKey) -> Self::Key {
-        s.clone()
-    }
-}
-
 /// An iterator over QUIC streams.
 #[derive(Default)]
 pub struct StreamIter {
@@ -1032,6 +1219,51 @@ impl PartialEq for RangeBuf {
 mod tests {
     use super::*;
 
+    #[test]
+    fn msg_end() {
+        let mut stream =
+            Stream::new(0, 0, 100, true, true, DEFAULT_STREAM_WINDOW);
+
+        assert_eq!(stream.send.write(&[0; 30], false), Ok(30));
+        stream.msg_ends.extend([10, 30]);
+
+        assert_eq!(stream.msg_end(0), Some(10));
+        assert_eq!(stream.msg_end(10), Some(30));
+        assert_eq!(stream.msg_end(30), None);
+
+        // The acknowledged messages are forgotten.
+        stream.send.ack_and_drop(0, 10);
+        assert_eq!(stream.msg_ends.len(), 2);
+        assert_eq!(stream.msg_end(0), Some(30));
+        assert_eq!(stream.msg_ends.len(), 1);
+    }
+
+    #[test]
+    fn adus_recovered() {
+        let mut stream =
+            Stream::new(0, 0, 100, true, true, DEFAULT_STREAM_WINDOW);
+
+        assert_eq!(stream.send.write(&[0; 60], false), Ok(60));
+        stream.push_adu(41, 10);
+        stream.push_adu(42, 30);
+        stream.push_adu(43, 60);
+
+        assert_eq!(stream.msg_end(0), Some(10));
+        assert_eq!(stream.msg_end(10), Some(30));
+
+        // The data recovered spans the end of an ADU and the start of the
+        // next one.
+        assert_eq!(stream.adus_recovered(25, 10), vec![42, 43]);
+
+        // ADUs are only reported once.
+        assert_eq!(stream.adus_recovered(25, 10), Vec::<u64>::new());
+
+        // The acknowledged ADUs are forgotten.
+        stream.send.ack_and_drop(0, 10);
+        assert_eq!(stream.adus_recovered(0, 10), Vec::<u64>::new());
+        assert!(stream.adus.is_empty());
+    }
+
     #[test]
     fn recv_flow_control() {
         let mut stream = Stream::new(0, 15, 0, true, true, DEFAULT_STREAM_WINDOW);
@@ -2218,4 +2450,5 @@ mod tests {
 }
 
 mod recv_buf;
+mod scheduler;
 mod send_buf;
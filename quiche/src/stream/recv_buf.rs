@@ -249,6 +249,40 @@ impl RecvBuf {
         Ok((len, self.is_fin()))
     }
 
+    /// Writes data from the receive buffer into the given output buffer,
+    /// skipping over missing data.
+    ///
+    /// This is like [`emit()`], except that the data is written starting from
+    /// the lowest buffered offset, even if some data before it has not been
+    /// received yet. The read offset then moves past the skipped data, so
+    /// data of the gap received later is discarded.
+    ///
+    /// On success the amount of data read, the stream offset of the data and
+    /// a flag indicating if there is no more data in the buffer, are returned
+    /// as a tuple.
+    ///
+    /// [`emit()`]: struct.RecvBuf.html#method.emit
+    pub fn emit_out_of_order(
+        &mut self, out: &mut [u8],
+    ) -> Result<(usize, u64, bool)> {
+        let first_off = match self.data.first_key_value() {
+            Some((_, buf)) => buf.off(),
+
+            None => return Err(Error::Done),
+        };
+
+        // Skipped data is never going to be read, so account for it as
+        // consumed for flow control.
+        let skipped = first_off - self.off;
+
+        self.off = first_off;
+        self.flow_control.add_consumed(skipped);
+
+        let (len, fin) = self.emit(out)?;
+
+        Ok((len, first_off, fin))
+    }
+
     /// Resets the stream at the given offset.
     pub fn reset(&mut self, error_code: u64, final_size: u64) -> Result<usize> {
         // Stream's size is already known, forbid changing it.
@@ -358,6 +392,11 @@ impl RecvBuf {
         self.drain
     }
 
+    /// Returns true if the stream has data to be read, possibly after a gap.
+    pub fn ready_out_of_order(&self) -> bool {
+        !self.data.is_empty()
+    }
+
     /// Returns true if the stream has data to be read.
     pub fn ready(&self) -> bool {
         let (_, buf) = match self.data.first_key_value() {
@@ -476,6 +515,39 @@ mod tests {
         assert_eq!(recv.emit(&mut buf), Err(Error::Done));
     }
 
+    #[test]
+    fn out_of_order_read() {
+        let mut recv = RecvBuf::new(u64::MAX, DEFAULT_STREAM_WINDOW);
+
+        let mut buf = [0; 32];
+
+        let first = RangeBuf::from(b"hello", 0, false);
+        let second = RangeBuf::from(b"world", 5, false);
+        let third = RangeBuf::from(b"something", 10, true);
+
+        assert!(recv.write(second).is_ok());
+        assert_eq!(recv.emit(&mut buf), Err(Error::Done));
+
+        // The missing data is skipped.
+        let (len, off, fin) = recv.emit_out_of_order(&mut buf).unwrap();
+        assert_eq!((len, off, fin), (5, 5, false));
+        assert_eq!(&buf[..len], b"world");
+        assert_eq!(recv.off, 10);
+
+        assert_eq!(recv.emit_out_of_order(&mut buf), Err(Error::Done));
+
+        // Data of the gap is discarded.
+        assert!(recv.write(first).is_ok());
+        assert_eq!(recv.emit_out_of_order(&mut buf), Err(Error::Done));
+
+        assert!(recv.write(third).is_ok());
+
+        let (len, off, fin) = recv.emit_out_of_order(&mut buf).unwrap();
+        assert_eq!((len, off, fin), (9, 10, true));
+        assert_eq!(&buf[..len], b"something");
+        assert!(recv.is_fin());
+    }
+
     #[test]
     fn split_read() {
         let mut recv = RecvBuf::new(u64::MAX, DEFAULT_STREAM_WINDOW);
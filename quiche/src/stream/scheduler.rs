@@ -0,0 +1,324 @@
+// Copyright (C) 2024, Cloudflare, Inc.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are
+// met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS
+// IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO,
+// THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR
+// PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+/// The default quantum of the [`DeficitRoundRobinScheduler`], in bytes.
+///
+/// It is below the size of a full STREAM frame, so that the incremental
+/// streams sending full frames still alternate frame by frame, as with the
+/// previous per-frame round robin. Streams sending smaller frames now send
+/// several of them per turn, up to the quantum, instead of a single one.
+///
+/// [`DeficitRoundRobinScheduler`]: struct.DeficitRoundRobinScheduler.html
+pub const DEFAULT_STREAM_QUANTUM: usize = 1000;
+
+/// Selects the stream to send data from.
+///
+/// The scheduler holds the streams that have buffered data and enough flow
+/// control credit to send some of it. Each time a STREAM frame is to be
+/// written, the connection sends data from the stream returned by
+/// [`peek()`]. See [`set_stream_scheduler()`].
+///
+/// [`peek()`]: trait.StreamScheduler.html#tymethod.peek
+/// [`set_stream_scheduler()`]: struct.Connection.html#method.set_stream_scheduler
+pub trait StreamScheduler: Send + Sync {
+    /// Adds a stream with data to send, with the given priority.
+    ///
+    /// The stream is not in the scheduler already.
+    fn insert(&mut self, stream_id: u64, urgency: u8, incremental: bool);
+
+    /// Removes a stream from the scheduler, if present.
+    fn remove(&mut self, stream_id: u64);
+
+    /// Returns whether the stream is in the scheduler.
+    fn contains(&self, stream_id: u64) -> bool;
+
+    /// Returns whether the scheduler holds no stream.
+    fn is_empty(&self) -> bool;
+
+    /// Returns the stream to send data from next, without removing it.
+    fn peek(&mut self) -> Option<u64>;
+
+    /// Records that `len` bytes of the stream were sent, the stream still
+    /// having data to send.
+    fn sent(&mut self, stream_id: u64, len: usize);
+}
+
+impl Default for Box<dyn StreamScheduler> {
+    fn default() -> Self {
+        Box::new(DeficitRoundRobinScheduler::new(DEFAULT_STREAM_QUANTUM))
+    }
+}
+
+/// The streams sharing an urgency level.
+#[derive(Default)]
+struct Band {
+    /// The non-incremental streams, sent one at a time by stream ID.
+    sequential: BTreeSet<u64>,
+
+    /// The incremental streams with their deficit, in round-robin order.
+    incremental: VecDeque<(u64, i64)>,
+}
+
+impl Band {
+    fn is_empty(&self) -> bool {
+        self.sequential.is_empty() && self.incremental.is_empty()
+    }
+}
+
+/// A scheduler with strict urgency bands and deficit round robin within a
+/// band, following the semantics of [RFC 9218].
+///
+/// Streams with a lower urgency are always served first. Within a band,
+/// non-incremental streams are sent one after another in stream ID order,
+/// before the incremental streams. The incremental streams share the band
+/// fairly in bytes: each turn credits a stream with `quantum` bytes, and the
+/// stream keeps sending until its credit is exhausted, the excess being
+/// deducted from its next turns.
+///
+/// Unlike a per-frame round robin, a stream sending large frames doesn't get
+/// more of the band than a stream sending small ones: a frame larger than
+/// the quantum makes the stream skip turns until the other streams caught
+/// up.
+///
+/// This is the default scheduler, with a quantum of
+/// [`DEFAULT_STREAM_QUANTUM`] bytes.
+///
+/// [RFC 9218]: https://www.rfc-editor.org/rfc/rfc9218.html
+/// [`DEFAULT_STREAM_QUANTUM`]: constant.DEFAULT_STREAM_QUANTUM.html
+pub struct DeficitRoundRobinScheduler {
+    quantum: i64,
+    bands: BTreeMap<u8, Band>,
+    urgencies: HashMap<u64, (u8, bool)>,
+}
+
+impl DeficitRoundRobinScheduler {
+    /// Creates a scheduler crediting the incremental streams with `quantum`
+    /// bytes per turn.
+    pub fn new(quantum: usize) -> Self {
+        DeficitRoundRobinScheduler {
+            quantum: quantum.max(1) as i64,
+            bands: BTreeMap::new(),
+            urgencies: HashMap::new(),
+        }
+    }
+}
+
+impl StreamScheduler for DeficitRoundRobinScheduler {
+    fn insert(&mut self, stream_id: u64, urgency: u8, incremental: bool) {
+        if self
+            .urgencies
+            .insert(stream_id, (urgency, incremental))
+            .is_some()
+        {
+            return;
+        }
+
+        let band = self.bands.entry(urgency).or_default();
+
+        if incremental {
+            band.incremental.push_back((stream_id, 0));
+        } else {
+            band.sequential.insert(stream_id);
+        }
+    }
+
+    fn remove(&mut self, stream_id: u64) {
+        let (urgency, incremental) = match self.urgencies.remove(&stream_id) {
+            Some(v) => v,
+
+            None => return,
+        };
+
+        let band = match self.bands.get_mut(&urgency) {
+            Some(v) => v,
+
+            None => return,
+        };
+
+        if incremental {
+            band.incremental.retain(|(id, _)| *id != stream_id);
+        } else {
+            band.sequential.remove(&stream_id);
+        }
+
+        if band.is_empty() {
+            self.bands.remove(&urgency);
+        }
+    }
+
+    fn contains(&self, stream_id: u64) -> bool {
+        self.urgencies.contains_key(&stream_id)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.urgencies.is_empty()
+    }
+
+    fn peek(&mut self) -> Option<u64> {
+        let band = self.bands.values_mut().next()?;
+
+        if let Some(id) = band.sequential.iter().next() {
+            return Some(*id);
+        }
+
+        // Start a new turn for the streams that exhausted their credit. The
+        // loop ends as each turn increases the deficits.
+        loop {
+            let (id, deficit) = band.incremental.front_mut()?;
+
+            if *deficit > 0 {
+                return Some(*id);
+            }
+
+            *deficit += self.quantum;
+
+            if *deficit <= 0 {
+                band.incremental.rotate_left(1);
+            }
+        }
+    }
+
+    fn sent(&mut self, stream_id: u64, len: usize) {
+        let band = match self.urgencies.get(&stream_id) {
+            Some((urgency, true)) => match self.bands.get_mut(urgency) {
+                Some(v) => v,
+
+                None => return,
+            },
+
+            _ => return,
+        };
+
+        if let Some((id, deficit)) = band.incremental.front_mut() {
+            if *id != stream_id {
+                return;
+            }
+
+            *deficit -= len as i64;
+
+            // The turn of the stream is over.
+            if *deficit <= 0 {
+                band.incremental.rotate_left(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn send(
+        scheduler: &mut DeficitRoundRobinScheduler, len: usize, n: usize,
+    ) -> Vec<u64> {
+        (0..n)
+            .map(|_| {
+                let id = scheduler.peek().unwrap();
+                scheduler.sent(id, len);
+                id
+            })
+            .collect()
+    }
+
+    #[test]
+    fn urgency_bands() {
+        let mut scheduler = DeficitRoundRobinScheduler::new(1000);
+
+        scheduler.insert(8, 5, true);
+        scheduler.insert(4, 3, true);
+        scheduler.insert(12, 3, false);
+        scheduler.insert(0, 3, false);
+
+        // Non-incremental streams first, by stream ID.
+        assert_eq!(send(&mut scheduler, 1200, 2), [0, 0]);
+        scheduler.remove(0);
+        assert_eq!(send(&mut scheduler, 1200, 1), [12]);
+        scheduler.remove(12);
+
+        assert_eq!(send(&mut scheduler, 1200, 2), [4, 4]);
+        scheduler.remove(4);
+
+        assert_eq!(send(&mut scheduler, 1200, 1), [8]);
+        scheduler.remove(8);
+
+        assert!(scheduler.is_empty());
+        assert_eq!(scheduler.peek(), None);
+    }
+
+    #[test]
+    fn deficit_round_robin() {
+        let mut scheduler = DeficitRoundRobinScheduler::new(1000);
+
+        scheduler.insert(0, 3, true);
+        scheduler.insert(4, 3, true);
+
+        // Full-sized frames alternate.
+        assert_eq!(send(&mut scheduler, 1200, 4), [0, 4, 0, 4]);
+
+        // Small frames are sent until the credit of the stream is exhausted,
+        // the excess of the previous turns being deducted.
+        scheduler.insert(8, 3, true);
+        assert_eq!(send(&mut scheduler, 300, 5), [0, 0, 4, 4, 8]);
+
+        assert!(scheduler.contains(4));
+        scheduler.remove(4);
+        assert!(!scheduler.contains(4));
+        assert_eq!(send(&mut scheduler, 1200, 2), [8, 0]);
+    }
+
+    #[test]
+    fn frames_larger_than_quantum() {
+        let mut scheduler = DeficitRoundRobinScheduler::new(1000);
+
+        scheduler.insert(0, 3, true);
+        scheduler.insert(4, 3, true);
+
+        // Stream 0 sends frames of 2500 bytes, stream 4 of 500 bytes.
+        let mut sent = [0; 2];
+        let mut order = Vec::new();
+
+        for _ in 0..100 {
+            let id = scheduler.peek().unwrap();
+            let len = if id == 0 { 2500 } else { 500 };
+
+            scheduler.sent(id, len);
+            sent[id as usize / 4] += len;
+            order.push(id);
+        }
+
+        // Stream 0 skips the turns it overdrew.
+        assert_eq!(order[..8], [0, 4, 4, 4, 4, 0, 4, 4]);
+
+        // Both streams get the same share of the band, within a quantum and
+        // a frame.
+        assert!(sent[0].abs_diff(sent[1]) <= 1000 + 2500);
+    }
+}
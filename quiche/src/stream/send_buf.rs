@@ -83,6 +83,10 @@ pub struct SendBuf {
     /// Ranges of data offsets that have been acked.
     acked: ranges::RangeSet,
 
+    /// Ranges of data offsets that have been acked by the peer. Unlike
+    /// `acked`, data dropped when the stream is reset is not included.
+    peer_acked: ranges::RangeSet,
+
     /// The error code received via STOP_SENDING.
     error: Option<u64>,
 }
@@ -250,11 +254,28 @@ impl SendBuf {
     /// Increments the acked data offset.
     pub fn ack(&mut self, off: u64, len: usize) {
         self.acked.insert(off..off + len as u64);
+        self.peer_acked.insert(off..off + len as u64);
     }
 
     pub fn ack_and_drop(&mut self, off: u64, len: usize) {
         self.ack(off, len);
 
+        self.drop_acked(off);
+    }
+
+    /// Gives up on delivering the data of the given range, which was lost.
+    ///
+    /// The data is dropped as if it was acked, except that it's not
+    /// considered acked by the peer.
+    pub fn drop_lost(&mut self, off: u64, len: usize) {
+        self.acked.insert(off..off + len as u64);
+
+        self.drop_acked(off);
+    }
+
+    /// Drops the data contiguously acked, `off` being the start of the newly
+    /// acked range.
+    fn drop_acked(&mut self, off: u64) {
         let ack_off = self.ack_off();
 
         if self.data.is_empty() {
@@ -358,7 +379,7 @@ impl SendBuf {
 
         // Mark relevant data as acked.
         self.off = unsent_off;
-        self.ack(0, self.off as usize);
+        self.acked.insert(0..self.off);
 
         self.pos = 0;
         self.len = 0;
@@ -470,6 +491,27 @@ impl SendBuf {
         }
     }
 
+    /// Returns the highest offset contiguously acked by the peer.
+    ///
+    /// This is the same as [`ack_off()`], except that data dropped when the
+    /// stream is reset is not considered acked.
+    ///
+    /// [`ack_off()`]: struct.SendBuf.html#method.ack_off
+    pub fn peer_ack_off(&self) -> u64 {
+        match self.peer_acked.iter().next() {
+            Some(std::ops::Range { start: 0, end }) => end,
+
+            Some(_) | None => 0,
+        }
+    }
+
+    /// Returns the ranges of data offsets acked by the peer.
+    pub fn peer_acked_ranges(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = std::ops::Range<u64>> + '_ {
+        self.peer_acked.iter()
+    }
+
     /// Returns the outgoing flow control capacity.
     pub fn cap(&self) -> Result<usize> {
         // The stream was stopped, so return the error code instead.
@@ -778,4 +820,27 @@ mod tests {
         assert_eq!(fin_off, 50);
         assert_eq!(unsent, 0);
     }
+
+    #[test]
+    fn peer_ack_off_after_reset() {
+        let mut buf = [0; 50];
+        let mut send = SendBuf::new(u64::MAX);
+
+        send.write(&buf, false).unwrap();
+
+        let (written, _fin) = send.emit(&mut buf).unwrap();
+        assert_eq!(written, 50);
+
+        send.ack_and_drop(0, 10);
+        send.ack_and_drop(20, 10);
+
+        // Resetting the stream marks all the data as acked.
+        assert_eq!(send.shutdown(), Ok((50, 0)));
+        assert_eq!(send.ack_off(), 50);
+        assert_eq!(send.peer_ack_off(), 10);
+
+        // Acks of the data in flight keep being tracked.
+        send.ack_and_drop(10, 10);
+        assert_eq!(send.peer_ack_off(), 30);
+    }
 }
@@ -346,6 +346,194 @@ impl Drop for Context {
     }
 }
 
+/// A TLS setting applied to a context.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Setting {
+    CertChainFile(String),
+    PrivKeyFile(String),
+    VerifyLocationsFile(String),
+    VerifyLocationsDirectory(String),
+    Verify(bool),
+    Keylog,
+    TicketKey(Vec<u8>),
+    EarlyData,
+    Alpn(Vec<Vec<u8>>),
+}
+
+impl Setting {
+    fn apply(&self, ctx: &mut Context) -> Result<()> {
+        match self {
+            Setting::CertChainFile(file) => ctx.use_certificate_chain_file(file),
+
+            Setting::PrivKeyFile(file) => ctx.use_privkey_file(file),
+
+            Setting::VerifyLocationsFile(file) =>
+                ctx.load_verify_locations_from_file(file),
+
+            Setting::VerifyLocationsDirectory(dir) =>
+                ctx.load_verify_locations_from_directory(dir),
+
+            Setting::Verify(verify) => {
+                ctx.set_verify(*verify);
+                Ok(())
+            },
+
+            Setting::Keylog => {
+                ctx.enable_keylog();
+                Ok(())
+            },
+
+            Setting::TicketKey(key) => ctx.set_ticket_key(key),
+
+            Setting::EarlyData => {
+                ctx.set_early_data_enabled(true);
+                Ok(())
+            },
+
+            Setting::Alpn(protos) => {
+                let protos: Vec<&[u8]> =
+                    protos.iter().map(|p| p.as_slice()).collect();
+
+                ctx.set_alpn(&protos)
+            },
+        }
+    }
+
+    /// Returns true if applying the setting cancels the effect of `other`.
+    ///
+    /// The verify locations add up, so only the same location is replaced,
+    /// while the other settings replace the previous setting of the same
+    /// kind.
+    fn overrides(&self, other: &Setting) -> bool {
+        match self {
+            Setting::VerifyLocationsFile(_) |
+            Setting::VerifyLocationsDirectory(_) => self == other,
+
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+/// A TLS context along with the settings applied to it.
+///
+/// A context can't be copied, so a clone only keeps the settings, and builds
+/// its own context from them the first time it is needed. A context created
+/// from a BoringSSL builder can't be rebuilt.
+pub struct ContextConfig {
+    ctx: Option<Context>,
+
+    settings: Vec<Setting>,
+
+    replayable: bool,
+
+    /// The error of a setting that couldn't be reported when it was applied,
+    /// returned when the next handshake is created.
+    error: Option<Error>,
+}
+
+impl ContextConfig {
+    pub fn new(ctx: Context) -> ContextConfig {
+        ContextConfig {
+            ctx: Some(ctx),
+            settings: Vec::new(),
+            replayable: true,
+            error: None,
+        }
+    }
+
+    #[cfg(feature = "boringssl-boring-crate")]
+    pub fn from_boring(
+        ssl_ctx_builder: boring::ssl::SslContextBuilder,
+    ) -> ContextConfig {
+        ContextConfig {
+            ctx: Some(Context::from_boring(ssl_ctx_builder)),
+            settings: Vec::new(),
+            replayable: false,
+            error: None,
+        }
+    }
+
+    /// Returns the context, building it from the recorded settings if needed.
+    pub fn ctx(&mut self) -> Result<&mut Context> {
+        if self.ctx.is_none() {
+            if !self.replayable {
+                return Err(Error::TlsFail);
+            }
+
+            let mut ctx = Context::new()?;
+
+            for setting in &self.settings {
+                setting.apply(&mut ctx)?;
+            }
+
+            self.ctx = Some(ctx);
+        }
+
+        self.ctx.as_mut().ok_or(Error::TlsFail)
+    }
+
+    /// Applies the given setting to the context, and records it for clones
+    /// in place of the settings it overrides.
+    pub fn apply(&mut self, setting: Setting) -> Result<()> {
+        setting.apply(self.ctx()?)?;
+
+        self.settings.retain(|s| !setting.overrides(s));
+        self.settings.push(setting);
+
+        Ok(())
+    }
+
+    /// Applies the given setting like [`apply()`], but defers the error, if
+    /// any, to the creation of the next handshake, for the callers that
+    /// can't report it.
+    ///
+    /// [`apply()`]: struct.ContextConfig.html#method.apply
+    pub fn apply_or_defer(&mut self, setting: Setting) {
+        if let Err(e) = self.apply(setting) {
+            error!("failed to apply TLS setting: {:?}", e);
+
+            self.error.get_or_insert(e);
+        }
+    }
+
+    pub fn new_handshake(&mut self) -> Result<Handshake> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        self.ctx()?.new_handshake()
+    }
+}
+
+impl Clone for ContextConfig {
+    fn clone(&self) -> ContextConfig {
+        if !self.replayable {
+            error!(
+                "cloned a TLS context created from a BoringSSL builder, \
+                 connections created with the clone will fail"
+            );
+        }
+
+        ContextConfig {
+            ctx: None,
+            settings: self.settings.clone(),
+            replayable: self.replayable,
+            error: self.error,
+        }
+    }
+}
+
+impl Default for ContextConfig {
+    fn default() -> ContextConfig {
+        ContextConfig {
+            ctx: None,
+            settings: Vec::new(),
+            replayable: true,
+            error: None,
+        }
+    }
+}
+
 pub struct Handshake {
     /// Raw pointer
     ptr: *mut SSL,
@@ -1214,3 +1402,35 @@ use boringssl::*;
 mod openssl_quictls;
 #[cfg(feature = "openssl")]
 use openssl_quictls::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_replaced() {
+        let mut ctx = ContextConfig::default();
+
+        for _ in 0..10 {
+            ctx.apply(Setting::Verify(false)).unwrap();
+            ctx.apply(Setting::Alpn(vec![b"proto".to_vec()])).unwrap();
+        }
+
+        ctx.apply(Setting::Verify(true)).unwrap();
+
+        // Only the last setting of each kind is kept for clones.
+        assert!(
+            ctx.settings ==
+                vec![
+                    Setting::Alpn(vec![b"proto".to_vec()]),
+                    Setting::Verify(true),
+                ]
+        );
+
+        // The verify locations add up.
+        let a = Setting::VerifyLocationsDirectory("a".to_string());
+        let b = Setting::VerifyLocationsDirectory("b".to_string());
+        assert!(!b.overrides(&a));
+        assert!(a.overrides(&a));
+    }
+}